@@ -0,0 +1,24 @@
+-- Create sip_transport enum
+CREATE TYPE sip_transport AS ENUM (
+    'udp',
+    'tcp'
+);
+
+-- Create sip_registrations table
+CREATE TABLE sip_registrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    extension_id UUID NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    contact_uri VARCHAR(255) NOT NULL,
+    user_agent VARCHAR(255),
+    source_address VARCHAR(64) NOT NULL,
+    transport sip_transport NOT NULL,
+    call_id VARCHAR(255) NOT NULL,
+    cseq INTEGER NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (extension_id, contact_uri)
+);
+
+-- Create indexes
+CREATE INDEX idx_sip_registrations_extension ON sip_registrations(extension_id);
+CREATE INDEX idx_sip_registrations_expires_at ON sip_registrations(expires_at);
@@ -0,0 +1,10 @@
+-- Link call records to Asterisk channels so AMI events can update them
+ALTER TABLE call_records
+    ADD COLUMN uniqueid VARCHAR(150),
+    ADD COLUMN answered_at TIMESTAMPTZ;
+
+ALTER TABLE call_records
+    ADD CONSTRAINT call_records_uniqueid_key UNIQUE (uniqueid);
+
+-- Create indexes
+CREATE INDEX idx_call_records_status ON call_records(status);
@@ -0,0 +1,41 @@
+-- Create route_destination_type enum
+CREATE TYPE route_destination_type AS ENUM (
+    'extension',
+    'queue',
+    'ivr',
+    'voicemail',
+    'hangup'
+);
+
+-- Create inbound_routes table
+CREATE TABLE inbound_routes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    did_pattern VARCHAR(50),
+    caller_id_pattern VARCHAR(50),
+    destination_type route_destination_type NOT NULL,
+    destination VARCHAR(100) NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 100,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create outbound_routes table
+CREATE TABLE outbound_routes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    dial_pattern VARCHAR(50) NOT NULL,
+    strip_digits INTEGER NOT NULL DEFAULT 0,
+    prepend VARCHAR(20),
+    trunk VARCHAR(100) NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 100,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_strip_digits CHECK (strip_digits >= 0)
+);
+
+-- Create indexes
+CREATE INDEX idx_inbound_routes_priority ON inbound_routes(priority);
+CREATE INDEX idx_outbound_routes_priority ON outbound_routes(priority);
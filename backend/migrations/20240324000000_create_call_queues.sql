@@ -0,0 +1,45 @@
+-- Create queue_strategy enum
+CREATE TYPE queue_strategy AS ENUM (
+    'ringall',
+    'round-robin',
+    'least-recent',
+    'fewest-calls'
+);
+
+-- Create call_queues table
+CREATE TABLE call_queues (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) UNIQUE NOT NULL,
+    strategy queue_strategy NOT NULL DEFAULT 'ringall',
+    ring_timeout INTEGER NOT NULL DEFAULT 15,
+    max_wait_time INTEGER NOT NULL DEFAULT 300,
+    overflow_destination_type route_destination_type,
+    overflow_destination VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_ring_timeout CHECK (ring_timeout > 0),
+    CONSTRAINT valid_max_wait_time CHECK (max_wait_time >= 0)
+);
+
+-- Create queue_members table
+CREATE TABLE queue_members (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    queue_id UUID NOT NULL REFERENCES call_queues(id) ON DELETE CASCADE,
+    extension_id UUID NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    penalty INTEGER NOT NULL DEFAULT 0,
+    logged_in BOOLEAN NOT NULL DEFAULT FALSE,
+    paused BOOLEAN NOT NULL DEFAULT FALSE,
+    pause_reason VARCHAR(100),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (queue_id, extension_id)
+);
+
+-- Create indexes
+CREATE INDEX idx_queue_members_queue ON queue_members(queue_id);
+CREATE INDEX idx_queue_members_extension ON queue_members(extension_id);
+
+-- Create triggers
+CREATE TRIGGER update_call_queues_updated_at
+    BEFORE UPDATE ON call_queues
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
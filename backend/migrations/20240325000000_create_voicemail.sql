@@ -0,0 +1,38 @@
+-- Create voicemail_boxes table
+CREATE TABLE voicemail_boxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    extension_id UUID UNIQUE NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    pin VARCHAR(10) NOT NULL,
+    email VARCHAR(255),
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    has_greeting BOOLEAN NOT NULL DEFAULT FALSE,
+    max_messages INTEGER NOT NULL DEFAULT 100,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_pin CHECK (pin ~ '^[0-9]{4,10}$'),
+    CONSTRAINT valid_max_messages CHECK (max_messages > 0)
+);
+
+-- Create voicemail_messages table
+CREATE TABLE voicemail_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    mailbox_id UUID NOT NULL REFERENCES voicemail_boxes(id) ON DELETE CASCADE,
+    caller_id VARCHAR(50) NOT NULL,
+    duration INTEGER NOT NULL DEFAULT 0,
+    file_path VARCHAR(500) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    read BOOLEAN NOT NULL DEFAULT FALSE,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_duration CHECK (duration >= 0)
+);
+
+-- Create indexes
+CREATE INDEX idx_voicemail_boxes_owner ON voicemail_boxes(owner_id);
+CREATE INDEX idx_voicemail_messages_mailbox ON voicemail_messages(mailbox_id, received_at DESC);
+
+-- Create triggers
+CREATE TRIGGER update_voicemail_boxes_updated_at
+    BEFORE UPDATE ON voicemail_boxes
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,17 @@
+-- Create ivr_menus table. The node graph is validated as a whole before it
+-- is saved, so it is stored as a single JSONB document.
+CREATE TABLE ivr_menus (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) UNIQUE NOT NULL,
+    description VARCHAR(255),
+    entry_node VARCHAR(50) NOT NULL,
+    nodes JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create triggers
+CREATE TRIGGER update_ivr_menus_updated_at
+    BEFORE UPDATE ON ivr_menus
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,83 @@
+-- Create ring_group_strategy enum
+CREATE TYPE ring_group_strategy AS ENUM (
+    'simultaneous',
+    'sequential'
+);
+
+-- Create ring_groups table
+CREATE TABLE ring_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_number VARCHAR(20) UNIQUE NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    strategy ring_group_strategy NOT NULL DEFAULT 'simultaneous',
+    timeout INTEGER NOT NULL DEFAULT 30,
+    member_timeout INTEGER NOT NULL DEFAULT 15,
+    failover_destination_type route_destination_type,
+    failover_destination VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_timeout CHECK (timeout > 0),
+    CONSTRAINT valid_member_timeout CHECK (member_timeout > 0)
+);
+
+-- Create ring_group_members table
+CREATE TABLE ring_group_members (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    ring_group_id UUID NOT NULL REFERENCES ring_groups(id) ON DELETE CASCADE,
+    extension_id UUID NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    delay INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (ring_group_id, extension_id),
+    CONSTRAINT valid_delay CHECK (delay >= 0)
+);
+
+-- Create indexes
+CREATE INDEX idx_ring_group_members_group ON ring_group_members(ring_group_id, position);
+CREATE INDEX idx_ring_group_members_extension ON ring_group_members(extension_id);
+
+-- Extensions and ring groups share one dialable numbering namespace. The
+-- advisory lock serialises concurrent inserts of the same number.
+CREATE OR REPLACE FUNCTION check_dial_number_namespace()
+RETURNS TRIGGER AS $$
+DECLARE
+    dial_number TEXT;
+BEGIN
+    IF TG_TABLE_NAME = 'pbx_extensions' THEN
+        dial_number := NEW.extension_number;
+    ELSE
+        dial_number := NEW.group_number;
+    END IF;
+
+    PERFORM pg_advisory_xact_lock(hashtext('dial_number:' || dial_number));
+
+    IF TG_TABLE_NAME = 'pbx_extensions' THEN
+        IF EXISTS (SELECT 1 FROM ring_groups WHERE group_number = dial_number) THEN
+            RAISE EXCEPTION 'Number % is already used by a ring group', dial_number
+                USING ERRCODE = 'unique_violation';
+        END IF;
+    ELSE
+        IF EXISTS (SELECT 1 FROM pbx_extensions WHERE extension_number = dial_number) THEN
+            RAISE EXCEPTION 'Number % is already used by an extension', dial_number
+                USING ERRCODE = 'unique_violation';
+        END IF;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+-- Create triggers
+CREATE TRIGGER check_pbx_extensions_number
+    BEFORE INSERT OR UPDATE OF extension_number ON pbx_extensions
+    FOR EACH ROW
+    EXECUTE FUNCTION check_dial_number_namespace();
+
+CREATE TRIGGER check_ring_groups_number
+    BEFORE INSERT OR UPDATE OF group_number ON ring_groups
+    FOR EACH ROW
+    EXECUTE FUNCTION check_dial_number_namespace();
+
+CREATE TRIGGER update_ring_groups_updated_at
+    BEFORE UPDATE ON ring_groups
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,26 @@
+-- Create recording_mode enum
+CREATE TYPE recording_mode AS ENUM (
+    'always',
+    'never'
+);
+
+-- Create recording_policies table
+CREATE TABLE recording_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    extension_id UUID UNIQUE NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    mode recording_mode NOT NULL DEFAULT 'never',
+    retention_days INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_retention_days CHECK (retention_days IS NULL OR retention_days > 0)
+);
+
+-- Create indexes
+CREATE INDEX idx_call_records_recording ON call_records(start_time)
+    WHERE recording_path IS NOT NULL;
+
+-- Create triggers
+CREATE TRIGGER update_recording_policies_updated_at
+    BEFORE UPDATE ON recording_policies
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,29 @@
+-- Create call_event_source enum
+CREATE TYPE call_event_source AS ENUM (
+    'api',
+    'ami',
+    'import'
+);
+
+-- Create call_events table
+CREATE TABLE call_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    call_id UUID NOT NULL REFERENCES call_records(id) ON DELETE CASCADE,
+    from_status VARCHAR(20),
+    to_status VARCHAR(20) NOT NULL,
+    source call_event_source NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_call_event_status CHECK (
+        to_status IN ('active', 'completed', 'failed', 'busy', 'noanswer')
+        AND (from_status IS NULL OR from_status IN ('active', 'completed', 'failed', 'busy', 'noanswer'))
+    )
+);
+
+-- Calls can no longer end before they start. Existing rows are not checked.
+ALTER TABLE call_records
+    ADD CONSTRAINT call_records_end_after_start
+    CHECK (end_time IS NULL OR end_time >= start_time) NOT VALID;
+
+-- Create indexes
+CREATE INDEX idx_call_events_call_id ON call_events(call_id, occurred_at);
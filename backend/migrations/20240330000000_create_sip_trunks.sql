@@ -0,0 +1,38 @@
+-- Create trunk_transport enum
+CREATE TYPE trunk_transport AS ENUM (
+    'udp',
+    'tcp',
+    'tls'
+);
+
+-- Create sip_trunks table
+CREATE TABLE sip_trunks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) UNIQUE NOT NULL,
+    host VARCHAR(255) NOT NULL,
+    port INTEGER NOT NULL DEFAULT 5060,
+    transport trunk_transport NOT NULL DEFAULT 'udp',
+    username VARCHAR(100),
+    secret VARCHAR(100),
+    register BOOLEAN NOT NULL DEFAULT FALSE,
+    outbound_caller_id VARCHAR(50),
+    max_channels INTEGER,
+    codecs TEXT[] NOT NULL DEFAULT ARRAY['ulaw', 'alaw'],
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_trunk_port CHECK (port BETWEEN 1 AND 65535),
+    CONSTRAINT valid_max_channels CHECK (max_channels IS NULL OR max_channels > 0),
+    CONSTRAINT registration_credentials CHECK (
+        NOT register OR (username IS NOT NULL AND secret IS NOT NULL)
+    )
+);
+
+-- Create indexes
+CREATE INDEX idx_outbound_routes_trunk ON outbound_routes(trunk);
+
+-- Create triggers
+CREATE TRIGGER update_sip_trunks_updated_at
+    BEFORE UPDATE ON sip_trunks
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,83 @@
+-- Create conference_rooms table
+CREATE TABLE conference_rooms (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_number VARCHAR(20) UNIQUE NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    user_pin VARCHAR(10),
+    admin_pin VARCHAR(10),
+    max_participants INTEGER,
+    record BOOLEAN NOT NULL DEFAULT FALSE,
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_user_pin CHECK (user_pin IS NULL OR user_pin ~ '^[0-9]{4,10}$'),
+    CONSTRAINT valid_admin_pin CHECK (admin_pin IS NULL OR admin_pin ~ '^[0-9]{4,10}$'),
+    CONSTRAINT distinct_pins CHECK (user_pin IS NULL OR admin_pin IS NULL OR user_pin <> admin_pin),
+    CONSTRAINT valid_max_participants CHECK (max_participants IS NULL OR max_participants > 1)
+);
+
+-- Create conference_attendance table
+CREATE TABLE conference_attendance (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id UUID NOT NULL REFERENCES conference_rooms(id) ON DELETE CASCADE,
+    channel VARCHAR(200) NOT NULL,
+    caller_id_num VARCHAR(50),
+    caller_id_name VARCHAR(100),
+    admin BOOLEAN NOT NULL DEFAULT FALSE,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    left_at TIMESTAMPTZ
+);
+
+-- Create indexes
+CREATE INDEX idx_conference_attendance_room ON conference_attendance(room_id, joined_at);
+CREATE INDEX idx_conference_attendance_open ON conference_attendance(room_id, channel)
+    WHERE left_at IS NULL;
+
+-- Conference rooms join extensions and ring groups in the dialable namespace
+CREATE OR REPLACE FUNCTION check_dial_number_namespace()
+RETURNS TRIGGER AS $$
+DECLARE
+    dial_number TEXT;
+BEGIN
+    IF TG_TABLE_NAME = 'pbx_extensions' THEN
+        dial_number := NEW.extension_number;
+    ELSIF TG_TABLE_NAME = 'ring_groups' THEN
+        dial_number := NEW.group_number;
+    ELSE
+        dial_number := NEW.room_number;
+    END IF;
+
+    PERFORM pg_advisory_xact_lock(hashtext('dial_number:' || dial_number));
+
+    IF TG_TABLE_NAME <> 'pbx_extensions'
+        AND EXISTS (SELECT 1 FROM pbx_extensions WHERE extension_number = dial_number) THEN
+        RAISE EXCEPTION 'Number % is already used by an extension', dial_number
+            USING ERRCODE = 'unique_violation';
+    END IF;
+
+    IF TG_TABLE_NAME <> 'ring_groups'
+        AND EXISTS (SELECT 1 FROM ring_groups WHERE group_number = dial_number) THEN
+        RAISE EXCEPTION 'Number % is already used by a ring group', dial_number
+            USING ERRCODE = 'unique_violation';
+    END IF;
+
+    IF TG_TABLE_NAME <> 'conference_rooms'
+        AND EXISTS (SELECT 1 FROM conference_rooms WHERE room_number = dial_number) THEN
+        RAISE EXCEPTION 'Number % is already used by a conference room', dial_number
+            USING ERRCODE = 'unique_violation';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+-- Create triggers
+CREATE TRIGGER check_conference_rooms_number
+    BEFORE INSERT OR UPDATE OF room_number ON conference_rooms
+    FOR EACH ROW
+    EXECUTE FUNCTION check_dial_number_namespace();
+
+CREATE TRIGGER update_conference_rooms_updated_at
+    BEFORE UPDATE ON conference_rooms
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
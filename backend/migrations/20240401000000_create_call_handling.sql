@@ -0,0 +1,37 @@
+-- Extensions can belong to a user, who may then manage their call handling
+ALTER TABLE pbx_extensions ADD COLUMN owner_id UUID REFERENCES users(id) ON DELETE SET NULL;
+
+-- Create extension_call_handling table
+CREATE TABLE extension_call_handling (
+    extension_id UUID PRIMARY KEY REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    dnd BOOLEAN NOT NULL DEFAULT FALSE,
+    forward_always VARCHAR(30),
+    forward_busy VARCHAR(30),
+    forward_no_answer VARCHAR(30),
+    follow_me_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_forward_always CHECK (forward_always IS NULL OR forward_always ~ '^[0-9]+$'),
+    CONSTRAINT valid_forward_busy CHECK (forward_busy IS NULL OR forward_busy ~ '^[0-9]+$'),
+    CONSTRAINT valid_forward_no_answer CHECK (forward_no_answer IS NULL OR forward_no_answer ~ '^[0-9]+$')
+);
+
+-- Create extension_follow_me table
+CREATE TABLE extension_follow_me (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    extension_id UUID NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    number VARCHAR(30) NOT NULL,
+    ring_time INTEGER NOT NULL,
+    CONSTRAINT unique_follow_me_position UNIQUE (extension_id, position),
+    CONSTRAINT valid_follow_me_number CHECK (number ~ '^[0-9]+$'),
+    CONSTRAINT valid_ring_time CHECK (ring_time BETWEEN 5 AND 120)
+);
+
+-- Create indexes
+CREATE INDEX idx_pbx_extensions_owner ON pbx_extensions(owner_id);
+
+-- Create triggers
+CREATE TRIGGER update_extension_call_handling_updated_at
+    BEFORE UPDATE ON extension_call_handling
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
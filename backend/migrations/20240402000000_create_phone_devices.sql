@@ -0,0 +1,27 @@
+-- Create phone_vendor enum
+CREATE TYPE phone_vendor AS ENUM ('yealink', 'polycom', 'grandstream');
+
+-- Create phone_devices table
+CREATE TABLE phone_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    mac_address VARCHAR(12) UNIQUE NOT NULL,
+    extension_id UUID NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    vendor phone_vendor NOT NULL,
+    model VARCHAR(50),
+    password_hash VARCHAR(255) NOT NULL,
+    last_fetched_at TIMESTAMPTZ,
+    last_fetch_address VARCHAR(100),
+    last_fetch_user_agent VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_mac_address CHECK (mac_address ~ '^[0-9a-f]{12}$')
+);
+
+-- Create indexes
+CREATE INDEX idx_phone_devices_extension ON phone_devices(extension_id);
+
+-- Create triggers
+CREATE TRIGGER update_phone_devices_updated_at
+    BEFORE UPDATE ON phone_devices
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
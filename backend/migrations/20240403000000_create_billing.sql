@@ -0,0 +1,64 @@
+-- Create rate_tables table
+CREATE TABLE rate_tables (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_currency CHECK (currency ~ '^[A-Z]{3}$')
+);
+
+-- Create rates table
+CREATE TABLE rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    rate_table_id UUID NOT NULL REFERENCES rate_tables(id) ON DELETE CASCADE,
+    prefix VARCHAR(20) NOT NULL,
+    description VARCHAR(100),
+    rate_per_minute NUMERIC(14, 6) NOT NULL,
+    connection_fee NUMERIC(14, 6) NOT NULL DEFAULT 0,
+    billing_increment INTEGER NOT NULL DEFAULT 60,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_rate_prefix UNIQUE (rate_table_id, prefix),
+    CONSTRAINT valid_prefix CHECK (prefix ~ '^[0-9]+$'),
+    CONSTRAINT valid_rate_per_minute CHECK (rate_per_minute >= 0),
+    CONSTRAINT valid_connection_fee CHECK (connection_fee >= 0),
+    CONSTRAINT valid_billing_increment CHECK (billing_increment BETWEEN 1 AND 3600)
+);
+
+-- Create extension_rate_tables table
+CREATE TABLE extension_rate_tables (
+    extension_id UUID PRIMARY KEY REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    rate_table_id UUID NOT NULL REFERENCES rate_tables(id) ON DELETE CASCADE
+);
+
+-- Create call_charges table. Prefix, description and currency are copied so
+-- invoices do not change when rates are edited, until calls are re-rated.
+CREATE TABLE call_charges (
+    call_id UUID PRIMARY KEY REFERENCES call_records(id) ON DELETE CASCADE,
+    extension_id UUID NOT NULL REFERENCES pbx_extensions(id) ON DELETE CASCADE,
+    rate_table_id UUID REFERENCES rate_tables(id) ON DELETE SET NULL,
+    rate_id UUID REFERENCES rates(id) ON DELETE SET NULL,
+    currency CHAR(3) NOT NULL,
+    prefix VARCHAR(20) NOT NULL,
+    description VARCHAR(100),
+    billed_seconds INTEGER NOT NULL,
+    cost NUMERIC(14, 6) NOT NULL,
+    rated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes
+CREATE UNIQUE INDEX idx_rate_tables_default ON rate_tables(is_default) WHERE is_default;
+CREATE INDEX idx_call_charges_extension ON call_charges(extension_id);
+
+-- Create triggers
+CREATE TRIGGER update_rate_tables_updated_at
+    BEFORE UPDATE ON rate_tables
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_rates_updated_at
+    BEFORE UPDATE ON rates
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
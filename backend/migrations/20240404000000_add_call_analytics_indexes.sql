@@ -0,0 +1,4 @@
+-- Analytics filter call records by start time and join them to extensions
+CREATE INDEX idx_call_records_start_time ON call_records(start_time);
+CREATE INDEX idx_call_records_caller_id ON call_records(caller_id);
+CREATE INDEX idx_call_records_recipient_id ON call_records(recipient_id);
@@ -0,0 +1,71 @@
+-- Create fax enums
+CREATE TYPE fax_job_status AS ENUM ('queued', 'sending', 'sent', 'failed', 'cancelled');
+CREATE TYPE fax_job_source AS ENUM ('upload', 'email');
+
+-- Create fax_lines table
+CREATE TABLE fax_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    did VARCHAR(20) UNIQUE NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    email VARCHAR(255),
+    station_id VARCHAR(20),
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_fax_did CHECK (did ~ '^\+?[0-9]{3,20}$')
+);
+
+-- Create inbound_faxes table
+CREATE TABLE inbound_faxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    line_id UUID REFERENCES fax_lines(id) ON DELETE SET NULL,
+    did VARCHAR(20) NOT NULL,
+    sender VARCHAR(50) NOT NULL,
+    remote_station_id VARCHAR(50),
+    pages INTEGER NOT NULL DEFAULT 0,
+    file_path VARCHAR(500) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    delivered_at TIMESTAMPTZ,
+    delivery_error TEXT,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_inbound_pages CHECK (pages >= 0)
+);
+
+-- Create fax_jobs table
+CREATE TABLE fax_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    line_id UUID NOT NULL REFERENCES fax_lines(id) ON DELETE CASCADE,
+    source fax_job_source NOT NULL,
+    destination VARCHAR(21) NOT NULL,
+    file_path VARCHAR(500) NOT NULL,
+    tiff_path VARCHAR(500) NOT NULL,
+    status fax_job_status NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 3,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    pages INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    CONSTRAINT valid_fax_destination CHECK (destination ~ '^\+?[0-9]{3,20}$'),
+    CONSTRAINT valid_fax_attempts CHECK (attempts >= 0 AND max_attempts BETWEEN 1 AND 10)
+);
+
+-- Create indexes
+CREATE INDEX idx_fax_lines_owner ON fax_lines(owner_id);
+CREATE INDEX idx_fax_lines_email ON fax_lines(LOWER(email));
+CREATE INDEX idx_inbound_faxes_line ON inbound_faxes(line_id, received_at DESC);
+CREATE INDEX idx_fax_jobs_line ON fax_jobs(line_id, created_at DESC);
+CREATE INDEX idx_fax_jobs_due ON fax_jobs(next_attempt_at) WHERE status = 'queued';
+
+-- Create triggers
+CREATE TRIGGER update_fax_lines_updated_at
+    BEFORE UPDATE ON fax_lines
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_fax_jobs_updated_at
+    BEFORE UPDATE ON fax_jobs
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,49 @@
+-- Create chat_conversation_kind enum
+CREATE TYPE chat_conversation_kind AS ENUM ('direct', 'group');
+
+-- Create chat_conversations table
+CREATE TABLE chat_conversations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind chat_conversation_kind NOT NULL,
+    name VARCHAR(100),
+    -- Both user ids in sorted order; keeps one direct conversation per pair
+    direct_key VARCHAR(73) UNIQUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    last_message_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_direct_key CHECK ((kind = 'direct') = (direct_key IS NOT NULL)),
+    CONSTRAINT valid_group_name CHECK (kind = 'direct' OR name IS NOT NULL)
+);
+
+-- Create chat_messages table
+CREATE TABLE chat_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    conversation_id UUID NOT NULL REFERENCES chat_conversations(id) ON DELETE CASCADE,
+    sender_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    body TEXT NOT NULL,
+    search_vector TSVECTOR GENERATED ALWAYS AS (to_tsvector('simple', body)) STORED,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_message_body CHECK (LENGTH(body) BETWEEN 1 AND 4000)
+);
+
+-- Create chat_members table
+CREATE TABLE chat_members (
+    conversation_id UUID NOT NULL REFERENCES chat_conversations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    last_read_message_id UUID REFERENCES chat_messages(id) ON DELETE SET NULL,
+    last_read_at TIMESTAMPTZ,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (conversation_id, user_id)
+);
+
+-- Create indexes
+CREATE INDEX idx_chat_members_user ON chat_members(user_id);
+CREATE INDEX idx_chat_messages_conversation ON chat_messages(conversation_id, created_at DESC, id DESC);
+CREATE INDEX idx_chat_messages_search ON chat_messages USING GIN (search_vector);
+
+-- Create triggers
+CREATE TRIGGER update_chat_conversations_updated_at
+    BEFORE UPDATE ON chat_conversations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,28 @@
+-- Create presence_status enum
+CREATE TYPE presence_status AS ENUM (
+    'available',
+    'on_the_phone',
+    'in_a_meeting',
+    'dnd',
+    'offline'
+);
+
+-- Create presence_overrides table
+-- A status set by hand; it replaces the derived one until it expires or is cleared
+CREATE TABLE presence_overrides (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    status presence_status NOT NULL,
+    note VARCHAR(255),
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes
+CREATE INDEX idx_presence_overrides_expires_at ON presence_overrides(expires_at);
+
+-- Create triggers
+CREATE TRIGGER update_presence_overrides_updated_at
+    BEFORE UPDATE ON presence_overrides
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
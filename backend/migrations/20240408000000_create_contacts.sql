@@ -0,0 +1,55 @@
+-- Create contact_label enum
+CREATE TYPE contact_label AS ENUM ('work', 'home', 'mobile', 'fax', 'other');
+
+-- Create contacts table
+-- Contacts without an owner form the shared company phonebook
+CREATE TABLE contacts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    display_name VARCHAR(200) NOT NULL,
+    first_name VARCHAR(100),
+    last_name VARCHAR(100),
+    organization VARCHAR(200),
+    title VARCHAR(100),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_display_name CHECK (LENGTH(TRIM(display_name)) > 0)
+);
+
+-- Create contact_numbers table
+CREATE TABLE contact_numbers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    contact_id UUID NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    label contact_label NOT NULL DEFAULT 'work',
+    number VARCHAR(40) NOT NULL,
+    -- The number as dialled, without formatting
+    digits VARCHAR(40) NOT NULL,
+    -- Set when the number could be normalized to E.164
+    e164 VARCHAR(16),
+    CONSTRAINT unique_contact_number_position UNIQUE (contact_id, position),
+    CONSTRAINT valid_contact_e164 CHECK (e164 IS NULL OR e164 ~ '^\+[1-9][0-9]{6,14}$')
+);
+
+-- Create contact_emails table
+CREATE TABLE contact_emails (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    contact_id UUID NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    label contact_label NOT NULL DEFAULT 'work',
+    email VARCHAR(255) NOT NULL,
+    CONSTRAINT unique_contact_email_position UNIQUE (contact_id, position)
+);
+
+-- Create indexes
+CREATE INDEX idx_contacts_owner ON contacts(owner_id);
+CREATE INDEX idx_contacts_display_name ON contacts(LOWER(display_name));
+CREATE INDEX idx_contact_numbers_e164 ON contact_numbers(e164);
+CREATE INDEX idx_contact_numbers_digits ON contact_numbers(digits);
+
+-- Create triggers
+CREATE TRIGGER update_contacts_updated_at
+    BEFORE UPDATE ON contacts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
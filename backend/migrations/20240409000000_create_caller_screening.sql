@@ -0,0 +1,69 @@
+-- Create caller screening enums
+CREATE TYPE screening_list AS ENUM (
+    'block',
+    'allow'
+);
+
+CREATE TYPE screening_match AS ENUM (
+    'exact',
+    'prefix',
+    'regex'
+);
+
+CREATE TYPE screening_action AS ENUM (
+    'reject',
+    'busy',
+    'voicemail',
+    'message'
+);
+
+-- Create caller_screening_entries table
+CREATE TABLE caller_screening_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    list screening_list NOT NULL DEFAULT 'block',
+    match_type screening_match NOT NULL DEFAULT 'exact',
+    pattern VARCHAR(100) NOT NULL,
+    description VARCHAR(200),
+    expires_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    call_id UUID REFERENCES call_records(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_screening_pattern CHECK (LENGTH(pattern) > 0)
+);
+
+-- What each inbound route does with blocked and anonymous callers
+ALTER TABLE inbound_routes
+    ADD COLUMN blocked_action screening_action NOT NULL DEFAULT 'reject',
+    ADD COLUMN blocked_destination VARCHAR(100),
+    ADD COLUMN reject_anonymous BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE inbound_routes
+    ADD CONSTRAINT valid_blocked_destination CHECK (
+        blocked_action IN ('reject', 'busy') OR blocked_destination IS NOT NULL
+    );
+
+-- Create caller_screening_log table
+CREATE TABLE caller_screening_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    call_id UUID REFERENCES call_records(id) ON DELETE CASCADE,
+    entry_id UUID REFERENCES caller_screening_entries(id) ON DELETE SET NULL,
+    route_id UUID REFERENCES inbound_routes(id) ON DELETE SET NULL,
+    caller_id VARCHAR(80) NOT NULL,
+    pattern VARCHAR(100),
+    anonymous BOOLEAN NOT NULL DEFAULT FALSE,
+    action screening_action NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes
+CREATE INDEX idx_caller_screening_entries_list ON caller_screening_entries(list, match_type);
+CREATE INDEX idx_caller_screening_log_call_id ON caller_screening_log(call_id);
+CREATE INDEX idx_caller_screening_log_entry_id ON caller_screening_log(entry_id, occurred_at);
+CREATE INDEX idx_caller_screening_log_occurred_at ON caller_screening_log(occurred_at);
+
+-- Create triggers
+CREATE TRIGGER update_caller_screening_entries_updated_at
+    BEFORE UPDATE ON caller_screening_entries
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
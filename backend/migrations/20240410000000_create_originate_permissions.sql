@@ -0,0 +1,8 @@
+-- Create originate_permissions table
+-- Users listed here may place click-to-call calls from extensions they own;
+-- admins need no entry
+CREATE TABLE originate_permissions (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
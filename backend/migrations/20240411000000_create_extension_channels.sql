@@ -0,0 +1,12 @@
+-- Create extension_channels table
+-- Live channels of extension phones, kept by the AMI call tracker; presence
+-- uses them because a call record only knows the first channel of a call
+CREATE TABLE extension_channels (
+    uniqueid VARCHAR(150) PRIMARY KEY,
+    extension_number VARCHAR(20) NOT NULL,
+    answered BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes
+CREATE INDEX idx_extension_channels_extension_number ON extension_channels(extension_number);
@@ -0,0 +1,8 @@
+-- Add email_token to fax_lines
+-- Email-to-fax messages name the line by this secret in the recipient
+-- address instead of the forgeable From header
+ALTER TABLE fax_lines
+    ADD COLUMN email_token VARCHAR(64) NOT NULL DEFAULT replace(gen_random_uuid()::text, '-', '');
+
+-- Create indexes
+CREATE UNIQUE INDEX idx_fax_lines_email_token ON fax_lines(email_token);
@@ -0,0 +1,9 @@
+-- Create internal_numbers view
+-- Every number dialled inside the PBX. Calls to these numbers are internal
+-- for both billing and call analytics.
+CREATE VIEW internal_numbers AS
+SELECT extension_number AS number FROM pbx_extensions
+UNION ALL
+SELECT group_number FROM ring_groups
+UNION ALL
+SELECT room_number FROM conference_rooms;
@@ -0,0 +1,7 @@
+-- Add spool_msg_id to voicemail_messages
+-- Messages recorded by Asterisk's VoiceMail() are imported from its spool;
+-- the msg_id from each message's .txt file keeps them from being imported twice
+ALTER TABLE voicemail_messages ADD COLUMN spool_msg_id VARCHAR(100);
+
+-- Create indexes
+CREATE UNIQUE INDEX idx_voicemail_messages_spool_msg_id ON voicemail_messages(mailbox_id, spool_msg_id);
@@ -0,0 +1,499 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig, AmiMessage},
+    error::AppError,
+    models::{
+        call_handling::FeatureCodeChange,
+        fax::{FaxAttemptResult, ReceivedFax},
+        pbx::CallStatus,
+        screening::{ScreeningAction, ScreeningDecision},
+    },
+    services::{
+        call_handling::{parse_feature_code, CallHandlingService},
+        conference::ConferenceService,
+        fax::FaxService,
+        pbx::PbxService,
+        recording::RecordingService,
+        screening::ScreeningService,
+        voicemail::VoicemailService,
+    },
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Q.850 hangup causes that matter for the final call status
+const CAUSE_USER_BUSY: u32 = 17;
+const CAUSE_NO_USER_RESPONSE: u32 = 18;
+const CAUSE_NO_ANSWER: u32 = 19;
+const CAUSE_CALL_REJECTED: u32 = 21;
+const CAUSE_NORMAL_CLEARING: u32 = 16;
+
+/// A call-level change derived from a raw AMI event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallEvent {
+    Started {
+        uniqueid: String,
+        caller_id: String,
+        recipient_id: String,
+    },
+    Answered {
+        uniqueids: Vec<String>,
+    },
+    Ended {
+        uniqueid: String,
+        cause: u32,
+    },
+    ConferenceJoined {
+        conference: String,
+        channel: String,
+        caller_id_num: Option<String>,
+        caller_id_name: Option<String>,
+        admin: bool,
+    },
+    ConferenceLeft {
+        conference: String,
+        channel: String,
+    },
+    ConferenceEnded {
+        conference: String,
+    },
+    CallHandlingChanged {
+        extension_number: String,
+        change: FeatureCodeChange,
+    },
+    FaxReceived(ReceivedFax),
+    FaxAttempted {
+        job_id: Uuid,
+        result: FaxAttemptResult,
+    },
+    CallScreened(ScreeningDecision),
+    /// Asterisk's `VoiceMail()` left new messages in a mailbox's spool.
+    VoicemailWaiting {
+        mailbox: String,
+    },
+}
+
+/// Maps AMI events onto call lifecycle changes. Only the first channel of a
+/// call (where Uniqueid equals Linkedid) creates and closes a call record;
+/// the other legs only contribute the answer signal.
+pub fn interpret_event(event: &AmiMessage) -> Option<CallEvent> {
+    match event.event()? {
+        "Newchannel" => {
+            let uniqueid = event.get("Uniqueid")?;
+            if event.get("Linkedid").is_some_and(|linkedid| linkedid != uniqueid) {
+                return None;
+            }
+            let caller_id = event
+                .get("CallerIDNum")
+                .filter(|num| !num.is_empty() && *num != "<unknown>")
+                .unwrap_or("unknown");
+            let recipient_id = event
+                .get("Exten")
+                .filter(|exten| !exten.is_empty())
+                .unwrap_or("s");
+            Some(CallEvent::Started {
+                uniqueid: uniqueid.to_string(),
+                caller_id: caller_id.to_string(),
+                recipient_id: recipient_id.to_string(),
+            })
+        }
+        // Asterisk 12+ reports each channel joining a bridge
+        "BridgeEnter" => {
+            let linkedid = event.get("Linkedid").or_else(|| event.get("Uniqueid"))?;
+            Some(CallEvent::Answered {
+                uniqueids: vec![linkedid.to_string()],
+            })
+        }
+        // Asterisk 11 and older report a single Bridge event per link
+        "Bridge" => {
+            if event.get("Bridgestate") != Some("Link") {
+                return None;
+            }
+            let uniqueids: Vec<String> = ["Uniqueid1", "Uniqueid2"]
+                .iter()
+                .filter_map(|key| event.get(key))
+                .map(str::to_string)
+                .collect();
+            (!uniqueids.is_empty()).then_some(CallEvent::Answered { uniqueids })
+        }
+        "Hangup" => {
+            let uniqueid = event.get("Uniqueid")?;
+            if event.get("Linkedid").is_some_and(|linkedid| linkedid != uniqueid) {
+                return None;
+            }
+            let cause = event
+                .get("Cause")
+                .and_then(|cause| cause.parse().ok())
+                .unwrap_or(CAUSE_NORMAL_CLEARING);
+            Some(CallEvent::Ended {
+                uniqueid: uniqueid.to_string(),
+                cause,
+            })
+        }
+        "ConfbridgeJoin" => {
+            let caller_id = |key: &str| {
+                event
+                    .get(key)
+                    .filter(|value| !value.is_empty() && *value != "<unknown>")
+                    .map(str::to_string)
+            };
+            Some(CallEvent::ConferenceJoined {
+                conference: event.get("Conference")?.to_string(),
+                channel: event.get("Channel")?.to_string(),
+                caller_id_num: caller_id("CallerIDNum"),
+                caller_id_name: caller_id("CallerIDName"),
+                admin: event.get("Admin").is_some_and(|admin| admin.eq_ignore_ascii_case("yes")),
+            })
+        }
+        "ConfbridgeLeave" => Some(CallEvent::ConferenceLeft {
+            conference: event.get("Conference")?.to_string(),
+            channel: event.get("Channel")?.to_string(),
+        }),
+        "ConfbridgeEnd" => Some(CallEvent::ConferenceEnded {
+            conference: event.get("Conference")?.to_string(),
+        }),
+        // Sent by the call handling feature codes in the generated dialplan
+        "UserEvent" if event.get("UserEvent") == Some("CallHandling") => {
+            Some(CallEvent::CallHandlingChanged {
+                extension_number: event.get("Extension")?.to_string(),
+                change: parse_feature_code(event.get("Setting")?, event.get("Value").unwrap_or_default())?,
+            })
+        }
+        // Sent by the fax-in context once ReceiveFAX returns; faxes without a
+        // single page leave nothing to store
+        "UserEvent" if event.get("UserEvent") == Some("FaxReceived") => {
+            let pages = event.get("Pages").and_then(|pages| pages.parse().ok()).unwrap_or(0);
+            if pages <= 0 {
+                return None;
+            }
+            let non_empty = |key: &str| event.get(key).map(str::trim).filter(|value| !value.is_empty());
+            Some(CallEvent::FaxReceived(ReceivedFax {
+                uniqueid: non_empty("FaxId")?.to_string(),
+                did: non_empty("DID")?.to_string(),
+                sender: non_empty("Sender").unwrap_or("unknown").to_string(),
+                remote_station_id: non_empty("Station").map(str::to_string),
+                pages,
+            }))
+        }
+        // Sent by the fax-out context after each outbound attempt. The hangup
+        // of a call that never reached SendFAX reports no status and is skipped.
+        "UserEvent" if event.get("UserEvent") == Some("FaxResult") => {
+            let status = event.get("Status").filter(|status| !status.is_empty())?;
+            let success = status == "SUCCESS";
+            Some(CallEvent::FaxAttempted {
+                job_id: event.get("Job")?.parse().ok()?,
+                result: FaxAttemptResult {
+                    success,
+                    error: event
+                        .get("Error")
+                        .filter(|error| !success && !error.is_empty())
+                        .map(str::to_string),
+                    pages: event.get("Pages").and_then(|pages| pages.parse().ok()),
+                },
+            })
+        }
+        // Sent by the caller-screening context before it drops a blocked call
+        "UserEvent" if event.get("UserEvent") == Some("CallScreening") => {
+            let action = match event.get("Action")? {
+                "reject" => ScreeningAction::Reject,
+                "busy" => ScreeningAction::Busy,
+                "voicemail" => ScreeningAction::Voicemail,
+                "message" => ScreeningAction::Message,
+                _ => return None,
+            };
+            Some(CallEvent::CallScreened(ScreeningDecision {
+                uniqueid: event.get("Call").filter(|call| !call.is_empty())?.to_string(),
+                entry_id: event.get("Entry").and_then(|id| id.parse().ok()),
+                route_id: event.get("Route").and_then(|id| id.parse().ok()),
+                caller_id: event
+                    .get("Caller")
+                    .map(str::trim)
+                    .filter(|caller| !caller.is_empty())
+                    .unwrap_or("anonymous")
+                    .to_string(),
+                action,
+            }))
+        }
+        "MessageWaiting" => {
+            // Every mailbox lives in the "default" voicemail context
+            let (mailbox, context) = event.get("Mailbox")?.split_once('@')?;
+            let waiting = event.get("Waiting")?;
+            let has_new = waiting != "0" && !waiting.eq_ignore_ascii_case("no");
+            if context != "default" || mailbox.is_empty() || !has_new {
+                return None;
+            }
+            Some(CallEvent::VoicemailWaiting {
+                mailbox: mailbox.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A change to a single channel of an extension's phone, on any leg of a
+/// call. Presence needs these because the call record only follows the
+/// first channel, which on an inbound call belongs to the trunk.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelEvent {
+    Opened {
+        uniqueid: String,
+        extension_number: String,
+    },
+    Answered {
+        uniqueid: String,
+    },
+    Closed {
+        uniqueid: String,
+    },
+}
+
+/// The extension behind a phone channel name such as `PJSIP/1001-0000002a`.
+pub fn channel_extension(channel: &str) -> Option<&str> {
+    let endpoint = channel
+        .strip_prefix("PJSIP/")
+        .or_else(|| channel.strip_prefix("SIP/"))
+        .or_else(|| channel.strip_prefix("IAX2/"))?;
+    let (extension, _) = endpoint.rsplit_once('-')?;
+    (!extension.is_empty()).then_some(extension)
+}
+
+pub fn interpret_channel_event(event: &AmiMessage) -> Option<ChannelEvent> {
+    let uniqueid = event.get("Uniqueid")?.to_string();
+    match event.event()? {
+        "Newchannel" => Some(ChannelEvent::Opened {
+            extension_number: channel_extension(event.get("Channel")?)?.to_string(),
+            uniqueid,
+        }),
+        // ChannelState 6 is Up
+        "Newstate" if event.get("ChannelState") == Some("6") => Some(ChannelEvent::Answered { uniqueid }),
+        "BridgeEnter" => Some(ChannelEvent::Answered { uniqueid }),
+        "Hangup" => Some(ChannelEvent::Closed { uniqueid }),
+        _ => None,
+    }
+}
+
+pub fn status_for_hangup(answered: bool, cause: u32) -> CallStatus {
+    if answered {
+        return CallStatus::Completed;
+    }
+    match cause {
+        CAUSE_USER_BUSY => CallStatus::Busy,
+        CAUSE_NORMAL_CLEARING | CAUSE_NO_USER_RESPONSE | CAUSE_NO_ANSWER | CAUSE_CALL_REJECTED => {
+            CallStatus::NoAnswer
+        }
+        _ => CallStatus::Failed,
+    }
+}
+
+/// Unique ids of the channels listed by `CoreShowChannels`. The linked id is
+/// included too, since a call record is keyed on its first channel.
+pub fn live_uniqueids(channels: &[AmiMessage]) -> Vec<String> {
+    let mut live: Vec<String> = channels
+        .iter()
+        .filter(|channel| channel.event() == Some("CoreShowChannel"))
+        .flat_map(|channel| [channel.get("Uniqueid"), channel.get("Linkedid")])
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    live.sort();
+    live.dedup();
+    live
+}
+
+/// Long-running task that keeps an AMI session open and mirrors channel
+/// events into `call_records`, conference events into the attendance history,
+/// feature code changes into the call handling settings, fax results into
+/// the fax tables, blocked calls into the screening log and recorded
+/// voicemail into the mailboxes.
+pub struct AmiCallTracker {
+    service: PbxService,
+    recordings: RecordingService,
+    conferences: ConferenceService,
+    call_handling: CallHandlingService,
+    fax: FaxService,
+    screening: ScreeningService,
+    voicemail: VoicemailService,
+    config: AmiConfig,
+}
+
+impl AmiCallTracker {
+    pub fn new(pool: PgPool, config: AmiConfig) -> Self {
+        Self {
+            service: PbxService::new(pool.clone()),
+            recordings: RecordingService::new(pool.clone()),
+            conferences: ConferenceService::new(pool.clone()),
+            call_handling: CallHandlingService::new(pool.clone()),
+            fax: FaxService::new(pool.clone()),
+            screening: ScreeningService::new(pool.clone()),
+            voicemail: VoicemailService::new(pool),
+            config,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut backoff = INITIAL_BACKOFF;
+        // Until the first session, calls left active could have ended any time
+        let mut last_listening = Utc::now();
+        loop {
+            match AmiClient::connect(&self.config).await {
+                Ok(mut client) => {
+                    tracing::info!("Connected to AMI at {}:{}", self.config.host, self.config.port);
+                    backoff = INITIAL_BACKOFF;
+                    // Asterisk may have restarted with an empty AstDB
+                    if let Err(e) = self.call_handling.sync_to_asterisk().await {
+                        tracing::warn!("Failed to sync call handling settings: {}", e);
+                    }
+                    if let Err(e) = self.screening.sync_to_asterisk().await {
+                        tracing::warn!("Failed to sync caller screening entries: {}", e);
+                    }
+                    // Hangups may have been missed while disconnected
+                    if let Err(e) = self.service.clear_extension_channels().await {
+                        tracing::warn!("Failed to clear extension channels: {}", e);
+                    }
+                    if let Err(e) = self.reconcile_calls(&mut client, last_listening).await {
+                        tracing::warn!("Failed to reconcile active calls: {}", e);
+                    }
+                    // So may MessageWaiting events for new voicemail
+                    if let Err(e) = self.voicemail.import_all_spools().await {
+                        tracing::warn!("Failed to import voicemail: {}", e);
+                    }
+                    if let Err(e) = self.consume(client).await {
+                        tracing::warn!("AMI session ended: {}", e);
+                    }
+                    last_listening = Utc::now();
+                }
+                Err(e) => tracing::warn!("AMI connection failed: {}", e),
+            }
+
+            tracing::info!("Reconnecting to AMI in {}s", backoff.as_secs());
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Ends calls still recorded as active whose channels Asterisk no longer
+    /// has. Events that arrive meanwhile are kept for `consume`.
+    async fn reconcile_calls(
+        &self,
+        client: &mut AmiClient,
+        last_listening: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let channels = client.list_action("CoreShowChannels", &[]).await?;
+        let live = live_uniqueids(&channels);
+        let ended = self.service.end_missing_channel_calls(&live, last_listening).await?;
+        if ended > 0 {
+            tracing::info!("Ended {} calls whose hangup was missed", ended);
+        }
+        Ok(())
+    }
+
+    async fn consume(&self, mut client: AmiClient) -> Result<(), AppError> {
+        loop {
+            let event = client.next_event().await?;
+            if let Some(call_event) = interpret_event(&event) {
+                // A failed write must not tear down the session
+                if let Err(e) = self.apply(call_event).await {
+                    tracing::error!("Failed to record AMI call event: {}", e);
+                }
+            }
+            if let Some(channel_event) = interpret_channel_event(&event) {
+                if let Err(e) = self.apply_channel(channel_event).await {
+                    tracing::error!("Failed to record AMI channel event: {}", e);
+                }
+            }
+        }
+    }
+
+    pub async fn apply(&self, event: CallEvent) -> Result<(), AppError> {
+        match event {
+            CallEvent::Started {
+                uniqueid,
+                caller_id,
+                recipient_id,
+            } => {
+                self.service
+                    .start_channel_call(&uniqueid, &caller_id, &recipient_id, Utc::now())
+                    .await?;
+            }
+            CallEvent::Answered { uniqueids } => {
+                self.service.mark_channel_call_answered(&uniqueids, Utc::now()).await?;
+            }
+            CallEvent::Ended { uniqueid, cause } => {
+                let answered = self.service.is_channel_call_answered(&uniqueid).await?;
+                self.service
+                    .end_channel_call(&uniqueid, Utc::now(), status_for_hangup(answered, cause))
+                    .await?;
+                self.recordings.attach_recording(&uniqueid).await?;
+            }
+            CallEvent::ConferenceJoined {
+                conference,
+                channel,
+                caller_id_num,
+                caller_id_name,
+                admin,
+            } => {
+                self.conferences
+                    .record_join(
+                        &conference,
+                        &channel,
+                        caller_id_num.as_deref(),
+                        caller_id_name.as_deref(),
+                        admin,
+                        Utc::now(),
+                    )
+                    .await?;
+            }
+            CallEvent::ConferenceLeft { conference, channel } => {
+                self.conferences.record_leave(&conference, &channel, Utc::now()).await?;
+            }
+            CallEvent::ConferenceEnded { conference } => {
+                self.conferences.record_end(&conference, Utc::now()).await?;
+            }
+            CallEvent::CallHandlingChanged {
+                extension_number,
+                change,
+            } => {
+                self.call_handling.apply_feature_code(&extension_number, change).await?;
+            }
+            CallEvent::FaxReceived(received) => {
+                self.fax.receive(received).await?;
+            }
+            CallEvent::FaxAttempted { job_id, result } => {
+                self.fax.record_attempt(job_id, result).await?;
+            }
+            CallEvent::CallScreened(decision) => {
+                self.screening.record_decision(decision).await?;
+            }
+            CallEvent::VoicemailWaiting { mailbox } => {
+                self.voicemail.import_spool(&mailbox).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn apply_channel(&self, event: ChannelEvent) -> Result<(), AppError> {
+        match event {
+            ChannelEvent::Opened {
+                uniqueid,
+                extension_number,
+            } => {
+                self.service.open_extension_channel(&uniqueid, &extension_number).await?;
+            }
+            ChannelEvent::Answered { uniqueid } => {
+                self.service.mark_extension_channel_answered(&uniqueid).await?;
+            }
+            ChannelEvent::Closed { uniqueid } => {
+                self.service.close_extension_channel(&uniqueid).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
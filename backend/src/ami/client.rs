@@ -0,0 +1,313 @@
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+};
+
+use crate::error::AppError;
+
+/// How long connecting, logging in or waiting for an action may take, so a
+/// stalled Asterisk can't hang the request that needed it.
+const AMI_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn with_timeout<T>(what: &str, future: impl Future<Output = Result<T, AppError>>) -> Result<T, AppError> {
+    tokio::time::timeout(AMI_TIMEOUT, future)
+        .await
+        .map_err(|_| AppError::Internal(format!("AMI {} timed out", what)))?
+}
+
+#[derive(Debug, Clone)]
+pub struct AmiConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub secret: String,
+}
+
+impl AmiConfig {
+    /// Returns `None` when AMI is not configured, so callers can treat it as disabled.
+    pub fn from_env() -> Option<Self> {
+        let host = std::env::var("AMI_HOST").ok()?;
+        let port = std::env::var("AMI_PORT")
+            .ok()
+            .map(|port| port.parse::<u16>().expect("AMI_PORT must be a number"))
+            .unwrap_or(5038);
+        let username = std::env::var("AMI_USERNAME").expect("AMI_USERNAME must be set");
+        let secret = std::env::var("AMI_SECRET").expect("AMI_SECRET must be set");
+
+        Some(Self {
+            host,
+            port,
+            username,
+            secret,
+        })
+    }
+}
+
+/// A single AMI packet: an ordered list of `Key: Value` lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AmiMessage {
+    pub fields: Vec<(String, String)>,
+}
+
+impl AmiMessage {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn event(&self) -> Option<&str> {
+        self.get("Event")
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self.get("Response"), Some(response) if response.eq_ignore_ascii_case("Success"))
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in &self.fields {
+            out.push_str(key);
+            out.push_str(": ");
+            out.push_str(value);
+            out.push_str("\r\n");
+        }
+        out.push_str("\r\n");
+        out
+    }
+}
+
+pub struct AmiClient {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    next_action_id: u64,
+    pending_events: VecDeque<AmiMessage>,
+}
+
+impl AmiClient {
+    /// Connects and logs in. Only call-related and user events are requested.
+    pub async fn connect(config: &AmiConfig) -> Result<Self, AppError> {
+        let stream = with_timeout("connection", async {
+            TcpStream::connect((config.host.as_str(), config.port))
+                .await
+                .map_err(|e| AppError::Internal(format!("AMI connection error: {}", e)))
+        })
+        .await?;
+        let (read_half, write_half) = stream.into_split();
+        let mut client = Self {
+            reader: BufReader::new(read_half),
+            writer: write_half,
+            next_action_id: 1,
+            pending_events: VecDeque::new(),
+        };
+
+        // The server greets with a single banner line, e.g. "Asterisk Call Manager/5.0.1"
+        let mut banner = String::new();
+        with_timeout("banner", async {
+            client
+                .reader
+                .read_line(&mut banner)
+                .await
+                .map_err(|e| AppError::Internal(format!("AMI read error: {}", e)))
+        })
+        .await?;
+        if !banner.starts_with("Asterisk Call Manager") {
+            return Err(AppError::Internal(format!(
+                "Unexpected AMI banner: {}",
+                banner.trim()
+            )));
+        }
+
+        let response = client
+            .send_action(
+                "Login",
+                &[
+                    ("Username", config.username.as_str()),
+                    ("Secret", config.secret.as_str()),
+                    ("Events", "call,user"),
+                ],
+            )
+            .await?;
+        if !response.is_success() {
+            return Err(AppError::Auth(format!(
+                "AMI login failed: {}",
+                response.get("Message").unwrap_or("no message")
+            )));
+        }
+
+        Ok(client)
+    }
+
+    /// Opens a short-lived session, sends a single action and logs off. Used by
+    /// request handlers that need to push a change to Asterisk.
+    pub async fn run_action(
+        config: &AmiConfig,
+        action: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<AmiMessage, AppError> {
+        let mut client = Self::connect(config).await?;
+        let response = client.send_action(action, fields).await?;
+        if let Err(e) = client.logoff().await {
+            tracing::debug!("AMI logoff failed: {}", e);
+        }
+
+        if !response.is_success() {
+            return Err(AppError::Internal(format!(
+                "AMI {} failed: {}",
+                action,
+                response.get("Message").unwrap_or("no message")
+            )));
+        }
+
+        Ok(response)
+    }
+
+    /// Like `run_action`, for list actions such as `PJSIPShowRegistrationsOutbound`
+    /// that answer with a series of events. Returns the events, without the
+    /// closing `EventList: Complete` event.
+    pub async fn run_list_action(
+        config: &AmiConfig,
+        action: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<Vec<AmiMessage>, AppError> {
+        let mut client = Self::connect(config).await?;
+        let events = client.list_action(action, fields).await?;
+        if let Err(e) = client.logoff().await {
+            tracing::debug!("AMI logoff failed: {}", e);
+        }
+
+        Ok(events)
+    }
+
+    /// Runs a list action on this session. Unrelated events that arrive
+    /// meanwhile stay queued for `next_event`.
+    pub async fn list_action(
+        &mut self,
+        action: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<Vec<AmiMessage>, AppError> {
+        let response = self.send_action(action, fields).await?;
+        if !response.is_success() {
+            return Err(AppError::Internal(format!(
+                "AMI {} failed: {}",
+                action,
+                response.get("Message").unwrap_or("no message")
+            )));
+        }
+        let action_id = response.get("ActionID").unwrap_or_default().to_string();
+
+        let mut events = Vec::new();
+        let mut unrelated = Vec::new();
+        let result = with_timeout(action, async {
+            loop {
+                let event = match self.pending_events.pop_front() {
+                    Some(event) => event,
+                    None => self.read_message().await?,
+                };
+                if event.event().is_none() {
+                    continue;
+                }
+                if event.get("ActionID") != Some(action_id.as_str()) {
+                    unrelated.push(event);
+                    continue;
+                }
+                if matches!(event.get("EventList"), Some(state) if state.eq_ignore_ascii_case("Complete")) {
+                    return Ok(());
+                }
+                events.push(event);
+            }
+        })
+        .await;
+        self.pending_events.extend(unrelated);
+        result?;
+
+        Ok(events)
+    }
+
+    /// Sends an action and waits for its response. Events that arrive in the
+    /// meantime are queued for `next_event`.
+    pub async fn send_action(
+        &mut self,
+        action: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<AmiMessage, AppError> {
+        let action_id = format!("oriontel-{}", self.next_action_id);
+        self.next_action_id += 1;
+
+        let mut message = AmiMessage::default();
+        message.fields.push(("Action".into(), action.into()));
+        message.fields.push(("ActionID".into(), action_id.clone()));
+        for (key, value) in fields {
+            // Values are single-line by protocol; drop anything that could start a new field
+            let value: String = value.chars().filter(|c| *c != '\r' && *c != '\n').collect();
+            message.fields.push((key.to_string(), value));
+        }
+
+        with_timeout(action, async {
+            self.writer
+                .write_all(message.serialize().as_bytes())
+                .await
+                .map_err(|e| AppError::Internal(format!("AMI write error: {}", e)))?;
+
+            loop {
+                let incoming = self.read_message().await?;
+                if incoming.get("Response").is_some() && incoming.get("ActionID") == Some(action_id.as_str()) {
+                    return Ok(incoming);
+                }
+                if incoming.event().is_some() {
+                    self.pending_events.push_back(incoming);
+                }
+            }
+        })
+        .await
+    }
+
+    pub async fn next_event(&mut self) -> Result<AmiMessage, AppError> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(event);
+        }
+        loop {
+            let message = self.read_message().await?;
+            if message.event().is_some() {
+                return Ok(message);
+            }
+        }
+    }
+
+    pub async fn logoff(mut self) -> Result<(), AppError> {
+        self.send_action("Logoff", &[]).await?;
+        Ok(())
+    }
+
+    async fn read_message(&mut self) -> Result<AmiMessage, AppError> {
+        let mut message = AmiMessage::default();
+        loop {
+            let mut line = String::new();
+            let read = self
+                .reader
+                .read_line(&mut line)
+                .await
+                .map_err(|e| AppError::Internal(format!("AMI read error: {}", e)))?;
+            if read == 0 {
+                return Err(AppError::Internal("AMI connection closed".into()));
+            }
+
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                if message.fields.is_empty() {
+                    continue;
+                }
+                return Ok(message);
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                message.fields.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod call_tracker;
+pub mod client;
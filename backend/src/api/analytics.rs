@@ -0,0 +1,33 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::auth::require_auth,
+    models::analytics::{AnalyticsQuery, CallAnalytics},
+    services::analytics::AnalyticsService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/analytics/calls",
+            get(call_analytics)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+async fn call_analytics(
+    State(pool): State<PgPool>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<CallAnalytics>, AppError> {
+    let service = AnalyticsService::new(pool);
+    let analytics = service
+        .call_analytics(query.from, query.to, query.tz.as_deref())
+        .await?;
+    Ok(Json(analytics))
+}
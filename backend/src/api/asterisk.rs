@@ -0,0 +1,43 @@
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::auth::require_admin,
+    models::asterisk::{ApplyConfigResponse, ConfigPreview},
+    services::asterisk_config::AsteriskConfigService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/pbx/config/preview",
+            get(preview_config)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/pbx/config/apply",
+            post(apply_config)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+async fn preview_config(
+    State(pool): State<PgPool>,
+) -> Result<Json<ConfigPreview>, AppError> {
+    let service = AsteriskConfigService::new(pool);
+    let preview = service.preview().await?;
+    Ok(Json(preview))
+}
+
+async fn apply_config(
+    State(pool): State<PgPool>,
+) -> Result<Json<ApplyConfigResponse>, AppError> {
+    let service = AsteriskConfigService::new(pool);
+    let response = service.apply().await?;
+    Ok(Json(response))
+}
@@ -0,0 +1,202 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        billing::{
+            AssignRateTableRequest, CreateRateRequest, CreateRateTableRequest, Invoice, InvoiceQuery, Rate,
+            RateCallsRequest, RateTable, RatingResult, UpdateRateRequest, UpdateRateTableRequest,
+        },
+    },
+    services::{billing::BillingService, pbx::PbxService},
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/billing/rate-tables",
+            post(create_rate_table)
+                .get(list_rate_tables)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/rate-tables/:id",
+            get(get_rate_table)
+                .put(update_rate_table)
+                .delete(delete_rate_table)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/rate-tables/:id/rates",
+            post(create_rate)
+                .get(list_rates)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/rates/:id",
+            put(update_rate)
+                .delete(delete_rate)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/extensions/:id/rate-table",
+            put(assign_rate_table)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/rate",
+            post(rate_calls)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/invoices",
+            get(list_invoices)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/billing/extensions/:id/invoices",
+            get(extension_invoices)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+async fn create_rate_table(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateRateTableRequest>,
+) -> Result<Json<RateTable>, AppError> {
+    request.validate()?;
+    let service = BillingService::new(pool);
+    let table = service.create_rate_table(request).await?;
+    Ok(Json(table))
+}
+
+async fn list_rate_tables(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<RateTable>>, AppError> {
+    let service = BillingService::new(pool);
+    let tables = service.list_rate_tables().await?;
+    Ok(Json(tables))
+}
+
+async fn get_rate_table(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RateTable>, AppError> {
+    let service = BillingService::new(pool);
+    let table = service.get_rate_table(id).await?;
+    Ok(Json(table))
+}
+
+async fn update_rate_table(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateRateTableRequest>,
+) -> Result<Json<RateTable>, AppError> {
+    request.validate()?;
+    let service = BillingService::new(pool);
+    let table = service.update_rate_table(id, request).await?;
+    Ok(Json(table))
+}
+
+async fn delete_rate_table(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = BillingService::new(pool);
+    service.delete_rate_table(id).await?;
+    Ok(())
+}
+
+async fn create_rate(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateRateRequest>,
+) -> Result<Json<Rate>, AppError> {
+    request.validate()?;
+    let service = BillingService::new(pool);
+    let rate = service.create_rate(id, request).await?;
+    Ok(Json(rate))
+}
+
+async fn list_rates(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<Rate>>, AppError> {
+    let service = BillingService::new(pool);
+    let rates = service.list_rates(id).await?;
+    Ok(Json(rates))
+}
+
+async fn update_rate(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateRateRequest>,
+) -> Result<Json<Rate>, AppError> {
+    request.validate()?;
+    let service = BillingService::new(pool);
+    let rate = service.update_rate(id, request).await?;
+    Ok(Json(rate))
+}
+
+async fn delete_rate(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = BillingService::new(pool);
+    service.delete_rate(id).await?;
+    Ok(())
+}
+
+async fn assign_rate_table(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AssignRateTableRequest>,
+) -> Result<(), AppError> {
+    let service = BillingService::new(pool);
+    service.assign_rate_table(id, request).await?;
+    Ok(())
+}
+
+async fn rate_calls(
+    State(pool): State<PgPool>,
+    Json(request): Json<RateCallsRequest>,
+) -> Result<Json<RatingResult>, AppError> {
+    let service = BillingService::new(pool);
+    let result = service.rate_calls(request.from, request.to, request.rerate).await?;
+    Ok(Json(result))
+}
+
+async fn list_invoices(
+    State(pool): State<PgPool>,
+    Query(query): Query<InvoiceQuery>,
+) -> Result<Json<Vec<Invoice>>, AppError> {
+    let service = BillingService::new(pool);
+    let invoices = service.invoices(query.from, query.to, None).await?;
+    Ok(Json(invoices))
+}
+
+/// Available to admins and the extension's owner.
+async fn extension_invoices(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InvoiceQuery>,
+) -> Result<Json<Vec<Invoice>>, AppError> {
+    let extension = PbxService::new(pool.clone()).get_extension(id).await?;
+    if auth_user.role != UserRole::Admin && extension.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+
+    let service = BillingService::new(pool);
+    let invoices = service.invoices(query.from, query.to, Some(id)).await?;
+    Ok(Json(invoices))
+}
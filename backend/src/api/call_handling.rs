@@ -0,0 +1,61 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        call_handling::{CallHandlingSettings, SetCallHandlingRequest},
+    },
+    services::{call_handling::CallHandlingService, pbx::PbxService},
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/extensions/:id/call-handling",
+            get(get_call_handling)
+                .put(set_call_handling)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+/// Call handling belongs to the extension's owner; admins can manage any extension.
+async fn ensure_extension_access(pool: &PgPool, auth_user: &AuthUser, id: Uuid) -> Result<(), AppError> {
+    let extension = PbxService::new(pool.clone()).get_extension(id).await?;
+    if auth_user.role != UserRole::Admin && extension.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(())
+}
+
+async fn get_call_handling(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CallHandlingSettings>, AppError> {
+    ensure_extension_access(&pool, &auth_user, id).await?;
+    let service = CallHandlingService::new(pool);
+    let settings = service.get_settings(id).await?;
+    Ok(Json(settings))
+}
+
+async fn set_call_handling(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetCallHandlingRequest>,
+) -> Result<Json<CallHandlingSettings>, AppError> {
+    request.validate()?;
+    ensure_extension_access(&pool, &auth_user, id).await?;
+    let service = CallHandlingService::new(pool);
+    let settings = service.set_settings(id, request).await?;
+    Ok(Json(settings))
+}
@@ -0,0 +1,72 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth},
+    models::cdr::{CdrExportQuery, CdrImportResult},
+    services::cdr::CdrService,
+};
+
+/// Master.csv files from busy systems easily exceed axum's 2 MB default.
+const IMPORT_BODY_LIMIT: usize = 100 * 1024 * 1024;
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/calls/export",
+            get(export_calls)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/calls/import",
+            post(import_calls)
+                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT))
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+async fn export_calls(
+    State(pool): State<PgPool>,
+    Query(query): Query<CdrExportQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from >= to {
+            return Err(AppError::Validation("'from' must be before 'to'".into()));
+        }
+    }
+
+    let service = CdrService::new(pool);
+    let filename = format!("calls-{}.csv", Utc::now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(service.export_csv(query)),
+    ))
+}
+
+async fn import_calls(
+    State(pool): State<PgPool>,
+    body: Bytes,
+) -> Result<Json<CdrImportResult>, AppError> {
+    if body.is_empty() {
+        return Err(AppError::Validation("Request body is empty".into()));
+    }
+
+    let service = CdrService::new(pool);
+    let result = service.import_master_csv(&body).await?;
+    Ok(Json(result))
+}
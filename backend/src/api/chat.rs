@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap},
+    response::Response,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use futures::{SinkExt, StreamExt};
+use sqlx::PgPool;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{decode_token, require_auth, AuthUser},
+    models::chat::{
+        AddMemberRequest, ChatClientEvent, ChatMessage, ChatSearchPage, ChatSearchQuery,
+        ChatServerEvent, ChatSocketQuery, ConversationResponse, CreateConversationRequest,
+        MarkReadRequest, MessageHistoryQuery, MessagePage, SendMessageRequest,
+    },
+    services::{
+        chat::{ChatHub, ChatService},
+        presence::PresenceService,
+    },
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/chat/conversations",
+            post(create_conversation)
+                .get(list_conversations)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/chat/conversations/:id",
+            get(get_conversation)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/chat/conversations/:id/members",
+            post(add_member)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/chat/conversations/:id/members/:user_id",
+            delete(remove_member)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/chat/conversations/:id/messages",
+            get(history)
+                .post(send_message)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/chat/conversations/:id/read",
+            post(mark_read)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/chat/search",
+            get(search)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        // Authenticates itself: browsers can only pass the token in the query
+        .route("/chat/ws", get(socket))
+}
+
+// Conversations
+async fn create_conversation(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateConversationRequest>,
+) -> Result<Json<ConversationResponse>, AppError> {
+    request.validate()?;
+    let service = ChatService::new(pool, hub);
+    let conversation = service.create_conversation(auth_user.user_id, request).await?;
+    Ok(Json(conversation))
+}
+
+async fn list_conversations(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<ConversationResponse>>, AppError> {
+    let service = ChatService::new(pool, hub);
+    let conversations = service.list_conversations(auth_user.user_id).await?;
+    Ok(Json(conversations))
+}
+
+async fn get_conversation(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConversationResponse>, AppError> {
+    let service = ChatService::new(pool, hub);
+    let conversation = service.conversation_for(id, auth_user.user_id).await?;
+    Ok(Json(conversation))
+}
+
+async fn add_member(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AddMemberRequest>,
+) -> Result<Json<ConversationResponse>, AppError> {
+    let service = ChatService::new(pool, hub);
+    let conversation = service.add_member(id, auth_user.user_id, request.user_id).await?;
+    Ok(Json(conversation))
+}
+
+async fn remove_member(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<(), AppError> {
+    let service = ChatService::new(pool, hub);
+    service.remove_member(id, auth_user.user_id, user_id).await?;
+    Ok(())
+}
+
+// Messages
+async fn history(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<MessageHistoryQuery>,
+) -> Result<Json<MessagePage>, AppError> {
+    let service = ChatService::new(pool, hub);
+    let page = service
+        .history(id, auth_user.user_id, query.before, query.limit)
+        .await?;
+    Ok(Json(page))
+}
+
+async fn send_message(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SendMessageRequest>,
+) -> Result<Json<ChatMessage>, AppError> {
+    request.validate()?;
+    let service = ChatService::new(pool, hub);
+    let message = service.send_message(id, auth_user.user_id, &request.body).await?;
+    Ok(Json(message))
+}
+
+async fn mark_read(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<MarkReadRequest>,
+) -> Result<(), AppError> {
+    let service = ChatService::new(pool, hub);
+    service.mark_read(id, auth_user.user_id, request.message_id).await?;
+    Ok(())
+}
+
+async fn search(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Query(query): Query<ChatSearchQuery>,
+) -> Result<Json<ChatSearchPage>, AppError> {
+    query.validate()?;
+    let service = ChatService::new(pool, hub);
+    let page = service
+        .search(auth_user.user_id, &query.q, query.conversation_id, query.before, query.limit)
+        .await?;
+    Ok(Json(page))
+}
+
+// Real-time delivery
+async fn socket(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    Query(query): Query<ChatSocketQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let token = query.token.or_else(|| {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string)
+    });
+    let auth_user = decode_token(&token.ok_or_else(|| AppError::Auth("Missing token".into()))?)?;
+
+    Ok(ws.on_upgrade(move |socket| run_socket(socket, pool, hub, auth_user)))
+}
+
+fn frame(event: &ChatServerEvent) -> Option<Message> {
+    serde_json::to_string(event).ok().map(Message::Text)
+}
+
+/// The presence changes a connection asked for.
+enum PresenceWatch {
+    None,
+    All,
+    Users(HashSet<Uuid>),
+}
+
+impl PresenceWatch {
+    fn includes(&self, user_id: Uuid) -> bool {
+        match self {
+            PresenceWatch::None => false,
+            PresenceWatch::All => true,
+            PresenceWatch::Users(user_ids) => user_ids.contains(&user_id),
+        }
+    }
+}
+
+/// Handles a frame from the client. Presence subscriptions belong to the
+/// connection; everything else goes to the chat service.
+async fn handle_frame(
+    text: &str,
+    chat: &ChatService,
+    presence: &PresenceService,
+    watch: &mut PresenceWatch,
+    auth_user: &AuthUser,
+) -> Result<Option<ChatServerEvent>, AppError> {
+    let event = serde_json::from_str::<ChatClientEvent>(text)
+        .map_err(|e| AppError::Validation(format!("Invalid frame: {}", e)))?;
+
+    match event {
+        ChatClientEvent::SubscribePresence { user_ids } => {
+            let snapshot = if user_ids.is_empty() {
+                *watch = PresenceWatch::All;
+                presence.presence(None).await?
+            } else {
+                let snapshot = presence.presence(Some(&user_ids)).await?;
+                *watch = PresenceWatch::Users(user_ids.into_iter().collect());
+                snapshot
+            };
+            Ok(Some(ChatServerEvent::PresenceSnapshot { presence: snapshot }))
+        }
+        ChatClientEvent::UnsubscribePresence => {
+            *watch = PresenceWatch::None;
+            Ok(None)
+        }
+        event => {
+            chat.handle_client_event(auth_user.user_id, &auth_user.username, event)
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+async fn run_socket(socket: WebSocket, pool: PgPool, hub: ChatHub, auth_user: AuthUser) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut deliveries = hub.subscribe();
+    let chat = ChatService::new(pool.clone(), hub.clone());
+    let presence = PresenceService::new(pool, hub.clone());
+    let mut watch = PresenceWatch::None;
+
+    hub.connect(auth_user.user_id);
+    if let Err(e) = presence.refresh(Some(&[auth_user.user_id])).await {
+        tracing::warn!("Failed to refresh presence of {}: {}", auth_user.username, e);
+    }
+
+    loop {
+        let outgoing = tokio::select! {
+            incoming = receiver.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    match handle_frame(&text, &chat, &presence, &mut watch, &auth_user).await {
+                        Ok(reply) => reply.as_ref().and_then(frame),
+                        Err(e) => frame(&ChatServerEvent::Error { message: e.to_string() }),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum; binary frames are not part of the protocol
+                Some(Ok(_)) => None,
+            },
+            delivery = deliveries.recv() => match delivery {
+                Ok(delivery) if delivery.recipients.contains(&auth_user.user_id) => match &delivery.event {
+                    ChatServerEvent::Presence { presence } if !watch.includes(presence.user_id) => None,
+                    event => frame(event),
+                },
+                Ok(_) => None,
+                Err(RecvError::Lagged(missed)) => frame(&ChatServerEvent::Error {
+                    message: format!("Missed {} events; reload the conversations", missed),
+                }),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if let Some(message) = outgoing {
+            if sender.send(message).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    hub.disconnect(auth_user.user_id);
+    if let Err(e) = presence.refresh(Some(&[auth_user.user_id])).await {
+        tracing::warn!("Failed to refresh presence of {}: {}", auth_user.username, e);
+    }
+    tracing::debug!("Chat socket closed for {}", auth_user.username);
+}
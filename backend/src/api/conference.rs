@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        conference::{
+            ConferenceAttendance, ConferenceHistoryQuery, ConferenceParticipant, ConferenceRoom,
+            CreateConferenceRoomRequest, ParticipantActionRequest, UpdateConferenceRoomRequest,
+        },
+    },
+    services::conference::ConferenceService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/conferences",
+            post(create_room)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/conferences",
+            get(list_rooms)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/conferences/:id",
+            get(get_room)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/conferences/:id",
+            put(update_room)
+                .delete(delete_room)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/conferences/:id/participants",
+            get(list_participants)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/conferences/:id/participants/mute",
+            post(mute_participant)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/conferences/:id/participants/unmute",
+            post(unmute_participant)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/conferences/:id/participants/kick",
+            post(kick_participant)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/conferences/:id/history",
+            get(list_history)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+/// Participants are visible to and controlled by the room owner and admins.
+async fn controllable_room(
+    service: &ConferenceService,
+    auth_user: &AuthUser,
+    id: Uuid,
+) -> Result<ConferenceRoom, AppError> {
+    let room = service.get_room(id).await?;
+    if auth_user.role != UserRole::Admin && room.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(room)
+}
+
+async fn create_room(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateConferenceRoomRequest>,
+) -> Result<Json<ConferenceRoom>, AppError> {
+    request.validate()?;
+    let service = ConferenceService::new(pool);
+    let room = service.create_room(request).await?;
+    Ok(Json(room))
+}
+
+async fn list_rooms(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<ConferenceRoom>>, AppError> {
+    let service = ConferenceService::new(pool);
+    let rooms = service.list_rooms().await?;
+    Ok(Json(rooms))
+}
+
+async fn get_room(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConferenceRoom>, AppError> {
+    let service = ConferenceService::new(pool);
+    let room = service.get_room(id).await?;
+    Ok(Json(room))
+}
+
+async fn update_room(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateConferenceRoomRequest>,
+) -> Result<Json<ConferenceRoom>, AppError> {
+    request.validate()?;
+    let service = ConferenceService::new(pool);
+    let room = service.update_room(id, request).await?;
+    Ok(Json(room))
+}
+
+async fn delete_room(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = ConferenceService::new(pool);
+    service.delete_room(id).await?;
+    Ok(())
+}
+
+async fn list_participants(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ConferenceParticipant>>, AppError> {
+    let service = ConferenceService::new(pool);
+    let room = controllable_room(&service, &auth_user, id).await?;
+    let participants = service.list_participants(&room).await?;
+    Ok(Json(participants))
+}
+
+async fn mute_participant(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ParticipantActionRequest>,
+) -> Result<(), AppError> {
+    request.validate()?;
+    let service = ConferenceService::new(pool);
+    let room = controllable_room(&service, &auth_user, id).await?;
+    service.mute_participant(&room, &request.channel, true).await
+}
+
+async fn unmute_participant(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ParticipantActionRequest>,
+) -> Result<(), AppError> {
+    request.validate()?;
+    let service = ConferenceService::new(pool);
+    let room = controllable_room(&service, &auth_user, id).await?;
+    service.mute_participant(&room, &request.channel, false).await
+}
+
+async fn kick_participant(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ParticipantActionRequest>,
+) -> Result<(), AppError> {
+    request.validate()?;
+    let service = ConferenceService::new(pool);
+    let room = controllable_room(&service, &auth_user, id).await?;
+    service.kick_participant(&room, &request.channel).await
+}
+
+async fn list_history(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ConferenceHistoryQuery>,
+) -> Result<Json<Vec<ConferenceAttendance>>, AppError> {
+    let service = ConferenceService::new(pool);
+    let room = controllable_room(&service, &auth_user, id).await?;
+    let history = service
+        .list_history(room.id, query.limit.unwrap_or(100), query.offset.unwrap_or(0))
+        .await?;
+    Ok(Json(history))
+}
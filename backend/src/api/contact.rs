@@ -0,0 +1,215 @@
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        contact::{
+            Contact, ContactExportQuery, ContactImportQuery, ContactImportResult, ContactListQuery,
+            ContactLookup, ContactLookupQuery, ContactScope, CreateContactRequest, UpdateContactRequest,
+            VcardVersion,
+        },
+    },
+    services::contact::ContactService,
+};
+
+/// Phonebooks exported from other systems easily exceed axum's 2 MB default.
+const IMPORT_BODY_LIMIT: usize = 20 * 1024 * 1024;
+const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/contacts",
+            post(create_contact)
+                .get(list_contacts)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/contacts/import",
+            post(import_contacts)
+                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT))
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/contacts/export",
+            get(export_contacts)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/contacts/lookup",
+            get(lookup_contact)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/contacts/:id",
+            get(get_contact)
+                .put(update_contact)
+                .delete(delete_contact)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+/// The owner of new contacts in `scope`. Only admins edit the company
+/// phonebook.
+fn scope_owner(auth_user: &AuthUser, scope: ContactScope) -> Result<Option<Uuid>, AppError> {
+    match scope {
+        ContactScope::Personal => Ok(Some(auth_user.user_id)),
+        ContactScope::Company if auth_user.role == UserRole::Admin => Ok(None),
+        ContactScope::Company => Err(AppError::Auth("Access denied".into())),
+    }
+}
+
+/// Everyone reads the company phonebook; personal contacts are private.
+async fn readable_contact(service: &ContactService, auth_user: &AuthUser, id: Uuid) -> Result<Contact, AppError> {
+    let contact = service.get_contact(id).await?;
+    if contact.owner_id.is_some() && contact.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::NotFound("Contact not found".into()));
+    }
+    Ok(contact)
+}
+
+async fn writable_contact(service: &ContactService, auth_user: &AuthUser, id: Uuid) -> Result<Contact, AppError> {
+    let contact = readable_contact(service, auth_user, id).await?;
+    if contact.owner_id.is_none() && auth_user.role != UserRole::Admin {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(contact)
+}
+
+async fn create_contact(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateContactRequest>,
+) -> Result<Json<Contact>, AppError> {
+    request.validate()?;
+    let owner_id = scope_owner(&auth_user, request.scope)?;
+    let service = ContactService::new(pool);
+    let contact = service.create_contact(owner_id, request).await?;
+    Ok(Json(contact))
+}
+
+async fn list_contacts(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ContactListQuery>,
+) -> Result<Json<Vec<Contact>>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let service = ContactService::new(pool);
+    let contacts = service
+        .list_contacts(auth_user.user_id, query.scope, query.q.as_deref(), limit, offset)
+        .await?;
+    Ok(Json(contacts))
+}
+
+async fn get_contact(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Contact>, AppError> {
+    let service = ContactService::new(pool);
+    let contact = readable_contact(&service, &auth_user, id).await?;
+    Ok(Json(contact))
+}
+
+async fn update_contact(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateContactRequest>,
+) -> Result<Json<Contact>, AppError> {
+    request.validate()?;
+    let service = ContactService::new(pool);
+    writable_contact(&service, &auth_user, id).await?;
+    let contact = service.update_contact(id, request).await?;
+    Ok(Json(contact))
+}
+
+async fn delete_contact(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = ContactService::new(pool);
+    writable_contact(&service, &auth_user, id).await?;
+    service.delete_contact(id).await?;
+    Ok(())
+}
+
+// vCard
+async fn import_contacts(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ContactImportQuery>,
+    body: Bytes,
+) -> Result<Json<ContactImportResult>, AppError> {
+    if body.is_empty() {
+        return Err(AppError::Validation("Request body is empty".into()));
+    }
+    let data = std::str::from_utf8(&body)
+        .map_err(|_| AppError::Validation("vCard data must be UTF-8".into()))?;
+
+    let owner_id = scope_owner(&auth_user, query.scope)?;
+    let service = ContactService::new(pool);
+    let result = service.import_vcards(owner_id, data).await?;
+    Ok(Json(result))
+}
+
+async fn export_contacts(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ContactExportQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ContactService::new(pool);
+    let vcards = service
+        .export_vcards(auth_user.user_id, query.scope, query.version)
+        .await?;
+    let version = match query.version {
+        VcardVersion::V3 => "3.0",
+        VcardVersion::V4 => "4.0",
+    };
+    let filename = format!("contacts-{}.vcf", Utc::now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, format!("text/vcard; charset=utf-8; version={}", version)),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        vcards,
+    ))
+}
+
+// Caller lookup
+async fn lookup_contact(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ContactLookupQuery>,
+) -> Result<Json<ContactLookup>, AppError> {
+    let service = ContactService::new(pool);
+    let lookup = match (query.number, query.call_id) {
+        (Some(number), None) => service.lookup(auth_user.user_id, &number).await?,
+        (None, Some(call_id)) => service.lookup_caller(auth_user.user_id, call_id).await?,
+        _ => {
+            return Err(AppError::Validation(
+                "Give either 'number' or 'call_id'".into(),
+            ))
+        }
+    };
+    Ok(Json(lookup))
+}
@@ -0,0 +1,382 @@
+use std::path::Path as FsPath;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        fax::{
+            CreateFaxLineRequest, FaxEmailToken, FaxJob, FaxJobSource, FaxLine, FaxListQuery,
+            InboundFax, SendFaxQuery, UpdateFaxLineRequest,
+        },
+    },
+    services::fax::FaxService,
+};
+
+/// Fax documents are larger than the default body limit allows.
+const FAX_BODY_LIMIT: usize = 20 * 1024 * 1024;
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/fax/lines",
+            post(create_line)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/fax/lines",
+            get(list_lines)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/lines/:id",
+            get(get_line)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/lines/:id",
+            put(update_line)
+                .delete(delete_line)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/fax/lines/:id/email-token",
+            get(get_email_token)
+                .post(rotate_email_token)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/inbound",
+            get(list_inbound)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/inbound/:id",
+            get(get_inbound)
+                .delete(delete_inbound)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/inbound/:id/document",
+            get(download_inbound)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/inbound/:id/deliver",
+            post(deliver_inbound)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/jobs",
+            post(send_fax)
+                .get(list_jobs)
+                .layer(DefaultBodyLimit::max(FAX_BODY_LIMIT))
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/jobs/:id",
+            get(get_job)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/jobs/:id/document",
+            get(download_job)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/jobs/:id/cancel",
+            post(cancel_job)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/fax/email",
+            post(send_email_fax)
+                .layer(DefaultBodyLimit::max(FAX_BODY_LIMIT))
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+/// Fax lines, their faxes and their jobs are visible to the line owner and to admins.
+async fn accessible_line(
+    service: &FaxService,
+    auth_user: &AuthUser,
+    id: Uuid,
+) -> Result<FaxLine, AppError> {
+    let line = service.get_line(id).await?;
+    if auth_user.role != UserRole::Admin && line.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(line)
+}
+
+/// Faxes for a DID that no longer has a line are only visible to admins.
+async fn accessible_inbound(
+    service: &FaxService,
+    auth_user: &AuthUser,
+    id: Uuid,
+) -> Result<InboundFax, AppError> {
+    let fax = service.get_inbound(id).await?;
+    match fax.line_id {
+        Some(line_id) => {
+            accessible_line(service, auth_user, line_id).await?;
+        }
+        None if auth_user.role != UserRole::Admin => {
+            return Err(AppError::Auth("Access denied".into()));
+        }
+        None => {}
+    }
+    Ok(fax)
+}
+
+async fn accessible_job(
+    service: &FaxService,
+    auth_user: &AuthUser,
+    id: Uuid,
+) -> Result<FaxJob, AppError> {
+    let job = service.get_job(id).await?;
+    accessible_line(service, auth_user, job.line_id).await?;
+    Ok(job)
+}
+
+/// Admins see everything; other users only what belongs to their lines.
+fn owner_filter(auth_user: &AuthUser) -> Option<Uuid> {
+    (auth_user.role != UserRole::Admin).then_some(auth_user.user_id)
+}
+
+async fn stream_pdf(path: &FsPath) -> Result<Response, AppError> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|_| AppError::NotFound("Fax document not found".into()))?;
+    let length = file
+        .metadata()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?
+        .len();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_LENGTH, length.to_string()),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
+// Lines
+async fn create_line(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateFaxLineRequest>,
+) -> Result<Json<FaxLine>, AppError> {
+    request.validate()?;
+    let service = FaxService::new(pool);
+    let line = service.create_line(request).await?;
+    Ok(Json(line))
+}
+
+async fn list_lines(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<FaxLine>>, AppError> {
+    let service = FaxService::new(pool);
+    let lines = service.list_lines(owner_filter(&auth_user)).await?;
+    Ok(Json(lines))
+}
+
+async fn get_line(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FaxLine>, AppError> {
+    let service = FaxService::new(pool);
+    let line = accessible_line(&service, &auth_user, id).await?;
+    Ok(Json(line))
+}
+
+async fn get_email_token(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FaxEmailToken>, AppError> {
+    let service = FaxService::new(pool);
+    let line = accessible_line(&service, &auth_user, id).await?;
+    let token = service.get_email_token(line.id).await?;
+    Ok(Json(token))
+}
+
+async fn rotate_email_token(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FaxEmailToken>, AppError> {
+    let service = FaxService::new(pool);
+    let line = accessible_line(&service, &auth_user, id).await?;
+    let token = service.rotate_email_token(line.id).await?;
+    Ok(Json(token))
+}
+
+async fn update_line(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateFaxLineRequest>,
+) -> Result<Json<FaxLine>, AppError> {
+    request.validate()?;
+    let service = FaxService::new(pool);
+    let line = service.update_line(id, request).await?;
+    Ok(Json(line))
+}
+
+async fn delete_line(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = FaxService::new(pool);
+    service.delete_line(id).await?;
+    Ok(())
+}
+
+// Inbound faxes
+async fn list_inbound(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<FaxListQuery>,
+) -> Result<Json<Vec<InboundFax>>, AppError> {
+    let service = FaxService::new(pool);
+    let faxes = service
+        .list_inbound(
+            owner_filter(&auth_user),
+            query.line_id,
+            query.limit.unwrap_or(100),
+            query.offset.unwrap_or(0),
+        )
+        .await?;
+    Ok(Json(faxes))
+}
+
+async fn get_inbound(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InboundFax>, AppError> {
+    let service = FaxService::new(pool);
+    let fax = accessible_inbound(&service, &auth_user, id).await?;
+    Ok(Json(fax))
+}
+
+async fn download_inbound(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let service = FaxService::new(pool);
+    let fax = accessible_inbound(&service, &auth_user, id).await?;
+    stream_pdf(FsPath::new(&fax.file_path)).await
+}
+
+async fn deliver_inbound(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InboundFax>, AppError> {
+    let service = FaxService::new(pool);
+    accessible_inbound(&service, &auth_user, id).await?;
+    let fax = service.deliver(id).await?;
+    Ok(Json(fax))
+}
+
+async fn delete_inbound(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = FaxService::new(pool);
+    accessible_inbound(&service, &auth_user, id).await?;
+    service.delete_inbound(id).await?;
+    Ok(())
+}
+
+// Outbound jobs
+async fn send_fax(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<SendFaxQuery>,
+    body: Bytes,
+) -> Result<Json<FaxJob>, AppError> {
+    query.validate()?;
+    let service = FaxService::new(pool);
+    let line = accessible_line(&service, &auth_user, query.line_id).await?;
+    let job = service
+        .queue_fax(&line, FaxJobSource::Upload, &query.destination, query.max_attempts, &body)
+        .await?;
+    Ok(Json(job))
+}
+
+async fn send_email_fax(
+    State(pool): State<PgPool>,
+    body: Bytes,
+) -> Result<Json<FaxJob>, AppError> {
+    let service = FaxService::new(pool);
+    let job = service.queue_email(&body).await?;
+    Ok(Json(job))
+}
+
+async fn list_jobs(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Query(query): Query<FaxListQuery>,
+) -> Result<Json<Vec<FaxJob>>, AppError> {
+    let service = FaxService::new(pool);
+    let jobs = service
+        .list_jobs(
+            owner_filter(&auth_user),
+            query.line_id,
+            query.limit.unwrap_or(100),
+            query.offset.unwrap_or(0),
+        )
+        .await?;
+    Ok(Json(jobs))
+}
+
+async fn get_job(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FaxJob>, AppError> {
+    let service = FaxService::new(pool);
+    let job = accessible_job(&service, &auth_user, id).await?;
+    Ok(Json(job))
+}
+
+async fn download_job(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let service = FaxService::new(pool);
+    let job = accessible_job(&service, &auth_user, id).await?;
+    stream_pdf(FsPath::new(&job.file_path)).await
+}
+
+async fn cancel_job(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FaxJob>, AppError> {
+    let service = FaxService::new(pool);
+    accessible_job(&service, &auth_user, id).await?;
+    let job = service.cancel_job(id).await?;
+    Ok(Json(job))
+}
@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth},
+    models::ivr::{CreateIvrMenuRequest, IvrMenu, IvrValidationResponse, UpdateIvrMenuRequest},
+    services::ivr::IvrService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/ivr",
+            post(create_menu)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/ivr",
+            get(list_menus)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/ivr/validate",
+            post(validate_menu)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/ivr/:id",
+            get(get_menu)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/ivr/:id",
+            put(update_menu)
+                .delete(delete_menu)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/ivr/:id/dialplan",
+            get(export_dialplan)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+async fn create_menu(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateIvrMenuRequest>,
+) -> Result<Json<IvrMenu>, AppError> {
+    request.validate()?;
+    let service = IvrService::new(pool);
+    let menu = service.create_menu(request).await?;
+    Ok(Json(menu))
+}
+
+async fn validate_menu(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateIvrMenuRequest>,
+) -> Result<Json<IvrValidationResponse>, AppError> {
+    request.validate()?;
+    let service = IvrService::new(pool);
+    let result = service.check_graph(&request.entry_node, &request.nodes).await?;
+    Ok(Json(result))
+}
+
+async fn get_menu(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<IvrMenu>, AppError> {
+    let service = IvrService::new(pool);
+    let menu = service.get_menu(id).await?;
+    Ok(Json(menu))
+}
+
+async fn list_menus(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<IvrMenu>>, AppError> {
+    let service = IvrService::new(pool);
+    let menus = service.list_menus().await?;
+    Ok(Json(menus))
+}
+
+async fn update_menu(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateIvrMenuRequest>,
+) -> Result<Json<IvrMenu>, AppError> {
+    request.validate()?;
+    let service = IvrService::new(pool);
+    let menu = service.update_menu(id, request).await?;
+    Ok(Json(menu))
+}
+
+async fn delete_menu(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = IvrService::new(pool);
+    service.delete_menu(id).await?;
+    Ok(())
+}
+
+async fn export_dialplan(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = IvrService::new(pool);
+    let dialplan = service.export_dialplan(id).await?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], dialplan))
+}
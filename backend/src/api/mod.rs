@@ -1,5 +1,25 @@
+pub mod analytics;
+pub mod asterisk;
 pub mod auth;
+pub mod billing;
 pub mod calendar;
+pub mod call_handling;
+pub mod cdr;
+pub mod chat;
+pub mod conference;
+pub mod contact;
 pub mod email;
+pub mod fax;
+pub mod ivr;
+pub mod originate;
 pub mod pbx;
-pub mod system; 
\ No newline at end of file
+pub mod presence;
+pub mod provisioning;
+pub mod queue;
+pub mod recording;
+pub mod ring_group;
+pub mod routing;
+pub mod screening;
+pub mod system;
+pub mod trunk;
+pub mod voicemail;
\ No newline at end of file
@@ -0,0 +1,94 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        originate::{OriginateCallRequest, OriginatePermission},
+        pbx::CallRecord,
+    },
+    services::{originate::OriginateService, pbx::PbxService},
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/calls/originate",
+            post(originate_call)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/calls/originate/permissions",
+            get(list_permissions)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/calls/originate/permissions/:user_id",
+            put(grant_permission)
+                .delete(revoke_permission)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+/// Calls are placed from the user's own extension and need the originate
+/// permission; admins may use any extension.
+async fn originate_call(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Json(request): Json<OriginateCallRequest>,
+) -> Result<Json<CallRecord>, AppError> {
+    request.validate()?;
+    let service = OriginateService::new(pool.clone());
+    let extension = PbxService::new(pool)
+        .get_extension_by_number(&request.extension)
+        .await?;
+    if auth_user.role != UserRole::Admin {
+        if extension.owner_id != Some(auth_user.user_id) {
+            return Err(AppError::Auth("Access denied".into()));
+        }
+        if !service.is_permitted(auth_user.user_id).await? {
+            return Err(AppError::Auth("Click to call is not enabled for this user".into()));
+        }
+    }
+
+    let call = service
+        .originate(&extension, &request.destination, request.ring_timeout, auth_user.user_id)
+        .await?;
+    Ok(Json(call))
+}
+
+// Permissions
+async fn list_permissions(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<OriginatePermission>>, AppError> {
+    let service = OriginateService::new(pool);
+    let permissions = service.list_permissions().await?;
+    Ok(Json(permissions))
+}
+
+async fn grant_permission(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<OriginatePermission>, AppError> {
+    let service = OriginateService::new(pool);
+    let permission = service.grant_permission(user_id, auth_user.user_id).await?;
+    Ok(Json(permission))
+}
+
+async fn revoke_permission(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = OriginateService::new(pool);
+    service.revoke_permission(user_id).await?;
+    Ok(())
+}
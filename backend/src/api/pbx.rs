@@ -1,22 +1,33 @@
 use axum::{
-    extract::{Path, Query, State},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::header,
+    response::IntoResponse,
     routing::{get, post, put, delete},
     Json, Router,
 };
+use chrono::Utc;
 use serde::Deserialize;
-use sqlx::PgPool;
+use sqlx::{types::JsonValue, PgPool};
 use uuid::Uuid;
 
 use crate::{
     error::AppError,
     middleware::auth::{require_auth, require_admin, AuthUser},
-    models::pbx::{
-        CallRecord, CreateCallRecordRequest, CreateExtensionRequest,
-        PbxExtension, UpdateCallRecordRequest, UpdateExtensionRequest,
+    models::{
+        auth::UserRole,
+        pbx::{
+            CallEvent, CallRecord, CreateCallRecordRequest, CreateExtensionRequest,
+            ExtensionImportQuery, ExtensionImportResult, ExtensionRegistrationStatus, PbxExtension,
+            UpdateCallRecordRequest, UpdateExtensionRequest,
+        },
     },
-    services::pbx::PbxService,
+    services::pbx::{extension_config_schemas, PbxService},
 };
 
+/// Room for a few thousand rows with generous config_data.
+const IMPORT_BODY_LIMIT: usize = 10 * 1024 * 1024;
+
 pub fn router() -> Router<PgPool> {
     Router::new()
         .route(
@@ -24,6 +35,22 @@ pub fn router() -> Router<PgPool> {
             post(create_extension)
                 .route_layer(axum::middleware::from_fn(require_admin))
         )
+        .route(
+            "/extensions/import",
+            post(import_extensions)
+                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT))
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/extensions/config-schema",
+            get(get_config_schemas)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/extensions/export",
+            get(export_extensions)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
         .route(
             "/extensions/:id",
             get(get_extension)
@@ -36,6 +63,16 @@ pub fn router() -> Router<PgPool> {
             get(list_extensions)
                 .route_layer(axum::middleware::from_fn(require_auth))
         )
+        .route(
+            "/extensions/registrations",
+            get(list_registration_statuses)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/extensions/:id/registration",
+            get(get_registration_status)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
         .route(
             "/calls",
             post(create_call_record)
@@ -47,6 +84,11 @@ pub fn router() -> Router<PgPool> {
                 .put(update_call_record)
                 .route_layer(axum::middleware::from_fn(require_auth))
         )
+        .route(
+            "/calls/:id/events",
+            get(list_call_events)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
         .route(
             "/calls",
             get(list_call_records)
@@ -70,6 +112,42 @@ async fn create_extension(
     Ok(Json(extension))
 }
 
+async fn get_config_schemas() -> Json<JsonValue> {
+    Json(extension_config_schemas())
+}
+
+async fn import_extensions(
+    State(pool): State<PgPool>,
+    Query(query): Query<ExtensionImportQuery>,
+    body: Bytes,
+) -> Result<Json<ExtensionImportResult>, AppError> {
+    if body.is_empty() {
+        return Err(AppError::Validation("Request body is empty".into()));
+    }
+
+    let service = PbxService::new(pool);
+    let result = service.import_extensions_csv(&body, query.dry_run).await?;
+    Ok(Json(result))
+}
+
+async fn export_extensions(
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PbxService::new(pool);
+    let csv = service.export_extensions_csv().await?;
+    let filename = format!("extensions-{}.csv", Utc::now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        csv,
+    ))
+}
+
 async fn get_extension(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
@@ -87,12 +165,19 @@ async fn list_extensions(
     Ok(Json(extensions))
 }
 
+/// The owner may manage the extension's call handling, so only admins can
+/// assign or remove one.
 async fn update_extension(
     State(pool): State<PgPool>,
+    auth_user: AuthUser,
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateExtensionRequest>,
 ) -> Result<Json<PbxExtension>, AppError> {
     request.validate()?;
+    let changes_owner = request.owner_id.is_some() || request.clear_owner.unwrap_or(false);
+    if changes_owner && auth_user.role != UserRole::Admin {
+        return Err(AppError::Auth("Only admins can change the owner".into()));
+    }
     let service = PbxService::new(pool);
     let extension = service.update_extension(id, request).await?;
     Ok(Json(extension))
@@ -107,6 +192,23 @@ async fn delete_extension(
     Ok(())
 }
 
+async fn get_registration_status(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ExtensionRegistrationStatus>, AppError> {
+    let service = PbxService::new(pool);
+    let status = service.get_registration_status(id).await?;
+    Ok(Json(status))
+}
+
+async fn list_registration_statuses(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<ExtensionRegistrationStatus>>, AppError> {
+    let service = PbxService::new(pool);
+    let statuses = service.list_registration_statuses().await?;
+    Ok(Json(statuses))
+}
+
 // Call record endpoints
 #[derive(Debug, Deserialize)]
 struct ListCallRecordsQuery {
@@ -116,10 +218,11 @@ struct ListCallRecordsQuery {
 
 async fn create_call_record(
     State(pool): State<PgPool>,
+    auth_user: AuthUser,
     Json(request): Json<CreateCallRecordRequest>,
 ) -> Result<Json<CallRecord>, AppError> {
     let service = PbxService::new(pool);
-    let record = service.create_call_record(request).await?;
+    let record = service.create_call_record(request, auth_user.user_id).await?;
     Ok(Json(record))
 }
 
@@ -134,14 +237,24 @@ async fn get_call_record(
 
 async fn update_call_record(
     State(pool): State<PgPool>,
+    auth_user: AuthUser,
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateCallRecordRequest>,
 ) -> Result<Json<CallRecord>, AppError> {
     let service = PbxService::new(pool);
-    let record = service.update_call_record(id, request).await?;
+    let record = service.update_call_record(id, request, auth_user.user_id).await?;
     Ok(Json(record))
 }
 
+async fn list_call_events(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<CallEvent>>, AppError> {
+    let service = PbxService::new(pool);
+    let events = service.list_call_events(id).await?;
+    Ok(Json(events))
+}
+
 async fn list_call_records(
     State(pool): State<PgPool>,
     Query(query): Query<ListCallRecordsQuery>,
@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, put},
+    Extension, Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        presence::{PresenceQuery, SetPresenceOverrideRequest, UserPresence},
+    },
+    services::{
+        chat::ChatHub,
+        presence::{parse_user_ids, PresenceService},
+    },
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/presence",
+            get(list_presence)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/presence/:user_id",
+            get(get_presence)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/presence/:user_id/override",
+            put(set_override)
+                .delete(clear_override)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+/// Users set their own status; admins may set anyone's.
+fn check_override_access(auth_user: &AuthUser, user_id: Uuid) -> Result<(), AppError> {
+    if auth_user.role != UserRole::Admin && auth_user.user_id != user_id {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(())
+}
+
+async fn list_presence(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    Query(query): Query<PresenceQuery>,
+) -> Result<Json<Vec<UserPresence>>, AppError> {
+    let user_ids = query.user_ids.as_deref().map(parse_user_ids).transpose()?;
+    let service = PresenceService::new(pool, hub);
+    let presence = service.presence(user_ids.as_deref()).await?;
+    Ok(Json(presence))
+}
+
+async fn get_presence(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<UserPresence>, AppError> {
+    let service = PresenceService::new(pool, hub);
+    let presence = service.get_presence(user_id).await?;
+    Ok(Json(presence))
+}
+
+async fn set_override(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<SetPresenceOverrideRequest>,
+) -> Result<Json<UserPresence>, AppError> {
+    request.validate()?;
+    check_override_access(&auth_user, user_id)?;
+    let service = PresenceService::new(pool, hub);
+    let presence = service.set_override(user_id, request).await?;
+    Ok(Json(presence))
+}
+
+async fn clear_override(
+    State(pool): State<PgPool>,
+    Extension(hub): Extension<ChatHub>,
+    auth_user: AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<UserPresence>, AppError> {
+    check_override_access(&auth_user, user_id)?;
+    let service = PresenceService::new(pool, hub);
+    let presence = service.clear_override(user_id).await?;
+    Ok(Json(presence))
+}
@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::require_admin,
+    models::provisioning::{CreatePhoneDeviceRequest, PhoneDevice, UpdatePhoneDeviceRequest},
+    services::provisioning::{parse_basic_auth, ProvisioningService},
+};
+
+const PROVISIONING_REALM: &str = "Basic realm=\"OrionTel provisioning\"";
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/provisioning/devices",
+            post(create_device)
+                .get(list_devices)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/provisioning/devices/:id",
+            get(get_device)
+                .put(update_device)
+                .delete(delete_device)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        // Phones authenticate with their own credentials, not a user token
+        .route("/provisioning/:filename", get(fetch_config))
+}
+
+async fn create_device(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreatePhoneDeviceRequest>,
+) -> Result<Json<PhoneDevice>, AppError> {
+    request.validate()?;
+    let service = ProvisioningService::new(pool);
+    let device = service.create_device(request).await?;
+    Ok(Json(device))
+}
+
+async fn list_devices(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<PhoneDevice>>, AppError> {
+    let service = ProvisioningService::new(pool);
+    let devices = service.list_devices().await?;
+    Ok(Json(devices))
+}
+
+async fn get_device(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PhoneDevice>, AppError> {
+    let service = ProvisioningService::new(pool);
+    let device = service.get_device(id).await?;
+    Ok(Json(device))
+}
+
+async fn update_device(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdatePhoneDeviceRequest>,
+) -> Result<Json<PhoneDevice>, AppError> {
+    request.validate()?;
+    let service = ProvisioningService::new(pool);
+    let device = service.update_device(id, request).await?;
+    Ok(Json(device))
+}
+
+async fn delete_device(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = ProvisioningService::new(pool);
+    service.delete_device(id).await?;
+    Ok(())
+}
+
+async fn fetch_config(
+    State(pool): State<PgPool>,
+    Path(filename): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let header_str = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let credentials = header_str(header::AUTHORIZATION.as_str()).and_then(parse_basic_auth);
+    // The server listens behind a reverse proxy, so the phone's address comes from it
+    let address = header_str("x-forwarded-for")
+        .and_then(|value| value.split(',').next())
+        .map(|value| value.trim().to_string());
+    let user_agent = header_str(header::USER_AGENT.as_str()).map(str::to_string);
+
+    let service = ProvisioningService::new(pool);
+    match service.fetch_config(&filename, credentials, address, user_agent).await {
+        Ok(file) => ([(header::CONTENT_TYPE, file.content_type)], file.body).into_response(),
+        // Phones only send credentials after a Basic challenge
+        Err(AppError::Auth(_)) => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, PROVISIONING_REALM)],
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
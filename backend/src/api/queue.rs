@@ -0,0 +1,171 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        queue::{CreateQueueRequest, PauseAgentRequest, QueueMember, QueueResponse, UpdateQueueRequest},
+    },
+    services::{pbx::PbxService, queue::QueueService},
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/queues",
+            post(create_queue)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/queues",
+            get(list_queues)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/queues/:id",
+            get(get_queue)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/queues/:id",
+            put(update_queue)
+                .delete(delete_queue)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/queues/:id/agents/:extension_id/login",
+            post(login_agent)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/queues/:id/agents/:extension_id/logout",
+            post(logout_agent)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/queues/:id/agents/:extension_id/pause",
+            post(pause_agent)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/queues/:id/agents/:extension_id/unpause",
+            post(unpause_agent)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+async fn create_queue(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateQueueRequest>,
+) -> Result<Json<QueueResponse>, AppError> {
+    request.validate()?;
+    let service = QueueService::new(pool);
+    let queue = service.create_queue(request).await?;
+    Ok(Json(queue))
+}
+
+async fn get_queue(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<QueueResponse>, AppError> {
+    let service = QueueService::new(pool);
+    let queue = service.get_queue(id).await?;
+    Ok(Json(queue))
+}
+
+async fn list_queues(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<QueueResponse>>, AppError> {
+    let service = QueueService::new(pool);
+    let queues = service.list_queues().await?;
+    Ok(Json(queues))
+}
+
+async fn update_queue(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateQueueRequest>,
+) -> Result<Json<QueueResponse>, AppError> {
+    request.validate()?;
+    let service = QueueService::new(pool);
+    let queue = service.update_queue(id, request).await?;
+    Ok(Json(queue))
+}
+
+async fn delete_queue(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = QueueService::new(pool);
+    service.delete_queue(id).await?;
+    Ok(())
+}
+
+// Agent endpoints
+/// Agents manage their own extension's membership; admins can manage any.
+async fn ensure_agent_access(pool: &PgPool, auth_user: &AuthUser, extension_id: Uuid) -> Result<(), AppError> {
+    if auth_user.role == UserRole::Admin {
+        return Ok(());
+    }
+    let extension = PbxService::new(pool.clone()).get_extension(extension_id).await?;
+    if extension.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(())
+}
+
+async fn login_agent(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, extension_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<QueueMember>, AppError> {
+    ensure_agent_access(&pool, &auth_user, extension_id).await?;
+    let service = QueueService::new(pool);
+    let member = service.set_agent_logged_in(id, extension_id, true).await?;
+    Ok(Json(member))
+}
+
+async fn logout_agent(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, extension_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<QueueMember>, AppError> {
+    ensure_agent_access(&pool, &auth_user, extension_id).await?;
+    let service = QueueService::new(pool);
+    let member = service.set_agent_logged_in(id, extension_id, false).await?;
+    Ok(Json(member))
+}
+
+async fn pause_agent(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, extension_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<PauseAgentRequest>,
+) -> Result<Json<QueueMember>, AppError> {
+    request.validate()?;
+    ensure_agent_access(&pool, &auth_user, extension_id).await?;
+    let service = QueueService::new(pool);
+    let member = service
+        .set_agent_paused(id, extension_id, true, request.reason)
+        .await?;
+    Ok(Json(member))
+}
+
+async fn unpause_agent(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, extension_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<QueueMember>, AppError> {
+    ensure_agent_access(&pool, &auth_user, extension_id).await?;
+    let service = QueueService::new(pool);
+    let member = service.set_agent_paused(id, extension_id, false, None).await?;
+    Ok(Json(member))
+}
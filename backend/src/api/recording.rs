@@ -0,0 +1,197 @@
+use std::io::SeekFrom;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        recording::{RecordingInfo, RecordingPolicy, RetentionRunResponse, SetRecordingPolicyRequest},
+    },
+    services::recording::{content_type_for, parse_range, RangeError, RecordingService},
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/calls/:id/recording",
+            get(download_recording)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/calls/:id/recording/info",
+            get(get_recording_info)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/recording-policies",
+            get(list_policies)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/extensions/:id/recording-policy",
+            get(get_policy)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/extensions/:id/recording-policy",
+            put(set_policy)
+                .delete(delete_policy)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/recordings/retention/run",
+            post(run_retention)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+/// Recordings are available to admins and the owners of the extensions on
+/// the call.
+async fn ensure_recording_access(
+    service: &RecordingService,
+    auth_user: &AuthUser,
+    call_id: Uuid,
+) -> Result<(), AppError> {
+    if auth_user.role != UserRole::Admin && !service.is_call_participant(call_id, auth_user.user_id).await? {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(())
+}
+
+/// Streams the recording, honouring a single-range `Range` header so players
+/// can seek without downloading the whole file.
+async fn download_recording(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let service = RecordingService::new(pool);
+    ensure_recording_access(&service, &auth_user, id).await?;
+    let path = service.recording_path_for_call(id).await?;
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|_| AppError::NotFound("Recording file not found".into()))?;
+    let size = file
+        .metadata()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?
+        .len();
+    let content_type = content_type_for(&path).to_string();
+
+    let range = match headers.get(header::RANGE).and_then(|value| value.to_str().ok()) {
+        Some(value) => match parse_range(value, size) {
+            Ok(range) => Some(range),
+            Err(RangeError::Invalid) => None,
+            Err(RangeError::Unsatisfiable) => {
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+                )
+                    .into_response());
+            }
+        },
+        None => None,
+    };
+
+    let Some(range) = range else {
+        return Ok((
+            [
+                (header::CONTENT_TYPE, content_type),
+                (header::CONTENT_LENGTH, size.to_string()),
+                (header::ACCEPT_RANGES, "bytes".to_string()),
+            ],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response());
+    };
+
+    file.seek(SeekFrom::Start(range.start))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to seek {}: {}", path.display(), e)))?;
+
+    Ok((
+        StatusCode::PARTIAL_CONTENT,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_LENGTH, range.length().to_string()),
+            (header::ACCEPT_RANGES, "bytes".to_string()),
+            (
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", range.start, range.end, size),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(file.take(range.length()))),
+    )
+        .into_response())
+}
+
+async fn get_recording_info(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RecordingInfo>, AppError> {
+    let service = RecordingService::new(pool);
+    ensure_recording_access(&service, &auth_user, id).await?;
+    let info = service.recording_info(id).await?;
+    Ok(Json(info))
+}
+
+async fn list_policies(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<RecordingPolicy>>, AppError> {
+    let service = RecordingService::new(pool);
+    let policies = service.list_policies().await?;
+    Ok(Json(policies))
+}
+
+async fn get_policy(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RecordingPolicy>, AppError> {
+    let service = RecordingService::new(pool);
+    let policy = service.get_policy(id).await?;
+    Ok(Json(policy))
+}
+
+async fn set_policy(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetRecordingPolicyRequest>,
+) -> Result<Json<RecordingPolicy>, AppError> {
+    request.validate()?;
+    let service = RecordingService::new(pool);
+    let policy = service.set_policy(id, request).await?;
+    Ok(Json(policy))
+}
+
+async fn delete_policy(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = RecordingService::new(pool);
+    service.delete_policy(id).await?;
+    Ok(())
+}
+
+async fn run_retention(
+    State(pool): State<PgPool>,
+) -> Result<Json<RetentionRunResponse>, AppError> {
+    let service = RecordingService::new(pool);
+    let recordings_removed = service.apply_retention().await?;
+    Ok(Json(RetentionRunResponse { recordings_removed }))
+}
@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth},
+    models::ring_group::{CreateRingGroupRequest, RingGroupResponse, UpdateRingGroupRequest},
+    services::ring_group::RingGroupService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/ring-groups",
+            post(create_group)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/ring-groups",
+            get(list_groups)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/ring-groups/:id",
+            get(get_group)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/ring-groups/:id",
+            put(update_group)
+                .delete(delete_group)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+async fn create_group(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateRingGroupRequest>,
+) -> Result<Json<RingGroupResponse>, AppError> {
+    request.validate()?;
+    let service = RingGroupService::new(pool);
+    let group = service.create_group(request).await?;
+    Ok(Json(group))
+}
+
+async fn get_group(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RingGroupResponse>, AppError> {
+    let service = RingGroupService::new(pool);
+    let group = service.get_group(id).await?;
+    Ok(Json(group))
+}
+
+async fn list_groups(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<RingGroupResponse>>, AppError> {
+    let service = RingGroupService::new(pool);
+    let groups = service.list_groups().await?;
+    Ok(Json(groups))
+}
+
+async fn update_group(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateRingGroupRequest>,
+) -> Result<Json<RingGroupResponse>, AppError> {
+    request.validate()?;
+    let service = RingGroupService::new(pool);
+    let group = service.update_group(id, request).await?;
+    Ok(Json(group))
+}
+
+async fn delete_group(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = RingGroupService::new(pool);
+    service.delete_group(id).await?;
+    Ok(())
+}
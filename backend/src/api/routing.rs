@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth},
+    models::routing::{
+        CreateInboundRouteRequest, CreateOutboundRouteRequest, InboundRoute, OutboundRoute,
+        RouteSimulationResult, SimulateRouteRequest, UpdateInboundRouteRequest,
+        UpdateOutboundRouteRequest,
+    },
+    services::routing::RoutingService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/routes/inbound",
+            post(create_inbound_route)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/routes/inbound",
+            get(list_inbound_routes)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/routes/inbound/:id",
+            get(get_inbound_route)
+                .put(update_inbound_route)
+                .delete(delete_inbound_route)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/routes/outbound",
+            post(create_outbound_route)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/routes/outbound",
+            get(list_outbound_routes)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/routes/outbound/:id",
+            get(get_outbound_route)
+                .put(update_outbound_route)
+                .delete(delete_outbound_route)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/routes/simulate",
+            post(simulate_route)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+// Inbound route endpoints
+async fn create_inbound_route(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateInboundRouteRequest>,
+) -> Result<Json<InboundRoute>, AppError> {
+    request.validate()?;
+    let service = RoutingService::new(pool);
+    let route = service.create_inbound_route(request).await?;
+    Ok(Json(route))
+}
+
+async fn get_inbound_route(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InboundRoute>, AppError> {
+    let service = RoutingService::new(pool);
+    let route = service.get_inbound_route(id).await?;
+    Ok(Json(route))
+}
+
+async fn list_inbound_routes(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<InboundRoute>>, AppError> {
+    let service = RoutingService::new(pool);
+    let routes = service.list_inbound_routes().await?;
+    Ok(Json(routes))
+}
+
+async fn update_inbound_route(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateInboundRouteRequest>,
+) -> Result<Json<InboundRoute>, AppError> {
+    request.validate()?;
+    let service = RoutingService::new(pool);
+    let route = service.update_inbound_route(id, request).await?;
+    Ok(Json(route))
+}
+
+async fn delete_inbound_route(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = RoutingService::new(pool);
+    service.delete_inbound_route(id).await?;
+    Ok(())
+}
+
+// Outbound route endpoints
+async fn create_outbound_route(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateOutboundRouteRequest>,
+) -> Result<Json<OutboundRoute>, AppError> {
+    request.validate()?;
+    let service = RoutingService::new(pool);
+    let route = service.create_outbound_route(request).await?;
+    Ok(Json(route))
+}
+
+async fn get_outbound_route(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OutboundRoute>, AppError> {
+    let service = RoutingService::new(pool);
+    let route = service.get_outbound_route(id).await?;
+    Ok(Json(route))
+}
+
+async fn list_outbound_routes(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<OutboundRoute>>, AppError> {
+    let service = RoutingService::new(pool);
+    let routes = service.list_outbound_routes().await?;
+    Ok(Json(routes))
+}
+
+async fn update_outbound_route(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateOutboundRouteRequest>,
+) -> Result<Json<OutboundRoute>, AppError> {
+    request.validate()?;
+    let service = RoutingService::new(pool);
+    let route = service.update_outbound_route(id, request).await?;
+    Ok(Json(route))
+}
+
+async fn delete_outbound_route(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = RoutingService::new(pool);
+    service.delete_outbound_route(id).await?;
+    Ok(())
+}
+
+async fn simulate_route(
+    State(pool): State<PgPool>,
+    Json(request): Json<SimulateRouteRequest>,
+) -> Result<Json<RouteSimulationResult>, AppError> {
+    request.validate()?;
+    let service = RoutingService::new(pool);
+    let result = service.simulate(request).await?;
+    Ok(Json(result))
+}
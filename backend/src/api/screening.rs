@@ -0,0 +1,157 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        screening::{
+            BlockCallerRequest, CreateScreeningEntryRequest, ScreeningCheckQuery, ScreeningCheckResult,
+            ScreeningEntry, ScreeningEntryQuery, ScreeningLogEntry, ScreeningLogQuery,
+            UpdateScreeningEntryRequest,
+        },
+    },
+    services::screening::ScreeningService,
+};
+
+const DEFAULT_LOG_LIMIT: i64 = 100;
+const MAX_LOG_LIMIT: i64 = 1000;
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/screening/entries",
+            post(create_entry)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/screening/entries",
+            get(list_entries)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/screening/entries/:id",
+            put(update_entry)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/screening/entries/:id",
+            get(get_entry)
+                .delete(delete_entry)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/screening/check",
+            get(check_caller)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/screening/log",
+            get(list_log)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/calls/:id/block",
+            post(block_caller)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+// Screening entries
+async fn create_entry(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateScreeningEntryRequest>,
+) -> Result<Json<ScreeningEntry>, AppError> {
+    request.validate()?;
+    let service = ScreeningService::new(pool);
+    let entry = service.create_entry(request, Some(auth_user.user_id), None).await?;
+    Ok(Json(entry))
+}
+
+async fn list_entries(
+    State(pool): State<PgPool>,
+    Query(query): Query<ScreeningEntryQuery>,
+) -> Result<Json<Vec<ScreeningEntry>>, AppError> {
+    let service = ScreeningService::new(pool);
+    let entries = service.list_entries(query.list, query.include_expired).await?;
+    Ok(Json(entries))
+}
+
+async fn get_entry(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScreeningEntry>, AppError> {
+    let service = ScreeningService::new(pool);
+    let entry = service.get_entry(id).await?;
+    Ok(Json(entry))
+}
+
+async fn update_entry(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateScreeningEntryRequest>,
+) -> Result<Json<ScreeningEntry>, AppError> {
+    request.validate()?;
+    let service = ScreeningService::new(pool);
+    let entry = service.update_entry(id, request).await?;
+    Ok(Json(entry))
+}
+
+/// Users may remove the entries they created, e.g. a caller blocked by mistake.
+async fn delete_entry(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = ScreeningService::new(pool);
+    let entry = service.get_entry(id).await?;
+    if auth_user.role != UserRole::Admin && entry.created_by != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    service.delete_entry(id).await?;
+    Ok(())
+}
+
+async fn check_caller(
+    State(pool): State<PgPool>,
+    Query(query): Query<ScreeningCheckQuery>,
+) -> Result<Json<ScreeningCheckResult>, AppError> {
+    let service = ScreeningService::new(pool);
+    let result = service.check(&query.number).await?;
+    Ok(Json(result))
+}
+
+async fn list_log(
+    State(pool): State<PgPool>,
+    Query(query): Query<ScreeningLogQuery>,
+) -> Result<Json<Vec<ScreeningLogEntry>>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let service = ScreeningService::new(pool);
+    let log = service
+        .list_log(query.call_id, query.entry_id, limit, offset)
+        .await?;
+    Ok(Json(log))
+}
+
+// Blocking from a call record
+async fn block_caller(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    request: Option<Json<BlockCallerRequest>>,
+) -> Result<Json<ScreeningEntry>, AppError> {
+    let Json(request) = request.unwrap_or_default();
+    request.validate()?;
+    let service = ScreeningService::new(pool);
+    let entry = service.block_caller(id, auth_user.user_id, request).await?;
+    Ok(Json(entry))
+}
@@ -0,0 +1,92 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth},
+    models::trunk::{CreateTrunkRequest, SipTrunk, TrunkStatus, UpdateTrunkRequest},
+    services::trunk::TrunkService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/trunks",
+            post(create_trunk)
+                .get(list_trunks)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/trunks/status",
+            get(list_trunk_statuses)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/trunks/:id",
+            get(get_trunk)
+                .put(update_trunk)
+                .delete(delete_trunk)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+}
+
+async fn create_trunk(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateTrunkRequest>,
+) -> Result<Json<SipTrunk>, AppError> {
+    request.validate()?;
+    let service = TrunkService::new(pool);
+    let trunk = service.create_trunk(request).await?;
+    Ok(Json(trunk))
+}
+
+async fn list_trunks(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<SipTrunk>>, AppError> {
+    let service = TrunkService::new(pool);
+    let trunks = service.list_trunks().await?;
+    Ok(Json(trunks))
+}
+
+async fn get_trunk(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SipTrunk>, AppError> {
+    let service = TrunkService::new(pool);
+    let trunk = service.get_trunk(id).await?;
+    Ok(Json(trunk))
+}
+
+async fn update_trunk(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateTrunkRequest>,
+) -> Result<Json<SipTrunk>, AppError> {
+    request.validate()?;
+    let service = TrunkService::new(pool);
+    let trunk = service.update_trunk(id, request).await?;
+    Ok(Json(trunk))
+}
+
+async fn delete_trunk(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = TrunkService::new(pool);
+    service.delete_trunk(id).await?;
+    Ok(())
+}
+
+async fn list_trunk_statuses(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<TrunkStatus>>, AppError> {
+    let service = TrunkService::new(pool);
+    let statuses = service.list_statuses().await?;
+    Ok(Json(statuses))
+}
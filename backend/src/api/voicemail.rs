@@ -0,0 +1,291 @@
+use std::path::Path as FsPath;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sqlx::PgPool;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{require_admin, require_auth, AuthUser},
+    models::{
+        auth::UserRole,
+        voicemail::{
+            CreateVoicemailBoxRequest, DepositVoicemailQuery, UpdateVoicemailBoxRequest,
+            VoicemailBox, VoicemailMessage,
+        },
+    },
+    services::voicemail::VoicemailService,
+};
+
+pub fn router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/voicemail/boxes",
+            post(create_mailbox)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/voicemail/boxes",
+            get(list_mailboxes)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id",
+            get(get_mailbox)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id",
+            put(update_mailbox)
+                .delete(delete_mailbox)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/voicemail/boxes/:id/greeting",
+            get(play_greeting)
+                .put(upload_greeting)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id/messages",
+            get(list_messages)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id/messages",
+            post(deposit_message)
+                .route_layer(axum::middleware::from_fn(require_admin))
+        )
+        .route(
+            "/voicemail/boxes/:id/messages/:message_id",
+            get(get_message)
+                .delete(delete_message)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id/messages/:message_id/audio",
+            get(play_message)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id/messages/:message_id/read",
+            post(mark_read)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+        .route(
+            "/voicemail/boxes/:id/messages/:message_id/unread",
+            post(mark_unread)
+                .route_layer(axum::middleware::from_fn(require_auth))
+        )
+}
+
+/// Mailboxes are visible to their owner and to admins.
+async fn accessible_mailbox(
+    service: &VoicemailService,
+    auth_user: &AuthUser,
+    id: Uuid,
+) -> Result<VoicemailBox, AppError> {
+    let mailbox = service.get_mailbox(id).await?;
+    if auth_user.role != UserRole::Admin && mailbox.owner_id != Some(auth_user.user_id) {
+        return Err(AppError::Auth("Access denied".into()));
+    }
+    Ok(mailbox)
+}
+
+fn content_type(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+}
+
+async fn stream_file(path: &FsPath, content_type: &str) -> Result<Response, AppError> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|_| AppError::NotFound("Audio file not found".into()))?;
+    let length = file
+        .metadata()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?
+        .len();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_LENGTH, length.to_string()),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
+// Mailboxes
+async fn create_mailbox(
+    State(pool): State<PgPool>,
+    Json(request): Json<CreateVoicemailBoxRequest>,
+) -> Result<Json<VoicemailBox>, AppError> {
+    request.validate()?;
+    let service = VoicemailService::new(pool);
+    let mailbox = service.create_mailbox(request).await?;
+    Ok(Json(mailbox))
+}
+
+async fn list_mailboxes(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<VoicemailBox>>, AppError> {
+    let service = VoicemailService::new(pool);
+    let mailboxes = if auth_user.role == UserRole::Admin {
+        service.list_mailboxes().await?
+    } else {
+        service.list_mailboxes_for_owner(auth_user.user_id).await?
+    };
+    Ok(Json(mailboxes))
+}
+
+async fn get_mailbox(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<VoicemailBox>, AppError> {
+    let service = VoicemailService::new(pool);
+    let mailbox = accessible_mailbox(&service, &auth_user, id).await?;
+    Ok(Json(mailbox))
+}
+
+async fn update_mailbox(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateVoicemailBoxRequest>,
+) -> Result<Json<VoicemailBox>, AppError> {
+    request.validate()?;
+    let service = VoicemailService::new(pool);
+    let mailbox = service.update_mailbox(id, request).await?;
+    Ok(Json(mailbox))
+}
+
+async fn delete_mailbox(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<(), AppError> {
+    let service = VoicemailService::new(pool);
+    service.delete_mailbox(id).await?;
+    Ok(())
+}
+
+async fn upload_greeting(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<VoicemailBox>, AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    let mailbox = service.set_greeting(id, content_type(&headers), &body).await?;
+    Ok(Json(mailbox))
+}
+
+async fn play_greeting(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let service = VoicemailService::new(pool);
+    let mailbox = accessible_mailbox(&service, &auth_user, id).await?;
+    let path = service
+        .greeting_path(&mailbox)
+        .ok_or_else(|| AppError::NotFound("Mailbox has no greeting".into()))?;
+    stream_file(&path, "audio/wav").await
+}
+
+// Messages
+async fn list_messages(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<VoicemailMessage>>, AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    let messages = service.list_messages(id).await?;
+    Ok(Json(messages))
+}
+
+async fn deposit_message(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DepositVoicemailQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<VoicemailMessage>, AppError> {
+    query.validate()?;
+    let service = VoicemailService::new(pool);
+    let message = service
+        .deposit_message(id, query, content_type(&headers), body.to_vec())
+        .await?;
+    Ok(Json(message))
+}
+
+async fn get_message(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<VoicemailMessage>, AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    let message = service.get_message(id, message_id).await?;
+    Ok(Json(message))
+}
+
+async fn play_message(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    let message = service.get_message(id, message_id).await?;
+    stream_file(FsPath::new(&message.file_path), &message.content_type).await
+}
+
+async fn mark_read(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<VoicemailMessage>, AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    let message = service.set_message_read(id, message_id, true).await?;
+    Ok(Json(message))
+}
+
+async fn mark_unread(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<VoicemailMessage>, AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    let message = service.set_message_read(id, message_id, false).await?;
+    Ok(Json(message))
+}
+
+async fn delete_message(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path((id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<(), AppError> {
+    let service = VoicemailService::new(pool);
+    accessible_mailbox(&service, &auth_user, id).await?;
+    service.delete_message(id, message_id).await?;
+    Ok(())
+}
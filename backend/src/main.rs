@@ -5,16 +5,18 @@ use axum::{
 };
 use dotenv::dotenv;
 use sqlx::PgPool;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod ami;
 mod api;
 mod config;
 mod db;
 mod error;
 mod models;
 mod services;
+mod sip;
 mod utils;
 
 #[tokio::main]
@@ -38,6 +40,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await
         .expect("Failed to connect to Postgres");
 
+    // SIP registrar
+    if let Ok(sip_bind) = std::env::var("SIP_REGISTRAR_BIND") {
+        let sip_addr: SocketAddr = sip_bind
+            .parse()
+            .expect("SIP_REGISTRAR_BIND must be a socket address");
+        let registrar = Arc::new(sip::registrar::SipRegistrar::from_env(pool.clone()));
+
+        let udp_registrar = registrar.clone();
+        tokio::spawn(async move {
+            if let Err(e) = udp_registrar.run_udp(sip_addr).await {
+                tracing::error!("SIP UDP listener stopped: {}", e);
+            }
+        });
+        let tcp_registrar = registrar.clone();
+        tokio::spawn(async move {
+            if let Err(e) = tcp_registrar.run_tcp(sip_addr).await {
+                tracing::error!("SIP TCP listener stopped: {}", e);
+            }
+        });
+        tokio::spawn(registrar.run_expiry_sweeper());
+    }
+
+    // AMI call tracking
+    if let Some(ami_config) = ami::client::AmiConfig::from_env() {
+        let tracker = ami::call_tracker::AmiCallTracker::new(pool.clone(), ami_config);
+        tokio::spawn(tracker.run());
+    }
+
+    // Recording retention
+    tokio::spawn(services::recording::RecordingService::new(pool.clone()).run_retention_job());
+
+    // Call rating
+    tokio::spawn(services::billing::BillingService::new(pool.clone()).run_rating_job());
+
+    // Outbound fax queue
+    tokio::spawn(services::fax::FaxService::new(pool.clone()).run_fax_job());
+
+    // Instant messaging and presence fan-out to WebSocket connections
+    let chat_hub = services::chat::ChatHub::new();
+    tokio::spawn(services::presence::PresenceService::new(pool.clone(), chat_hub.clone()).run_presence_job());
+
     // CORS configuration
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -49,9 +92,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .merge(api::system::router())
         .merge(api::auth::router())
         .merge(api::pbx::router())
+        .merge(api::originate::router())
+        .merge(api::call_handling::router())
+        .merge(api::provisioning::router())
+        .merge(api::cdr::router())
+        .merge(api::analytics::router())
+        .merge(api::billing::router())
+        .merge(api::asterisk::router())
+        .merge(api::routing::router())
+        .merge(api::screening::router())
+        .merge(api::trunk::router())
+        .merge(api::queue::router())
+        .merge(api::conference::router())
+        .merge(api::ring_group::router())
+        .merge(api::recording::router())
+        .merge(api::ivr::router())
+        .merge(api::voicemail::router())
         .merge(api::calendar::router())
         .merge(api::email::router())
+        .merge(api::fax::router())
+        .merge(api::chat::router())
+        .merge(api::presence::router())
+        .merge(api::contact::router())
         .layer(cors)
+        .layer(Extension(chat_hub))
         .layer(Extension(pool));
 
     // Run our application
@@ -32,29 +32,35 @@ where
             .await
             .map_err(|_| AppError::Auth("Missing authorization header".into()))?;
 
-        // Decode the token
-        let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-        let token_data = decode::<Claims>(
-            bearer.token(),
-            &DecodingKey::from_secret(jwt_secret.as_bytes()),
-            &Validation::default(),
-        )
-        .map_err(|_| AppError::Auth("Invalid token".into()))?;
+        decode_token(bearer.token())
+    }
+}
 
-        let claims = token_data.claims;
+/// Validates a JWT and returns the user it was issued to. Also used where the
+/// token does not come in a header, e.g. WebSocket upgrades.
+pub fn decode_token(token: &str) -> Result<AuthUser, AppError> {
+    // Decode the token
+    let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+    let token_data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|_| AppError::Auth("Invalid token".into()))?;
 
-        // Check if the token is expired
-        let current_time = chrono::Utc::now().timestamp();
-        if claims.exp < current_time {
-            return Err(AppError::Auth("Token expired".into()));
-        }
+    let claims = token_data.claims;
 
-        Ok(AuthUser {
-            user_id: claims.sub,
-            username: claims.username,
-            role: claims.role,
-        })
+    // Check if the token is expired
+    let current_time = chrono::Utc::now().timestamp();
+    if claims.exp < current_time {
+        return Err(AppError::Auth("Token expired".into()));
     }
+
+    Ok(AuthUser {
+        user_id: claims.sub,
+        username: claims.username,
+        role: claims.role,
+    })
 }
 
 pub async fn require_auth<B>(
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// IANA time zone for the busiest hours, e.g. "Europe/Berlin". Defaults to UTC.
+    pub tz: Option<String>,
+}
+
+/// Calls between two extensions are internal; calls from an extension to any
+/// other number are outbound; everything else is inbound.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum CallDirection {
+    Inbound,
+    Outbound,
+    Internal,
+}
+
+/// Raw counts by final status. Talk time only covers answered calls.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct CallCounts {
+    pub total: i64,
+    pub answered: i64,
+    pub busy: i64,
+    pub no_answer: i64,
+    pub failed: i64,
+    pub active: i64,
+    pub talk_seconds: i64,
+}
+
+/// Counts with the ratios derived from them. Ratios are percentages of
+/// finished calls and are `None` when there are none.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CallKpis {
+    #[serde(flatten)]
+    pub counts: CallCounts,
+    /// Answer-seizure ratio.
+    pub asr: Option<f64>,
+    /// Average call duration of answered calls.
+    pub acd_seconds: Option<f64>,
+    pub busy_rate: Option<f64>,
+    pub no_answer_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PeakConcurrency {
+    pub calls: i64,
+    /// When the peak was first reached; `None` without any calls.
+    pub at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HourlyCalls {
+    /// Hour of the day, 0 to 23, in the requested time zone.
+    pub hour: i32,
+    pub calls: i64,
+    pub answered: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectionStats {
+    pub direction: CallDirection,
+    #[serde(flatten)]
+    pub kpis: CallKpis,
+}
+
+/// Calls an extension placed or received. Calls between two extensions count
+/// for both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionStats {
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub name: String,
+    pub placed: i64,
+    pub received: i64,
+    #[serde(flatten)]
+    pub kpis: CallKpis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallAnalytics {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub summary: CallKpis,
+    pub peak_concurrency: PeakConcurrency,
+    /// Hours with calls, busiest first.
+    pub busiest_hours: Vec<HourlyCalls>,
+    pub by_direction: Vec<DirectionStats>,
+    /// Extensions with calls, most calls first.
+    pub by_extension: Vec<ExtensionStats>,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::models::{
+    conference::ConferenceRoom,
+    fax::FaxLine,
+    ivr::IvrMenu,
+    pbx::PbxExtension,
+    queue::QueueResponse,
+    recording::RecordingPolicy,
+    ring_group::RingGroupResponse,
+    routing::{InboundRoute, OutboundRoute},
+    trunk::SipTrunk,
+    voicemail::VoicemailBox,
+};
+
+/// Everything the config generator reads from the database in one pass.
+#[derive(Debug, Default)]
+pub struct ConfigSnapshot {
+    pub extensions: Vec<PbxExtension>,
+    pub inbound_routes: Vec<InboundRoute>,
+    pub outbound_routes: Vec<OutboundRoute>,
+    pub queues: Vec<QueueResponse>,
+    pub voicemail_boxes: Vec<VoicemailBox>,
+    pub ivr_menus: Vec<IvrMenu>,
+    pub ring_groups: Vec<RingGroupResponse>,
+    pub recording_policies: Vec<RecordingPolicy>,
+    pub trunks: Vec<SipTrunk>,
+    pub conference_rooms: Vec<ConferenceRoom>,
+    pub fax_lines: Vec<FaxLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedConfigFile {
+    pub name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigFileDiff {
+    pub name: String,
+    pub path: String,
+    pub changed: bool,
+    pub diff: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigPreview {
+    pub files: Vec<ConfigFileDiff>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApplyConfigResponse {
+    pub files_written: Vec<String>,
+    pub applied_at: DateTime<Utc>,
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+/// A price list. Calls from an extension are rated with the table assigned
+/// to it, or with the default table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateTable {
+    pub id: Uuid,
+    pub name: String,
+    /// ISO 4217 code, e.g. "EUR".
+    pub currency: String,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateRateTableRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateRateTableRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    pub is_default: Option<bool>,
+}
+
+/// Price of calls to numbers starting with `prefix`. The longest matching
+/// prefix in a table wins.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Rate {
+    pub id: Uuid,
+    pub rate_table_id: Uuid,
+    pub prefix: String,
+    pub description: Option<String>,
+    pub rate_per_minute: Decimal,
+    /// Charged once per answered call.
+    pub connection_fee: Decimal,
+    /// Talk time is rounded up to a multiple of this many seconds.
+    pub billing_increment: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateRateRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub prefix: String,
+    #[validate(length(min = 1, max = 100))]
+    pub description: Option<String>,
+    pub rate_per_minute: Decimal,
+    pub connection_fee: Option<Decimal>,
+    #[validate(range(min = 1, max = 3600))]
+    pub billing_increment: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateRateRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub description: Option<String>,
+    pub rate_per_minute: Option<Decimal>,
+    pub connection_fee: Option<Decimal>,
+    #[validate(range(min = 1, max = 3600))]
+    pub billing_increment: Option<i32>,
+}
+
+/// Assigns a rate table to an extension; `None` falls back to the default table.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignRateTableRequest {
+    pub rate_table_id: Option<Uuid>,
+}
+
+/// What a rated call costs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CallCost {
+    pub billed_seconds: i32,
+    pub cost: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateCallsRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Also replace charges of calls that were rated before.
+    #[serde(default)]
+    pub rerate: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RatingResult {
+    pub rated: u64,
+    /// Calls from an extension that could not be priced; see `unrated_calls`.
+    pub unrated: u64,
+    pub unrated_calls: Vec<UnratedCall>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnratedReason {
+    NoRateTable,
+    NoMatchingRate,
+    /// The call has no answer time, so its talk time is unknown.
+    NotAnswered,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnratedCall {
+    pub call_id: Uuid,
+    pub reason: UnratedReason,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InvoiceLine {
+    pub prefix: String,
+    pub description: Option<String>,
+    pub calls: i64,
+    pub billed_seconds: i64,
+    pub cost: Decimal,
+}
+
+/// Charges of one extension over a billing period, grouped by destination.
+/// An extension whose calls were rated in several currencies gets one
+/// invoice per currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub extension_name: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub currency: String,
+    pub lines: Vec<InvoiceLine>,
+    pub total_calls: i64,
+    pub total_seconds: i64,
+    /// Sum of the lines, rounded to cents.
+    pub total: Decimal,
+}
+
+/// One rated call as stored, used to build invoices.
+#[derive(Debug, Clone)]
+pub struct CallCharge {
+    pub call_id: Uuid,
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub extension_name: String,
+    pub currency: String,
+    pub prefix: String,
+    pub description: Option<String>,
+    pub billed_seconds: i32,
+    pub cost: Decimal,
+}
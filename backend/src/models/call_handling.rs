@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+pub struct FollowMeDestination {
+    #[validate(length(min = 1, max = 30))]
+    pub number: String,
+    /// Seconds to ring this destination before moving on.
+    #[validate(range(min = 5, max = 120))]
+    pub ring_time: i32,
+}
+
+/// How calls to an extension are handled. Forwarding targets are dialled
+/// from the internal context, so they may be extensions or external numbers
+/// in the form the outbound routes expect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHandlingSettings {
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub dnd: bool,
+    pub forward_always: Option<String>,
+    pub forward_busy: Option<String>,
+    pub forward_no_answer: Option<String>,
+    pub follow_me_enabled: bool,
+    /// Tried in order after the extension itself rings out.
+    pub follow_me: Vec<FollowMeDestination>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Replaces all call handling settings of an extension.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetCallHandlingRequest {
+    #[serde(default)]
+    pub dnd: bool,
+    #[validate(length(min = 1, max = 30))]
+    pub forward_always: Option<String>,
+    #[validate(length(min = 1, max = 30))]
+    pub forward_busy: Option<String>,
+    #[validate(length(min = 1, max = 30))]
+    pub forward_no_answer: Option<String>,
+    #[serde(default)]
+    pub follow_me_enabled: bool,
+    #[serde(default)]
+    #[validate]
+    pub follow_me: Vec<FollowMeDestination>,
+}
+
+/// A setting changed from a phone with a feature code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureCodeChange {
+    Dnd(bool),
+    ForwardAlways(Option<String>),
+    ForwardBusy(Option<String>),
+    ForwardNoAnswer(Option<String>),
+    FollowMe(bool),
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::models::pbx::CallStatus;
+
+#[derive(Debug, Deserialize)]
+pub struct CdrExportQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub caller_id: Option<String>,
+    pub status: Option<CallStatus>,
+}
+
+/// One usable row of an Asterisk `Master.csv` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterCsvRecord {
+    pub uniqueid: String,
+    pub caller_id: String,
+    pub recipient_id: String,
+    pub start_time: DateTime<Utc>,
+    pub answered_at: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration: i32,
+    pub status: CallStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CdrImportError {
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CdrImportResult {
+    pub imported: u64,
+    pub duplicates: u64,
+    pub errors: Vec<CdrImportError>,
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+use crate::models::presence::UserPresence;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "chat_conversation_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ConversationKind {
+    Direct,
+    Group,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: Uuid,
+    pub kind: ConversationKind,
+    /// Always set for groups; direct conversations are named after the other member.
+    pub name: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A member with their read receipt: the last message they have read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub last_read_message_id: Option<Uuid>,
+    pub last_read_at: Option<DateTime<Utc>>,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// A conversation as seen by one member.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationResponse {
+    #[serde(flatten)]
+    pub conversation: Conversation,
+    pub members: Vec<ConversationMember>,
+    /// Messages from others after the member's read receipt.
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatMessage {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub sender_username: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateConversationRequest {
+    pub kind: ConversationKind,
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    /// The other participants; the creator is always a member.
+    #[validate(length(min = 1, max = 100))]
+    pub member_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddMemberRequest {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SendMessageRequest {
+    #[validate(length(min = 1, max = 4000))]
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarkReadRequest {
+    pub message_id: Uuid,
+}
+
+/// Pages go backwards in time: pass the previous page's `next_cursor` as
+/// `before` to get older messages.
+#[derive(Debug, Deserialize)]
+pub struct MessageHistoryQuery {
+    pub before: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessagePage {
+    /// Newest first.
+    pub messages: Vec<ChatMessage>,
+    /// Set when older messages may exist.
+    pub next_cursor: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChatSearchQuery {
+    #[validate(length(min = 1, max = 200))]
+    pub q: String,
+    pub conversation_id: Option<Uuid>,
+    pub before: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatSearchHit {
+    #[serde(flatten)]
+    pub message: ChatMessage,
+    /// The matching part of the body as HTML: the text is escaped and matches
+    /// are wrapped in `<b>`.
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatSearchPage {
+    pub hits: Vec<ChatSearchHit>,
+    pub next_cursor: Option<Uuid>,
+}
+
+/// Browsers cannot set headers on WebSocket requests, so the token may also
+/// come in the query.
+#[derive(Debug, Deserialize)]
+pub struct ChatSocketQuery {
+    pub token: Option<String>,
+}
+
+/// Frames a client sends over the WebSocket.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatClientEvent {
+    Send { conversation_id: Uuid, body: String },
+    Typing { conversation_id: Uuid },
+    Read { conversation_id: Uuid, message_id: Uuid },
+    /// Pushes presence changes of these users to this connection, or of
+    /// everyone if the list is empty. Replaces earlier subscriptions.
+    SubscribePresence {
+        #[serde(default)]
+        user_ids: Vec<Uuid>,
+    },
+    UnsubscribePresence,
+}
+
+/// Frames pushed to the members of a conversation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatServerEvent {
+    Message {
+        message: ChatMessage,
+    },
+    Read {
+        conversation_id: Uuid,
+        user_id: Uuid,
+        message_id: Uuid,
+        read_at: DateTime<Utc>,
+    },
+    Typing {
+        conversation_id: Uuid,
+        user_id: Uuid,
+        username: String,
+    },
+    /// The recipient was added to a conversation, or its members changed.
+    ConversationUpdated {
+        conversation_id: Uuid,
+    },
+    /// A member left or was removed; also sent to that member.
+    ConversationLeft {
+        conversation_id: Uuid,
+        user_id: Uuid,
+    },
+    /// A presence change of a user the connection subscribed to.
+    Presence {
+        presence: UserPresence,
+    },
+    /// The current presence of the users, sent when subscribing.
+    PresenceSnapshot {
+        presence: Vec<UserPresence>,
+    },
+    /// Only sent to the connection whose frame failed.
+    Error {
+        message: String,
+    },
+}
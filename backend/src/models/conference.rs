@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConferenceRoom {
+    pub id: Uuid,
+    /// Dialable number, shared with extensions and ring groups.
+    pub room_number: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub user_pin: Option<String>,
+    #[serde(skip_serializing)]
+    pub admin_pin: Option<String>,
+    pub max_participants: Option<i32>,
+    pub record: bool,
+    /// May see and control participants without being an admin.
+    pub owner_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateConferenceRoomRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub room_number: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub user_pin: Option<String>,
+    pub admin_pin: Option<String>,
+    #[validate(range(min = 2, max = 500))]
+    pub max_participants: Option<i32>,
+    pub record: Option<bool>,
+    pub owner_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateConferenceRoomRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub room_number: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub user_pin: Option<String>,
+    pub admin_pin: Option<String>,
+    #[validate(range(min = 2, max = 500))]
+    pub max_participants: Option<i32>,
+    pub record: Option<bool>,
+    pub owner_id: Option<Uuid>,
+}
+
+/// Someone currently in the room, as reported by Asterisk.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConferenceParticipant {
+    pub channel: String,
+    pub caller_id_num: Option<String>,
+    pub caller_id_name: Option<String>,
+    pub admin: bool,
+    pub muted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ParticipantActionRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub channel: String,
+}
+
+/// One stay in a room. `left_at` is empty while the participant is still there.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConferenceAttendance {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub channel: String,
+    pub caller_id_num: Option<String>,
+    pub caller_id_name: Option<String>,
+    pub admin: bool,
+    pub joined_at: DateTime<Utc>,
+    pub left_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConferenceHistoryQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
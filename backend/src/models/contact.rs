@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "contact_label", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ContactLabel {
+    Work,
+    Home,
+    Mobile,
+    Fax,
+    Other,
+}
+
+/// Which address book a contact belongs to. Company contacts have no owner.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactScope {
+    #[default]
+    Personal,
+    Company,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContactNumber {
+    pub label: ContactLabel,
+    /// As entered or imported.
+    pub number: String,
+    /// Unset for numbers that cannot be normalized, such as internal extensions.
+    pub e164: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContactEmail {
+    pub label: ContactLabel,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub id: Uuid,
+    /// Unset for contacts in the company phonebook.
+    pub owner_id: Option<Uuid>,
+    pub display_name: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub organization: Option<String>,
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub numbers: Vec<ContactNumber>,
+    pub emails: Vec<ContactEmail>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+pub struct ContactNumberInput {
+    #[serde(default = "default_label")]
+    pub label: ContactLabel,
+    #[validate(length(min = 1, max = 40))]
+    pub number: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+pub struct ContactEmailInput {
+    #[serde(default = "default_label")]
+    pub label: ContactLabel,
+    #[validate(email)]
+    pub email: String,
+}
+
+fn default_label() -> ContactLabel {
+    ContactLabel::Work
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+pub struct CreateContactRequest {
+    #[serde(default)]
+    pub scope: ContactScope,
+    /// Defaults to the first and last name, or the organization.
+    #[validate(length(min = 1, max = 200))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub first_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub last_name: Option<String>,
+    #[validate(length(max = 200))]
+    pub organization: Option<String>,
+    #[validate(length(max = 100))]
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    #[validate]
+    pub numbers: Vec<ContactNumberInput>,
+    #[serde(default)]
+    #[validate]
+    pub emails: Vec<ContactEmailInput>,
+}
+
+/// Numbers and emails replace the existing ones when given.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateContactRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub first_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub last_name: Option<String>,
+    #[validate(length(max = 200))]
+    pub organization: Option<String>,
+    #[validate(length(max = 100))]
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub numbers: Option<Vec<ContactNumberInput>>,
+    pub emails: Option<Vec<ContactEmailInput>>,
+}
+
+/// Lists the user's personal contacts and the company phonebook, or one of
+/// them. `q` matches names, organization, emails and numbers.
+#[derive(Debug, Deserialize)]
+pub struct ContactListQuery {
+    pub scope: Option<ContactScope>,
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum VcardVersion {
+    #[serde(rename = "3.0")]
+    V3,
+    #[default]
+    #[serde(rename = "4.0")]
+    V4,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContactExportQuery {
+    pub scope: Option<ContactScope>,
+    #[serde(default)]
+    pub version: VcardVersion,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContactImportQuery {
+    #[serde(default)]
+    pub scope: ContactScope,
+}
+
+/// A card that could not be imported; `index` counts from 0 in file order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactImportError {
+    pub index: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactImportResult {
+    pub imported: usize,
+    pub errors: Vec<ContactImportError>,
+}
+
+/// Looks up a number, or the caller of a call record.
+#[derive(Debug, Deserialize)]
+pub struct ContactLookupQuery {
+    pub number: Option<String>,
+    pub call_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactMatch {
+    pub contact_id: Uuid,
+    pub display_name: String,
+    pub organization: Option<String>,
+    pub scope: ContactScope,
+    pub label: ContactLabel,
+    pub number: String,
+}
+
+/// Personal matches come before company ones.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactLookup {
+    pub number: String,
+    pub e164: Option<String>,
+    pub matches: Vec<ContactMatch>,
+}
@@ -55,6 +55,14 @@ pub struct EmailAttachment {
     pub path: String,
 }
 
+/// A file attached to a system-generated message, e.g. a voicemail recording.
+#[derive(Debug, Clone)]
+pub struct OutgoingAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmailMetrics {
     pub total_sent: i64,
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+/// A fax number. Calls to the DID are answered as faxes and delivered to
+/// `email`; faxes emailed with the line's token are sent from the DID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaxLine {
+    pub id: Uuid,
+    pub did: String,
+    pub name: String,
+    pub email: Option<String>,
+    /// Identifier sent to the remote machine, usually the number in international format.
+    pub station_id: Option<String>,
+    pub owner_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateFaxLineRequest {
+    #[validate(length(min = 3, max = 20))]
+    pub did: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 20))]
+    pub station_id: Option<String>,
+    pub owner_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateFaxLineRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 20))]
+    pub station_id: Option<String>,
+    pub owner_id: Option<Uuid>,
+}
+
+/// The secret that authorizes email-to-fax for a line. Messages are sent to
+/// `<destination>+<token>@<fax domain>`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaxEmailToken {
+    pub line_id: Uuid,
+    pub token: String,
+}
+
+/// A received fax, stored as PDF. `line_id` is empty when the DID has no
+/// fax line any more.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboundFax {
+    pub id: Uuid,
+    pub line_id: Option<Uuid>,
+    pub did: String,
+    pub sender: String,
+    pub remote_station_id: Option<String>,
+    pub pages: i32,
+    pub file_path: String,
+    pub size_bytes: i64,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub delivery_error: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "fax_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FaxJobStatus {
+    Queued,
+    Sending,
+    Sent,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "fax_job_source", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FaxJobSource {
+    Upload,
+    Email,
+}
+
+/// An outbound fax. Failed attempts are retried until `max_attempts` is
+/// reached; `next_attempt_at` is when a queued job is picked up.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaxJob {
+    pub id: Uuid,
+    pub line_id: Uuid,
+    pub source: FaxJobSource,
+    pub destination: String,
+    pub file_path: String,
+    #[serde(skip_serializing)]
+    pub tiff_path: String,
+    pub status: FaxJobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub pages: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Query of a PDF upload; the body is the document.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SendFaxQuery {
+    pub line_id: Uuid,
+    #[validate(length(min = 3, max = 21))]
+    pub destination: String,
+    #[validate(range(min = 1, max = 10))]
+    pub max_attempts: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FaxListQuery {
+    pub line_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Outcome of one send attempt as reported by the dialplan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaxAttemptResult {
+    pub success: bool,
+    pub error: Option<String>,
+    pub pages: Option<i32>,
+}
+
+/// A fax the dialplan finished receiving into the spool directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedFax {
+    pub uniqueid: String,
+    pub did: String,
+    pub sender: String,
+    pub remote_station_id: Option<String>,
+    pub pages: i32,
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IvrDestinationType {
+    Extension,
+    Queue,
+    Node,
+    Hangup,
+}
+
+/// Where a caller goes next. `target` is an extension number, queue name or
+/// node key, and is unused for `hangup`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IvrDestination {
+    #[serde(rename = "type")]
+    pub destination_type: IvrDestinationType,
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IvrOption {
+    /// A single DTMF key: 0-9, * or #.
+    pub digit: String,
+    pub destination: IvrDestination,
+}
+
+/// A node with options plays its prompt and waits for input. A node without
+/// options only plays its prompt and continues to `timeout_destination`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IvrNode {
+    pub key: String,
+    /// Asterisk sound file name without extension, e.g. "custom/welcome".
+    pub prompt: Option<String>,
+    #[serde(default = "default_node_timeout")]
+    pub timeout: i32,
+    #[serde(default)]
+    pub options: Vec<IvrOption>,
+    pub timeout_destination: Option<IvrDestination>,
+    pub invalid_destination: Option<IvrDestination>,
+}
+
+fn default_node_timeout() -> i32 {
+    5
+}
+
+impl IvrNode {
+    pub fn waits_for_input(&self) -> bool {
+        !self.options.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IvrMenu {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub entry_node: String,
+    pub nodes: Json<Vec<IvrNode>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateIvrMenuRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub entry_node: String,
+    #[validate(length(min = 1, max = 100))]
+    pub nodes: Vec<IvrNode>,
+}
+
+/// Replaces the whole graph; it is validated again before saving.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateIvrMenuRequest {
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub entry_node: String,
+    #[validate(length(min = 1, max = 100))]
+    pub nodes: Vec<IvrNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IvrValidationResponse {
+    pub valid: bool,
+    pub errors: Vec<String>,
+}
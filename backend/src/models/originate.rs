@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+/// Rings the user's own phone and, once it is answered, dials the
+/// destination from it.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct OriginateCallRequest {
+    /// Number of the extension whose phone should ring.
+    #[validate(length(min = 1, max = 20))]
+    pub extension: String,
+    #[validate(length(min = 1, max = 30))]
+    pub destination: String,
+    /// Seconds to ring the phone before giving up.
+    #[validate(range(min = 5, max = 120))]
+    pub ring_timeout: Option<u32>,
+}
+
+/// Lets a user place calls from the extensions they own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OriginatePermission {
+    pub user_id: Uuid,
+    pub granted_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
@@ -11,11 +11,13 @@ pub struct PbxExtension {
     pub name: String,
     pub extension_type: ExtensionType,
     pub config_data: JsonValue,
+    /// The user whose phone this is; they may manage its call handling.
+    pub owner_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ExtensionType {
     Sip,
@@ -36,7 +38,8 @@ pub struct CallRecord {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum CallStatus {
     Active,
@@ -46,6 +49,133 @@ pub enum CallStatus {
     NoAnswer,
 }
 
+impl CallStatus {
+    pub fn is_final(&self) -> bool {
+        *self != CallStatus::Active
+    }
+
+    /// A call starts active and ends exactly once; ended calls never reopen.
+    pub fn can_transition_to(&self, next: CallStatus) -> bool {
+        *self == CallStatus::Active && next.is_final()
+    }
+}
+
+/// Where a call status change came from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "call_event_source", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CallEventSource {
+    Api,
+    Ami,
+    Import,
+}
+
+/// One status change of a call. `from_status` is empty for the event that
+/// created the record.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CallEvent {
+    pub id: Uuid,
+    pub call_id: Uuid,
+    pub from_status: Option<CallStatus>,
+    pub to_status: CallStatus,
+    pub source: CallEventSource,
+    pub user_id: Option<Uuid>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// PJSIP transport an endpoint is bound to; maps to the `transport-<name>`
+/// sections of pjsip.conf.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionTransport {
+    Udp,
+    Tcp,
+    Tls,
+    Ws,
+    Wss,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DtmfMode {
+    Rfc4733,
+    Inband,
+    Info,
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IaxEncryption {
+    No,
+    Aes128,
+}
+
+/// `config_data` of a SIP extension. Unknown keys are rejected so typos do
+/// not go unnoticed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct SipExtensionConfig {
+    #[validate(length(min = 6, max = 100))]
+    pub secret: String,
+    /// Defaults to the extension number.
+    #[validate(length(min = 1, max = 100))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_username: Option<String>,
+    /// Dialplan context for calls from the phone; defaults to `from-internal`.
+    #[validate(length(min = 1, max = 80))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codecs: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nat: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<ExtensionTransport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dtmf_mode: Option<DtmfMode>,
+    #[validate(range(min = 1, max = 10))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_contacts: Option<i64>,
+    #[validate(range(min = 5, max = 300))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ring_timeout: Option<i64>,
+}
+
+/// `config_data` of an IAX2 extension. iax.conf is not generated yet, so these
+/// are kept for the administrator's own configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct IaxExtensionConfig {
+    /// IAX2 trunking, which bundles calls to the same peer into one stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trunk: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<IaxEncryption>,
+    #[validate(range(min = 5, max = 300))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ring_timeout: Option<i64>,
+}
+
+/// `config_data` of a custom extension, which dials an arbitrary target.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct CustomExtensionConfig {
+    /// Passed to `Dial()`, e.g. `Local/500@ivr`.
+    #[validate(length(min = 1, max = 255))]
+    pub dial_string: String,
+    #[validate(range(min = 5, max = 300))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ring_timeout: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtensionConfig {
+    Sip(SipExtensionConfig),
+    Iax(IaxExtensionConfig),
+    Custom(CustomExtensionConfig),
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateExtensionRequest {
     #[validate(length(min = 3, max = 20))]
@@ -54,6 +184,32 @@ pub struct CreateExtensionRequest {
     pub name: String,
     pub extension_type: ExtensionType,
     pub config_data: JsonValue,
+    pub owner_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtensionImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Problems found in one CSV row. `line` counts the header as line 1.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExtensionImportRow {
+    pub line: u64,
+    pub extension_number: Option<String>,
+    pub errors: Vec<String>,
+}
+
+/// Per-row report of a bulk import. Nothing is created unless every row is
+/// valid, so `created` is 0 for dry runs and rejected files.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtensionImportResult {
+    pub dry_run: bool,
+    pub total: u64,
+    pub valid: u64,
+    pub created: u64,
+    pub rows: Vec<ExtensionImportRow>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -62,6 +218,10 @@ pub struct UpdateExtensionRequest {
     pub name: Option<String>,
     pub extension_type: Option<ExtensionType>,
     pub config_data: Option<JsonValue>,
+    /// Only admins may set or clear the owner.
+    pub owner_id: Option<Uuid>,
+    /// Removes the owner.
+    pub clear_owner: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,10 +232,55 @@ pub struct CreateCallRecordRequest {
     pub status: CallStatus,
 }
 
+/// Ends an active call. The duration is worked out from `start_time` and
+/// `end_time` on the server.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateCallRecordRequest {
     pub end_time: DateTime<Utc>,
-    pub duration: i32,
     pub status: CallStatus,
     pub recording_path: Option<String>,
-} 
\ No newline at end of file
+    /// When the call was answered. A completed call without one counts as
+    /// answered at its start.
+    pub answered_at: Option<DateTime<Utc>>,
+} 
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "sip_transport", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SipTransport {
+    Udp,
+    Tcp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SipRegistration {
+    pub id: Uuid,
+    pub extension_id: Uuid,
+    pub contact_uri: String,
+    pub user_agent: Option<String>,
+    pub source_address: String,
+    pub transport: SipTransport,
+    pub call_id: String,
+    pub cseq: i32,
+    pub expires_at: DateTime<Utc>,
+    pub registered_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtensionRegistrationStatus {
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub registered: bool,
+    pub contacts: Vec<SipRegistration>,
+}
+
+#[derive(Debug)]
+pub struct UpsertRegistrationRequest {
+    pub extension_id: Uuid,
+    pub contact_uri: String,
+    pub user_agent: Option<String>,
+    pub source_address: String,
+    pub transport: SipTransport,
+    pub call_id: String,
+    pub cseq: i32,
+    pub expires_at: DateTime<Utc>,
+}
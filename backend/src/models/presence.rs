@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "presence_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    Available,
+    OnThePhone,
+    InAMeeting,
+    Dnd,
+    Offline,
+}
+
+/// The state presence is derived from.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct PresenceSignals {
+    /// The phone of one of the user's extensions is in an answered call.
+    pub on_call: bool,
+    /// One of the user's extensions has do not disturb on.
+    pub dnd: bool,
+    /// A calendar event the user created or attends is under way.
+    pub in_meeting: bool,
+    /// The user has a chat connection or a registered phone.
+    pub online: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserPresence {
+    pub user_id: Uuid,
+    pub username: String,
+    pub status: PresenceStatus,
+    /// Set while a manual override decides the status.
+    pub manual: bool,
+    pub note: Option<String>,
+    /// When the manual override ends; never if unset.
+    pub until: Option<DateTime<Utc>>,
+    pub signals: PresenceSignals,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetPresenceOverrideRequest {
+    pub status: PresenceStatus,
+    #[validate(length(max = 255))]
+    pub note: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// `user_ids` is a comma-separated list; all users if unset.
+#[derive(Debug, Deserialize)]
+pub struct PresenceQuery {
+    pub user_ids: Option<String>,
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "phone_vendor", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PhoneVendor {
+    Yealink,
+    Polycom,
+    Grandstream,
+}
+
+/// A desk phone that fetches its configuration from OrionTel. The phone logs
+/// in to the provisioning server with its MAC address and `password`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhoneDevice {
+    pub id: Uuid,
+    /// Twelve lowercase hex digits without separators.
+    pub mac_address: String,
+    pub extension_id: Uuid,
+    pub vendor: PhoneVendor,
+    pub model: Option<String>,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub last_fetched_at: Option<DateTime<Utc>>,
+    pub last_fetch_address: Option<String>,
+    pub last_fetch_user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreatePhoneDeviceRequest {
+    /// Accepted with or without `:` or `-` separators, in either case.
+    #[validate(length(min = 12, max = 17))]
+    pub mac_address: String,
+    pub extension_id: Uuid,
+    pub vendor: PhoneVendor,
+    #[validate(length(min = 1, max = 50))]
+    pub model: Option<String>,
+    #[validate(length(min = 8, max = 100))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdatePhoneDeviceRequest {
+    pub extension_id: Option<Uuid>,
+    pub vendor: Option<PhoneVendor>,
+    #[validate(length(min = 1, max = 50))]
+    pub model: Option<String>,
+    #[validate(length(min = 8, max = 100))]
+    pub password: Option<String>,
+}
+
+/// The kinds of file phones ask the provisioning server for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProvisioningFile {
+    /// `<mac>.cfg`: the Yealink config, or the Polycom master config.
+    MacConfig,
+    /// `<mac>-sip.cfg`: the Polycom account config listed in the master config.
+    PolycomAccount,
+    /// `cfg<mac>.xml`: the Grandstream config.
+    GrandstreamXml,
+}
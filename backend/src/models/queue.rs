@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+use crate::models::routing::RouteDestinationType;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "queue_strategy")]
+pub enum QueueStrategy {
+    #[serde(rename = "ringall")]
+    #[sqlx(rename = "ringall")]
+    RingAll,
+    #[serde(rename = "round-robin")]
+    #[sqlx(rename = "round-robin")]
+    RoundRobin,
+    #[serde(rename = "least-recent")]
+    #[sqlx(rename = "least-recent")]
+    LeastRecent,
+    #[serde(rename = "fewest-calls")]
+    #[sqlx(rename = "fewest-calls")]
+    FewestCalls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallQueue {
+    pub id: Uuid,
+    pub name: String,
+    pub strategy: QueueStrategy,
+    pub ring_timeout: i32,
+    pub max_wait_time: i32,
+    pub overflow_destination_type: Option<RouteDestinationType>,
+    pub overflow_destination: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueMember {
+    pub id: Uuid,
+    pub queue_id: Uuid,
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub extension_name: String,
+    pub penalty: i32,
+    pub logged_in: bool,
+    pub paused: bool,
+    pub pause_reason: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueResponse {
+    pub queue: CallQueue,
+    pub members: Vec<QueueMember>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct QueueMemberRequest {
+    pub extension_id: Uuid,
+    #[validate(range(min = 0, max = 100))]
+    pub penalty: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateQueueRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub strategy: QueueStrategy,
+    #[validate(range(min = 5, max = 300))]
+    pub ring_timeout: Option<i32>,
+    #[validate(range(min = 0, max = 86400))]
+    pub max_wait_time: Option<i32>,
+    pub overflow_destination_type: Option<RouteDestinationType>,
+    #[validate(length(max = 100))]
+    pub overflow_destination: Option<String>,
+    #[validate]
+    pub members: Vec<QueueMemberRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateQueueRequest {
+    pub strategy: Option<QueueStrategy>,
+    #[validate(range(min = 5, max = 300))]
+    pub ring_timeout: Option<i32>,
+    #[validate(range(min = 0, max = 86400))]
+    pub max_wait_time: Option<i32>,
+    pub overflow_destination_type: Option<RouteDestinationType>,
+    #[validate(length(max = 100))]
+    pub overflow_destination: Option<String>,
+    /// Removes the overflow destination, so callers are hung up.
+    pub clear_overflow: Option<bool>,
+    /// Replaces the full member list when present.
+    #[validate]
+    pub members: Option<Vec<QueueMemberRequest>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct PauseAgentRequest {
+    #[validate(length(max = 100))]
+    pub reason: Option<String>,
+}
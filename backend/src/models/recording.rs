@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "recording_mode", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingMode {
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingPolicy {
+    pub id: Uuid,
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub mode: RecordingMode,
+    /// Overrides RECORDING_RETENTION_DAYS for calls involving this extension.
+    pub retention_days: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetRecordingPolicyRequest {
+    pub mode: RecordingMode,
+    #[validate(range(min = 1, max = 3650))]
+    pub retention_days: Option<i32>,
+}
+
+/// Format details read from a WAV file's `fmt ` and `data` chunks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WavInfo {
+    pub format_tag: u16,
+    pub codec: String,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub byte_rate: u32,
+    pub data_size: u64,
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub call_id: Uuid,
+    pub content_type: String,
+    pub size_bytes: u64,
+    /// Missing when the file is not a WAV file.
+    pub wav: Option<WavInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetentionRunResponse {
+    pub recordings_removed: u64,
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+use crate::models::routing::RouteDestinationType;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "ring_group_strategy", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RingGroupStrategy {
+    Simultaneous,
+    Sequential,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingGroup {
+    pub id: Uuid,
+    pub group_number: String,
+    pub name: String,
+    pub strategy: RingGroupStrategy,
+    /// Total time the group rings before failing over.
+    pub timeout: i32,
+    /// How long each member rings in sequential mode.
+    pub member_timeout: i32,
+    pub failover_destination_type: Option<RouteDestinationType>,
+    pub failover_destination: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingGroupMember {
+    pub id: Uuid,
+    pub ring_group_id: Uuid,
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub extension_name: String,
+    pub position: i32,
+    /// Seconds to wait before this member starts ringing.
+    pub delay: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingGroupResponse {
+    pub group: RingGroup,
+    pub members: Vec<RingGroupMember>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RingGroupMemberRequest {
+    pub extension_id: Uuid,
+    #[validate(range(min = 0, max = 300))]
+    pub delay: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateRingGroupRequest {
+    #[validate(length(min = 3, max = 20))]
+    pub group_number: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub strategy: RingGroupStrategy,
+    #[validate(range(min = 5, max = 600))]
+    pub timeout: Option<i32>,
+    #[validate(range(min = 5, max = 300))]
+    pub member_timeout: Option<i32>,
+    pub failover_destination_type: Option<RouteDestinationType>,
+    #[validate(length(max = 100))]
+    pub failover_destination: Option<String>,
+    /// Members ring in list order in sequential mode.
+    #[validate]
+    pub members: Vec<RingGroupMemberRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateRingGroupRequest {
+    #[validate(length(min = 3, max = 20))]
+    pub group_number: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub strategy: Option<RingGroupStrategy>,
+    #[validate(range(min = 5, max = 600))]
+    pub timeout: Option<i32>,
+    #[validate(range(min = 5, max = 300))]
+    pub member_timeout: Option<i32>,
+    pub failover_destination_type: Option<RouteDestinationType>,
+    #[validate(length(max = 100))]
+    pub failover_destination: Option<String>,
+    /// Replaces the full member list when present.
+    #[validate]
+    pub members: Option<Vec<RingGroupMemberRequest>>,
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+use crate::models::screening::ScreeningAction;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "route_destination_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RouteDestinationType {
+    Extension,
+    Queue,
+    Ivr,
+    Voicemail,
+    Hangup,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteDirection {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundRoute {
+    pub id: Uuid,
+    pub name: String,
+    pub did_pattern: Option<String>,
+    pub caller_id_pattern: Option<String>,
+    pub destination_type: RouteDestinationType,
+    pub destination: String,
+    pub priority: i32,
+    pub enabled: bool,
+    /// Applied to callers on the blocklist.
+    pub blocked_action: ScreeningAction,
+    /// Mailbox for `voicemail`, sound file for `message`.
+    pub blocked_destination: Option<String>,
+    /// Treats callers without a caller ID as blocked.
+    pub reject_anonymous: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundRoute {
+    pub id: Uuid,
+    pub name: String,
+    pub dial_pattern: String,
+    pub strip_digits: i32,
+    pub prepend: Option<String>,
+    pub trunk: String,
+    pub priority: i32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateInboundRouteRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1, max = 50))]
+    pub did_pattern: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub caller_id_pattern: Option<String>,
+    pub destination_type: RouteDestinationType,
+    #[validate(length(max = 100))]
+    pub destination: String,
+    pub priority: Option<i32>,
+    pub enabled: Option<bool>,
+    pub blocked_action: Option<ScreeningAction>,
+    #[validate(length(min = 1, max = 100))]
+    pub blocked_destination: Option<String>,
+    pub reject_anonymous: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateInboundRouteRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub did_pattern: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub caller_id_pattern: Option<String>,
+    pub destination_type: Option<RouteDestinationType>,
+    #[validate(length(max = 100))]
+    pub destination: Option<String>,
+    pub priority: Option<i32>,
+    pub enabled: Option<bool>,
+    pub blocked_action: Option<ScreeningAction>,
+    #[validate(length(min = 1, max = 100))]
+    pub blocked_destination: Option<String>,
+    pub reject_anonymous: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateOutboundRouteRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1, max = 50))]
+    pub dial_pattern: String,
+    #[validate(range(min = 0, max = 20))]
+    pub strip_digits: Option<i32>,
+    #[validate(length(max = 20))]
+    pub prepend: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub trunk: String,
+    pub priority: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateOutboundRouteRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub dial_pattern: Option<String>,
+    #[validate(range(min = 0, max = 20))]
+    pub strip_digits: Option<i32>,
+    #[validate(length(max = 20))]
+    pub prepend: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub trunk: Option<String>,
+    pub priority: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SimulateRouteRequest {
+    pub direction: RouteDirection,
+    /// The dialed DID for inbound calls, or the digits a user dialed for outbound calls.
+    #[validate(length(min = 1, max = 50))]
+    pub number: String,
+    #[validate(length(max = 50))]
+    pub caller_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleEvaluation {
+    pub route_id: Uuid,
+    pub route_name: String,
+    pub matched: bool,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSimulationResult {
+    pub direction: RouteDirection,
+    pub matched: bool,
+    pub route_id: Option<Uuid>,
+    pub route_name: Option<String>,
+    pub reason: String,
+    pub destination_type: Option<RouteDestinationType>,
+    pub destination: Option<String>,
+    pub trunk: Option<String>,
+    pub dialed_number: Option<String>,
+    pub evaluated: Vec<RuleEvaluation>,
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+/// Allow entries win over block entries, so a blocked prefix can have exceptions.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "screening_list", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningList {
+    #[default]
+    Block,
+    Allow,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "screening_match", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningMatch {
+    /// The caller's number, in any of its national or international forms.
+    #[default]
+    Exact,
+    /// Numbers starting with the pattern, e.g. `0900` or `+1900`.
+    Prefix,
+    /// A POSIX extended regular expression searched in the caller ID.
+    Regex,
+}
+
+/// What an inbound route does with a blocked caller.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "screening_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningAction {
+    #[default]
+    Reject,
+    Busy,
+    /// Straight to the mailbox in `blocked_destination`.
+    Voicemail,
+    /// Plays the sound file in `blocked_destination`, then hangs up.
+    Message,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningEntry {
+    pub id: Uuid,
+    pub list: ScreeningList,
+    pub match_type: ScreeningMatch,
+    pub pattern: String,
+    pub description: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    /// The call the entry was created from, if any.
+    pub call_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateScreeningEntryRequest {
+    #[serde(default)]
+    pub list: ScreeningList,
+    #[serde(default)]
+    pub match_type: ScreeningMatch,
+    #[validate(length(min = 1, max = 100))]
+    pub pattern: String,
+    #[validate(length(max = 200))]
+    pub description: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateScreeningEntryRequest {
+    pub list: Option<ScreeningList>,
+    pub match_type: Option<ScreeningMatch>,
+    #[validate(length(min = 1, max = 100))]
+    pub pattern: Option<String>,
+    #[validate(length(max = 200))]
+    pub description: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Removes the expiry, keeping the entry until it is deleted.
+    pub permanent: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreeningEntryQuery {
+    pub list: Option<ScreeningList>,
+    #[serde(default)]
+    pub include_expired: bool,
+}
+
+/// Blocks the caller of a call record.
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct BlockCallerRequest {
+    #[validate(length(max = 200))]
+    pub description: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreeningCheckQuery {
+    pub number: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScreeningCheckResult {
+    pub number: String,
+    pub blocked: bool,
+    /// Unset when no entry matches.
+    pub entry: Option<ScreeningEntry>,
+    /// Routes with `reject_anonymous` block these callers too.
+    pub anonymous: bool,
+}
+
+/// A blocked call, as reported by the generated dialplan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreeningDecision {
+    /// Linkedid of the call, which the call record is stored under.
+    pub uniqueid: String,
+    /// Unset when the caller was rejected for being anonymous.
+    pub entry_id: Option<Uuid>,
+    pub route_id: Option<Uuid>,
+    pub caller_id: String,
+    pub action: ScreeningAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningLogEntry {
+    pub id: Uuid,
+    pub call_id: Option<Uuid>,
+    pub entry_id: Option<Uuid>,
+    pub route_id: Option<Uuid>,
+    pub caller_id: String,
+    /// The matching pattern at the time of the call.
+    pub pattern: Option<String>,
+    pub anonymous: bool,
+    pub action: ScreeningAction,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreeningLogQuery {
+    pub call_id: Option<Uuid>,
+    pub entry_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
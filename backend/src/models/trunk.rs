@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "trunk_transport", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TrunkTransport {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SipTrunk {
+    pub id: Uuid,
+    /// Used as the PJSIP section name and referenced by outbound routes.
+    pub name: String,
+    pub host: String,
+    pub port: i32,
+    pub transport: TrunkTransport,
+    pub username: Option<String>,
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+    /// Whether Asterisk registers to the provider with the credentials above.
+    pub register: bool,
+    pub outbound_caller_id: Option<String>,
+    /// Concurrent outbound calls allowed through the trunk; `None` means no limit.
+    pub max_channels: Option<i32>,
+    pub codecs: Vec<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateTrunkRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1, max = 255))]
+    pub host: String,
+    #[validate(range(min = 1, max = 65535))]
+    pub port: Option<i32>,
+    pub transport: Option<TrunkTransport>,
+    #[validate(length(min = 1, max = 100))]
+    pub username: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub secret: Option<String>,
+    pub register: Option<bool>,
+    #[validate(length(min = 1, max = 50))]
+    pub outbound_caller_id: Option<String>,
+    #[validate(range(min = 1, max = 10000))]
+    pub max_channels: Option<i32>,
+    pub codecs: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateTrunkRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub host: Option<String>,
+    #[validate(range(min = 1, max = 65535))]
+    pub port: Option<i32>,
+    pub transport: Option<TrunkTransport>,
+    #[validate(length(min = 1, max = 100))]
+    pub username: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub secret: Option<String>,
+    pub register: Option<bool>,
+    #[validate(length(min = 1, max = 50))]
+    pub outbound_caller_id: Option<String>,
+    #[validate(range(min = 1, max = 10000))]
+    pub max_channels: Option<i32>,
+    pub codecs: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrunkRegistrationState {
+    Registered,
+    Unregistered,
+    Rejected,
+    Stopped,
+    /// The trunk does not register, or is disabled.
+    Disabled,
+    /// Asterisk could not be asked, or did not report the registration.
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrunkStatus {
+    pub trunk_id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    pub register: bool,
+    pub state: TrunkRegistrationState,
+    /// The status text as reported by Asterisk.
+    pub detail: Option<String>,
+}
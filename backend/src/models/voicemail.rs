@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoicemailBox {
+    pub id: Uuid,
+    pub extension_id: Uuid,
+    pub extension_number: String,
+    pub extension_name: String,
+    #[serde(skip_serializing)]
+    pub pin: String,
+    pub email: Option<String>,
+    pub owner_id: Option<Uuid>,
+    pub has_greeting: bool,
+    pub max_messages: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoicemailMessage {
+    pub id: Uuid,
+    pub mailbox_id: Uuid,
+    pub caller_id: String,
+    pub duration: i32,
+    #[serde(skip_serializing)]
+    pub file_path: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub read: bool,
+    pub received_at: DateTime<Utc>,
+}
+
+/// A message Asterisk's `VoiceMail()` left in its spool, as described by the
+/// `msgNNNN.txt` file next to the audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpoolMessage {
+    pub msg_id: String,
+    pub caller_id: String,
+    pub duration: i32,
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateVoicemailBoxRequest {
+    pub extension_id: Uuid,
+    #[validate(length(min = 4, max = 10))]
+    pub pin: String,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub owner_id: Option<Uuid>,
+    #[validate(range(min = 1, max = 1000))]
+    pub max_messages: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateVoicemailBoxRequest {
+    #[validate(length(min = 4, max = 10))]
+    pub pin: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub owner_id: Option<Uuid>,
+    #[validate(range(min = 1, max = 1000))]
+    pub max_messages: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct DepositVoicemailQuery {
+    #[validate(length(min = 1, max = 50))]
+    pub caller_id: String,
+    #[validate(range(min = 0))]
+    pub duration: i32,
+}
@@ -0,0 +1,262 @@
+use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    error::AppError,
+    models::analytics::{
+        CallAnalytics, CallCounts, CallDirection, CallKpis, DirectionStats, ExtensionStats, HourlyCalls,
+        PeakConcurrency,
+    },
+};
+
+/// Longest range a single report may cover.
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// `part` as a percentage of `whole`, rounded to two decimals.
+pub fn percentage(part: i64, whole: i64) -> Option<f64> {
+    (whole > 0).then(|| (part as f64 * 10_000.0 / whole as f64).round() / 100.0)
+}
+
+pub fn call_kpis(counts: CallCounts) -> CallKpis {
+    // Calls still in progress have no outcome yet
+    let finished = counts.total - counts.active;
+    CallKpis {
+        asr: percentage(counts.answered, finished),
+        acd_seconds: (counts.answered > 0)
+            .then(|| (counts.talk_seconds as f64 * 10.0 / counts.answered as f64).round() / 10.0),
+        busy_rate: percentage(counts.busy, finished),
+        no_answer_rate: percentage(counts.no_answer, finished),
+        counts,
+    }
+}
+
+pub fn sum_counts<'a>(counts: impl IntoIterator<Item = &'a CallCounts>) -> CallCounts {
+    counts.into_iter().fold(CallCounts::default(), |sum, counts| CallCounts {
+        total: sum.total + counts.total,
+        answered: sum.answered + counts.answered,
+        busy: sum.busy + counts.busy,
+        no_answer: sum.no_answer + counts.no_answer,
+        failed: sum.failed + counts.failed,
+        active: sum.active + counts.active,
+        talk_seconds: sum.talk_seconds + counts.talk_seconds,
+    })
+}
+
+fn parse_direction(direction: &str) -> CallDirection {
+    match direction {
+        "internal" => CallDirection::Internal,
+        "outbound" => CallDirection::Outbound,
+        _ => CallDirection::Inbound,
+    }
+}
+
+pub struct AnalyticsService {
+    pool: PgPool,
+}
+
+impl AnalyticsService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// All figures are aggregated in the database; no call rows are loaded.
+    /// Calls belong to the range by start time, except for the peak, which
+    /// counts every call overlapping the range.
+    pub async fn call_analytics(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        tz: Option<&str>,
+    ) -> Result<CallAnalytics, AppError> {
+        if from >= to {
+            return Err(AppError::Validation("'from' must be before 'to'".into()));
+        }
+        if to - from > chrono::Duration::days(MAX_RANGE_DAYS) {
+            return Err(AppError::Validation(format!("The range may cover at most {} days", MAX_RANGE_DAYS)));
+        }
+        let tz = tz.unwrap_or("UTC");
+        let known_tz = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1) as "exists!""#,
+            tz
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !known_tz {
+            return Err(AppError::Validation(format!("Unknown time zone '{}'", tz)));
+        }
+
+        let by_direction = self.direction_stats(from, to).await?;
+        let summary = call_kpis(sum_counts(by_direction.iter().map(|stats| &stats.kpis.counts)));
+
+        Ok(CallAnalytics {
+            from,
+            to,
+            summary,
+            peak_concurrency: self.peak_concurrency(from, to).await?,
+            busiest_hours: self.busiest_hours(from, to, tz).await?,
+            by_direction,
+            by_extension: self.extension_stats(from, to).await?,
+        })
+    }
+
+    async fn direction_stats(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<DirectionStats>, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH calls AS (
+                SELECT c.status, c.start_time, c.answered_at, c.end_time,
+                       EXISTS (SELECT 1 FROM pbx_extensions e WHERE e.extension_number = c.caller_id) as from_extension,
+                       EXISTS (SELECT 1 FROM internal_numbers i WHERE i.number = c.recipient_id) as to_internal
+                FROM call_records c
+                WHERE c.start_time >= $1 AND c.start_time < $2
+            )
+            SELECT
+                CASE
+                    WHEN from_extension AND to_internal THEN 'internal'
+                    WHEN from_extension THEN 'outbound'
+                    ELSE 'inbound'
+                END as "direction!",
+                COUNT(*) as "total!",
+                COUNT(*) FILTER (WHERE status = 'completed') as "answered!",
+                COUNT(*) FILTER (WHERE status = 'busy') as "busy!",
+                COUNT(*) FILTER (WHERE status = 'noanswer') as "no_answer!",
+                COUNT(*) FILTER (WHERE status = 'failed') as "failed!",
+                COUNT(*) FILTER (WHERE status = 'active') as "active!",
+                COALESCE(SUM(EXTRACT(EPOCH FROM end_time - COALESCE(answered_at, start_time)))
+                    FILTER (WHERE status = 'completed'), 0)::bigint as "talk_seconds!"
+            FROM calls
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DirectionStats {
+                direction: parse_direction(&row.direction),
+                kpis: call_kpis(CallCounts {
+                    total: row.total,
+                    answered: row.answered,
+                    busy: row.busy,
+                    no_answer: row.no_answer,
+                    failed: row.failed,
+                    active: row.active,
+                    talk_seconds: row.talk_seconds,
+                }),
+            })
+            .collect())
+    }
+
+    /// Sweeps call starts (+1) and ends (-1) in time order. At the same
+    /// instant ends come first, so back-to-back calls do not overlap. Calls
+    /// still active count until now.
+    async fn peak_concurrency(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<PeakConcurrency, AppError> {
+        let peak = sqlx::query!(
+            r#"
+            WITH spans AS (
+                SELECT GREATEST(start_time, $1) as span_start,
+                       LEAST(COALESCE(end_time, NOW()), $2) as span_end
+                FROM call_records
+                WHERE start_time < $2 AND (end_time IS NULL OR end_time > $1)
+            ),
+            points AS (
+                SELECT span_start as at, 1 as delta FROM spans WHERE span_end > span_start
+                UNION ALL
+                SELECT span_end, -1 FROM spans WHERE span_end > span_start
+            ),
+            running AS (
+                SELECT at, SUM(delta) OVER (ORDER BY at, delta ROWS UNBOUNDED PRECEDING) as concurrent
+                FROM points
+            )
+            SELECT at as "at!", concurrent as "concurrent!"
+            FROM running
+            ORDER BY concurrent DESC, at
+            LIMIT 1
+            "#,
+            from,
+            to,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match peak {
+            Some(peak) => PeakConcurrency {
+                calls: peak.concurrent,
+                at: Some(peak.at),
+            },
+            None => PeakConcurrency { calls: 0, at: None },
+        })
+    }
+
+    async fn busiest_hours(&self, from: DateTime<Utc>, to: DateTime<Utc>, tz: &str) -> Result<Vec<HourlyCalls>, AppError> {
+        let hours = sqlx::query_as!(
+            HourlyCalls,
+            r#"
+            SELECT EXTRACT(HOUR FROM start_time AT TIME ZONE $3)::int as "hour!",
+                   COUNT(*) as "calls!",
+                   COUNT(*) FILTER (WHERE status = 'completed') as "answered!"
+            FROM call_records
+            WHERE start_time >= $1 AND start_time < $2
+            GROUP BY 1
+            ORDER BY 2 DESC, 1
+            "#,
+            from,
+            to,
+            tz,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(hours)
+    }
+
+    async fn extension_stats(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<ExtensionStats>, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT e.id, e.extension_number, e.name,
+                   COUNT(*) FILTER (WHERE c.caller_id = e.extension_number) as "placed!",
+                   COUNT(*) FILTER (WHERE c.recipient_id = e.extension_number) as "received!",
+                   COUNT(*) as "total!",
+                   COUNT(*) FILTER (WHERE c.status = 'completed') as "answered!",
+                   COUNT(*) FILTER (WHERE c.status = 'busy') as "busy!",
+                   COUNT(*) FILTER (WHERE c.status = 'noanswer') as "no_answer!",
+                   COUNT(*) FILTER (WHERE c.status = 'failed') as "failed!",
+                   COUNT(*) FILTER (WHERE c.status = 'active') as "active!",
+                   COALESCE(SUM(EXTRACT(EPOCH FROM c.end_time - COALESCE(c.answered_at, c.start_time)))
+                       FILTER (WHERE c.status = 'completed'), 0)::bigint as "talk_seconds!"
+            FROM pbx_extensions e
+            JOIN call_records c ON c.caller_id = e.extension_number OR c.recipient_id = e.extension_number
+            WHERE c.start_time >= $1 AND c.start_time < $2
+            GROUP BY e.id, e.extension_number, e.name
+            ORDER BY 6 DESC, e.extension_number
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ExtensionStats {
+                extension_id: row.id,
+                extension_number: row.extension_number,
+                name: row.name,
+                placed: row.placed,
+                received: row.received,
+                kpis: call_kpis(CallCounts {
+                    total: row.total,
+                    answered: row.answered,
+                    busy: row.busy,
+                    no_answer: row.no_answer,
+                    failed: row.failed,
+                    active: row.active,
+                    talk_seconds: row.talk_seconds,
+                }),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,1028 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use similar::TextDiff;
+use sqlx::PgPool;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    error::AppError,
+    models::{
+        asterisk::{
+            ApplyConfigResponse, ConfigFileDiff, ConfigPreview, ConfigSnapshot,
+            RenderedConfigFile,
+        },
+        conference::ConferenceRoom,
+        fax::FaxLine,
+        pbx::{ExtensionType, PbxExtension},
+        queue::QueueStrategy,
+        recording::RecordingMode,
+        ring_group::RingGroupStrategy,
+        routing::{InboundRoute, RouteDestinationType},
+        screening::ScreeningAction,
+        trunk::{SipTrunk, TrunkTransport},
+    },
+    services::{
+        conference::{bridge_profile, ConferenceService},
+        fax::{FaxService, FAX_IN_CONTEXT, FAX_OUT_CONTEXT},
+        ivr::{render_ivr_menu, IvrService},
+        pbx::PbxService,
+        queue::{member_interface, QueueService},
+        recording::RecordingService,
+        ring_group::{sequential_schedule, simultaneous_members, RingGroupService},
+        routing::{trunk_disabled, RoutingService},
+        screening::{ANONYMOUS_CALLER_REGEX, SCREENING_FAMILY},
+        trunk::{registration_section, TrunkService},
+        voicemail::VoicemailService,
+    },
+};
+
+const GENERATED_HEADER: &str = "; Generated by OrionTel from the database. Manual changes will be overwritten.\n";
+const DEFAULT_CONTEXT: &str = "from-internal";
+const INBOUND_CONTEXT: &str = "from-trunk";
+/// Each route gets its own numbered context, e.g. outbound-route-1.
+const OUTBOUND_ROUTE_CONTEXT: &str = "outbound-route";
+const INBOUND_ROUTE_CONTEXT: &str = "inbound-route";
+const QUEUE_CONTEXT: &str = "queues";
+const RING_GROUP_CONTEXT: &str = "ringgroups";
+const RECORD_CONTEXT: &str = "record-call";
+const CONFERENCE_CONTEXT: &str = "conferences";
+const CONFERENCE_USER_PROFILE: &str = "oriontel-user";
+const CONFERENCE_ADMIN_PROFILE: &str = "oriontel-admin";
+const CONFERENCE_PIN_ATTEMPTS: i32 = 3;
+const CALL_HANDLING_CONTEXT: &str = "call-handling";
+const SCREENING_CONTEXT: &str = "caller-screening";
+/// Forwards chained through more extensions than this ring the last one instead.
+const MAX_FORWARD_HOPS: i32 = 5;
+/// Calls placed by an always-record extension carry RECORD_CALLS from pjsip.conf.
+const RECORD_IF_CALLER_RECORDS: &str = "GosubIf($[\"${RECORD_CALLS}\" = \"1\"]?record-call,s,1)";
+const DEFAULT_CODECS: &str = "ulaw,alaw";
+const DEFAULT_RING_TIMEOUT: i64 = 30;
+const TRUNK_GROUP_CATEGORY: &str = "trunks";
+const TRUNK_RETRY_INTERVAL: i32 = 60;
+
+pub struct AsteriskConfigService {
+    pool: PgPool,
+    config_dir: PathBuf,
+}
+
+impl AsteriskConfigService {
+    pub fn new(pool: PgPool) -> Self {
+        let config_dir = std::env::var("ASTERISK_CONFIG_DIR")
+            .unwrap_or_else(|_| "/etc/asterisk".into());
+        Self {
+            pool,
+            config_dir: PathBuf::from(config_dir),
+        }
+    }
+
+    async fn load_snapshot(&self) -> Result<ConfigSnapshot, AppError> {
+        let pbx_service = PbxService::new(self.pool.clone());
+        let routing_service = RoutingService::new(self.pool.clone());
+
+        Ok(ConfigSnapshot {
+            extensions: pbx_service.list_extensions().await?,
+            inbound_routes: routing_service.list_inbound_routes().await?,
+            outbound_routes: routing_service.list_outbound_routes().await?,
+            queues: QueueService::new(self.pool.clone()).list_queues().await?,
+            voicemail_boxes: VoicemailService::new(self.pool.clone()).list_mailboxes().await?,
+            ivr_menus: IvrService::new(self.pool.clone()).list_menus().await?,
+            ring_groups: RingGroupService::new(self.pool.clone()).list_groups().await?,
+            recording_policies: RecordingService::new(self.pool.clone()).list_policies().await?,
+            trunks: TrunkService::new(self.pool.clone()).list_trunks().await?,
+            conference_rooms: ConferenceService::new(self.pool.clone()).list_rooms().await?,
+            fax_lines: FaxService::new(self.pool.clone()).list_lines(None).await?,
+        })
+    }
+
+    pub async fn render(&self) -> Result<Vec<RenderedConfigFile>, AppError> {
+        let snapshot = self.load_snapshot().await?;
+        Ok(render_all(&snapshot))
+    }
+
+    pub async fn preview(&self) -> Result<ConfigPreview, AppError> {
+        let mut files = Vec::new();
+
+        for file in self.render().await? {
+            let path = self.config_dir.join(&file.name);
+            let current = read_existing(&path).await?;
+            let changed = current != file.content;
+            let diff = TextDiff::from_lines(&current, &file.content)
+                .unified_diff()
+                .context_radius(3)
+                .header(&format!("a/{}", file.name), &format!("b/{}", file.name))
+                .to_string();
+
+            files.push(ConfigFileDiff {
+                name: file.name,
+                path: path.display().to_string(),
+                changed,
+                diff,
+            });
+        }
+
+        Ok(ConfigPreview { files })
+    }
+
+    pub async fn apply(&self) -> Result<ApplyConfigResponse, AppError> {
+        let rendered = self.render().await?;
+        let files_written = write_config_set(&self.config_dir, &rendered).await?;
+
+        tracing::info!("Applied Asterisk configuration to {}", self.config_dir.display());
+
+        Ok(ApplyConfigResponse {
+            files_written,
+            applied_at: Utc::now(),
+        })
+    }
+}
+
+async fn read_existing(path: &Path) -> Result<String, AppError> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(AppError::Internal(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+fn temp_path(path: &Path) -> Result<PathBuf, AppError> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| AppError::Internal(format!("Invalid config path {}", path.display())))?;
+    Ok(path.with_file_name(format!(".{}.oriontel.tmp", file_name)))
+}
+
+/// Writes the temporary file for `path` and returns its path.
+async fn write_temp(path: &Path, content: &str) -> Result<PathBuf, AppError> {
+    let temp = temp_path(path)?;
+    let written = async {
+        let mut file = tokio::fs::File::create(&temp).await?;
+        file.write_all(content.as_bytes()).await?;
+        file.sync_all().await
+    }
+    .await;
+
+    match written {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            Err(AppError::Internal(format!("Failed to write {}: {}", path.display(), e)))
+        }
+    }
+}
+
+/// Writes every file to a temporary file in the same directory first and
+/// renames them over the targets only once all writes succeeded, so a failed
+/// write leaves the old set intact and Asterisk never sees a half-written
+/// file. Returns the paths written.
+pub async fn write_config_set(dir: &Path, files: &[RenderedConfigFile]) -> Result<Vec<String>, AppError> {
+    let mut staged: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for file in files {
+        let path = dir.join(&file.name);
+        match write_temp(&path, &file.content).await {
+            Ok(temp) => staged.push((path, temp)),
+            Err(e) => {
+                for (_, temp) in &staged {
+                    let _ = tokio::fs::remove_file(temp).await;
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    // Renames within one directory rarely fail; if one does, the error names
+    // the files already replaced
+    let mut files_written = Vec::new();
+    for (i, (path, temp)) in staged.iter().enumerate() {
+        if let Err(e) = tokio::fs::rename(temp, path).await {
+            for (_, temp) in &staged[i..] {
+                let _ = tokio::fs::remove_file(temp).await;
+            }
+            return Err(AppError::Internal(format!(
+                "Failed to replace {}: {} (already replaced: {})",
+                path.display(),
+                e,
+                if files_written.is_empty() { "none".to_string() } else { files_written.join(", ") }
+            )));
+        }
+        files_written.push(path.display().to_string());
+    }
+
+    Ok(files_written)
+}
+
+pub fn render_all(snapshot: &ConfigSnapshot) -> Vec<RenderedConfigFile> {
+    vec![
+        RenderedConfigFile {
+            name: "pjsip.conf".into(),
+            content: render_pjsip_conf(snapshot),
+        },
+        RenderedConfigFile {
+            name: "extensions.conf".into(),
+            content: render_extensions_conf(snapshot),
+        },
+        RenderedConfigFile {
+            name: "queues.conf".into(),
+            content: render_queues_conf(snapshot),
+        },
+        RenderedConfigFile {
+            name: "voicemail.conf".into(),
+            content: render_voicemail_conf(snapshot),
+        },
+        RenderedConfigFile {
+            name: "confbridge.conf".into(),
+            content: render_confbridge_conf(snapshot),
+        },
+    ]
+}
+
+/// Renders endpoint, auth and aor sections for every SIP extension.
+pub fn render_pjsip_conf(snapshot: &ConfigSnapshot) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+
+    for extension in snapshot
+        .extensions
+        .iter()
+        .filter(|extension| extension.extension_type == ExtensionType::Sip)
+    {
+        let number = escape_value(&extension.extension_number);
+        let username = config_str(extension, "auth_username").unwrap_or(&extension.extension_number);
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "; {}", escape_value(&extension.name));
+        let _ = writeln!(out, "[{}]", number);
+        let _ = writeln!(out, "type=endpoint");
+        let _ = writeln!(out, "context={}", escape_value(config_str(extension, "context").unwrap_or(DEFAULT_CONTEXT)));
+        let _ = writeln!(out, "disallow=all");
+        let _ = writeln!(out, "allow={}", escape_value(&codecs(extension)));
+        let _ = writeln!(out, "auth={}-auth", number);
+        let _ = writeln!(out, "aors={}", number);
+        let _ = writeln!(
+            out,
+            "callerid=\"{}\" <{}>",
+            escape_value(&extension.name).replace('"', ""),
+            number
+        );
+        if let Some(transport) = config_str(extension, "transport") {
+            let _ = writeln!(out, "transport=transport-{}", escape_value(transport));
+        }
+        if let Some(dtmf_mode) = config_str(extension, "dtmf_mode") {
+            let _ = writeln!(out, "dtmf_mode={}", escape_value(dtmf_mode));
+        }
+        if records_calls(snapshot, extension) {
+            let _ = writeln!(out, "set_var=RECORD_CALLS=1");
+        }
+        if extension.config_data.get("nat").and_then(|v| v.as_bool()).unwrap_or(false) {
+            let _ = writeln!(out, "direct_media=no");
+            let _ = writeln!(out, "rtp_symmetric=yes");
+            let _ = writeln!(out, "force_rport=yes");
+            let _ = writeln!(out, "rewrite_contact=yes");
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}-auth]", number);
+        let _ = writeln!(out, "type=auth");
+        let _ = writeln!(out, "auth_type=userpass");
+        let _ = writeln!(out, "username={}", escape_value(username));
+        let _ = writeln!(out, "password={}", escape_value(config_str(extension, "secret").unwrap_or("")));
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}]", number);
+        let _ = writeln!(out, "type=aor");
+        let _ = writeln!(
+            out,
+            "max_contacts={}",
+            extension.config_data.get("max_contacts").and_then(|v| v.as_i64()).unwrap_or(1)
+        );
+        let _ = writeln!(out, "remove_existing=yes");
+    }
+
+    for trunk in snapshot.trunks.iter().filter(|trunk| trunk.enabled) {
+        render_trunk(&mut out, trunk);
+    }
+
+    out
+}
+
+/// Renders a provider trunk: endpoint, outbound auth, aor pointing at the
+/// provider, identify so inbound calls from the host land in the inbound
+/// context, and a registration when the trunk registers.
+fn render_trunk(out: &mut String, trunk: &SipTrunk) {
+    let name = escape_value(&trunk.name);
+    let transport = match trunk.transport {
+        TrunkTransport::Udp => "udp",
+        TrunkTransport::Tcp => "tcp",
+        TrunkTransport::Tls => "tls",
+    };
+    let server_uri = format!("sip:{}:{}", escape_value(&trunk.host), trunk.port);
+    let has_auth = trunk.username.is_some() && trunk.secret.is_some();
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "; Trunk {}", name);
+    let _ = writeln!(out, "[{}]", name);
+    let _ = writeln!(out, "type=endpoint");
+    let _ = writeln!(out, "transport=transport-{}", transport);
+    let _ = writeln!(out, "context={}", INBOUND_CONTEXT);
+    let _ = writeln!(out, "disallow=all");
+    let _ = writeln!(out, "allow={}", escape_value(&trunk.codecs.join(",")));
+    let _ = writeln!(out, "aors={}", name);
+    if has_auth {
+        let _ = writeln!(out, "outbound_auth={}-auth", name);
+    }
+    if let Some(username) = &trunk.username {
+        let _ = writeln!(out, "from_user={}", escape_value(username));
+    }
+    let _ = writeln!(out, "from_domain={}", escape_value(&trunk.host));
+    let _ = writeln!(out, "direct_media=no");
+    let _ = writeln!(out, "send_pai=yes");
+
+    if has_auth {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}-auth]", name);
+        let _ = writeln!(out, "type=auth");
+        let _ = writeln!(out, "auth_type=userpass");
+        let _ = writeln!(out, "username={}", escape_value(trunk.username.as_deref().unwrap_or("")));
+        let _ = writeln!(out, "password={}", escape_value(trunk.secret.as_deref().unwrap_or("")));
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", name);
+    let _ = writeln!(out, "type=aor");
+    let _ = writeln!(out, "contact={}", server_uri);
+    let _ = writeln!(out, "qualify_frequency=60");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}-identify]", name);
+    let _ = writeln!(out, "type=identify");
+    let _ = writeln!(out, "endpoint={}", name);
+    let _ = writeln!(out, "match={}", escape_value(&trunk.host));
+
+    if trunk.register && has_auth {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}]", escape_value(&registration_section(&trunk.name)));
+        let _ = writeln!(out, "type=registration");
+        let _ = writeln!(out, "transport=transport-{}", transport);
+        let _ = writeln!(out, "outbound_auth={}-auth", name);
+        let _ = writeln!(out, "server_uri={}", server_uri);
+        let _ = writeln!(
+            out,
+            "client_uri=sip:{}@{}:{}",
+            escape_value(trunk.username.as_deref().unwrap_or("")),
+            escape_value(&trunk.host),
+            trunk.port
+        );
+        let _ = writeln!(out, "contact_user={}", escape_value(trunk.username.as_deref().unwrap_or("")));
+        let _ = writeln!(out, "retry_interval={}", TRUNK_RETRY_INTERVAL);
+        let _ = writeln!(out, "forbidden_retry_interval=600");
+        let _ = writeln!(out, "expiration=3600");
+    }
+}
+
+/// Renders a dialplan context that rings each extension directly.
+pub fn render_extensions_conf(snapshot: &ConfigSnapshot) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+
+    // Recording lines are only emitted once some extension records its calls
+    let recording = snapshot
+        .recording_policies
+        .iter()
+        .any(|policy| policy.mode == RecordingMode::Always);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", DEFAULT_CONTEXT);
+    for extension in &snapshot.extensions {
+        let number = escape_value(&extension.extension_number);
+        let dial_target = match extension.extension_type {
+            ExtensionType::Sip => format!("PJSIP/{}", number),
+            ExtensionType::Iax => format!("IAX2/{}", number),
+            ExtensionType::Custom => match config_str(extension, "dial_string") {
+                Some(dial_string) => escape_value(dial_string),
+                None => continue,
+            },
+        };
+        let ring_timeout = extension
+            .config_data
+            .get("ring_timeout")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(DEFAULT_RING_TIMEOUT);
+
+        let _ = writeln!(out, "exten => {},1,NoOp({})", number, escape_app_arg(&extension.name));
+        if records_calls(snapshot, extension) {
+            let _ = writeln!(out, " same => n,Gosub({},s,1)", RECORD_CONTEXT);
+        } else if recording {
+            let _ = writeln!(out, " same => n,{}", RECORD_IF_CALLER_RECORDS);
+        }
+        let _ = writeln!(out, " same => n,Gosub({},s,1({}))", CALL_HANDLING_CONTEXT, number);
+        let _ = writeln!(out, " same => n,Dial({},{})", dial_target, ring_timeout);
+        let _ = writeln!(out, " same => n,Gosub({},dialed,1({}))", CALL_HANDLING_CONTEXT, number);
+        let _ = writeln!(out, " same => n,Hangup()");
+    }
+
+    for response in &snapshot.ring_groups {
+        let number = escape_value(&response.group.group_number);
+        let _ = writeln!(out, "exten => {},1,Goto({},{},1)", number, RING_GROUP_CONTEXT, number);
+    }
+
+    for room in &snapshot.conference_rooms {
+        let number = escape_value(&room.room_number);
+        let _ = writeln!(out, "exten => {},1,Goto({},{},1)", number, CONFERENCE_CONTEXT, number);
+    }
+
+    render_feature_codes(&mut out);
+
+    // Outbound routes. Asterisk picks the most specific pattern within a
+    // context, so every route gets a context of its own; includes are searched
+    // in order, after the context's own extensions, so route priority decides.
+    // Routes over a disabled trunk are left out so the call is not sent anywhere.
+    let mut outbound_routes: Vec<_> = snapshot
+        .outbound_routes
+        .iter()
+        .filter(|route| route.enabled && !trunk_disabled(route, &snapshot.trunks))
+        .collect();
+    outbound_routes.sort_by(|a, b| (a.priority, &a.name).cmp(&(b.priority, &b.name)));
+    for n in 1..=outbound_routes.len() {
+        let _ = writeln!(out, "include => {}-{}", OUTBOUND_ROUTE_CONTEXT, n);
+    }
+    for (n, route) in outbound_routes.into_iter().enumerate() {
+        let trunk = snapshot.trunks.iter().find(|trunk| trunk.name == route.trunk);
+        let trunk_name = escape_app_arg(&route.trunk);
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}-{}]", OUTBOUND_ROUTE_CONTEXT, n + 1);
+        let _ = writeln!(out, "exten => {},1,NoOp(Outbound route {})", escape_value(&route.dial_pattern), escape_app_arg(&route.name));
+        if recording {
+            let _ = writeln!(out, " same => n,{}", RECORD_IF_CALLER_RECORDS);
+        }
+        if let Some(caller_id) = trunk.and_then(|trunk| trunk.outbound_caller_id.as_deref()) {
+            let _ = writeln!(out, " same => n,Set(CALLERID(num)={})", escape_app_arg(caller_id));
+        }
+        let max_channels = trunk.and_then(|trunk| trunk.max_channels);
+        if let Some(max_channels) = max_channels {
+            let _ = writeln!(out, " same => n,Set(GROUP({})={})", TRUNK_GROUP_CATEGORY, trunk_name);
+            let _ = writeln!(
+                out,
+                " same => n,GotoIf($[${{GROUP_COUNT({}@{})}} > {}]?trunk-full)",
+                trunk_name,
+                TRUNK_GROUP_CATEGORY,
+                max_channels
+            );
+        }
+        let _ = writeln!(
+            out,
+            " same => n,Dial(PJSIP/{}${{EXTEN:{}}}@{})",
+            escape_app_arg(route.prepend.as_deref().unwrap_or("")),
+            route.strip_digits.max(0),
+            trunk_name
+        );
+        let _ = writeln!(out, " same => n,Hangup()");
+        if max_channels.is_some() {
+            let _ = writeln!(out, " same => n(trunk-full),Congestion()");
+        }
+    }
+
+    // Inbound routes, matched on DID and optionally on caller ID, one context
+    // per route like the outbound ones. Fax lines answer their DID first.
+    let mut inbound_routes: Vec<_> = snapshot.inbound_routes.iter().filter(|route| route.enabled).collect();
+    inbound_routes.sort_by(|a, b| (a.priority, &a.name).cmp(&(b.priority, &b.name)));
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", INBOUND_CONTEXT);
+    for line in &snapshot.fax_lines {
+        let did = escape_value(&line.did);
+        let _ = writeln!(out, "exten => {},1,Goto({},{},1)", did, FAX_IN_CONTEXT, did);
+    }
+    for n in 1..=inbound_routes.len() {
+        let _ = writeln!(out, "include => {}-{}", INBOUND_ROUTE_CONTEXT, n);
+    }
+    for (n, route) in inbound_routes.into_iter().enumerate() {
+        let did = route.did_pattern.as_deref().unwrap_or("_X!");
+        let exten = match &route.caller_id_pattern {
+            Some(caller_id) => format!("{}/{}", escape_value(did), escape_value(caller_id)),
+            None => escape_value(did),
+        };
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}-{}]", INBOUND_ROUTE_CONTEXT, n + 1);
+        let _ = writeln!(out, "exten => {},1,NoOp(Inbound route {})", exten, escape_app_arg(&route.name));
+        let _ = writeln!(out, " same => n,{}", screening_gosub(route));
+        let _ = writeln!(out, " same => n,{}", destination_app(&route.destination_type, &route.destination));
+    }
+
+    // Queue entry points: wait up to max_wait_time, then fall through to the overflow destination
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", QUEUE_CONTEXT);
+    for response in &snapshot.queues {
+        let queue = &response.queue;
+        let name = escape_app_arg(&queue.name);
+        let _ = writeln!(out, "exten => {},1,Answer()", name);
+        let _ = writeln!(out, " same => n,Queue({},t,,,{})", name, queue.max_wait_time);
+        let overflow = match &queue.overflow_destination_type {
+            Some(destination_type) => destination_app(
+                destination_type,
+                queue.overflow_destination.as_deref().unwrap_or(""),
+            ),
+            None => "Hangup()".to_string(),
+        };
+        let _ = writeln!(out, " same => n,{}", overflow);
+    }
+
+    render_ring_groups(&mut out, snapshot);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", CONFERENCE_CONTEXT);
+    for room in &snapshot.conference_rooms {
+        render_conference_room(&mut out, room);
+    }
+
+    render_call_handling(&mut out);
+
+    render_caller_screening(&mut out);
+
+    render_fax(&mut out, &snapshot.fax_lines);
+
+    // Starts one MixMonitor per call, named after the linkedid the AMI call
+    // tracker uses as the call's uniqueid
+    if recording {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}]", RECORD_CONTEXT);
+        let _ = writeln!(out, "exten => s,1,GotoIf($[\"${{RECORDING}}\" = \"1\"]?done)");
+        let _ = writeln!(out, " same => n,Set(__RECORDING=1)");
+        let _ = writeln!(out, " same => n,MixMonitor(${{CHANNEL(linkedid)}}.wav)");
+        let _ = writeln!(out, " same => n(done),Return()");
+    }
+
+    for menu in &snapshot.ivr_menus {
+        let _ = writeln!(out);
+        out.push_str(&render_ivr_menu(menu));
+    }
+
+    out
+}
+
+/// Feature codes dialled from a phone change its call handling. The new value
+/// goes straight into AstDB and is reported with a UserEvent, which the AMI
+/// call tracker stores in the database.
+fn render_feature_codes(out: &mut String) {
+    // (code, AstDB key, setting, value, announcement); a value of None deletes the key
+    let codes: [(&str, &str, &str, Option<&str>, &str); 10] = [
+        ("*78", "DND", "dnd", Some("1"), "do-not-disturb&activated"),
+        ("*79", "DND", "dnd", None, "do-not-disturb&de-activated"),
+        ("_*72X.", "CF", "forward_always", Some("${EXTEN:3}"), "call-fwd-unconditional&activated"),
+        ("*73", "CF", "forward_always", None, "call-fwd-unconditional&cancelled"),
+        ("_*90X.", "CFB", "forward_busy", Some("${EXTEN:3}"), "call-fwd-on-busy&activated"),
+        ("*91", "CFB", "forward_busy", None, "call-fwd-on-busy&cancelled"),
+        ("_*52X.", "CFNA", "forward_no_answer", Some("${EXTEN:3}"), "call-fwd-no-ans&activated"),
+        ("*53", "CFNA", "forward_no_answer", None, "call-fwd-no-ans&cancelled"),
+        ("*21", "FM", "follow_me", Some("1"), "activated"),
+        ("*22", "FM", "follow_me", None, "de-activated"),
+    ];
+
+    for (code, family, setting, value, announcement) in codes {
+        let key = match family {
+            "FM" => format!("{}/${{CALLERID(num)}}/enabled", family),
+            _ => format!("{}/${{CALLERID(num)}}", family),
+        };
+        let _ = writeln!(out, "exten => {},1,Answer()", code);
+        match value {
+            Some(value) => {
+                let _ = writeln!(out, " same => n,Set(DB({})={})", key, value);
+            }
+            None => {
+                let _ = writeln!(out, " same => n,NoOp(${{DB_DELETE({})}})", key);
+            }
+        }
+        // Follow-me and DND report 1/0; forwards report the number, empty when cancelled
+        let reported = match (value, setting) {
+            (Some(value), _) => value,
+            (None, "dnd" | "follow_me") => "0",
+            (None, _) => "",
+        };
+        let _ = writeln!(
+            out,
+            " same => n,UserEvent(CallHandling,Extension: ${{CALLERID(num)}},Setting: {},Value: {})",
+            setting, reported
+        );
+        let _ = writeln!(out, " same => n,Playback({})", announcement);
+        let _ = writeln!(out, " same => n,Hangup()");
+    }
+}
+
+/// Subroutines around each extension's Dial. They read the settings from
+/// AstDB at call time, so feature codes take effect without a reload.
+/// `s` runs before ringing (DND, unconditional forward); `dialed` runs after
+/// (busy forward, follow-me, no-answer forward). ARG1 is the extension number.
+fn render_call_handling(out: &mut String) {
+    let forward_guard = format!("$[0${{FWDHOPS}} >= {}]", MAX_FORWARD_HOPS);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", CALL_HANDLING_CONTEXT);
+    let _ = writeln!(out, "exten => s,1,GotoIf($[\"${{DB(DND/${{ARG1}})}}\" = \"1\"]?busy,1)");
+    let _ = writeln!(out, " same => n,GotoIf($[\"${{DB(CF/${{ARG1}})}}\" = \"\"]?done)");
+    let _ = writeln!(out, " same => n,GotoIf({}?done)", forward_guard);
+    let _ = writeln!(out, " same => n,Set(__FWDHOPS=$[0${{FWDHOPS}} + 1])");
+    let _ = writeln!(out, " same => n,Dial(Local/${{DB(CF/${{ARG1}})}}@{})", DEFAULT_CONTEXT);
+    let _ = writeln!(out, " same => n,Hangup()");
+    let _ = writeln!(out, " same => n(done),Return()");
+
+    let _ = writeln!(out, "exten => dialed,1,GotoIf($[\"${{DIALSTATUS}}\" = \"ANSWER\"]?done)");
+    let _ = writeln!(out, " same => n,GotoIf($[\"${{DIALSTATUS}}\" = \"BUSY\"]?busy,1)");
+    let _ = writeln!(out, " same => n,GotoIf($[\"${{DB(FM/${{ARG1}}/enabled)}}\" != \"1\"]?no-answer)");
+    let _ = writeln!(out, " same => n,Set(LOCAL(i)=0)");
+    let _ = writeln!(out, " same => n(follow-me),GotoIf($[${{i}} >= 0${{DB(FM/${{ARG1}}/count)}}]?no-answer)");
+    let _ = writeln!(
+        out,
+        " same => n,Dial(Local/${{DB(FM/${{ARG1}}/${{i}}/number)}}@{},${{DB(FM/${{ARG1}}/${{i}}/time)}})",
+        DEFAULT_CONTEXT
+    );
+    let _ = writeln!(out, " same => n,GotoIf($[\"${{DIALSTATUS}}\" = \"ANSWER\"]?done)");
+    let _ = writeln!(out, " same => n,Set(LOCAL(i)=$[${{i}} + 1])");
+    let _ = writeln!(out, " same => n,Goto(follow-me)");
+    let _ = writeln!(out, " same => n(no-answer),GotoIf($[\"${{DB(CFNA/${{ARG1}})}}\" = \"\"]?done)");
+    let _ = writeln!(out, " same => n,GotoIf({}?done)", forward_guard);
+    let _ = writeln!(out, " same => n,Set(__FWDHOPS=$[0${{FWDHOPS}} + 1])");
+    let _ = writeln!(out, " same => n,Dial(Local/${{DB(CFNA/${{ARG1}})}}@{})", DEFAULT_CONTEXT);
+    let _ = writeln!(out, " same => n,Hangup()");
+    let _ = writeln!(out, " same => n(done),Return()");
+
+    let _ = writeln!(out, "exten => busy,1,GotoIf($[\"${{DB(CFB/${{ARG1}})}}\" = \"\"]?busy-tone)");
+    let _ = writeln!(out, " same => n,GotoIf({}?busy-tone)", forward_guard);
+    let _ = writeln!(out, " same => n,Set(__FWDHOPS=$[0${{FWDHOPS}} + 1])");
+    let _ = writeln!(out, " same => n,Dial(Local/${{DB(CFB/${{ARG1}})}}@{})", DEFAULT_CONTEXT);
+    let _ = writeln!(out, " same => n,Hangup()");
+    let _ = writeln!(out, " same => n(busy-tone),Busy(10)");
+    let _ = writeln!(out, " same => n,Hangup()");
+}
+
+/// Screens the caller before an inbound route's destination runs.
+fn screening_gosub(route: &InboundRoute) -> String {
+    let action = match route.blocked_action {
+        ScreeningAction::Reject => "reject",
+        ScreeningAction::Busy => "busy",
+        ScreeningAction::Voicemail => "voicemail",
+        ScreeningAction::Message => "message",
+    };
+    format!(
+        "Gosub({},s,1({},{},{},{}))",
+        SCREENING_CONTEXT,
+        action,
+        escape_app_arg(route.blocked_destination.as_deref().unwrap_or("")),
+        u8::from(route.reject_anonymous),
+        route.id
+    )
+}
+
+/// Checks the caller against the screening entries in AstDB, in order, so
+/// changes take effect without a reload. Entries past their expiry are
+/// skipped. A blocked call is reported with a UserEvent, which the AMI call
+/// tracker logs against the call record, and never returns to the route. The
+/// caller ID comes from the trunk, so it is filtered before it goes into the
+/// event; UserEvent would take a comma in it as the start of another header.
+/// ARG1 is the action, ARG2 its mailbox or sound file, ARG3 1 to reject
+/// anonymous callers and ARG4 the route id.
+fn render_caller_screening(out: &mut String) {
+    let entry = |key: &str| format!("${{DB({}/${{i}}/{})}}", SCREENING_FAMILY, key);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", SCREENING_CONTEXT);
+    let _ = writeln!(out, "exten => s,1,Set(LOCAL(caller)=${{CALLERID(num)}})");
+    let _ = writeln!(out, " same => n,Set(LOCAL(entry)=)");
+    let _ = writeln!(out, " same => n,Set(LOCAL(i)=0)");
+    let _ = writeln!(
+        out,
+        " same => n(next),GotoIf($[${{i}} >= 0${{DB({}/count)}}]?anonymous)",
+        SCREENING_FAMILY
+    );
+    let _ = writeln!(
+        out,
+        " same => n,GotoIf($[\"{}\" != \"\" & ${{EPOCH}} >= 0{}]?skip)",
+        entry("expires"),
+        entry("expires")
+    );
+    let _ = writeln!(out, " same => n,GotoIf(${{REGEX(\"{}\" ${{caller}})}}?matched)", entry("match"));
+    let _ = writeln!(out, " same => n(skip),Set(LOCAL(i)=$[${{i}} + 1])");
+    let _ = writeln!(out, " same => n,Goto(next)");
+    let _ = writeln!(out, " same => n(matched),GotoIf($[\"{}\" = \"allow\"]?done)", entry("list"));
+    let _ = writeln!(out, " same => n,Set(LOCAL(entry)={})", entry("id"));
+    let _ = writeln!(out, " same => n,Goto(blocked)");
+    let _ = writeln!(out, " same => n(anonymous),GotoIf($[\"${{ARG3}}\" != \"1\"]?done)");
+    let _ = writeln!(out, " same => n,GotoIf($[\"${{CALLERID(pres):0:6}}\" = \"prohib\"]?blocked)");
+    let _ = writeln!(
+        out,
+        " same => n,GotoIf(${{REGEX(\"{}\" ${{TOLOWER(${{caller}})}})}}?blocked)",
+        ANONYMOUS_CALLER_REGEX
+    );
+    let _ = writeln!(out, " same => n(done),Return()");
+    let _ = writeln!(
+        out,
+        " same => n(blocked),UserEvent(CallScreening,Call: ${{CHANNEL(linkedid)}},Entry: ${{entry}},Route: ${{ARG4}},Caller: ${{FILTER(0-9+*#a-zA-Z,${{caller}})}},Action: ${{ARG1}})"
+    );
+    let _ = writeln!(out, " same => n,Goto(${{ARG1}},1)");
+
+    let _ = writeln!(out, "exten => reject,1,Hangup(21)");
+    let _ = writeln!(out, "exten => busy,1,Busy(10)");
+    let _ = writeln!(out, " same => n,Hangup()");
+    let _ = writeln!(out, "exten => voicemail,1,VoiceMail(${{ARG2}}@default,u)");
+    let _ = writeln!(out, " same => n,Hangup()");
+    let _ = writeln!(out, "exten => message,1,Answer()");
+    let _ = writeln!(out, " same => n,Playback(${{ARG2}})");
+    let _ = writeln!(out, " same => n,Hangup()");
+}
+
+/// `fax-in` receives into the Asterisk spool directory; `fax-out` runs the
+/// calls the fax job originates. Both report back with UserEvents, which the
+/// AMI call tracker hands to the fax service. UserEvent splits its arguments
+/// on commas, so the reported values must not contain any.
+fn render_fax(out: &mut String, lines: &[FaxLine]) {
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", FAX_IN_CONTEXT);
+    for line in lines {
+        let did = escape_value(&line.did);
+        let _ = writeln!(out, "exten => {},1,NoOp(Fax line {})", did, escape_app_arg(&line.name));
+        let _ = writeln!(out, " same => n,Set(FAXDID={})", did);
+        let _ = writeln!(
+            out,
+            " same => n,Set(FAXOPT(localstationid)={})",
+            escape_app_arg(line.station_id.as_deref().unwrap_or(&line.did))
+        );
+        let _ = writeln!(out, " same => n,Answer()");
+        let _ = writeln!(out, " same => n,ReceiveFAX(${{ASTSPOOLDIR}}/fax/${{UNIQUEID}}.tif)");
+        let _ = writeln!(out, " same => n,Hangup()");
+    }
+    let _ = writeln!(
+        out,
+        "exten => h,1,UserEvent(FaxReceived,FaxId: ${{UNIQUEID}},DID: ${{FAXDID}},Sender: ${{CALLERID(num)}},Station: ${{FAXOPT(remotestationid)}},Pages: ${{FAXOPT(pages)}})"
+    );
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", FAX_OUT_CONTEXT);
+    let _ = writeln!(out, "exten => s,1,Set(FAXOPT(localstationid)=${{FAX_STATION}})");
+    let _ = writeln!(out, " same => n,SendFAX(${{FAX_FILE}},z)");
+    let _ = writeln!(out, " same => n,Hangup()");
+    let _ = writeln!(
+        out,
+        "exten => h,1,UserEvent(FaxResult,Job: ${{FAX_JOB}},Status: ${{FAXOPT(status)}},Error: ${{FAXOPT(error)}},Pages: ${{FAXOPT(pages)}})"
+    );
+    // Run by Originate when the far end never answers
+    let _ = writeln!(out, "exten => failed,1,UserEvent(FaxResult,Job: ${{FAX_JOB}},Status: FAILED,Error: Call failed with reason ${{REASON}},Pages: 0)");
+}
+
+/// Ring groups dial members through Local channels into the internal context.
+/// Delayed members in simultaneous groups go through a context that waits first.
+fn render_ring_groups(out: &mut String, snapshot: &ConfigSnapshot) {
+    let mut delays = Vec::new();
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", RING_GROUP_CONTEXT);
+    for response in &snapshot.ring_groups {
+        let group = &response.group;
+        let _ = writeln!(
+            out,
+            "exten => {},1,NoOp(Ring group {})",
+            escape_value(&group.group_number),
+            escape_app_arg(&group.name)
+        );
+
+        match group.strategy {
+            RingGroupStrategy::Simultaneous => {
+                let targets: Vec<String> = simultaneous_members(group, &response.members)
+                    .into_iter()
+                    .map(|member| {
+                        let number = escape_app_arg(&member.extension_number);
+                        if member.delay > 0 {
+                            if !delays.contains(&member.delay) {
+                                delays.push(member.delay);
+                            }
+                            format!("Local/{}@ringgroup-delay-{}", number, member.delay)
+                        } else {
+                            format!("Local/{}@{}", number, DEFAULT_CONTEXT)
+                        }
+                    })
+                    .collect();
+                if !targets.is_empty() {
+                    let _ = writeln!(out, " same => n,Dial({},{})", targets.join("&"), group.timeout);
+                }
+            }
+            RingGroupStrategy::Sequential => {
+                for step in sequential_schedule(group, &response.members) {
+                    if step.wait > 0 {
+                        let _ = writeln!(out, " same => n,Wait({})", step.wait);
+                    }
+                    let _ = writeln!(
+                        out,
+                        " same => n,Dial(Local/{}@{},{})",
+                        escape_app_arg(&step.extension_number),
+                        DEFAULT_CONTEXT,
+                        step.ring_time
+                    );
+                }
+            }
+        }
+
+        let failover = match &group.failover_destination_type {
+            Some(destination_type) => destination_app(
+                destination_type,
+                group.failover_destination.as_deref().unwrap_or(""),
+            ),
+            None => "Hangup()".to_string(),
+        };
+        let _ = writeln!(out, " same => n,{}", failover);
+    }
+
+    delays.sort_unstable();
+    for delay in delays {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[ringgroup-delay-{}]", delay);
+        let _ = writeln!(out, "exten => _X.,1,Wait({})", delay);
+        let _ = writeln!(out, " same => n,Goto({},${{EXTEN}},1)", DEFAULT_CONTEXT);
+    }
+}
+
+/// Callers are asked for a PIN when the room has one. The admin PIN joins with
+/// the admin profile; when only an admin PIN is set, users just press #.
+fn render_conference_room(out: &mut String, room: &ConferenceRoom) {
+    let number = escape_value(&room.room_number);
+    let bridge = escape_app_arg(&bridge_profile(&room.room_number));
+
+    let _ = writeln!(out, "exten => {},1,NoOp(Conference {})", number, escape_app_arg(&room.name));
+    let _ = writeln!(out, " same => n,Answer()");
+
+    if room.user_pin.is_none() && room.admin_pin.is_none() {
+        let _ = writeln!(out, " same => n,ConfBridge({},{},{})", number, bridge, CONFERENCE_USER_PROFILE);
+        let _ = writeln!(out, " same => n,Hangup()");
+        return;
+    }
+
+    let _ = writeln!(out, " same => n,Set(TRIES=0)");
+    let _ = writeln!(out, " same => n(pin),Set(TRIES=$[${{TRIES}} + 1])");
+    let _ = writeln!(out, " same => n,Read(PIN,conf-getpin,10,,1,10)");
+    if let Some(pin) = &room.admin_pin {
+        let _ = writeln!(out, " same => n,GotoIf($[\"${{PIN}}\" = \"{}\"]?admin)", escape_app_arg(pin));
+    }
+    let user_pin = room.user_pin.as_deref().unwrap_or("");
+    let _ = writeln!(out, " same => n,GotoIf($[\"${{PIN}}\" = \"{}\"]?user)", escape_app_arg(user_pin));
+    let _ = writeln!(out, " same => n,Playback(conf-invalidpin)");
+    let _ = writeln!(out, " same => n,GotoIf($[${{TRIES}} < {}]?pin)", CONFERENCE_PIN_ATTEMPTS);
+    let _ = writeln!(out, " same => n,Hangup()");
+    if room.admin_pin.is_some() {
+        let _ = writeln!(out, " same => n(admin),ConfBridge({},{},{})", number, bridge, CONFERENCE_ADMIN_PROFILE);
+        let _ = writeln!(out, " same => n,Hangup()");
+    }
+    let _ = writeln!(out, " same => n(user),ConfBridge({},{},{})", number, bridge, CONFERENCE_USER_PROFILE);
+    let _ = writeln!(out, " same => n,Hangup()");
+}
+
+/// Renders the shared user profiles and one bridge profile per room.
+pub fn render_confbridge_conf(snapshot: &ConfigSnapshot) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", CONFERENCE_USER_PROFILE);
+    let _ = writeln!(out, "type=user");
+    let _ = writeln!(out, "announce_user_count=yes");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[{}]", CONFERENCE_ADMIN_PROFILE);
+    let _ = writeln!(out, "type=user");
+    let _ = writeln!(out, "admin=yes");
+    let _ = writeln!(out, "marked=yes");
+    let _ = writeln!(out, "announce_user_count=yes");
+
+    for room in &snapshot.conference_rooms {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "; {}", escape_value(&room.name));
+        let _ = writeln!(out, "[{}]", escape_value(&bridge_profile(&room.room_number)));
+        let _ = writeln!(out, "type=bridge");
+        if let Some(max_participants) = room.max_participants {
+            let _ = writeln!(out, "max_members={}", max_participants);
+        }
+        let _ = writeln!(out, "record_conference={}", if room.record { "yes" } else { "no" });
+    }
+
+    out
+}
+
+/// Renders one section per queue with its logged-in members as static members.
+pub fn render_queues_conf(snapshot: &ConfigSnapshot) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[general]");
+    let _ = writeln!(out, "persistentmembers=no");
+
+    for response in &snapshot.queues {
+        let queue = &response.queue;
+        let strategy = match queue.strategy {
+            QueueStrategy::RingAll => "ringall",
+            QueueStrategy::RoundRobin => "rrmemory",
+            QueueStrategy::LeastRecent => "leastrecent",
+            QueueStrategy::FewestCalls => "fewestcalls",
+        };
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}]", escape_value(&queue.name));
+        let _ = writeln!(out, "strategy={}", strategy);
+        let _ = writeln!(out, "timeout={}", queue.ring_timeout);
+        let _ = writeln!(out, "retry=5");
+        let _ = writeln!(out, "ringinuse=no");
+        let _ = writeln!(out, "joinempty=yes");
+        let _ = writeln!(out, "leavewhenempty=no");
+        for member in response.members.iter().filter(|member| member.logged_in) {
+            let _ = writeln!(
+                out,
+                "member => {},{},{}",
+                escape_value(&member_interface(&member.extension_number)),
+                member.penalty,
+                escape_app_arg(&member.extension_name)
+            );
+        }
+    }
+
+    out
+}
+
+/// Renders one mailbox per extension. Messages are imported from the spool
+/// and emailed by OrionTel, so Asterisk's own attachment mail is turned off.
+pub fn render_voicemail_conf(snapshot: &ConfigSnapshot) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[general]");
+    let _ = writeln!(out, "format=wav");
+    let _ = writeln!(out, "attach=no");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[default]");
+    for mailbox in &snapshot.voicemail_boxes {
+        let _ = writeln!(
+            out,
+            "{} => {},{}",
+            escape_value(&mailbox.extension_number),
+            escape_app_arg(&mailbox.pin),
+            escape_app_arg(&mailbox.extension_name)
+        );
+    }
+
+    out
+}
+
+/// Dialplan application that sends a call to a route destination.
+pub fn destination_app(destination_type: &RouteDestinationType, destination: &str) -> String {
+    let destination = escape_app_arg(destination);
+    match destination_type {
+        RouteDestinationType::Extension => format!("Goto({},{},1)", DEFAULT_CONTEXT, destination),
+        RouteDestinationType::Queue => format!("Goto({},{},1)", QUEUE_CONTEXT, destination),
+        RouteDestinationType::Ivr => format!("Goto(ivr-{},s,1)", destination),
+        RouteDestinationType::Voicemail => format!("VoiceMail({}@default,u)", destination),
+        RouteDestinationType::Hangup => "Hangup()".to_string(),
+    }
+}
+
+fn records_calls(snapshot: &ConfigSnapshot, extension: &PbxExtension) -> bool {
+    snapshot
+        .recording_policies
+        .iter()
+        .any(|policy| policy.extension_id == extension.id && policy.mode == RecordingMode::Always)
+}
+
+fn config_str<'a>(extension: &'a PbxExtension, key: &str) -> Option<&'a str> {
+    extension.config_data.get(key).and_then(|v| v.as_str())
+}
+
+fn codecs(extension: &PbxExtension) -> String {
+    match extension.config_data.get("codecs") {
+        Some(serde_json::Value::Array(list)) => {
+            let list: Vec<&str> = list.iter().filter_map(|v| v.as_str()).collect();
+            if list.is_empty() {
+                DEFAULT_CODECS.to_string()
+            } else {
+                list.join(",")
+            }
+        }
+        Some(serde_json::Value::String(list)) if !list.trim().is_empty() => list.clone(),
+        _ => DEFAULT_CODECS.to_string(),
+    }
+}
+
+/// Strips line breaks and escapes the comment character so database values
+/// cannot inject extra config lines.
+pub fn escape_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| *c != '\r' && *c != '\n')
+        .collect::<String>()
+        .replace(';', "\\;")
+}
+
+/// Escapes a value used as a dialplan application argument.
+pub fn escape_app_arg(value: &str) -> String {
+    escape_value(value)
+        .replace(',', "\\,")
+        .replace('(', "")
+        .replace(')', "")
+}
@@ -0,0 +1,563 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    error::AppError,
+    models::{
+        billing::{
+            AssignRateTableRequest, CallCharge, CallCost, CreateRateRequest, CreateRateTableRequest, Invoice,
+            InvoiceLine, Rate, RateTable, RatingResult, UnratedCall, UnratedReason, UpdateRateRequest,
+            UpdateRateTableRequest,
+        },
+        pbx::CallStatus,
+    },
+    services::pbx::PbxService,
+};
+
+const RATING_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// The background pass picks up calls that ended within this many days.
+const RATING_LOOKBACK_DAYS: i64 = 7;
+const DEFAULT_BILLING_INCREMENT: i32 = 60;
+/// Stored call costs keep this many decimal places; invoice totals are rounded to cents.
+const COST_SCALE: u32 = 6;
+const INVOICE_SCALE: u32 = 2;
+
+pub fn validate_prefix(prefix: &str) -> Result<(), AppError> {
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::Validation(format!("Prefix '{}' may only contain digits", prefix)));
+    }
+    Ok(())
+}
+
+pub fn validate_amount(name: &str, amount: Decimal) -> Result<(), AppError> {
+    if amount.is_sign_negative() {
+        return Err(AppError::Validation(format!("{} cannot be negative", name)));
+    }
+    Ok(())
+}
+
+pub fn validate_currency(currency: &str) -> Result<(), AppError> {
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(AppError::Validation(format!("Currency '{}' must be an ISO 4217 code", currency)));
+    }
+    Ok(())
+}
+
+/// Longest-prefix match on the dialled number. A leading `+` is ignored.
+pub fn find_rate<'a>(rates: &'a [Rate], number: &str) -> Option<&'a Rate> {
+    let number = number.trim().trim_start_matches('+');
+    rates
+        .iter()
+        .filter(|rate| number.starts_with(rate.prefix.as_str()))
+        .max_by_key(|rate| rate.prefix.len())
+}
+
+/// Prices `talk_seconds` of an answered call: the talk time is rounded up to
+/// the billing increment, and the connection fee is added once.
+pub fn rate_call(rate: &Rate, talk_seconds: i64) -> CallCost {
+    let increment = i64::from(rate.billing_increment.max(1));
+    let talk_seconds = talk_seconds.max(0);
+    let billed_seconds = (talk_seconds + increment - 1) / increment * increment;
+    let billed_seconds = i32::try_from(billed_seconds).unwrap_or(i32::MAX);
+
+    let cost = rate.connection_fee + rate.rate_per_minute * Decimal::from(billed_seconds) / Decimal::from(60);
+    CallCost {
+        billed_seconds,
+        cost: cost.round_dp_with_strategy(COST_SCALE, RoundingStrategy::MidpointAwayFromZero),
+    }
+}
+
+/// Talk time runs from the answer to the end. Calls recorded without an
+/// answer time have none, so ring time is never billed.
+pub fn talk_seconds(answered_at: Option<DateTime<Utc>>, end_time: DateTime<Utc>) -> Option<i64> {
+    answered_at.map(|answered_at| (end_time - answered_at).num_seconds().max(0))
+}
+
+/// Picks the rate for a call and the talk time to bill, or says why the call
+/// cannot be priced.
+pub fn select_rate<'a>(
+    rates: &'a [Rate],
+    number: &str,
+    talk_seconds: Option<i64>,
+) -> Result<(&'a Rate, i64), UnratedReason> {
+    let rate = find_rate(rates, number).ok_or(UnratedReason::NoMatchingRate)?;
+    let talk_seconds = talk_seconds.ok_or(UnratedReason::NotAnswered)?;
+    Ok((rate, talk_seconds))
+}
+
+/// Groups charges into one invoice per extension and currency, with a line
+/// per destination prefix.
+pub fn build_invoices(charges: &[CallCharge], period_start: DateTime<Utc>, period_end: DateTime<Utc>) -> Vec<Invoice> {
+    let mut grouped: BTreeMap<(String, String), Vec<&CallCharge>> = BTreeMap::new();
+    for charge in charges {
+        grouped
+            .entry((charge.extension_number.clone(), charge.currency.clone()))
+            .or_default()
+            .push(charge);
+    }
+
+    grouped
+        .into_values()
+        .map(|charges| {
+            let first = charges[0];
+            let mut lines: BTreeMap<&str, InvoiceLine> = BTreeMap::new();
+            for charge in &charges {
+                let line = lines.entry(charge.prefix.as_str()).or_insert_with(|| InvoiceLine {
+                    prefix: charge.prefix.clone(),
+                    description: charge.description.clone(),
+                    calls: 0,
+                    billed_seconds: 0,
+                    cost: Decimal::ZERO,
+                });
+                line.calls += 1;
+                line.billed_seconds += i64::from(charge.billed_seconds);
+                line.cost += charge.cost;
+            }
+            let lines: Vec<InvoiceLine> = lines.into_values().collect();
+            let total: Decimal = lines.iter().map(|line| line.cost).sum();
+
+            Invoice {
+                extension_id: first.extension_id,
+                extension_number: first.extension_number.clone(),
+                extension_name: first.extension_name.clone(),
+                period_start,
+                period_end,
+                currency: first.currency.clone(),
+                total_calls: lines.iter().map(|line| line.calls).sum(),
+                total_seconds: lines.iter().map(|line| line.billed_seconds).sum(),
+                total: total.round_dp_with_strategy(INVOICE_SCALE, RoundingStrategy::MidpointAwayFromZero),
+                lines,
+            }
+        })
+        .collect()
+}
+
+fn check_period(from: DateTime<Utc>, to: DateTime<Utc>) -> Result<(), AppError> {
+    if from >= to {
+        return Err(AppError::Validation("'from' must be before 'to'".into()));
+    }
+    Ok(())
+}
+
+pub struct BillingService {
+    pool: PgPool,
+}
+
+impl BillingService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Rate tables
+    pub async fn create_rate_table(&self, request: CreateRateTableRequest) -> Result<RateTable, AppError> {
+        validate_currency(&request.currency)?;
+
+        let mut tx = self.pool.begin().await?;
+        if request.is_default {
+            sqlx::query!("UPDATE rate_tables SET is_default = FALSE WHERE is_default")
+                .execute(&mut *tx)
+                .await?;
+        }
+        let table = sqlx::query_as!(
+            RateTable,
+            r#"
+            INSERT INTO rate_tables (name, currency, is_default)
+            VALUES ($1, $2, $3)
+            RETURNING id, name, currency, is_default, created_at, updated_at
+            "#,
+            request.name,
+            request.currency,
+            request.is_default,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(table)
+    }
+
+    pub async fn get_rate_table(&self, id: Uuid) -> Result<RateTable, AppError> {
+        let table = sqlx::query_as!(
+            RateTable,
+            r#"
+            SELECT id, name, currency, is_default, created_at, updated_at
+            FROM rate_tables
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Rate table not found".into()))?;
+
+        Ok(table)
+    }
+
+    pub async fn list_rate_tables(&self) -> Result<Vec<RateTable>, AppError> {
+        let tables = sqlx::query_as!(
+            RateTable,
+            r#"
+            SELECT id, name, currency, is_default, created_at, updated_at
+            FROM rate_tables
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tables)
+    }
+
+    pub async fn update_rate_table(&self, id: Uuid, request: UpdateRateTableRequest) -> Result<RateTable, AppError> {
+        if let Some(currency) = &request.currency {
+            validate_currency(currency)?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+        if request.is_default == Some(true) {
+            sqlx::query!("UPDATE rate_tables SET is_default = FALSE WHERE is_default AND id <> $1", id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let table = sqlx::query_as!(
+            RateTable,
+            r#"
+            UPDATE rate_tables
+            SET
+                name = COALESCE($1, name),
+                currency = COALESCE($2, currency),
+                is_default = COALESCE($3, is_default),
+                updated_at = $4
+            WHERE id = $5
+            RETURNING id, name, currency, is_default, created_at, updated_at
+            "#,
+            request.name,
+            request.currency,
+            request.is_default,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Rate table not found".into()))?;
+        tx.commit().await?;
+
+        Ok(table)
+    }
+
+    /// Existing charges keep the prices they were rated with.
+    pub async fn delete_rate_table(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM rate_tables WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Rate table not found".into()));
+        }
+
+        Ok(())
+    }
+
+    // Rates
+    pub async fn create_rate(&self, rate_table_id: Uuid, request: CreateRateRequest) -> Result<Rate, AppError> {
+        validate_prefix(&request.prefix)?;
+        validate_amount("rate_per_minute", request.rate_per_minute)?;
+        let connection_fee = request.connection_fee.unwrap_or(Decimal::ZERO);
+        validate_amount("connection_fee", connection_fee)?;
+        self.get_rate_table(rate_table_id).await?;
+
+        let existing = sqlx::query!(
+            "SELECT id FROM rates WHERE rate_table_id = $1 AND prefix = $2",
+            rate_table_id,
+            request.prefix
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation(format!("Prefix {} already has a rate", request.prefix)));
+        }
+
+        let rate = sqlx::query_as!(
+            Rate,
+            r#"
+            INSERT INTO rates (rate_table_id, prefix, description, rate_per_minute, connection_fee, billing_increment)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, rate_table_id, prefix, description, rate_per_minute, connection_fee,
+                      billing_increment, created_at, updated_at
+            "#,
+            rate_table_id,
+            request.prefix,
+            request.description,
+            request.rate_per_minute,
+            connection_fee,
+            request.billing_increment.unwrap_or(DEFAULT_BILLING_INCREMENT),
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(rate)
+    }
+
+    pub async fn list_rates(&self, rate_table_id: Uuid) -> Result<Vec<Rate>, AppError> {
+        self.get_rate_table(rate_table_id).await?;
+
+        let rates = sqlx::query_as!(
+            Rate,
+            r#"
+            SELECT id, rate_table_id, prefix, description, rate_per_minute, connection_fee,
+                   billing_increment, created_at, updated_at
+            FROM rates
+            WHERE rate_table_id = $1
+            ORDER BY prefix
+            "#,
+            rate_table_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rates)
+    }
+
+    pub async fn update_rate(&self, id: Uuid, request: UpdateRateRequest) -> Result<Rate, AppError> {
+        if let Some(rate_per_minute) = request.rate_per_minute {
+            validate_amount("rate_per_minute", rate_per_minute)?;
+        }
+        if let Some(connection_fee) = request.connection_fee {
+            validate_amount("connection_fee", connection_fee)?;
+        }
+
+        let rate = sqlx::query_as!(
+            Rate,
+            r#"
+            UPDATE rates
+            SET
+                description = COALESCE($1, description),
+                rate_per_minute = COALESCE($2, rate_per_minute),
+                connection_fee = COALESCE($3, connection_fee),
+                billing_increment = COALESCE($4, billing_increment),
+                updated_at = $5
+            WHERE id = $6
+            RETURNING id, rate_table_id, prefix, description, rate_per_minute, connection_fee,
+                      billing_increment, created_at, updated_at
+            "#,
+            request.description,
+            request.rate_per_minute,
+            request.connection_fee,
+            request.billing_increment,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Rate not found".into()))?;
+
+        Ok(rate)
+    }
+
+    pub async fn delete_rate(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM rates WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Rate not found".into()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn assign_rate_table(&self, extension_id: Uuid, request: AssignRateTableRequest) -> Result<(), AppError> {
+        PbxService::new(self.pool.clone()).get_extension(extension_id).await?;
+
+        match request.rate_table_id {
+            Some(rate_table_id) => {
+                self.get_rate_table(rate_table_id).await?;
+                sqlx::query!(
+                    r#"
+                    INSERT INTO extension_rate_tables (extension_id, rate_table_id)
+                    VALUES ($1, $2)
+                    ON CONFLICT (extension_id) DO UPDATE SET rate_table_id = EXCLUDED.rate_table_id
+                    "#,
+                    extension_id,
+                    rate_table_id
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!("DELETE FROM extension_rate_tables WHERE extension_id = $1", extension_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Rating
+    /// Prices completed calls placed from extensions to outside numbers
+    /// between `from` and `to`, by start time. Calls that already have a charge
+    /// are skipped unless `rerate` is set; re-rating a call that no longer
+    /// matches any rate or has no answer time removes its charge. Runs in one
+    /// transaction.
+    pub async fn rate_calls(&self, from: DateTime<Utc>, to: DateTime<Utc>, rerate: bool) -> Result<RatingResult, AppError> {
+        check_period(from, to)?;
+
+        let calls = sqlx::query!(
+            r#"
+            SELECT c.id, c.recipient_id, c.answered_at, c.end_time as "end_time!",
+                   e.id as extension_id,
+                   EXISTS (SELECT 1 FROM internal_numbers i WHERE i.number = c.recipient_id) as "internal!",
+                   COALESCE(x.rate_table_id, (SELECT id FROM rate_tables WHERE is_default)) as rate_table_id
+            FROM call_records c
+            JOIN pbx_extensions e ON e.extension_number = c.caller_id
+            LEFT JOIN extension_rate_tables x ON x.extension_id = e.id
+            WHERE c.status = $1
+              AND c.end_time IS NOT NULL
+              AND c.start_time >= $2
+              AND c.start_time < $3
+              AND ($4 OR NOT EXISTS (SELECT 1 FROM call_charges WHERE call_id = c.id))
+            "#,
+            CallStatus::Completed as CallStatus,
+            from,
+            to,
+            rerate,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tables: HashMap<Uuid, (String, Vec<Rate>)> = HashMap::new();
+        for table in self.list_rate_tables().await? {
+            let rates = self.list_rates(table.id).await?;
+            tables.insert(table.id, (table.currency, rates));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut rated = 0;
+        let mut unrated_calls = Vec::new();
+        for call in calls {
+            // Internal calls are never charged, and lose any charge from before
+            if call.internal {
+                if rerate {
+                    sqlx::query!("DELETE FROM call_charges WHERE call_id = $1", call.id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                continue;
+            }
+
+            let priced = match call.rate_table_id.and_then(|id| tables.get(&id)) {
+                Some((currency, rates)) => {
+                    select_rate(rates, &call.recipient_id, talk_seconds(call.answered_at, call.end_time))
+                        .map(|(rate, talk)| (currency, rate, talk))
+                }
+                None => Err(UnratedReason::NoRateTable),
+            };
+            let (currency, rate, talk) = match priced {
+                Ok(priced) => priced,
+                Err(reason) => {
+                    if rerate {
+                        sqlx::query!("DELETE FROM call_charges WHERE call_id = $1", call.id)
+                            .execute(&mut *tx)
+                            .await?;
+                    }
+                    unrated_calls.push(UnratedCall { call_id: call.id, reason });
+                    continue;
+                }
+            };
+
+            let cost = rate_call(rate, talk);
+            sqlx::query!(
+                r#"
+                INSERT INTO call_charges (
+                    call_id, extension_id, rate_table_id, rate_id, currency, prefix, description,
+                    billed_seconds, cost, rated_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (call_id) DO UPDATE
+                SET extension_id = EXCLUDED.extension_id,
+                    rate_table_id = EXCLUDED.rate_table_id,
+                    rate_id = EXCLUDED.rate_id,
+                    currency = EXCLUDED.currency,
+                    prefix = EXCLUDED.prefix,
+                    description = EXCLUDED.description,
+                    billed_seconds = EXCLUDED.billed_seconds,
+                    cost = EXCLUDED.cost,
+                    rated_at = EXCLUDED.rated_at
+                "#,
+                call.id,
+                call.extension_id,
+                rate.rate_table_id,
+                rate.id,
+                currency,
+                rate.prefix,
+                rate.description,
+                cost.billed_seconds,
+                cost.cost,
+                Utc::now(),
+            )
+            .execute(&mut *tx)
+            .await?;
+            rated += 1;
+        }
+        tx.commit().await?;
+
+        Ok(RatingResult {
+            rated,
+            unrated: unrated_calls.len() as u64,
+            unrated_calls,
+        })
+    }
+
+    /// Periodically rates calls that ended recently and have no charge yet.
+    pub async fn run_rating_job(self) {
+        let mut interval = tokio::time::interval(RATING_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now = Utc::now();
+            match self.rate_calls(now - chrono::Duration::days(RATING_LOOKBACK_DAYS), now, false).await {
+                Ok(RatingResult { rated: 0, .. }) => {}
+                Ok(result) => tracing::info!("Rated {} calls", result.rated),
+                Err(e) => tracing::error!("Call rating failed: {}", e),
+            }
+        }
+    }
+
+    // Invoices
+    /// Invoices for calls started within the period, for one extension or all.
+    pub async fn invoices(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        extension_id: Option<Uuid>,
+    ) -> Result<Vec<Invoice>, AppError> {
+        check_period(from, to)?;
+
+        let charges = sqlx::query_as!(
+            CallCharge,
+            r#"
+            SELECT ch.call_id, ch.extension_id, e.extension_number, e.name as extension_name,
+                   ch.currency, ch.prefix, ch.description, ch.billed_seconds, ch.cost
+            FROM call_charges ch
+            JOIN call_records c ON c.id = ch.call_id
+            JOIN pbx_extensions e ON e.id = ch.extension_id
+            WHERE c.start_time >= $1
+              AND c.start_time < $2
+              AND ($3::uuid IS NULL OR ch.extension_id = $3)
+            ORDER BY e.extension_number, c.start_time
+            "#,
+            from,
+            to,
+            extension_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(build_invoices(&charges, from, to))
+    }
+}
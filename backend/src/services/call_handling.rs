@@ -0,0 +1,415 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig},
+    error::AppError,
+    models::call_handling::{
+        CallHandlingSettings, FeatureCodeChange, FollowMeDestination, SetCallHandlingRequest,
+    },
+    services::pbx::PbxService,
+};
+
+const MAX_FOLLOW_ME_DESTINATIONS: usize = 10;
+
+// AstDB families read by the generated [call-handling] dialplan
+pub const DND_FAMILY: &str = "DND";
+pub const FORWARD_ALWAYS_FAMILY: &str = "CF";
+pub const FORWARD_BUSY_FAMILY: &str = "CFB";
+pub const FORWARD_NO_ANSWER_FAMILY: &str = "CFNA";
+pub const FOLLOW_ME_FAMILY: &str = "FM";
+
+/// One AstDB change; `value: None` deletes the key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AstDbWrite {
+    pub family: &'static str,
+    pub key: String,
+    pub value: Option<String>,
+}
+
+pub fn validate_forward_number(number: &str, extension_number: &str) -> Result<(), AppError> {
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::Validation(format!(
+            "Forwarding number '{}' may only contain digits",
+            number
+        )));
+    }
+    if number == extension_number {
+        return Err(AppError::Validation("An extension cannot forward to itself".into()));
+    }
+    Ok(())
+}
+
+/// The AstDB state that mirrors `settings`. The follow-me tree is expected to
+/// be cleared first, so stale destinations do not linger.
+pub fn astdb_writes(settings: &CallHandlingSettings) -> Vec<AstDbWrite> {
+    let number = &settings.extension_number;
+    let write = |family: &'static str, key: String, value: Option<String>| AstDbWrite { family, key, value };
+
+    let mut writes = vec![
+        write(DND_FAMILY, number.clone(), settings.dnd.then(|| "1".to_string())),
+        write(FORWARD_ALWAYS_FAMILY, number.clone(), settings.forward_always.clone()),
+        write(FORWARD_BUSY_FAMILY, number.clone(), settings.forward_busy.clone()),
+        write(FORWARD_NO_ANSWER_FAMILY, number.clone(), settings.forward_no_answer.clone()),
+    ];
+
+    if settings.follow_me_enabled && !settings.follow_me.is_empty() {
+        writes.push(write(FOLLOW_ME_FAMILY, format!("{}/enabled", number), Some("1".into())));
+    }
+    if !settings.follow_me.is_empty() {
+        writes.push(write(
+            FOLLOW_ME_FAMILY,
+            format!("{}/count", number),
+            Some(settings.follow_me.len().to_string()),
+        ));
+    }
+    for (index, destination) in settings.follow_me.iter().enumerate() {
+        writes.push(write(
+            FOLLOW_ME_FAMILY,
+            format!("{}/{}/number", number, index),
+            Some(destination.number.clone()),
+        ));
+        writes.push(write(
+            FOLLOW_ME_FAMILY,
+            format!("{}/{}/time", number, index),
+            Some(destination.ring_time.to_string()),
+        ));
+    }
+
+    writes
+}
+
+/// Reads the `Setting`/`Value` pair sent by the feature code dialplan.
+pub fn parse_feature_code(setting: &str, value: &str) -> Option<FeatureCodeChange> {
+    let number = || (!value.is_empty()).then(|| value.to_string());
+    match setting {
+        "dnd" => Some(FeatureCodeChange::Dnd(value == "1")),
+        "forward_always" => Some(FeatureCodeChange::ForwardAlways(number())),
+        "forward_busy" => Some(FeatureCodeChange::ForwardBusy(number())),
+        "forward_no_answer" => Some(FeatureCodeChange::ForwardNoAnswer(number())),
+        "follow_me" => Some(FeatureCodeChange::FollowMe(value == "1")),
+        _ => None,
+    }
+}
+
+pub struct CallHandlingService {
+    pool: PgPool,
+}
+
+impl CallHandlingService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Extensions without stored settings get everything switched off.
+    pub async fn get_settings(&self, extension_id: Uuid) -> Result<CallHandlingSettings, AppError> {
+        let extension = PbxService::new(self.pool.clone()).get_extension(extension_id).await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT dnd, forward_always, forward_busy, forward_no_answer,
+                   follow_me_enabled, updated_at
+            FROM extension_call_handling
+            WHERE extension_id = $1
+            "#,
+            extension_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let follow_me = self.list_follow_me(extension_id).await?;
+
+        Ok(match row {
+            Some(row) => CallHandlingSettings {
+                extension_id,
+                extension_number: extension.extension_number,
+                dnd: row.dnd,
+                forward_always: row.forward_always,
+                forward_busy: row.forward_busy,
+                forward_no_answer: row.forward_no_answer,
+                follow_me_enabled: row.follow_me_enabled,
+                follow_me,
+                updated_at: Some(row.updated_at),
+            },
+            None => CallHandlingSettings {
+                extension_id,
+                extension_number: extension.extension_number,
+                dnd: false,
+                forward_always: None,
+                forward_busy: None,
+                forward_no_answer: None,
+                follow_me_enabled: false,
+                follow_me,
+                updated_at: None,
+            },
+        })
+    }
+
+    async fn list_follow_me(&self, extension_id: Uuid) -> Result<Vec<FollowMeDestination>, AppError> {
+        let destinations = sqlx::query_as!(
+            FollowMeDestination,
+            r#"
+            SELECT number, ring_time
+            FROM extension_follow_me
+            WHERE extension_id = $1
+            ORDER BY position
+            "#,
+            extension_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(destinations)
+    }
+
+    pub async fn set_settings(
+        &self,
+        extension_id: Uuid,
+        request: SetCallHandlingRequest,
+    ) -> Result<CallHandlingSettings, AppError> {
+        let extension = PbxService::new(self.pool.clone()).get_extension(extension_id).await?;
+        let number = &extension.extension_number;
+        for forward in [&request.forward_always, &request.forward_busy, &request.forward_no_answer]
+            .into_iter()
+            .flatten()
+        {
+            validate_forward_number(forward, number)?;
+        }
+        if request.follow_me.len() > MAX_FOLLOW_ME_DESTINATIONS {
+            return Err(AppError::Validation(format!(
+                "Follow-me allows at most {} destinations",
+                MAX_FOLLOW_ME_DESTINATIONS
+            )));
+        }
+        for destination in &request.follow_me {
+            validate_forward_number(&destination.number, number)?;
+        }
+        if request.follow_me_enabled && request.follow_me.is_empty() {
+            return Err(AppError::Validation("Follow-me needs at least one destination".into()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO extension_call_handling (
+                extension_id, dnd, forward_always, forward_busy, forward_no_answer,
+                follow_me_enabled, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (extension_id) DO UPDATE
+            SET dnd = EXCLUDED.dnd,
+                forward_always = EXCLUDED.forward_always,
+                forward_busy = EXCLUDED.forward_busy,
+                forward_no_answer = EXCLUDED.forward_no_answer,
+                follow_me_enabled = EXCLUDED.follow_me_enabled,
+                updated_at = EXCLUDED.updated_at
+            "#,
+            extension_id,
+            request.dnd,
+            request.forward_always,
+            request.forward_busy,
+            request.forward_no_answer,
+            request.follow_me_enabled,
+            Utc::now(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM extension_follow_me WHERE extension_id = $1", extension_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, destination) in request.follow_me.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO extension_follow_me (extension_id, position, number, ring_time)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                extension_id,
+                position as i32,
+                destination.number,
+                destination.ring_time,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        let settings = self.get_settings(extension_id).await?;
+        self.push_to_asterisk(std::slice::from_ref(&settings)).await;
+        Ok(settings)
+    }
+
+    /// Stores a change made with a feature code. Asterisk already holds the
+    /// new value, so nothing is pushed back.
+    pub async fn apply_feature_code(&self, extension_number: &str, change: FeatureCodeChange) -> Result<(), AppError> {
+        let extension = match PbxService::new(self.pool.clone())
+            .get_extension_by_number(extension_number)
+            .await
+        {
+            Ok(extension) => extension,
+            Err(AppError::NotFound(_)) => {
+                tracing::warn!("Feature code used from unknown extension {}", extension_number);
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        sqlx::query!(
+            "INSERT INTO extension_call_handling (extension_id) VALUES ($1) ON CONFLICT (extension_id) DO NOTHING",
+            extension.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let now = Utc::now();
+        let result = match change {
+            FeatureCodeChange::Dnd(dnd) => {
+                sqlx::query!(
+                    "UPDATE extension_call_handling SET dnd = $1, updated_at = $2 WHERE extension_id = $3",
+                    dnd,
+                    now,
+                    extension.id
+                )
+                .execute(&self.pool)
+                .await
+            }
+            FeatureCodeChange::ForwardAlways(number) => {
+                sqlx::query!(
+                    "UPDATE extension_call_handling SET forward_always = $1, updated_at = $2 WHERE extension_id = $3",
+                    number,
+                    now,
+                    extension.id
+                )
+                .execute(&self.pool)
+                .await
+            }
+            FeatureCodeChange::ForwardBusy(number) => {
+                sqlx::query!(
+                    "UPDATE extension_call_handling SET forward_busy = $1, updated_at = $2 WHERE extension_id = $3",
+                    number,
+                    now,
+                    extension.id
+                )
+                .execute(&self.pool)
+                .await
+            }
+            FeatureCodeChange::ForwardNoAnswer(number) => {
+                sqlx::query!(
+                    "UPDATE extension_call_handling SET forward_no_answer = $1, updated_at = $2 WHERE extension_id = $3",
+                    number,
+                    now,
+                    extension.id
+                )
+                .execute(&self.pool)
+                .await
+            }
+            FeatureCodeChange::FollowMe(enabled) => {
+                sqlx::query!(
+                    "UPDATE extension_call_handling SET follow_me_enabled = $1, updated_at = $2 WHERE extension_id = $3",
+                    enabled,
+                    now,
+                    extension.id
+                )
+                .execute(&self.pool)
+                .await
+            }
+        };
+        result?;
+
+        Ok(())
+    }
+
+    /// Every extension that has stored settings.
+    pub async fn list_settings(&self) -> Result<Vec<CallHandlingSettings>, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.extension_id, e.extension_number, c.dnd, c.forward_always, c.forward_busy,
+                   c.forward_no_answer, c.follow_me_enabled, c.updated_at
+            FROM extension_call_handling c
+            JOIN pbx_extensions e ON e.id = c.extension_id
+            ORDER BY e.extension_number
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut settings = Vec::new();
+        for row in rows {
+            settings.push(CallHandlingSettings {
+                extension_id: row.extension_id,
+                extension_number: row.extension_number,
+                dnd: row.dnd,
+                forward_always: row.forward_always,
+                forward_busy: row.forward_busy,
+                forward_no_answer: row.forward_no_answer,
+                follow_me_enabled: row.follow_me_enabled,
+                follow_me: self.list_follow_me(row.extension_id).await?,
+                updated_at: Some(row.updated_at),
+            });
+        }
+
+        Ok(settings)
+    }
+
+    /// Rewrites AstDB from the database, e.g. after Asterisk restarted. The
+    /// database is the source of truth.
+    pub async fn sync_to_asterisk(&self) -> Result<(), AppError> {
+        let settings = self.list_settings().await?;
+        self.push_to_asterisk(&settings).await;
+        Ok(())
+    }
+
+    async fn push_to_asterisk(&self, settings: &[CallHandlingSettings]) {
+        let Some(config) = AmiConfig::from_env() else {
+            return;
+        };
+        if let Err(e) = Self::write_astdb(&config, settings).await {
+            tracing::warn!("Failed to push call handling settings to Asterisk: {}", e);
+        }
+    }
+
+    async fn write_astdb(config: &AmiConfig, settings: &[CallHandlingSettings]) -> Result<(), AppError> {
+        let mut client = AmiClient::connect(config).await?;
+
+        for extension in settings {
+            // Deleting a missing key answers with an error, which is fine here
+            client
+                .send_action(
+                    "DBDelTree",
+                    &[("Family", FOLLOW_ME_FAMILY), ("Key", &extension.extension_number)],
+                )
+                .await?;
+            for write in astdb_writes(extension) {
+                match &write.value {
+                    Some(value) => {
+                        let response = client
+                            .send_action(
+                                "DBPut",
+                                &[("Family", write.family), ("Key", &write.key), ("Val", value)],
+                            )
+                            .await?;
+                        if !response.is_success() {
+                            return Err(AppError::Internal(format!(
+                                "AMI DBPut {}/{} failed: {}",
+                                write.family,
+                                write.key,
+                                response.get("Message").unwrap_or("no message")
+                            )));
+                        }
+                    }
+                    None => {
+                        client
+                            .send_action("DBDel", &[("Family", write.family), ("Key", &write.key)])
+                            .await?;
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = client.logoff().await {
+            tracing::debug!("AMI logoff failed: {}", e);
+        }
+        Ok(())
+    }
+}
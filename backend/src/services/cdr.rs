@@ -0,0 +1,295 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        cdr::{CdrExportQuery, CdrImportError, CdrImportResult, MasterCsvRecord},
+        pbx::{CallEventSource, CallStatus},
+    },
+};
+
+pub const EXPORT_COLUMNS: [&str; 10] = [
+    "id",
+    "uniqueid",
+    "caller_id",
+    "recipient_id",
+    "start_time",
+    "answered_at",
+    "end_time",
+    "duration",
+    "status",
+    "recording_path",
+];
+
+/// Rows are sent to the client in chunks of roughly this size.
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+// Master.csv column positions (cdr_csv with loguniqueid=yes)
+const COL_SRC: usize = 1;
+const COL_DST: usize = 2;
+const COL_CLID: usize = 4;
+const COL_START: usize = 9;
+const COL_ANSWER: usize = 10;
+const COL_END: usize = 11;
+const COL_DURATION: usize = 12;
+const COL_DISPOSITION: usize = 14;
+const COL_UNIQUEID: usize = 16;
+
+pub fn call_status_str(status: &CallStatus) -> &'static str {
+    match status {
+        CallStatus::Active => "active",
+        CallStatus::Completed => "completed",
+        CallStatus::Failed => "failed",
+        CallStatus::Busy => "busy",
+        CallStatus::NoAnswer => "noanswer",
+    }
+}
+
+/// Caller IDs come from outside, so stop spreadsheets from treating them as
+/// formulas. Plain numbers such as "+15551234" are left alone.
+pub fn sanitize_csv_field(value: &str) -> String {
+    if is_formula_like(value) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Reverses `sanitize_csv_field` when reading back a file we exported. Only a
+/// quote in front of something the guard would have caught is removed.
+pub fn unsanitize_csv_field(value: &str) -> &str {
+    match value.strip_prefix('\'') {
+        Some(rest) if is_formula_like(rest) => rest,
+        _ => value,
+    }
+}
+
+fn is_formula_like(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some('=') | Some('@') | Some('\t') | Some('\r') => true,
+        Some('+') | Some('-') => !chars.as_str().chars().all(|c| c.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+pub fn disposition_status(disposition: &str) -> CallStatus {
+    match disposition.trim().to_ascii_uppercase().as_str() {
+        "ANSWERED" => CallStatus::Completed,
+        "BUSY" => CallStatus::Busy,
+        "NO ANSWER" => CallStatus::NoAnswer,
+        _ => CallStatus::Failed,
+    }
+}
+
+fn parse_master_time(value: &str) -> Result<Option<DateTime<Utc>>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|time| Some(time.and_utc()))
+        .map_err(|_| format!("Invalid timestamp '{}'", value))
+}
+
+fn parse_master_record(record: &csv::StringRecord) -> Result<MasterCsvRecord, String> {
+    let field = |index: usize| record.get(index).unwrap_or("").trim();
+
+    if record.len() <= COL_UNIQUEID || field(COL_UNIQUEID).is_empty() {
+        return Err("Missing uniqueid; enable loguniqueid in cdr.conf".into());
+    }
+
+    let caller_id = if field(COL_SRC).is_empty() {
+        field(COL_CLID)
+    } else {
+        field(COL_SRC)
+    };
+    let start_time = parse_master_time(field(COL_START))?
+        .ok_or_else(|| "Missing start time".to_string())?;
+    let duration = field(COL_DURATION)
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid duration '{}'", field(COL_DURATION)))?;
+
+    Ok(MasterCsvRecord {
+        uniqueid: field(COL_UNIQUEID).to_string(),
+        caller_id: caller_id.to_string(),
+        recipient_id: field(COL_DST).to_string(),
+        start_time,
+        answered_at: parse_master_time(field(COL_ANSWER))?,
+        end_time: parse_master_time(field(COL_END))?,
+        duration,
+        status: disposition_status(field(COL_DISPOSITION)),
+    })
+}
+
+/// Parses an Asterisk `Master.csv`. Timestamps are read as UTC, which matches
+/// `usegmtime=yes` in cdr.conf. Bad lines are reported instead of aborting.
+pub fn parse_master_csv(data: &[u8]) -> (Vec<MasterCsvRecord>, Vec<CdrImportError>) {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data);
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (index, result) in reader.records().enumerate() {
+        let line = index as u64 + 1;
+        match result {
+            Ok(record) => match parse_master_record(&record) {
+                Ok(parsed) => records.push(parsed),
+                Err(message) => errors.push(CdrImportError {
+                    line: record.position().map_or(line, |position| position.line()),
+                    message,
+                }),
+            },
+            Err(e) => errors.push(CdrImportError {
+                line: e.position().map_or(line, |position| position.line()),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    (records, errors)
+}
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|time| time.to_rfc3339()).unwrap_or_default()
+}
+
+pub struct CdrService {
+    pool: PgPool,
+}
+
+impl CdrService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Streams matching call records as CSV. Rows are read from a database
+    /// cursor and handed over in chunks, so memory use stays flat; the bounded
+    /// channel stops the query when the client reads slowly or disconnects.
+    pub fn export_csv(&self, query: CdrExportQuery) -> mpsc::Receiver<Result<Vec<u8>, std::io::Error>> {
+        let (mut sender, receiver) = mpsc::channel(8);
+        let pool = self.pool.clone();
+
+        tokio::spawn(async move {
+            let status = query.status.as_ref().map(call_status_str);
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            let _ = writer.write_record(EXPORT_COLUMNS);
+
+            let mut rows = sqlx::query!(
+                r#"
+                SELECT id, uniqueid, caller_id, recipient_id, start_time, answered_at,
+                       end_time, duration, status::text as "status!", recording_path
+                FROM call_records
+                WHERE ($1::timestamptz IS NULL OR start_time >= $1)
+                  AND ($2::timestamptz IS NULL OR start_time < $2)
+                  AND ($3::text IS NULL OR caller_id = $3)
+                  AND ($4::text IS NULL OR status::text = $4)
+                ORDER BY start_time, id
+                "#,
+                query.from,
+                query.to,
+                query.caller_id,
+                status,
+            )
+            .fetch(&pool);
+
+            while let Some(row) = rows.next().await {
+                let row = match row {
+                    Ok(row) => row,
+                    Err(e) => {
+                        tracing::error!("CDR export failed: {}", e);
+                        let _ = sender
+                            .send(Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))
+                            .await;
+                        return;
+                    }
+                };
+
+                let _ = writer.write_record([
+                    row.id.to_string(),
+                    row.uniqueid.unwrap_or_default(),
+                    sanitize_csv_field(&row.caller_id),
+                    sanitize_csv_field(&row.recipient_id),
+                    row.start_time.to_rfc3339(),
+                    format_time(row.answered_at),
+                    format_time(row.end_time),
+                    row.duration.map(|duration| duration.to_string()).unwrap_or_default(),
+                    row.status,
+                    row.recording_path.unwrap_or_default(),
+                ]);
+                let _ = writer.flush();
+
+                if writer.get_ref().len() >= EXPORT_CHUNK_SIZE {
+                    let chunk = std::mem::take(writer.get_mut());
+                    if sender.send(Ok(chunk)).await.is_err() {
+                        // Client went away
+                        return;
+                    }
+                }
+            }
+
+            let _ = writer.flush();
+            let chunk = std::mem::take(writer.get_mut());
+            if !chunk.is_empty() {
+                let _ = sender.send(Ok(chunk)).await;
+            }
+        });
+
+        receiver
+    }
+
+    /// Imports a `Master.csv` file in one transaction. Calls already known by
+    /// uniqueid, from the AMI tracker or an earlier import, are counted as duplicates.
+    pub async fn import_master_csv(&self, data: &[u8]) -> Result<CdrImportResult, AppError> {
+        let (records, errors) = parse_master_csv(data);
+
+        let mut tx = self.pool.begin().await?;
+        let mut imported = 0;
+        let mut duplicates = 0;
+        for record in records {
+            let result = sqlx::query!(
+                r#"
+                WITH imported AS (
+                    INSERT INTO call_records (
+                        caller_id, recipient_id, start_time, answered_at, end_time,
+                        duration, status, uniqueid
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (uniqueid) DO NOTHING
+                    RETURNING id, status
+                )
+                INSERT INTO call_events (call_id, to_status, source)
+                SELECT id, status, $9 FROM imported
+                "#,
+                record.caller_id,
+                record.recipient_id,
+                record.start_time,
+                record.answered_at,
+                record.end_time,
+                record.duration,
+                record.status as _,
+                record.uniqueid,
+                CallEventSource::Import as CallEventSource,
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                duplicates += 1;
+            } else {
+                imported += 1;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(CdrImportResult {
+            imported,
+            duplicates,
+            errors,
+        })
+    }
+}
@@ -0,0 +1,750 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+use tokio::sync::broadcast;
+
+use crate::{
+    error::AppError,
+    models::{
+        chat::{
+            ChatClientEvent, ChatMessage, ChatSearchHit, ChatSearchPage, ChatServerEvent,
+            Conversation, ConversationKind, ConversationMember, ConversationResponse,
+            CreateConversationRequest, MessagePage,
+        },
+        presence::PresenceStatus,
+    },
+};
+
+/// Events buffered per subscriber before a slow connection starts missing them.
+const HUB_CAPACITY: usize = 1024;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+const MAX_MESSAGE_LENGTH: usize = 4000;
+
+/// Key of the direct conversation between two users, independent of who
+/// started it.
+pub fn direct_key(a: Uuid, b: Uuid) -> String {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    format!("{}:{}", first, second)
+}
+
+/// Message bodies are stored as sent but must have visible content.
+pub fn validate_message_body(body: &str) -> Result<(), AppError> {
+    if body.trim().is_empty() {
+        return Err(AppError::Validation("Message is empty".into()));
+    }
+    if body.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Message is longer than {} characters",
+            MAX_MESSAGE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Private-use characters ts_headline marks matches with. They are removed
+/// from bodies before highlighting, so only real matches carry them.
+const MATCH_START: char = '\u{E000}';
+const MATCH_END: char = '\u{E001}';
+
+/// Turns a ts_headline fragment into HTML: the message text is escaped and
+/// only the match markers become `<b>` tags.
+pub fn render_snippet(headline: &str) -> String {
+    let mut out = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            MATCH_START => out.push_str("<b>"),
+            MATCH_END => out.push_str("</b>"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Pages are fetched with one extra row; its presence means there is more.
+/// The cursor is the last item kept.
+pub fn split_page<T>(mut items: Vec<T>, size: i64, id: impl Fn(&T) -> Uuid) -> (Vec<T>, Option<Uuid>) {
+    if items.len() as i64 <= size {
+        return (items, None);
+    }
+    items.truncate(size as usize);
+    let cursor = items.last().map(id);
+    (items, cursor)
+}
+
+/// An event and the users whose connections should receive it.
+#[derive(Debug, Clone)]
+pub struct ChatDelivery {
+    pub recipients: Vec<Uuid>,
+    pub event: ChatServerEvent,
+}
+
+/// Fans events out to every open WebSocket in this process. Each connection
+/// subscribes and keeps the events addressed to its user. The hub also knows
+/// who is connected and the presence last pushed for each user.
+#[derive(Clone)]
+pub struct ChatHub {
+    sender: broadcast::Sender<Arc<ChatDelivery>>,
+    connections: Arc<Mutex<HashMap<Uuid, usize>>>,
+    presence: Arc<Mutex<HashMap<Uuid, PresenceStatus>>>,
+}
+
+impl Default for ChatHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChatHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(HUB_CAPACITY);
+        Self {
+            sender,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            presence: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ChatDelivery>> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, recipients: Vec<Uuid>, event: ChatServerEvent) {
+        if recipients.is_empty() {
+            return;
+        }
+        // Nobody connected is not an error
+        let _ = self.sender.send(Arc::new(ChatDelivery { recipients, event }));
+    }
+
+    /// Counts a new connection of the user.
+    pub fn connect(&self, user_id: Uuid) {
+        *self.connections.lock().unwrap().entry(user_id).or_insert(0) += 1;
+    }
+
+    pub fn disconnect(&self, user_id: Uuid) {
+        let mut connections = self.connections.lock().unwrap();
+        if let Some(count) = connections.get_mut(&user_id) {
+            *count -= 1;
+            if *count == 0 {
+                connections.remove(&user_id);
+            }
+        }
+    }
+
+    pub fn is_connected(&self, user_id: Uuid) -> bool {
+        self.connections.lock().unwrap().contains_key(&user_id)
+    }
+
+    pub fn connected_users(&self) -> Vec<Uuid> {
+        self.connections.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Remembers the user's presence and reports whether it changed since
+    /// it was last recorded.
+    pub fn record_presence(&self, user_id: Uuid, status: PresenceStatus) -> bool {
+        self.presence.lock().unwrap().insert(user_id, status) != Some(status)
+    }
+}
+
+pub struct ChatService {
+    pool: PgPool,
+    hub: ChatHub,
+}
+
+impl ChatService {
+    pub fn new(pool: PgPool, hub: ChatHub) -> Self {
+        Self { pool, hub }
+    }
+
+    /// Conversations a user is not in are reported as missing, so their
+    /// existence does not leak.
+    async fn ensure_member(&self, conversation_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
+        sqlx::query!(
+            "SELECT user_id FROM chat_members WHERE conversation_id = $1 AND user_id = $2",
+            conversation_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Conversation not found".into()))?;
+
+        Ok(())
+    }
+
+    async fn member_ids(&self, conversation_id: Uuid) -> Result<Vec<Uuid>, AppError> {
+        let members = sqlx::query!(
+            "SELECT user_id FROM chat_members WHERE conversation_id = $1",
+            conversation_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members.into_iter().map(|member| member.user_id).collect())
+    }
+
+    async fn get_conversation(&self, id: Uuid) -> Result<Conversation, AppError> {
+        let conversation = sqlx::query_as!(
+            Conversation,
+            r#"
+            SELECT id, kind as "kind: ConversationKind", name, created_by, last_message_at,
+                   created_at, updated_at
+            FROM chat_conversations
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Conversation not found".into()))?;
+
+        Ok(conversation)
+    }
+
+    // Conversations
+    /// Starting a direct conversation that already exists returns it.
+    pub async fn create_conversation(
+        &self,
+        creator_id: Uuid,
+        request: CreateConversationRequest,
+    ) -> Result<ConversationResponse, AppError> {
+        let mut seen = HashSet::from([creator_id]);
+        let others: Vec<Uuid> = request
+            .member_ids
+            .into_iter()
+            .filter(|id| seen.insert(*id))
+            .collect();
+
+        let (name, key) = match request.kind {
+            ConversationKind::Direct => {
+                let [other] = others.as_slice() else {
+                    return Err(AppError::Validation(
+                        "A direct conversation has exactly one other member".into(),
+                    ));
+                };
+                (None, Some(direct_key(creator_id, *other)))
+            }
+            ConversationKind::Group => {
+                let name = request
+                    .name
+                    .filter(|name| !name.trim().is_empty())
+                    .ok_or_else(|| AppError::Validation("A group conversation needs a name".into()))?;
+                if others.is_empty() {
+                    return Err(AppError::Validation("A group conversation needs members".into()));
+                }
+                (Some(name), None)
+            }
+        };
+
+        let known = sqlx::query!(
+            r#"SELECT COUNT(*) as "count!" FROM users WHERE id = ANY($1)"#,
+            &others
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count;
+        if known != others.len() as i64 {
+            return Err(AppError::Validation("Unknown user in member_ids".into()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let created = sqlx::query!(
+            r#"
+            INSERT INTO chat_conversations (kind, name, direct_key, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (direct_key) DO NOTHING
+            RETURNING id
+            "#,
+            request.kind as ConversationKind,
+            name,
+            key,
+            creator_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(created) = created else {
+            tx.rollback().await?;
+            let existing = sqlx::query!(
+                "SELECT id FROM chat_conversations WHERE direct_key = $1",
+                key
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            return self.conversation_for(existing.id, creator_id).await;
+        };
+
+        let mut members = others;
+        members.push(creator_id);
+        sqlx::query!(
+            "INSERT INTO chat_members (conversation_id, user_id) SELECT $1, UNNEST($2::uuid[])",
+            created.id,
+            &members
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.hub.publish(
+            members,
+            ChatServerEvent::ConversationUpdated {
+                conversation_id: created.id,
+            },
+        );
+        self.conversation_for(created.id, creator_id).await
+    }
+
+    /// A conversation with its members and the user's unread count.
+    pub async fn conversation_for(&self, id: Uuid, user_id: Uuid) -> Result<ConversationResponse, AppError> {
+        self.ensure_member(id, user_id).await?;
+        let conversation = self.get_conversation(id).await?;
+        let mut responses = self.with_members(vec![conversation], user_id).await?;
+        responses
+            .pop()
+            .ok_or_else(|| AppError::NotFound("Conversation not found".into()))
+    }
+
+    /// The user's conversations, most recently active first.
+    pub async fn list_conversations(&self, user_id: Uuid) -> Result<Vec<ConversationResponse>, AppError> {
+        let conversations = sqlx::query_as!(
+            Conversation,
+            r#"
+            SELECT c.id, c.kind as "kind: ConversationKind", c.name, c.created_by, c.last_message_at,
+                   c.created_at, c.updated_at
+            FROM chat_conversations c
+            JOIN chat_members m ON m.conversation_id = c.id
+            WHERE m.user_id = $1
+            ORDER BY COALESCE(c.last_message_at, c.created_at) DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.with_members(conversations, user_id).await
+    }
+
+    async fn with_members(
+        &self,
+        conversations: Vec<Conversation>,
+        user_id: Uuid,
+    ) -> Result<Vec<ConversationResponse>, AppError> {
+        let ids: Vec<Uuid> = conversations.iter().map(|conversation| conversation.id).collect();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT m.conversation_id, m.user_id, u.username, m.last_read_message_id, m.last_read_at,
+                   m.joined_at
+            FROM chat_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.conversation_id = ANY($1)
+            ORDER BY u.username
+            "#,
+            &ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut members: HashMap<Uuid, Vec<ConversationMember>> = HashMap::new();
+        for row in rows {
+            members.entry(row.conversation_id).or_default().push(ConversationMember {
+                user_id: row.user_id,
+                username: row.username,
+                last_read_message_id: row.last_read_message_id,
+                last_read_at: row.last_read_at,
+                joined_at: row.joined_at,
+            });
+        }
+
+        // Messages from others that sort after the user's read receipt
+        let unread: HashMap<Uuid, i64> = sqlx::query!(
+            r#"
+            SELECT msg.conversation_id, COUNT(*) as "count!"
+            FROM chat_members me
+            JOIN chat_messages msg ON msg.conversation_id = me.conversation_id
+            LEFT JOIN chat_messages last_read ON last_read.id = me.last_read_message_id
+            WHERE me.user_id = $1
+              AND me.conversation_id = ANY($2)
+              AND msg.sender_id <> $1
+              AND (last_read.id IS NULL OR (msg.created_at, msg.id) > (last_read.created_at, last_read.id))
+            GROUP BY msg.conversation_id
+            "#,
+            user_id,
+            &ids
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.conversation_id, row.count))
+        .collect();
+
+        Ok(conversations
+            .into_iter()
+            .map(|conversation| ConversationResponse {
+                members: members.remove(&conversation.id).unwrap_or_default(),
+                unread_count: unread.get(&conversation.id).copied().unwrap_or(0),
+                conversation,
+            })
+            .collect())
+    }
+
+    /// Any member of a group may add people.
+    pub async fn add_member(
+        &self,
+        conversation_id: Uuid,
+        actor_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<ConversationResponse, AppError> {
+        self.ensure_member(conversation_id, actor_id).await?;
+        let conversation = self.get_conversation(conversation_id).await?;
+        if conversation.kind != ConversationKind::Group {
+            return Err(AppError::Validation("Members can only be added to group conversations".into()));
+        }
+
+        let user = sqlx::query!("SELECT id FROM users WHERE id = $1", user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        if user.is_none() {
+            return Err(AppError::Validation("User does not exist".into()));
+        }
+
+        sqlx::query!(
+            "INSERT INTO chat_members (conversation_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            conversation_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.hub.publish(
+            self.member_ids(conversation_id).await?,
+            ChatServerEvent::ConversationUpdated { conversation_id },
+        );
+        self.conversation_for(conversation_id, actor_id).await
+    }
+
+    /// Members may leave a group; only its creator may remove others.
+    pub async fn remove_member(
+        &self,
+        conversation_id: Uuid,
+        actor_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        self.ensure_member(conversation_id, actor_id).await?;
+        let conversation = self.get_conversation(conversation_id).await?;
+        if conversation.kind != ConversationKind::Group {
+            return Err(AppError::Validation("Direct conversations cannot be left".into()));
+        }
+        if actor_id != user_id && conversation.created_by != Some(actor_id) {
+            return Err(AppError::Auth("Only the creator can remove members".into()));
+        }
+
+        let mut recipients = self.member_ids(conversation_id).await?;
+        let result = sqlx::query!(
+            "DELETE FROM chat_members WHERE conversation_id = $1 AND user_id = $2",
+            conversation_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Member not found".into()));
+        }
+
+        recipients.retain(|id| *id != user_id);
+        recipients.push(user_id);
+        self.hub.publish(
+            recipients,
+            ChatServerEvent::ConversationLeft {
+                conversation_id,
+                user_id,
+            },
+        );
+        Ok(())
+    }
+
+    // Messages
+    async fn get_message(&self, id: Uuid) -> Result<ChatMessage, AppError> {
+        let message = sqlx::query_as!(
+            ChatMessage,
+            r#"
+            SELECT m.id, m.conversation_id, m.sender_id, u.username as sender_username, m.body, m.created_at
+            FROM chat_messages m
+            JOIN users u ON u.id = m.sender_id
+            WHERE m.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Message not found".into()))?;
+
+        Ok(message)
+    }
+
+    /// Stores and pushes a message. Sending also moves the sender's read
+    /// receipt to it.
+    pub async fn send_message(
+        &self,
+        conversation_id: Uuid,
+        sender_id: Uuid,
+        body: &str,
+    ) -> Result<ChatMessage, AppError> {
+        validate_message_body(body)?;
+        self.ensure_member(conversation_id, sender_id).await?;
+
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO chat_messages (conversation_id, sender_id, body, created_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            "#,
+            conversation_id,
+            sender_id,
+            body,
+            now
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE chat_conversations SET last_message_at = $1, updated_at = $1 WHERE id = $2",
+            now,
+            conversation_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE chat_members
+            SET last_read_message_id = $1, last_read_at = $2
+            WHERE conversation_id = $3 AND user_id = $4
+            "#,
+            inserted.id,
+            now,
+            conversation_id,
+            sender_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let message = self.get_message(inserted.id).await?;
+        self.hub.publish(
+            self.member_ids(conversation_id).await?,
+            ChatServerEvent::Message {
+                message: message.clone(),
+            },
+        );
+        Ok(message)
+    }
+
+    /// Messages before the cursor message, newest first.
+    pub async fn history(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        before: Option<Uuid>,
+        limit: Option<i64>,
+    ) -> Result<MessagePage, AppError> {
+        self.ensure_member(conversation_id, user_id).await?;
+        let size = page_size(limit);
+
+        let messages = sqlx::query_as!(
+            ChatMessage,
+            r#"
+            SELECT m.id, m.conversation_id, m.sender_id, u.username as sender_username, m.body, m.created_at
+            FROM chat_messages m
+            JOIN users u ON u.id = m.sender_id
+            WHERE m.conversation_id = $1
+              AND ($2::uuid IS NULL
+                   OR (m.created_at, m.id) < (SELECT created_at, id FROM chat_messages WHERE id = $2))
+            ORDER BY m.created_at DESC, m.id DESC
+            LIMIT $3
+            "#,
+            conversation_id,
+            before,
+            size + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let (messages, next_cursor) = split_page(messages, size, |message| message.id);
+        Ok(MessagePage { messages, next_cursor })
+    }
+
+    /// Full-text search over the user's conversations, newest first. The
+    /// query uses web search syntax: quoted phrases, `or` and `-word`.
+    pub async fn search(
+        &self,
+        user_id: Uuid,
+        query: &str,
+        conversation_id: Option<Uuid>,
+        before: Option<Uuid>,
+        limit: Option<i64>,
+    ) -> Result<ChatSearchPage, AppError> {
+        let size = page_size(limit);
+        let markers = format!("{}{}", MATCH_START, MATCH_END);
+        let options = format!("StartSel={}, StopSel={}, MaxFragments=2", MATCH_START, MATCH_END);
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT m.id, m.conversation_id, m.sender_id, u.username as sender_username, m.body, m.created_at,
+                   ts_headline('simple', translate(m.body, $6, ''), q, $7) as "snippet!"
+            FROM chat_messages m
+            JOIN chat_members me ON me.conversation_id = m.conversation_id AND me.user_id = $1
+            JOIN users u ON u.id = m.sender_id
+            CROSS JOIN websearch_to_tsquery('simple', $2) q
+            WHERE m.search_vector @@ q
+              AND ($3::uuid IS NULL OR m.conversation_id = $3)
+              AND ($4::uuid IS NULL
+                   OR (m.created_at, m.id) < (SELECT created_at, id FROM chat_messages WHERE id = $4))
+            ORDER BY m.created_at DESC, m.id DESC
+            LIMIT $5
+            "#,
+            user_id,
+            query,
+            conversation_id,
+            before,
+            size + 1,
+            markers,
+            options
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let hits = rows
+            .into_iter()
+            .map(|row| ChatSearchHit {
+                message: ChatMessage {
+                    id: row.id,
+                    conversation_id: row.conversation_id,
+                    sender_id: row.sender_id,
+                    sender_username: row.sender_username,
+                    body: row.body,
+                    created_at: row.created_at,
+                },
+                snippet: render_snippet(&row.snippet),
+            })
+            .collect();
+
+        let (hits, next_cursor) = split_page(hits, size, |hit: &ChatSearchHit| hit.message.id);
+        Ok(ChatSearchPage { hits, next_cursor })
+    }
+
+    /// Moves the user's read receipt forward to `message_id`. Receipts never
+    /// move back, so out-of-order reports are ignored.
+    pub async fn mark_read(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<(), AppError> {
+        self.ensure_member(conversation_id, user_id).await?;
+        let message = self.get_message(message_id).await?;
+        if message.conversation_id != conversation_id {
+            return Err(AppError::NotFound("Message not found".into()));
+        }
+
+        let read_at = Utc::now();
+        let result = sqlx::query!(
+            r#"
+            UPDATE chat_members me
+            SET last_read_message_id = $1, last_read_at = $2
+            WHERE me.conversation_id = $3
+              AND me.user_id = $4
+              AND NOT EXISTS (
+                  SELECT 1 FROM chat_messages last_read
+                  WHERE last_read.id = me.last_read_message_id
+                    AND (last_read.created_at, last_read.id) >= ($5, $1)
+              )
+            "#,
+            message.id,
+            read_at,
+            conversation_id,
+            user_id,
+            message.created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            self.hub.publish(
+                self.member_ids(conversation_id).await?,
+                ChatServerEvent::Read {
+                    conversation_id,
+                    user_id,
+                    message_id: message.id,
+                    read_at,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Typing indicators go to the other members only and are not stored.
+    pub async fn typing(&self, conversation_id: Uuid, user_id: Uuid, username: &str) -> Result<(), AppError> {
+        self.ensure_member(conversation_id, user_id).await?;
+
+        let mut recipients = self.member_ids(conversation_id).await?;
+        recipients.retain(|id| *id != user_id);
+        self.hub.publish(
+            recipients,
+            ChatServerEvent::Typing {
+                conversation_id,
+                user_id,
+                username: username.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Handles a frame received over a user's WebSocket.
+    pub async fn handle_client_event(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        event: ChatClientEvent,
+    ) -> Result<(), AppError> {
+        match event {
+            ChatClientEvent::Send { conversation_id, body } => {
+                self.send_message(conversation_id, user_id, &body).await?;
+            }
+            ChatClientEvent::Typing { conversation_id } => {
+                self.typing(conversation_id, user_id, username).await?;
+            }
+            ChatClientEvent::Read {
+                conversation_id,
+                message_id,
+            } => {
+                self.mark_read(conversation_id, user_id, message_id).await?;
+            }
+            // Subscriptions belong to the connection, which handles them
+            ChatClientEvent::SubscribePresence { .. } | ChatClientEvent::UnsubscribePresence => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig, AmiMessage},
+    error::AppError,
+    models::conference::{
+        ConferenceAttendance, ConferenceParticipant, ConferenceRoom, CreateConferenceRoomRequest,
+        UpdateConferenceRoomRequest,
+    },
+    services::voicemail::validate_pin,
+};
+
+/// Bridge profile rendered into confbridge.conf for a room.
+pub fn bridge_profile(room_number: &str) -> String {
+    format!("conference-{}", room_number)
+}
+
+/// Both PINs are optional, but when both are set they must differ so the
+/// dialplan can tell admins from users.
+pub fn validate_pins(user_pin: Option<&str>, admin_pin: Option<&str>) -> Result<(), AppError> {
+    if let Some(pin) = user_pin {
+        validate_pin(pin)?;
+    }
+    if let Some(pin) = admin_pin {
+        validate_pin(pin)?;
+    }
+    if user_pin.is_some() && user_pin == admin_pin {
+        return Err(AppError::Validation("User and admin PINs must differ".into()));
+    }
+    Ok(())
+}
+
+/// Reads the `ConfbridgeList` events returned for one conference.
+pub fn parse_participants(events: &[AmiMessage]) -> Vec<ConferenceParticipant> {
+    let yes = |value: Option<&str>| value.is_some_and(|value| value.eq_ignore_ascii_case("yes"));
+    let caller_id = |value: Option<&str>| {
+        value
+            .filter(|value| !value.is_empty() && *value != "<unknown>")
+            .map(str::to_string)
+    };
+
+    events
+        .iter()
+        .filter(|event| event.event() == Some("ConfbridgeList"))
+        .filter_map(|event| {
+            Some(ConferenceParticipant {
+                channel: event.get("Channel")?.to_string(),
+                caller_id_num: caller_id(event.get("CallerIDNum")),
+                caller_id_name: caller_id(event.get("CallerIDName")),
+                admin: yes(event.get("Admin")),
+                muted: yes(event.get("Muted")),
+            })
+        })
+        .collect()
+}
+
+fn ami_config() -> Result<AmiConfig, AppError> {
+    AmiConfig::from_env()
+        .ok_or_else(|| AppError::Internal("AMI is not configured; set AMI_HOST to control conferences".into()))
+}
+
+pub struct ConferenceService {
+    pool: PgPool,
+}
+
+impl ConferenceService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Room numbers share the namespace of extension and ring group numbers.
+    /// The database enforces this too; checking here gives a readable error.
+    async fn ensure_number_available(&self, number: &str, room_id: Option<Uuid>) -> Result<(), AppError> {
+        if !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::Validation("Room number may only contain digits".into()));
+        }
+
+        let extension = sqlx::query!(
+            "SELECT id FROM pbx_extensions WHERE extension_number = $1",
+            number
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if extension.is_some() {
+            return Err(AppError::Validation("Number is already used by an extension".into()));
+        }
+
+        let group = sqlx::query!("SELECT id FROM ring_groups WHERE group_number = $1", number)
+            .fetch_optional(&self.pool)
+            .await?;
+        if group.is_some() {
+            return Err(AppError::Validation("Number is already used by a ring group".into()));
+        }
+
+        let room = sqlx::query!("SELECT id FROM conference_rooms WHERE room_number = $1", number)
+            .fetch_optional(&self.pool)
+            .await?;
+        if matches!(room, Some(room) if Some(room.id) != room_id) {
+            return Err(AppError::Validation("Number is already used by a conference room".into()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_room(&self, request: CreateConferenceRoomRequest) -> Result<ConferenceRoom, AppError> {
+        self.ensure_number_available(&request.room_number, None).await?;
+        validate_pins(request.user_pin.as_deref(), request.admin_pin.as_deref())?;
+
+        let room = sqlx::query_as!(
+            ConferenceRoom,
+            r#"
+            INSERT INTO conference_rooms (
+                room_number, name, user_pin, admin_pin, max_participants, record, owner_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, room_number, name, user_pin, admin_pin, max_participants,
+                      record, owner_id, created_at, updated_at
+            "#,
+            request.room_number,
+            request.name,
+            request.user_pin,
+            request.admin_pin,
+            request.max_participants,
+            request.record.unwrap_or(false),
+            request.owner_id,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(room)
+    }
+
+    pub async fn get_room(&self, id: Uuid) -> Result<ConferenceRoom, AppError> {
+        let room = sqlx::query_as!(
+            ConferenceRoom,
+            r#"
+            SELECT id, room_number, name, user_pin, admin_pin, max_participants,
+                   record, owner_id, created_at, updated_at
+            FROM conference_rooms
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Conference room not found".into()))?;
+
+        Ok(room)
+    }
+
+    pub async fn list_rooms(&self) -> Result<Vec<ConferenceRoom>, AppError> {
+        let rooms = sqlx::query_as!(
+            ConferenceRoom,
+            r#"
+            SELECT id, room_number, name, user_pin, admin_pin, max_participants,
+                   record, owner_id, created_at, updated_at
+            FROM conference_rooms
+            ORDER BY room_number
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rooms)
+    }
+
+    pub async fn update_room(
+        &self,
+        id: Uuid,
+        request: UpdateConferenceRoomRequest,
+    ) -> Result<ConferenceRoom, AppError> {
+        if let Some(number) = &request.room_number {
+            self.ensure_number_available(number, Some(id)).await?;
+        }
+        let current = self.get_room(id).await?;
+        validate_pins(
+            request.user_pin.as_deref().or(current.user_pin.as_deref()),
+            request.admin_pin.as_deref().or(current.admin_pin.as_deref()),
+        )?;
+
+        let room = sqlx::query_as!(
+            ConferenceRoom,
+            r#"
+            UPDATE conference_rooms
+            SET room_number = COALESCE($1, room_number),
+                name = COALESCE($2, name),
+                user_pin = COALESCE($3, user_pin),
+                admin_pin = COALESCE($4, admin_pin),
+                max_participants = COALESCE($5, max_participants),
+                record = COALESCE($6, record),
+                owner_id = COALESCE($7, owner_id),
+                updated_at = $8
+            WHERE id = $9
+            RETURNING id, room_number, name, user_pin, admin_pin, max_participants,
+                      record, owner_id, created_at, updated_at
+            "#,
+            request.room_number,
+            request.name,
+            request.user_pin,
+            request.admin_pin,
+            request.max_participants,
+            request.record,
+            request.owner_id,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Conference room not found".into()))?;
+
+        Ok(room)
+    }
+
+    pub async fn delete_room(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM conference_rooms WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Conference room not found".into()));
+        }
+
+        Ok(())
+    }
+
+    // Live control through AMI
+    pub async fn list_participants(&self, room: &ConferenceRoom) -> Result<Vec<ConferenceParticipant>, AppError> {
+        let config = ami_config()?;
+        match AmiClient::run_list_action(&config, "ConfbridgeList", &[("Conference", &room.room_number)]).await {
+            Ok(events) => Ok(parse_participants(&events)),
+            // Asterisk answers with an error rather than an empty list for an idle room
+            Err(AppError::Internal(message)) if message.contains("No active conferences") => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn mute_participant(&self, room: &ConferenceRoom, channel: &str, muted: bool) -> Result<(), AppError> {
+        let action = if muted { "ConfbridgeMute" } else { "ConfbridgeUnmute" };
+        AmiClient::run_action(
+            &ami_config()?,
+            action,
+            &[("Conference", &room.room_number), ("Channel", channel)],
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn kick_participant(&self, room: &ConferenceRoom, channel: &str) -> Result<(), AppError> {
+        AmiClient::run_action(
+            &ami_config()?,
+            "ConfbridgeKick",
+            &[("Conference", &room.room_number), ("Channel", channel)],
+        )
+        .await?;
+        Ok(())
+    }
+
+    // Attendance history, written by the AMI call tracker
+    pub async fn record_join(
+        &self,
+        conference: &str,
+        channel: &str,
+        caller_id_num: Option<&str>,
+        caller_id_name: Option<&str>,
+        admin: bool,
+        joined_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        // Conferences started outside OrionTel have no room and are ignored
+        sqlx::query!(
+            r#"
+            INSERT INTO conference_attendance (
+                room_id, channel, caller_id_num, caller_id_name, admin, joined_at
+            )
+            SELECT id, $2, $3, $4, $5, $6
+            FROM conference_rooms
+            WHERE room_number = $1
+            "#,
+            conference,
+            channel,
+            caller_id_num,
+            caller_id_name,
+            admin,
+            joined_at,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_leave(&self, conference: &str, channel: &str, left_at: DateTime<Utc>) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE conference_attendance a
+            SET left_at = $3
+            FROM conference_rooms r
+            WHERE r.id = a.room_id AND r.room_number = $1
+              AND a.channel = $2 AND a.left_at IS NULL
+            "#,
+            conference,
+            channel,
+            left_at,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Closes every open attendance when Asterisk reports the conference ended.
+    pub async fn record_end(&self, conference: &str, ended_at: DateTime<Utc>) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE conference_attendance a
+            SET left_at = $2
+            FROM conference_rooms r
+            WHERE r.id = a.room_id AND r.room_number = $1 AND a.left_at IS NULL
+            "#,
+            conference,
+            ended_at,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_history(
+        &self,
+        room_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ConferenceAttendance>, AppError> {
+        let history = sqlx::query_as!(
+            ConferenceAttendance,
+            r#"
+            SELECT id, room_id, channel, caller_id_num, caller_id_name, admin, joined_at, left_at
+            FROM conference_attendance
+            WHERE room_id = $1
+            ORDER BY joined_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            room_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(history)
+    }
+}
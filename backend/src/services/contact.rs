@@ -0,0 +1,902 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::contact::{
+        Contact, ContactEmail, ContactEmailInput, ContactImportError, ContactImportResult, ContactLabel,
+        ContactLookup, ContactMatch, ContactNumber, ContactNumberInput, ContactScope,
+        CreateContactRequest, UpdateContactRequest, VcardVersion,
+    },
+    services::pbx::PbxService,
+};
+
+const MAX_NUMBERS: usize = 20;
+const MAX_EMAILS: usize = 20;
+const MAX_CARDS: usize = 5000;
+/// vCard lines longer than this many octets are folded.
+const VCARD_LINE_LENGTH: usize = 75;
+
+/// A number as dialled: digits, `*` and `#`, with a leading `+` kept.
+pub fn clean_number(number: &str) -> String {
+    let number = number.trim();
+    let mut cleaned: String = number
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '*' || *c == '#')
+        .collect();
+    if number.starts_with('+') {
+        cleaned.insert(0, '+');
+    }
+    cleaned
+}
+
+/// Normalizes a number to E.164. Numbers in national format (with a leading
+/// `0` trunk prefix, or ten digits in the North American plan) need the
+/// default `country_code`. Short numbers such as extensions are not
+/// normalized.
+pub fn normalize_e164(number: &str, country_code: Option<&str>) -> Option<String> {
+    let cleaned = clean_number(number);
+    if cleaned.contains(['*', '#']) {
+        return None;
+    }
+
+    let international = if let Some(rest) = cleaned.strip_prefix('+') {
+        rest.to_string()
+    } else if let Some(rest) = cleaned.strip_prefix("00") {
+        rest.to_string()
+    } else {
+        match country_code {
+            Some("1") if cleaned.len() == 10 => format!("1{}", cleaned),
+            Some("1") if cleaned.len() == 11 && cleaned.starts_with('1') => cleaned,
+            Some(code) if cleaned.len() > 1 && cleaned.starts_with('0') => format!("{}{}", code, &cleaned[1..]),
+            _ => return None,
+        }
+    };
+
+    let valid = (7..=15).contains(&international.len())
+        && !international.starts_with('0')
+        && international.chars().all(|c| c.is_ascii_digit());
+    valid.then(|| format!("+{}", international))
+}
+
+/// E.164 forms a caller ID may stand for. Trunks often deliver
+/// international numbers without the `+`, so those are tried as well.
+pub fn lookup_candidates(number: &str, country_code: Option<&str>) -> Vec<String> {
+    let mut candidates: Vec<String> = normalize_e164(number, country_code).into_iter().collect();
+    let cleaned = clean_number(number);
+    if !cleaned.starts_with(['+', '0']) {
+        if let Some(international) = normalize_e164(&format!("+{}", cleaned), None) {
+            if !candidates.contains(&international) {
+                candidates.push(international);
+            }
+        }
+    }
+    candidates
+}
+
+/// Country of numbers written in national format, e.g. 49 or 1, from
+/// `PHONE_COUNTRY_CODE`.
+pub fn phone_country_code() -> Option<String> {
+    std::env::var("PHONE_COUNTRY_CODE")
+        .ok()
+        .map(|code| code.trim().trim_start_matches('+').to_string())
+        .filter(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The name shown for a contact when none is given.
+pub fn default_display_name(
+    first_name: Option<&str>,
+    last_name: Option<&str>,
+    organization: Option<&str>,
+) -> Option<String> {
+    let name = [first_name, last_name]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !name.is_empty() {
+        return Some(name);
+    }
+    organization
+        .map(str::trim)
+        .filter(|organization| !organization.is_empty())
+        .map(str::to_string)
+}
+
+/// Builds a case-insensitive substring pattern for LIKE.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+// vCard
+/// Splits at `separator` outside double quotes.
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            parts.push(&text[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Splits a structured value such as N at unescaped semicolons.
+fn split_components(value: &str) -> Vec<String> {
+    let mut components = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    components.last_mut().unwrap().extend(['\\', next]);
+                }
+            }
+            ';' => components.push(String::new()),
+            c => components.last_mut().unwrap().push(c),
+        }
+    }
+    components.iter().map(|component| unescape_text(component)).collect()
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// One content line: the property name, its TYPE values and the raw value.
+struct VcardProperty {
+    name: String,
+    types: Vec<String>,
+    value: String,
+}
+
+fn parse_property(line: &str) -> Option<VcardProperty> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == ':' && !quoted).then_some(i)
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = split_unquoted(head, ';').into_iter();
+    let name = parts.next()?;
+    // Grouped properties look like item1.TEL
+    let name = name.rsplit('.').next().unwrap_or(name).to_ascii_uppercase();
+
+    let mut types = Vec::new();
+    for param in parts {
+        match param.split_once('=') {
+            Some((key, values)) if key.eq_ignore_ascii_case("TYPE") => {
+                types.extend(
+                    values
+                        .trim_matches('"')
+                        .split(',')
+                        .map(|value| value.trim().to_ascii_lowercase()),
+                );
+            }
+            Some(_) => {}
+            // vCard 2.1 style bare types, e.g. TEL;CELL
+            None => types.push(param.trim().to_ascii_lowercase()),
+        }
+    }
+
+    Some(VcardProperty {
+        name,
+        types,
+        value: value.to_string(),
+    })
+}
+
+fn number_label(types: &[String]) -> ContactLabel {
+    let has = |name: &str| types.iter().any(|t| t == name);
+    if has("cell") {
+        ContactLabel::Mobile
+    } else if has("fax") {
+        ContactLabel::Fax
+    } else if has("home") {
+        ContactLabel::Home
+    } else if has("work") {
+        ContactLabel::Work
+    } else {
+        ContactLabel::Other
+    }
+}
+
+fn email_label(types: &[String]) -> ContactLabel {
+    if types.iter().any(|t| t == "home") {
+        ContactLabel::Home
+    } else if types.iter().any(|t| t == "work") {
+        ContactLabel::Work
+    } else {
+        ContactLabel::Other
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+fn parse_card(lines: &[String]) -> Result<CreateContactRequest, AppError> {
+    let mut request = CreateContactRequest {
+        scope: ContactScope::Personal,
+        display_name: None,
+        first_name: None,
+        last_name: None,
+        organization: None,
+        title: None,
+        notes: None,
+        numbers: Vec::new(),
+        emails: Vec::new(),
+    };
+    let mut version = None;
+
+    for line in lines {
+        let Some(property) = parse_property(line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "VERSION" => version = Some(property.value.trim().to_string()),
+            "FN" => request.display_name = non_empty(unescape_text(&property.value)),
+            "N" => {
+                let mut components = split_components(&property.value).into_iter();
+                request.last_name = components.next().and_then(non_empty);
+                request.first_name = components.next().and_then(non_empty);
+            }
+            "ORG" => {
+                request.organization = split_components(&property.value).into_iter().next().and_then(non_empty)
+            }
+            "TITLE" => request.title = non_empty(unescape_text(&property.value)),
+            "NOTE" => request.notes = non_empty(unescape_text(&property.value)),
+            "TEL" => {
+                let value = property.value.trim();
+                let number = value
+                    .get(..4)
+                    .filter(|scheme| scheme.eq_ignore_ascii_case("tel:"))
+                    .map_or(value, |_| &value[4..]);
+                // Drop URI parameters such as ;ext=
+                let number = number.split(';').next().unwrap_or_default().trim();
+                if !number.is_empty() {
+                    request.numbers.push(ContactNumberInput {
+                        label: number_label(&property.types),
+                        number: number.to_string(),
+                    });
+                }
+            }
+            "EMAIL" => {
+                if let Some(email) = non_empty(unescape_text(&property.value)) {
+                    request.emails.push(ContactEmailInput {
+                        label: email_label(&property.types),
+                        email,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match version.as_deref() {
+        Some("2.1") | Some("3.0") | Some("4.0") => {}
+        Some(other) => return Err(AppError::Validation(format!("Unsupported vCard version {}", other))),
+        None => return Err(AppError::Validation("vCard has no VERSION".into())),
+    }
+    if request.display_name.is_none() {
+        request.display_name = default_display_name(
+            request.first_name.as_deref(),
+            request.last_name.as_deref(),
+            request.organization.as_deref(),
+        );
+    }
+    if request.display_name.is_none() {
+        return Err(AppError::Validation("vCard has no name".into()));
+    }
+
+    Ok(request)
+}
+
+/// Parses a vCard 3.0 or 4.0 file with any number of cards. Each card is
+/// parsed on its own, so one broken card does not fail the others.
+pub fn parse_vcards(data: &str) -> Vec<Result<CreateContactRequest, AppError>> {
+    // Unfold continuation lines first
+    let mut lines: Vec<String> = Vec::new();
+    for line in data.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut cards = Vec::new();
+    let mut current: Option<Vec<String>> = None;
+    for line in lines {
+        let upper = line.trim().to_ascii_uppercase();
+        if upper == "BEGIN:VCARD" {
+            current = Some(Vec::new());
+        } else if upper == "END:VCARD" {
+            if let Some(card) = current.take() {
+                cards.push(parse_card(&card));
+            }
+        } else if let Some(card) = current.as_mut() {
+            card.push(line);
+        }
+    }
+    cards
+}
+
+/// Appends a content line, folded at 75 octets without splitting characters.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > VCARD_LINE_LENGTH {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn number_types(label: ContactLabel) -> &'static str {
+    match label {
+        ContactLabel::Work => "work,voice",
+        ContactLabel::Home => "home,voice",
+        ContactLabel::Mobile => "cell,voice",
+        ContactLabel::Fax => "fax",
+        ContactLabel::Other => "voice",
+    }
+}
+
+fn email_type(label: ContactLabel) -> Option<&'static str> {
+    match label {
+        ContactLabel::Work => Some("work"),
+        ContactLabel::Home => Some("home"),
+        _ => None,
+    }
+}
+
+pub fn render_vcards(contacts: &[Contact], version: VcardVersion) -> String {
+    let mut out = String::new();
+    for contact in contacts {
+        push_line(&mut out, "BEGIN:VCARD");
+        push_line(
+            &mut out,
+            match version {
+                VcardVersion::V3 => "VERSION:3.0",
+                VcardVersion::V4 => "VERSION:4.0",
+            },
+        );
+        push_line(&mut out, &format!("UID:urn:uuid:{}", contact.id));
+        push_line(&mut out, &format!("FN:{}", escape_text(&contact.display_name)));
+        push_line(
+            &mut out,
+            &format!(
+                "N:{};{};;;",
+                escape_text(contact.last_name.as_deref().unwrap_or_default()),
+                escape_text(contact.first_name.as_deref().unwrap_or_default())
+            ),
+        );
+        if let Some(organization) = &contact.organization {
+            push_line(&mut out, &format!("ORG:{}", escape_text(organization)));
+        }
+        if let Some(title) = &contact.title {
+            push_line(&mut out, &format!("TITLE:{}", escape_text(title)));
+        }
+        for number in &contact.numbers {
+            let types = number_types(number.label);
+            let line = match version {
+                VcardVersion::V3 => format!("TEL;TYPE={}:{}", types.to_ascii_uppercase(), number.number),
+                VcardVersion::V4 => {
+                    let uri = number.e164.clone().unwrap_or_else(|| clean_number(&number.number));
+                    format!("TEL;VALUE=uri;TYPE=\"{}\":tel:{}", types, uri)
+                }
+            };
+            push_line(&mut out, &line);
+        }
+        for email in &contact.emails {
+            let line = match (version, email_type(email.label)) {
+                (VcardVersion::V3, Some(kind)) => {
+                    format!("EMAIL;TYPE=INTERNET,{}:{}", kind.to_ascii_uppercase(), email.email)
+                }
+                (VcardVersion::V3, None) => format!("EMAIL;TYPE=INTERNET:{}", email.email),
+                (VcardVersion::V4, Some(kind)) => format!("EMAIL;TYPE={}:{}", kind, email.email),
+                (VcardVersion::V4, None) => format!("EMAIL:{}", email.email),
+            };
+            push_line(&mut out, &line);
+        }
+        if let Some(notes) = &contact.notes {
+            push_line(&mut out, &format!("NOTE:{}", escape_text(notes)));
+        }
+        push_line(&mut out, "END:VCARD");
+    }
+    out
+}
+
+pub struct ContactService {
+    pool: PgPool,
+    country_code: Option<String>,
+}
+
+impl ContactService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            country_code: phone_country_code(),
+        }
+    }
+
+    fn check_counts(numbers: usize, emails: usize) -> Result<(), AppError> {
+        if numbers > MAX_NUMBERS {
+            return Err(AppError::Validation(format!("A contact has at most {} numbers", MAX_NUMBERS)));
+        }
+        if emails > MAX_EMAILS {
+            return Err(AppError::Validation(format!("A contact has at most {} emails", MAX_EMAILS)));
+        }
+        Ok(())
+    }
+
+    async fn replace_numbers(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        contact_id: Uuid,
+        numbers: &[ContactNumberInput],
+    ) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM contact_numbers WHERE contact_id = $1", contact_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for (position, number) in numbers.iter().enumerate() {
+            let digits = clean_number(&number.number);
+            if digits.trim_start_matches('+').is_empty() {
+                return Err(AppError::Validation(format!("'{}' is not a phone number", number.number)));
+            }
+            sqlx::query!(
+                r#"
+                INSERT INTO contact_numbers (contact_id, position, label, number, digits, e164)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+                contact_id,
+                position as i32,
+                number.label as ContactLabel,
+                number.number.trim(),
+                digits,
+                normalize_e164(&number.number, self.country_code.as_deref()),
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn replace_emails(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        contact_id: Uuid,
+        emails: &[ContactEmailInput],
+    ) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM contact_emails WHERE contact_id = $1", contact_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for (position, email) in emails.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO contact_emails (contact_id, position, label, email)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                contact_id,
+                position as i32,
+                email.label as ContactLabel,
+                email.email.trim(),
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds numbers and emails to contact rows loaded without them.
+    async fn with_details(&self, mut contacts: Vec<Contact>) -> Result<Vec<Contact>, AppError> {
+        let ids: Vec<Uuid> = contacts.iter().map(|contact| contact.id).collect();
+
+        let mut numbers: HashMap<Uuid, Vec<ContactNumber>> = HashMap::new();
+        for row in sqlx::query!(
+            r#"
+            SELECT contact_id, label as "label: ContactLabel", number, e164
+            FROM contact_numbers
+            WHERE contact_id = ANY($1)
+            ORDER BY contact_id, position
+            "#,
+            &ids
+        )
+        .fetch_all(&self.pool)
+        .await?
+        {
+            numbers.entry(row.contact_id).or_default().push(ContactNumber {
+                label: row.label,
+                number: row.number,
+                e164: row.e164,
+            });
+        }
+
+        let mut emails: HashMap<Uuid, Vec<ContactEmail>> = HashMap::new();
+        for row in sqlx::query!(
+            r#"
+            SELECT contact_id, label as "label: ContactLabel", email
+            FROM contact_emails
+            WHERE contact_id = ANY($1)
+            ORDER BY contact_id, position
+            "#,
+            &ids
+        )
+        .fetch_all(&self.pool)
+        .await?
+        {
+            emails.entry(row.contact_id).or_default().push(ContactEmail {
+                label: row.label,
+                email: row.email,
+            });
+        }
+
+        for contact in &mut contacts {
+            contact.numbers = numbers.remove(&contact.id).unwrap_or_default();
+            contact.emails = emails.remove(&contact.id).unwrap_or_default();
+        }
+        Ok(contacts)
+    }
+
+    /// Creates a contact in `owner_id`'s address book, or in the company
+    /// phonebook when `owner_id` is unset.
+    pub async fn create_contact(
+        &self,
+        owner_id: Option<Uuid>,
+        request: CreateContactRequest,
+    ) -> Result<Contact, AppError> {
+        Self::check_counts(request.numbers.len(), request.emails.len())?;
+        let display_name = request
+            .display_name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| {
+                default_display_name(
+                    request.first_name.as_deref(),
+                    request.last_name.as_deref(),
+                    request.organization.as_deref(),
+                )
+            })
+            .ok_or_else(|| AppError::Validation("A contact needs a name or an organization".into()))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let created = sqlx::query!(
+            r#"
+            INSERT INTO contacts (owner_id, display_name, first_name, last_name, organization, title, notes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+            owner_id,
+            display_name.trim(),
+            request.first_name,
+            request.last_name,
+            request.organization,
+            request.title,
+            request.notes,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        self.replace_numbers(&mut tx, created.id, &request.numbers).await?;
+        self.replace_emails(&mut tx, created.id, &request.emails).await?;
+        tx.commit().await?;
+
+        self.get_contact(created.id).await
+    }
+
+    pub async fn get_contact(&self, id: Uuid) -> Result<Contact, AppError> {
+        let contact = sqlx::query!(
+            r#"
+            SELECT id, owner_id, display_name, first_name, last_name, organization, title, notes,
+                   created_at, updated_at
+            FROM contacts
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Contact not found".into()))?;
+
+        let contact = Contact {
+            id: contact.id,
+            owner_id: contact.owner_id,
+            display_name: contact.display_name,
+            first_name: contact.first_name,
+            last_name: contact.last_name,
+            organization: contact.organization,
+            title: contact.title,
+            notes: contact.notes,
+            numbers: Vec::new(),
+            emails: Vec::new(),
+            created_at: contact.created_at,
+            updated_at: contact.updated_at,
+        };
+        let mut contacts = self.with_details(vec![contact]).await?;
+        contacts
+            .pop()
+            .ok_or_else(|| AppError::NotFound("Contact not found".into()))
+    }
+
+    /// The user's personal contacts and the company phonebook, or one of
+    /// them, ordered by name.
+    pub async fn list_contacts(
+        &self,
+        user_id: Uuid,
+        scope: Option<ContactScope>,
+        search: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Contact>, AppError> {
+        let search = search.map(str::trim).filter(|search| !search.is_empty());
+        let text_pattern = search.map(like_pattern);
+        let number_pattern = search
+            .map(clean_number)
+            .filter(|digits| digits.trim_start_matches('+').len() >= 3)
+            .map(|digits| like_pattern(digits.trim_start_matches('+')));
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.id, c.owner_id, c.display_name, c.first_name, c.last_name, c.organization,
+                   c.title, c.notes, c.created_at, c.updated_at
+            FROM contacts c
+            WHERE ((c.owner_id IS NULL AND $2) OR (c.owner_id = $1 AND $3))
+              AND ($4::text IS NULL
+                   OR c.display_name ILIKE $4
+                   OR c.organization ILIKE $4
+                   OR EXISTS (SELECT 1 FROM contact_emails e WHERE e.contact_id = c.id AND e.email ILIKE $4)
+                   OR EXISTS (SELECT 1 FROM contact_numbers n WHERE n.contact_id = c.id AND n.digits LIKE $5))
+            ORDER BY LOWER(c.display_name), c.id
+            LIMIT $6 OFFSET $7
+            "#,
+            user_id,
+            scope != Some(ContactScope::Personal),
+            scope != Some(ContactScope::Company),
+            text_pattern,
+            number_pattern,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let contacts = rows
+            .into_iter()
+            .map(|row| Contact {
+                id: row.id,
+                owner_id: row.owner_id,
+                display_name: row.display_name,
+                first_name: row.first_name,
+                last_name: row.last_name,
+                organization: row.organization,
+                title: row.title,
+                notes: row.notes,
+                numbers: Vec::new(),
+                emails: Vec::new(),
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            })
+            .collect();
+        self.with_details(contacts).await
+    }
+
+    pub async fn update_contact(&self, id: Uuid, request: UpdateContactRequest) -> Result<Contact, AppError> {
+        let contact = self.get_contact(id).await?;
+        for number in request.numbers.iter().flatten() {
+            number.validate()?;
+        }
+        for email in request.emails.iter().flatten() {
+            email.validate()?;
+        }
+        Self::check_counts(
+            request.numbers.as_ref().map_or(0, Vec::len),
+            request.emails.as_ref().map_or(0, Vec::len),
+        )?;
+        let display_name = request.display_name.as_deref().map(str::trim);
+        if display_name == Some("") {
+            return Err(AppError::Validation("display_name must not be empty".into()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE contacts
+            SET display_name = COALESCE($1, display_name),
+                first_name = COALESCE($2, first_name),
+                last_name = COALESCE($3, last_name),
+                organization = COALESCE($4, organization),
+                title = COALESCE($5, title),
+                notes = COALESCE($6, notes),
+                updated_at = NOW()
+            WHERE id = $7
+            "#,
+            display_name,
+            request.first_name,
+            request.last_name,
+            request.organization,
+            request.title,
+            request.notes,
+            contact.id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(numbers) = &request.numbers {
+            self.replace_numbers(&mut tx, contact.id, numbers).await?;
+        }
+        if let Some(emails) = &request.emails {
+            self.replace_emails(&mut tx, contact.id, emails).await?;
+        }
+        tx.commit().await?;
+
+        self.get_contact(contact.id).await
+    }
+
+    pub async fn delete_contact(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM contacts WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Contact not found".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Imports every valid card into one address book and reports the rest.
+    pub async fn import_vcards(&self, owner_id: Option<Uuid>, data: &str) -> Result<ContactImportResult, AppError> {
+        let cards = parse_vcards(data);
+        if cards.is_empty() {
+            return Err(AppError::Validation("No vCards found".into()));
+        }
+        if cards.len() > MAX_CARDS {
+            return Err(AppError::Validation(format!("At most {} vCards can be imported at once", MAX_CARDS)));
+        }
+
+        let mut result = ContactImportResult {
+            imported: 0,
+            errors: Vec::new(),
+        };
+        for (index, card) in cards.into_iter().enumerate() {
+            let imported = match card {
+                Ok(request) => match request.validate() {
+                    Ok(()) => self.create_contact(owner_id, request).await.map(|_| ()),
+                    Err(e) => Err(e.into()),
+                },
+                Err(e) => Err(e),
+            };
+            match imported {
+                Ok(()) => result.imported += 1,
+                // A lost connection fails the whole import
+                Err(AppError::Database(e)) => return Err(AppError::Database(e)),
+                Err(e) => result.errors.push(ContactImportError {
+                    index,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub async fn export_vcards(
+        &self,
+        user_id: Uuid,
+        scope: Option<ContactScope>,
+        version: VcardVersion,
+    ) -> Result<String, AppError> {
+        let contacts = self.list_contacts(user_id, scope, None, i64::MAX, 0).await?;
+        Ok(render_vcards(&contacts, version))
+    }
+
+    /// Finds the contacts a number belongs to among the user's personal
+    /// contacts and the company phonebook.
+    pub async fn lookup(&self, user_id: Uuid, number: &str) -> Result<ContactLookup, AppError> {
+        let candidates = lookup_candidates(number, self.country_code.as_deref());
+        let digits = clean_number(number);
+        if digits.trim_start_matches('+').is_empty() {
+            return Err(AppError::Validation(format!("'{}' is not a phone number", number)));
+        }
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (c.id)
+                   c.id, c.owner_id, c.display_name, c.organization,
+                   n.label as "label: ContactLabel", n.number
+            FROM contact_numbers n
+            JOIN contacts c ON c.id = n.contact_id
+            WHERE (c.owner_id IS NULL OR c.owner_id = $1)
+              AND (n.e164 = ANY($2) OR n.digits = $3)
+            ORDER BY c.id, n.position
+            "#,
+            user_id,
+            &candidates,
+            digits
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut matches: Vec<ContactMatch> = rows
+            .into_iter()
+            .map(|row| ContactMatch {
+                contact_id: row.id,
+                display_name: row.display_name,
+                organization: row.organization,
+                scope: if row.owner_id.is_some() {
+                    ContactScope::Personal
+                } else {
+                    ContactScope::Company
+                },
+                label: row.label,
+                number: row.number,
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            (a.scope != ContactScope::Personal, a.display_name.to_lowercase())
+                .cmp(&(b.scope != ContactScope::Personal, b.display_name.to_lowercase()))
+        });
+
+        Ok(ContactLookup {
+            number: number.to_string(),
+            e164: candidates.into_iter().next(),
+            matches,
+        })
+    }
+
+    /// Looks up the caller of a call record.
+    pub async fn lookup_caller(&self, user_id: Uuid, call_id: Uuid) -> Result<ContactLookup, AppError> {
+        let call = PbxService::new(self.pool.clone()).get_call_record(call_id).await?;
+        self.lookup(user_id, &call.caller_id).await
+    }
+}
@@ -1,4 +1,5 @@
 use lettre::{
+    message::{header::ContentType, Attachment, MultiPart, SinglePart},
     transport::smtp::authentication::Credentials,
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
@@ -12,7 +13,7 @@ use crate::{
         auth::User,
         email::{
             CreateEmailRequest, CreateTemplateRequest, Email, EmailMetrics,
-            EmailStatus, EmailTemplate, UpdateEmailRequest,
+            EmailStatus, EmailTemplate, OutgoingAttachment, UpdateEmailRequest,
         },
     },
 };
@@ -125,6 +126,44 @@ impl EmailService {
         Ok(())
     }
 
+    /// Sends a system notification straight to an address, bypassing the
+    /// user mailbox tables.
+    pub async fn send_with_attachment(
+        &self,
+        to_address: &str,
+        subject: &str,
+        body: &str,
+        attachment: OutgoingAttachment,
+    ) -> Result<(), AppError> {
+        let from_address = std::env::var("SMTP_FROM_ADDRESS").expect("SMTP_FROM_ADDRESS must be set");
+        let content_type = ContentType::parse(&attachment.content_type)
+            .map_err(|e| AppError::Internal(format!("Invalid attachment content type: {}", e)))?;
+
+        let message = Message::builder()
+            .from(
+                from_address
+                    .parse()
+                    .map_err(|e| AppError::Internal(format!("Invalid from address: {}", e)))?,
+            )
+            .to(to_address
+                .parse()
+                .map_err(|e| AppError::Validation(format!("Invalid recipient address: {}", e)))?)
+            .subject(subject)
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(body.to_string()))
+                    .singlepart(Attachment::new(attachment.filename).body(attachment.data, content_type)),
+            )
+            .map_err(|e| AppError::Internal(format!("Email creation error: {}", e)))?;
+
+        self.smtp_transport
+            .send(message)
+            .await
+            .map_err(|e| AppError::Internal(format!("Email sending error: {}", e)))?;
+
+        Ok(())
+    }
+
     pub async fn get_email(&self, id: Uuid) -> Result<Email, AppError> {
         let email = sqlx::query_as!(
             Email,
@@ -0,0 +1,906 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use mailparse::{MailAddr, MailHeaderMap, ParsedMail};
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig},
+    error::AppError,
+    models::{
+        email::OutgoingAttachment,
+        fax::{
+            CreateFaxLineRequest, FaxAttemptResult, FaxEmailToken, FaxJob, FaxJobSource, FaxJobStatus,
+            FaxLine, InboundFax, ReceivedFax, UpdateFaxLineRequest,
+        },
+    },
+    services::email::EmailService,
+};
+
+/// Dialplan context that receives faxes for the fax line DIDs.
+pub const FAX_IN_CONTEXT: &str = "fax-in";
+/// Dialplan context outbound fax calls are originated into.
+pub const FAX_OUT_CONTEXT: &str = "fax-out";
+
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 3;
+/// Wait before the next attempt, by number of failed attempts so far.
+const RETRY_DELAYS_MINUTES: [i64; 4] = [2, 5, 15, 30];
+const JOB_INTERVAL: Duration = Duration::from_secs(30);
+const JOB_BATCH_SIZE: i64 = 5;
+/// A job still sending after this long lost its result, e.g. to an Asterisk restart.
+const SENDING_TIMEOUT_MINUTES: i64 = 15;
+const ORIGINATE_TIMEOUT_MS: &str = "60000";
+const DIAL_CONTEXT: &str = "from-internal";
+
+/// Digits with an optional leading `+`, as dialled through the outbound routes.
+pub fn validate_fax_number(number: &str) -> Result<(), AppError> {
+    let digits = number.strip_prefix('+').unwrap_or(number);
+    if (3..=20).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!("'{}' is not a valid fax number", number)))
+    }
+}
+
+pub fn is_pdf(data: &[u8]) -> bool {
+    data.starts_with(b"%PDF-")
+}
+
+/// Little- or big-endian TIFF header.
+pub fn is_tiff(data: &[u8]) -> bool {
+    data.starts_with(b"II*\0") || data.starts_with(b"MM\0*")
+}
+
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let index = (attempts.max(1) as usize - 1).min(RETRY_DELAYS_MINUTES.len() - 1);
+    chrono::Duration::minutes(RETRY_DELAYS_MINUTES[index])
+}
+
+/// Status of a job after an attempt; failed attempts requeue it until the
+/// attempts run out.
+pub fn next_job_status(success: bool, attempts: i32, max_attempts: i32) -> FaxJobStatus {
+    if success {
+        FaxJobStatus::Sent
+    } else if attempts < max_attempts {
+        FaxJobStatus::Queued
+    } else {
+        FaxJobStatus::Failed
+    }
+}
+
+/// Ghostscript arguments rendering a PDF as the multi-page TIFF-F (G3, fine
+/// resolution, A4) that `SendFAX` expects.
+pub fn pdf_to_tiff_args(pdf: &Path, tiff: &Path) -> Vec<String> {
+    vec![
+        "-q".into(),
+        "-dNOPAUSE".into(),
+        "-dBATCH".into(),
+        "-dSAFER".into(),
+        "-sDEVICE=tiffg3".into(),
+        "-r204x196".into(),
+        "-sPAPERSIZE=a4".into(),
+        "-dFIXEDMEDIA".into(),
+        "-dPDFFitPage".into(),
+        format!("-sOutputFile={}", tiff.display()),
+        pdf.display().to_string(),
+    ]
+}
+
+/// libtiff's `tiff2pdf` arguments; fax pages keep their own resolution.
+pub fn tiff_to_pdf_args(tiff: &Path, pdf: &Path) -> Vec<String> {
+    vec![
+        "-z".into(),
+        "-o".into(),
+        pdf.display().to_string(),
+        tiff.display().to_string(),
+    ]
+}
+
+/// A fax submitted by email, to the number in the local part of the first
+/// recipient. The line it is sent from is named by the token after a `+` in
+/// that local part; `from` can be forged and is only logged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaxEmail {
+    pub from: String,
+    pub destination: String,
+    pub token: Option<String>,
+    pub document: Vec<u8>,
+}
+
+fn first_address(mail: &ParsedMail, header: &str) -> Option<String> {
+    let value = mail.headers.get_first_value(header)?;
+    let addresses = mailparse::addrparse(&value).ok()?;
+    addresses.iter().find_map(|address| match address {
+        MailAddr::Single(info) => Some(info.addr.clone()),
+        MailAddr::Group(group) => group.addrs.first().map(|info| info.addr.clone()),
+    })
+}
+
+fn find_pdf(mail: &ParsedMail) -> Option<Vec<u8>> {
+    if mail.subparts.is_empty() {
+        if mail.ctype.mimetype.starts_with("text/") {
+            return None;
+        }
+        return mail.get_body_raw().ok().filter(|body| is_pdf(body));
+    }
+    mail.subparts.iter().find_map(find_pdf)
+}
+
+/// Parses a raw RFC 822 message, e.g. to `4930123456+<token>@fax.example.com`
+/// with a PDF attached. Separators in the number (spaces, dashes, dots,
+/// brackets) are ignored.
+pub fn parse_fax_email(raw: &[u8]) -> Result<FaxEmail, AppError> {
+    let mail = mailparse::parse_mail(raw)
+        .map_err(|e| AppError::Validation(format!("Invalid email message: {}", e)))?;
+
+    let from = first_address(&mail, "From")
+        .ok_or_else(|| AppError::Validation("Email has no sender".into()))?
+        .to_lowercase();
+    let recipient = first_address(&mail, "To")
+        .ok_or_else(|| AppError::Validation("Email has no recipient".into()))?;
+    let local_part = recipient.split('@').next().unwrap_or_default();
+    // The number itself may start with a +
+    let (number, token) = match local_part.rsplit_once('+') {
+        Some((number, token)) if !number.is_empty() => (number, Some(token.trim().to_ascii_lowercase())),
+        _ => (local_part, None),
+    };
+    let destination: String = number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+    validate_fax_number(&destination)?;
+
+    let document = find_pdf(&mail).ok_or_else(|| AppError::Validation("Email has no PDF attachment".into()))?;
+
+    Ok(FaxEmail {
+        from,
+        destination,
+        token: token.filter(|token| !token.is_empty()),
+        document,
+    })
+}
+
+pub struct FaxService {
+    pool: PgPool,
+    storage_dir: PathBuf,
+    /// Directory shared with Asterisk: `ReceiveFAX` writes here and `SendFAX`
+    /// reads the converted outbound documents from here.
+    spool_dir: PathBuf,
+}
+
+impl FaxService {
+    pub fn new(pool: PgPool) -> Self {
+        let storage_dir = std::env::var("FAX_PATH")
+            .unwrap_or_else(|_| "/var/lib/oriontel/fax".into());
+        let spool_dir = std::env::var("FAX_SPOOL_PATH")
+            .unwrap_or_else(|_| "/var/spool/asterisk/fax".into());
+        Self {
+            pool,
+            storage_dir: PathBuf::from(storage_dir),
+            spool_dir: PathBuf::from(spool_dir),
+        }
+    }
+
+    // Lines
+    pub async fn create_line(&self, request: CreateFaxLineRequest) -> Result<FaxLine, AppError> {
+        validate_fax_number(&request.did)?;
+
+        let existing = sqlx::query!("SELECT id FROM fax_lines WHERE did = $1", request.did)
+            .fetch_optional(&self.pool)
+            .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation("DID already has a fax line".into()));
+        }
+
+        let line = sqlx::query_as!(
+            FaxLine,
+            r#"
+            INSERT INTO fax_lines (did, name, email, station_id, owner_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, did, name, email, station_id, owner_id, created_at, updated_at
+            "#,
+            request.did,
+            request.name,
+            request.email,
+            request.station_id,
+            request.owner_id,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(line)
+    }
+
+    pub async fn get_line(&self, id: Uuid) -> Result<FaxLine, AppError> {
+        let line = sqlx::query_as!(
+            FaxLine,
+            r#"
+            SELECT id, did, name, email, station_id, owner_id, created_at, updated_at
+            FROM fax_lines
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fax line not found".into()))?;
+
+        Ok(line)
+    }
+
+    /// All lines, or only those of one owner.
+    pub async fn list_lines(&self, owner_id: Option<Uuid>) -> Result<Vec<FaxLine>, AppError> {
+        let lines = sqlx::query_as!(
+            FaxLine,
+            r#"
+            SELECT id, did, name, email, station_id, owner_id, created_at, updated_at
+            FROM fax_lines
+            WHERE $1::uuid IS NULL OR owner_id = $1
+            ORDER BY did
+            "#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lines)
+    }
+
+    pub async fn update_line(&self, id: Uuid, request: UpdateFaxLineRequest) -> Result<FaxLine, AppError> {
+        let line = sqlx::query_as!(
+            FaxLine,
+            r#"
+            UPDATE fax_lines
+            SET name = COALESCE($1, name),
+                email = COALESCE($2, email),
+                station_id = COALESCE($3, station_id),
+                owner_id = COALESCE($4, owner_id),
+                updated_at = $5
+            WHERE id = $6
+            RETURNING id, did, name, email, station_id, owner_id, created_at, updated_at
+            "#,
+            request.name,
+            request.email,
+            request.station_id,
+            request.owner_id,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fax line not found".into()))?;
+
+        Ok(line)
+    }
+
+    /// Deletes the line with its outbound jobs. Received faxes are kept.
+    pub async fn delete_line(&self, id: Uuid) -> Result<(), AppError> {
+        let jobs = sqlx::query!("SELECT file_path, tiff_path FROM fax_jobs WHERE line_id = $1", id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let result = sqlx::query!("DELETE FROM fax_lines WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Fax line not found".into()));
+        }
+
+        for job in jobs {
+            remove_file(Path::new(&job.file_path)).await;
+            remove_file(Path::new(&job.tiff_path)).await;
+        }
+
+        Ok(())
+    }
+
+    // Inbound faxes
+    pub async fn get_inbound(&self, id: Uuid) -> Result<InboundFax, AppError> {
+        let fax = sqlx::query_as!(
+            InboundFax,
+            r#"
+            SELECT id, line_id, did, sender, remote_station_id, pages, file_path, size_bytes,
+                   delivered_at, delivery_error, received_at
+            FROM inbound_faxes
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fax not found".into()))?;
+
+        Ok(fax)
+    }
+
+    /// Received faxes, newest first, optionally limited to the lines of one owner.
+    pub async fn list_inbound(
+        &self,
+        owner_id: Option<Uuid>,
+        line_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<InboundFax>, AppError> {
+        let faxes = sqlx::query_as!(
+            InboundFax,
+            r#"
+            SELECT f.id, f.line_id, f.did, f.sender, f.remote_station_id, f.pages, f.file_path,
+                   f.size_bytes, f.delivered_at, f.delivery_error, f.received_at
+            FROM inbound_faxes f
+            LEFT JOIN fax_lines l ON l.id = f.line_id
+            WHERE ($1::uuid IS NULL OR l.owner_id = $1)
+              AND ($2::uuid IS NULL OR f.line_id = $2)
+            ORDER BY f.received_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            owner_id,
+            line_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(faxes)
+    }
+
+    pub async fn delete_inbound(&self, id: Uuid) -> Result<(), AppError> {
+        let fax = self.get_inbound(id).await?;
+
+        sqlx::query!("DELETE FROM inbound_faxes WHERE id = $1", fax.id)
+            .execute(&self.pool)
+            .await?;
+        remove_file(Path::new(&fax.file_path)).await;
+
+        Ok(())
+    }
+
+    /// Converts a fax `ReceiveFAX` left in the spool directory to PDF, stores
+    /// it and emails it to the line. The spooled TIFF is removed once stored.
+    pub async fn receive(&self, received: ReceivedFax) -> Result<InboundFax, AppError> {
+        // The uniqueid becomes a file name, so keep it to what Asterisk generates
+        if received.uniqueid.is_empty()
+            || !received
+                .uniqueid
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+        {
+            return Err(AppError::Validation(format!("Invalid fax uniqueid '{}'", received.uniqueid)));
+        }
+
+        let tiff = self.spool_dir.join(format!("{}.tif", received.uniqueid));
+        let data = tokio::fs::read(&tiff)
+            .await
+            .map_err(|e| AppError::NotFound(format!("Failed to read {}: {}", tiff.display(), e)))?;
+        if !is_tiff(&data) {
+            return Err(AppError::Validation(format!("{} is not a TIFF file", tiff.display())));
+        }
+
+        let line = sqlx::query!("SELECT id, email FROM fax_lines WHERE did = $1", received.did)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let id = Uuid::new_v4();
+        let pdf = self.storage_dir.join("inbound").join(format!("{}.pdf", id));
+        create_parent(&pdf).await?;
+        convert(&tiff2pdf_program(), &tiff_to_pdf_args(&tiff, &pdf)).await?;
+        let size_bytes = tokio::fs::metadata(&pdf)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", pdf.display(), e)))?
+            .len() as i64;
+
+        let file_path = pdf.to_string_lossy().to_string();
+        let inserted = sqlx::query_as!(
+            InboundFax,
+            r#"
+            INSERT INTO inbound_faxes (id, line_id, did, sender, remote_station_id, pages, file_path, size_bytes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, line_id, did, sender, remote_station_id, pages, file_path, size_bytes,
+                      delivered_at, delivery_error, received_at
+            "#,
+            id,
+            line.as_ref().map(|line| line.id),
+            received.did,
+            received.sender,
+            received.remote_station_id,
+            received.pages,
+            file_path,
+            size_bytes,
+        )
+        .fetch_one(&self.pool)
+        .await;
+
+        let fax = match inserted {
+            Ok(fax) => fax,
+            Err(e) => {
+                remove_file(&pdf).await;
+                return Err(e.into());
+            }
+        };
+        remove_file(&tiff).await;
+
+        if !line.is_some_and(|line| line.email.is_some()) {
+            return Ok(fax);
+        }
+
+        // Delivery problems are kept on the fax; it can be sent again later
+        match self.deliver(fax.id).await {
+            Ok(fax) => Ok(fax),
+            Err(e) => {
+                tracing::warn!("Failed to email fax {}: {}", fax.id, e);
+                self.get_inbound(fax.id).await
+            }
+        }
+    }
+
+    /// Emails a received fax to its line's address and records the outcome.
+    pub async fn deliver(&self, id: Uuid) -> Result<InboundFax, AppError> {
+        let fax = self.get_inbound(id).await?;
+        let line = match fax.line_id {
+            Some(line_id) => Some(self.get_line(line_id).await?),
+            None => None,
+        };
+        let Some((line, address)) = line.and_then(|line| line.email.clone().map(|address| (line, address))) else {
+            return Err(AppError::Validation("Fax line has no email address".into()));
+        };
+
+        let result = self.email_fax(&line, &address, &fax).await;
+        let (delivered_at, delivery_error) = match &result {
+            Ok(()) => (Some(Utc::now()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        sqlx::query!(
+            "UPDATE inbound_faxes SET delivered_at = COALESCE($1, delivered_at), delivery_error = $2 WHERE id = $3",
+            delivered_at,
+            delivery_error,
+            fax.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        result?;
+        self.get_inbound(fax.id).await
+    }
+
+    async fn email_fax(&self, line: &FaxLine, address: &str, fax: &InboundFax) -> Result<(), AppError> {
+        let data = tokio::fs::read(&fax.file_path)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", fax.file_path, e)))?;
+        let email_service = EmailService::new(self.pool.clone())?;
+
+        let subject = format!("Fax from {} to {}", fax.sender, fax.did);
+        let body = format!(
+            "You have received a fax on {} ({}).\n\nFrom: {}\nRemote station: {}\nPages: {}\nReceived: {}\n",
+            line.did,
+            line.name,
+            fax.sender,
+            fax.remote_station_id.as_deref().unwrap_or("unknown"),
+            fax.pages,
+            fax.received_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        );
+
+        email_service
+            .send_with_attachment(
+                address,
+                &subject,
+                &body,
+                OutgoingAttachment {
+                    filename: format!("fax-{}.pdf", fax.received_at.format("%Y%m%d-%H%M%S")),
+                    content_type: "application/pdf".into(),
+                    data,
+                },
+            )
+            .await
+    }
+
+    // Outbound jobs
+    pub async fn get_job(&self, id: Uuid) -> Result<FaxJob, AppError> {
+        let job = sqlx::query_as!(
+            FaxJob,
+            r#"
+            SELECT id, line_id, source as "source: FaxJobSource", destination, file_path, tiff_path,
+                   status as "status: FaxJobStatus", attempts, max_attempts, next_attempt_at, pages,
+                   last_error, created_at, updated_at, completed_at
+            FROM fax_jobs
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fax job not found".into()))?;
+
+        Ok(job)
+    }
+
+    /// Jobs, newest first, optionally limited to the lines of one owner.
+    pub async fn list_jobs(
+        &self,
+        owner_id: Option<Uuid>,
+        line_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<FaxJob>, AppError> {
+        let jobs = sqlx::query_as!(
+            FaxJob,
+            r#"
+            SELECT j.id, j.line_id, j.source as "source: FaxJobSource", j.destination, j.file_path,
+                   j.tiff_path, j.status as "status: FaxJobStatus", j.attempts, j.max_attempts,
+                   j.next_attempt_at, j.pages, j.last_error, j.created_at, j.updated_at, j.completed_at
+            FROM fax_jobs j
+            JOIN fax_lines l ON l.id = j.line_id
+            WHERE ($1::uuid IS NULL OR l.owner_id = $1)
+              AND ($2::uuid IS NULL OR j.line_id = $2)
+            ORDER BY j.created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            owner_id,
+            line_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// Stores the PDF, converts it for `SendFAX` and queues the job. The
+    /// conversion runs up front so unreadable documents are rejected here.
+    pub async fn queue_fax(
+        &self,
+        line: &FaxLine,
+        source: FaxJobSource,
+        destination: &str,
+        max_attempts: Option<i32>,
+        data: &[u8],
+    ) -> Result<FaxJob, AppError> {
+        validate_fax_number(destination)?;
+        if !is_pdf(data) {
+            return Err(AppError::Validation("Document must be a PDF".into()));
+        }
+
+        let id = Uuid::new_v4();
+        let pdf = self.storage_dir.join("outbound").join(format!("{}.pdf", id));
+        let tiff = self.spool_dir.join("outbound").join(format!("{}.tif", id));
+        write_file(&pdf, data).await?;
+        create_parent(&tiff).await?;
+        if let Err(e) = convert(&ghostscript_program(), &pdf_to_tiff_args(&pdf, &tiff)).await {
+            remove_file(&pdf).await;
+            remove_file(&tiff).await;
+            return Err(AppError::Validation(format!("Could not convert the PDF: {}", e)));
+        }
+
+        let file_path = pdf.to_string_lossy().to_string();
+        let tiff_path = tiff.to_string_lossy().to_string();
+        let inserted = sqlx::query_as!(
+            FaxJob,
+            r#"
+            INSERT INTO fax_jobs (id, line_id, source, destination, file_path, tiff_path, max_attempts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, line_id, source as "source: FaxJobSource", destination, file_path, tiff_path,
+                      status as "status: FaxJobStatus", attempts, max_attempts, next_attempt_at, pages,
+                      last_error, created_at, updated_at, completed_at
+            "#,
+            id,
+            line.id,
+            source as FaxJobSource,
+            destination,
+            file_path,
+            tiff_path,
+            max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+        )
+        .fetch_one(&self.pool)
+        .await;
+
+        match inserted {
+            Ok(job) => Ok(job),
+            Err(e) => {
+                remove_file(&pdf).await;
+                remove_file(&tiff).await;
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Queues a fax from a raw email message, sent from the line whose token
+    /// is in the recipient address.
+    pub async fn queue_email(&self, raw: &[u8]) -> Result<FaxJob, AppError> {
+        let email = parse_fax_email(raw)?;
+        let token = email
+            .token
+            .as_deref()
+            .ok_or_else(|| AppError::Auth("Recipient address has no fax line token".into()))?;
+
+        let line = sqlx::query_as!(
+            FaxLine,
+            r#"
+            SELECT id, did, name, email, station_id, owner_id, created_at, updated_at
+            FROM fax_lines
+            WHERE email_token = $1
+            "#,
+            token
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Auth("Unknown fax line token".into()))?;
+
+        tracing::info!("Fax to {} emailed by {} for line {}", email.destination, email.from, line.did);
+        self.queue_fax(&line, FaxJobSource::Email, &email.destination, None, &email.document)
+            .await
+    }
+
+    pub async fn get_email_token(&self, line_id: Uuid) -> Result<FaxEmailToken, AppError> {
+        let token = sqlx::query_as!(
+            FaxEmailToken,
+            "SELECT id as line_id, email_token as token FROM fax_lines WHERE id = $1",
+            line_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fax line not found".into()))?;
+
+        Ok(token)
+    }
+
+    /// Replaces the token, e.g. after it leaked; mail to the old address is refused.
+    pub async fn rotate_email_token(&self, line_id: Uuid) -> Result<FaxEmailToken, AppError> {
+        let token = sqlx::query_as!(
+            FaxEmailToken,
+            r#"
+            UPDATE fax_lines
+            SET email_token = $1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING id as line_id, email_token as token
+            "#,
+            Uuid::new_v4().simple().to_string(),
+            line_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fax line not found".into()))?;
+
+        Ok(token)
+    }
+
+    pub async fn cancel_job(&self, id: Uuid) -> Result<FaxJob, AppError> {
+        let job = self.get_job(id).await?;
+
+        let result = sqlx::query!(
+            "UPDATE fax_jobs SET status = 'cancelled', completed_at = $1, updated_at = $1 WHERE id = $2 AND status = 'queued'",
+            Utc::now(),
+            job.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::Validation("Only queued faxes can be cancelled".into()));
+        }
+
+        self.get_job(job.id).await
+    }
+
+    /// Records the outcome of a send attempt and requeues or closes the job.
+    pub async fn record_attempt(&self, id: Uuid, result: FaxAttemptResult) -> Result<(), AppError> {
+        let job = self.get_job(id).await?;
+        if job.status != FaxJobStatus::Sending {
+            // Late result for a job that already timed out or was closed
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let status = next_job_status(result.success, job.attempts, job.max_attempts);
+        let completed_at = (status != FaxJobStatus::Queued).then_some(now);
+        sqlx::query!(
+            r#"
+            UPDATE fax_jobs
+            SET status = $1,
+                pages = COALESCE($2, pages),
+                last_error = $3,
+                next_attempt_at = $4,
+                completed_at = $5,
+                updated_at = $6
+            WHERE id = $7 AND status = 'sending'
+            "#,
+            status as FaxJobStatus,
+            result.pages,
+            result.error,
+            now + retry_delay(job.attempts),
+            completed_at,
+            now,
+            job.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        match status {
+            FaxJobStatus::Sent => tracing::info!("Fax {} sent to {}", job.id, job.destination),
+            FaxJobStatus::Failed => tracing::warn!(
+                "Fax {} to {} failed after {} attempts: {}",
+                job.id,
+                job.destination,
+                job.attempts,
+                result.error.as_deref().unwrap_or("unknown error")
+            ),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Marks due jobs as sending and originates a call for each. The
+    /// `fax-out` dialplan reports the result with a `FaxResult` UserEvent.
+    pub async fn send_due_jobs(&self, config: &AmiConfig) -> Result<usize, AppError> {
+        let jobs = sqlx::query!(
+            r#"
+            UPDATE fax_jobs j
+            SET status = 'sending', attempts = attempts + 1, updated_at = NOW()
+            FROM fax_lines l
+            WHERE l.id = j.line_id
+              AND j.id IN (
+                  SELECT id FROM fax_jobs
+                  WHERE status = 'queued' AND next_attempt_at <= NOW()
+                  ORDER BY next_attempt_at
+                  LIMIT $1
+                  FOR UPDATE SKIP LOCKED
+              )
+            RETURNING j.id, j.destination, j.tiff_path, l.did, l.name, l.station_id
+            "#,
+            JOB_BATCH_SIZE
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for job in &jobs {
+            let channel = format!("Local/{}@{}", job.destination, DIAL_CONTEXT);
+            let caller_id = format!("\"{}\" <{}>", job.name.replace('"', ""), job.did);
+            let job_var = format!("FAX_JOB={}", job.id);
+            let file_var = format!("FAX_FILE={}", job.tiff_path);
+            let station_var = format!("FAX_STATION={}", job.station_id.as_deref().unwrap_or(&job.did));
+
+            let originated = AmiClient::run_action(
+                config,
+                "Originate",
+                &[
+                    ("Channel", &channel),
+                    ("Context", FAX_OUT_CONTEXT),
+                    ("Exten", "s"),
+                    ("Priority", "1"),
+                    ("CallerID", &caller_id),
+                    ("Timeout", ORIGINATE_TIMEOUT_MS),
+                    ("Variable", &job_var),
+                    ("Variable", &file_var),
+                    ("Variable", &station_var),
+                    ("Async", "true"),
+                ],
+            )
+            .await;
+
+            if let Err(e) = originated {
+                self.record_attempt(
+                    job.id,
+                    FaxAttemptResult {
+                        success: false,
+                        error: Some(e.to_string()),
+                        pages: None,
+                    },
+                )
+                .await?;
+            }
+        }
+
+        Ok(jobs.len())
+    }
+
+    /// Jobs that never reported back count as failed attempts.
+    async fn expire_stale_jobs(&self) -> Result<(), AppError> {
+        let stale = sqlx::query!(
+            "SELECT id FROM fax_jobs WHERE status = 'sending' AND updated_at < $1",
+            Utc::now() - chrono::Duration::minutes(SENDING_TIMEOUT_MINUTES)
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for job in stale {
+            self.record_attempt(
+                job.id,
+                FaxAttemptResult {
+                    success: false,
+                    error: Some("No result from Asterisk".into()),
+                    pages: None,
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Periodically sends queued faxes. Without AMI nothing can be sent, so
+    /// the job does not start and faxes stay queued.
+    pub async fn run_fax_job(self) {
+        let Some(config) = AmiConfig::from_env() else {
+            tracing::info!("AMI is not configured; outbound faxes will stay queued");
+            return;
+        };
+
+        let mut interval = tokio::time::interval(JOB_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.expire_stale_jobs().await {
+                tracing::error!("Failed to expire stale fax jobs: {}", e);
+            }
+            match self.send_due_jobs(&config).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Started {} fax transmissions", count),
+                Err(e) => tracing::error!("Fax job failed: {}", e),
+            }
+        }
+    }
+}
+
+fn ghostscript_program() -> String {
+    std::env::var("FAX_GHOSTSCRIPT").unwrap_or_else(|_| "gs".into())
+}
+
+fn tiff2pdf_program() -> String {
+    std::env::var("FAX_TIFF2PDF").unwrap_or_else(|_| "tiff2pdf".into())
+}
+
+async fn convert(program: &str, args: &[String]) -> Result<(), AppError> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to run {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Internal(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+async fn create_parent(path: &Path) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    Ok(())
+}
+
+async fn write_file(path: &Path, data: &[u8]) -> Result<(), AppError> {
+    create_parent(path).await?;
+
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to create {}: {}", path.display(), e)))?;
+    file.write_all(data)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to write {}: {}", path.display(), e)))?;
+    file.sync_all()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to sync {}: {}", path.display(), e)))?;
+
+    Ok(())
+}
+
+async fn remove_file(path: &Path) {
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Failed to remove fax file {}: {}", path.display(), e);
+        }
+    }
+}
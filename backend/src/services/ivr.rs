@@ -0,0 +1,474 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Write,
+};
+
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    error::AppError,
+    models::{
+        ivr::{
+            CreateIvrMenuRequest, IvrDestination, IvrDestinationType, IvrMenu, IvrNode,
+            IvrValidationResponse, UpdateIvrMenuRequest,
+        },
+        routing::RouteDestinationType,
+    },
+    services::asterisk_config::{destination_app, escape_app_arg},
+};
+
+const VALID_DIGITS: &[&str] = &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "*", "#"];
+
+/// Context a route or queue overflow jumps to for this menu.
+pub fn menu_context(menu_name: &str) -> String {
+    format!("ivr-{}", menu_name)
+}
+
+/// Node keys cannot contain '-', so the last '-' always separates menu and node.
+fn node_context(menu_name: &str, node_key: &str) -> String {
+    format!("ivrnode-{}-{}", menu_name, node_key)
+}
+
+fn validate_menu_name(name: &str) -> Result<(), AppError> {
+    if name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(AppError::Validation(
+            "Menu name may only contain letters, digits, '-' and '_'".into(),
+        ))
+    }
+}
+
+fn is_valid_node_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Every outgoing edge of a node with a label for error messages.
+fn node_destinations(node: &IvrNode) -> Vec<(String, &IvrDestination)> {
+    let mut destinations: Vec<(String, &IvrDestination)> = node
+        .options
+        .iter()
+        .map(|option| (format!("option {}", option.digit), &option.destination))
+        .collect();
+    if let Some(destination) = &node.timeout_destination {
+        destinations.push(("timeout".to_string(), destination));
+    }
+    if let Some(destination) = &node.invalid_destination {
+        destinations.push(("invalid input".to_string(), destination));
+    }
+    destinations
+}
+
+/// Checks a menu graph and returns every problem found. `extensions` and
+/// `queues` are the extension numbers and queue names that currently exist.
+pub fn validate_graph(
+    entry_node: &str,
+    nodes: &[IvrNode],
+    extensions: &HashSet<String>,
+    queues: &HashSet<String>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let mut by_key: HashMap<&str, &IvrNode> = HashMap::new();
+    for node in nodes {
+        if !is_valid_node_key(&node.key) {
+            errors.push(format!(
+                "Node key '{}' may only contain letters, digits and '_'",
+                node.key
+            ));
+        }
+        if by_key.insert(node.key.as_str(), node).is_some() {
+            errors.push(format!("Node key '{}' is used more than once", node.key));
+        }
+    }
+
+    if !by_key.contains_key(entry_node) {
+        errors.push(format!("Entry node '{}' does not exist", entry_node));
+    }
+
+    for node in nodes {
+        if !(1..=60).contains(&node.timeout) {
+            errors.push(format!("Node '{}': timeout must be between 1 and 60 seconds", node.key));
+        }
+
+        let mut digits = HashSet::new();
+        for option in &node.options {
+            if !VALID_DIGITS.contains(&option.digit.as_str()) {
+                errors.push(format!("Node '{}': '{}' is not a DTMF key", node.key, option.digit));
+            } else if !digits.insert(option.digit.as_str()) {
+                errors.push(format!("Node '{}': option {} is defined more than once", node.key, option.digit));
+            }
+        }
+
+        if !node.waits_for_input() && node.invalid_destination.is_some() {
+            errors.push(format!(
+                "Node '{}': invalid-input handling needs at least one option",
+                node.key
+            ));
+        }
+
+        for (label, destination) in node_destinations(node) {
+            let target = destination.target.as_deref().unwrap_or("");
+            let problem = match destination.destination_type {
+                IvrDestinationType::Hangup => None,
+                _ if target.is_empty() => Some("has no target".to_string()),
+                IvrDestinationType::Extension if !extensions.contains(target) => {
+                    Some(format!("points at missing extension {}", target))
+                }
+                IvrDestinationType::Queue if !queues.contains(target) => {
+                    Some(format!("points at missing queue {}", target))
+                }
+                IvrDestinationType::Node if !by_key.contains_key(target) => {
+                    Some(format!("points at missing node '{}'", target))
+                }
+                _ => None,
+            };
+            if let Some(problem) = problem {
+                errors.push(format!("Node '{}': {} {}", node.key, label, problem));
+            }
+        }
+    }
+
+    // Everything must be reachable from the entry node
+    let mut reachable = HashSet::new();
+    if by_key.contains_key(entry_node) {
+        let mut pending = VecDeque::from([entry_node]);
+        while let Some(key) = pending.pop_front() {
+            if !reachable.insert(key) {
+                continue;
+            }
+            for (_, destination) in node_destinations(by_key[key]) {
+                if destination.destination_type == IvrDestinationType::Node {
+                    if let Some(target) = destination.target.as_deref() {
+                        if by_key.contains_key(target) {
+                            pending.push_back(target);
+                        }
+                    }
+                }
+            }
+        }
+
+        for node in nodes {
+            if !reachable.contains(node.key.as_str()) {
+                errors.push(format!("Node '{}' is unreachable from the entry node", node.key));
+            }
+        }
+    }
+
+    // A node without options moves on immediately, so a loop made only of such
+    // nodes would spin forever without giving the caller a chance to press a key.
+    let mut reported: HashSet<Vec<&str>> = HashSet::new();
+    for node in nodes {
+        let mut path: Vec<&str> = Vec::new();
+        let mut current = node;
+        while !current.waits_for_input() {
+            if let Some(position) = path.iter().position(|key| *key == current.key) {
+                let mut cycle = path[position..].to_vec();
+                let mut canonical = cycle.clone();
+                canonical.sort_unstable();
+                if reported.insert(canonical) {
+                    cycle.push(current.key.as_str());
+                    errors.push(format!(
+                        "Nodes {} loop without waiting for input",
+                        cycle.join(" -> ")
+                    ));
+                }
+                break;
+            }
+            path.push(current.key.as_str());
+
+            match &current.timeout_destination {
+                Some(IvrDestination {
+                    destination_type: IvrDestinationType::Node,
+                    target: Some(target),
+                }) => match by_key.get(target.as_str()) {
+                    Some(next) => current = *next,
+                    None => break,
+                },
+                _ => break,
+            }
+        }
+    }
+
+    errors
+}
+
+fn ivr_destination_app(menu_name: &str, destination: Option<&IvrDestination>, fallback: &str) -> String {
+    let Some(destination) = destination else {
+        return fallback.to_string();
+    };
+    let target = destination.target.as_deref().unwrap_or("");
+    match destination.destination_type {
+        IvrDestinationType::Extension => destination_app(&RouteDestinationType::Extension, target),
+        IvrDestinationType::Queue => destination_app(&RouteDestinationType::Queue, target),
+        IvrDestinationType::Node => format!(
+            "Goto({},s,1)",
+            escape_app_arg(&node_context(menu_name, target))
+        ),
+        IvrDestinationType::Hangup => "Hangup()".to_string(),
+    }
+}
+
+fn write_steps(out: &mut String, exten: &str, steps: &[String]) {
+    for (index, step) in steps.iter().enumerate() {
+        if index == 0 {
+            let _ = writeln!(out, "exten => {},1,{}", exten, step);
+        } else {
+            let _ = writeln!(out, " same => n,{}", step);
+        }
+    }
+}
+
+/// Renders the menu as dialplan: an entry context named after the menu plus
+/// one context per node.
+pub fn render_ivr_menu(menu: &IvrMenu) -> String {
+    let mut out = String::new();
+    let name = menu.name.as_str();
+
+    let _ = writeln!(out, "[{}]", escape_app_arg(&menu_context(name)));
+    write_steps(
+        &mut out,
+        "s",
+        &[
+            "Answer()".to_string(),
+            format!("Goto({},s,1)", escape_app_arg(&node_context(name, &menu.entry_node))),
+        ],
+    );
+
+    for node in menu.nodes.iter() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[{}]", escape_app_arg(&node_context(name, &node.key)));
+        let prompt = node.prompt.as_deref().map(escape_app_arg);
+
+        if node.waits_for_input() {
+            let mut steps = Vec::new();
+            if let Some(prompt) = &prompt {
+                steps.push(format!("Background({})", prompt));
+            }
+            steps.push(format!("WaitExten({})", node.timeout));
+            write_steps(&mut out, "s", &steps);
+
+            for option in &node.options {
+                write_steps(
+                    &mut out,
+                    &escape_app_arg(&option.digit),
+                    &[ivr_destination_app(name, Some(&option.destination), "Hangup()")],
+                );
+            }
+            write_steps(
+                &mut out,
+                "t",
+                &[ivr_destination_app(name, node.timeout_destination.as_ref(), "Hangup()")],
+            );
+            match &node.invalid_destination {
+                Some(destination) => write_steps(
+                    &mut out,
+                    "i",
+                    &[ivr_destination_app(name, Some(destination), "Hangup()")],
+                ),
+                None => write_steps(
+                    &mut out,
+                    "i",
+                    &["Playback(invalid)".to_string(), "Goto(s,1)".to_string()],
+                ),
+            }
+        } else {
+            let first = match &prompt {
+                Some(prompt) => format!("Playback({})", prompt),
+                None => format!("NoOp({})", escape_app_arg(&node.key)),
+            };
+            write_steps(
+                &mut out,
+                "s",
+                &[
+                    first,
+                    ivr_destination_app(name, node.timeout_destination.as_ref(), "Hangup()"),
+                ],
+            );
+        }
+    }
+
+    out
+}
+
+pub struct IvrService {
+    pool: PgPool,
+}
+
+impl IvrService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn check_graph(&self, entry_node: &str, nodes: &[IvrNode]) -> Result<IvrValidationResponse, AppError> {
+        // Ring group and conference room numbers are dialled like extensions
+        let extensions: HashSet<String> = sqlx::query!(
+            r#"
+            SELECT extension_number as "number!" FROM pbx_extensions
+            UNION
+            SELECT group_number FROM ring_groups
+            UNION
+            SELECT room_number FROM conference_rooms
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.number)
+        .collect();
+        let queues: HashSet<String> = sqlx::query!("SELECT name FROM call_queues")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.name)
+            .collect();
+
+        let errors = validate_graph(entry_node, nodes, &extensions, &queues);
+        Ok(IvrValidationResponse {
+            valid: errors.is_empty(),
+            errors,
+        })
+    }
+
+    async fn ensure_valid_graph(&self, entry_node: &str, nodes: &[IvrNode]) -> Result<(), AppError> {
+        let result = self.check_graph(entry_node, nodes).await?;
+        if result.valid {
+            Ok(())
+        } else {
+            Err(AppError::Validation(result.errors.join("; ")))
+        }
+    }
+
+    pub async fn create_menu(&self, request: CreateIvrMenuRequest) -> Result<IvrMenu, AppError> {
+        validate_menu_name(&request.name)?;
+        self.ensure_valid_graph(&request.entry_node, &request.nodes).await?;
+
+        let existing = sqlx::query!("SELECT id FROM ivr_menus WHERE name = $1", request.name)
+            .fetch_optional(&self.pool)
+            .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation("IVR menu already exists".into()));
+        }
+
+        let menu = sqlx::query_as!(
+            IvrMenu,
+            r#"
+            INSERT INTO ivr_menus (name, description, entry_node, nodes)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, name, description, entry_node,
+                      nodes as "nodes: Json<Vec<IvrNode>>", created_at, updated_at
+            "#,
+            request.name,
+            request.description,
+            request.entry_node,
+            Json(&request.nodes) as _,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(menu)
+    }
+
+    pub async fn get_menu(&self, id: Uuid) -> Result<IvrMenu, AppError> {
+        let menu = sqlx::query_as!(
+            IvrMenu,
+            r#"
+            SELECT id, name, description, entry_node,
+                   nodes as "nodes: Json<Vec<IvrNode>>", created_at, updated_at
+            FROM ivr_menus
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("IVR menu not found".into()))?;
+
+        Ok(menu)
+    }
+
+    pub async fn list_menus(&self) -> Result<Vec<IvrMenu>, AppError> {
+        let menus = sqlx::query_as!(
+            IvrMenu,
+            r#"
+            SELECT id, name, description, entry_node,
+                   nodes as "nodes: Json<Vec<IvrNode>>", created_at, updated_at
+            FROM ivr_menus
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(menus)
+    }
+
+    pub async fn update_menu(
+        &self,
+        id: Uuid,
+        request: UpdateIvrMenuRequest,
+    ) -> Result<IvrMenu, AppError> {
+        self.ensure_valid_graph(&request.entry_node, &request.nodes).await?;
+
+        let menu = sqlx::query_as!(
+            IvrMenu,
+            r#"
+            UPDATE ivr_menus
+            SET description = COALESCE($1, description),
+                entry_node = $2,
+                nodes = $3,
+                updated_at = $4
+            WHERE id = $5
+            RETURNING id, name, description, entry_node,
+                      nodes as "nodes: Json<Vec<IvrNode>>", created_at, updated_at
+            "#,
+            request.description,
+            request.entry_node,
+            Json(&request.nodes) as _,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("IVR menu not found".into()))?;
+
+        Ok(menu)
+    }
+
+    pub async fn delete_menu(&self, id: Uuid) -> Result<(), AppError> {
+        let menu = self.get_menu(id).await?;
+
+        let route = sqlx::query!(
+            "SELECT name FROM inbound_routes WHERE destination_type = 'ivr' AND destination = $1 LIMIT 1",
+            menu.name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(route) = route {
+            return Err(AppError::Validation(format!(
+                "IVR menu is used by inbound route {}",
+                route.name
+            )));
+        }
+
+        let result = sqlx::query!("DELETE FROM ivr_menus WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("IVR menu not found".into()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn export_dialplan(&self, id: Uuid) -> Result<String, AppError> {
+        let menu = self.get_menu(id).await?;
+        Ok(render_ivr_menu(&menu))
+    }
+}
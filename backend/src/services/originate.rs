@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig},
+    error::AppError,
+    models::{
+        originate::OriginatePermission,
+        pbx::{CallRecord, CallStatus, ExtensionConfig, PbxExtension},
+    },
+    services::pbx::{parse_extension_config, PbxService},
+};
+
+const DIAL_CONTEXT: &str = "from-internal";
+const DEFAULT_RING_TIMEOUT: u32 = 30;
+/// How long to wait for Asterisk's verdict beyond the ring timeout.
+const RESPONSE_GRACE: Duration = Duration::from_secs(30);
+
+// OriginateResponse reasons, from Asterisk's AST_CONTROL values
+const REASON_HANGUP: u32 = 1;
+const REASON_RING_TIMEOUT: u32 = 3;
+const REASON_BUSY: u32 = 5;
+
+/// Destinations are dialled like numbers typed on the phone: digits, `*`
+/// and `#`, optionally with a leading `+`.
+pub fn validate_destination(destination: &str) -> Result<(), AppError> {
+    let digits = destination.strip_prefix('+').unwrap_or(destination);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '*' || c == '#') {
+        return Err(AppError::Validation(
+            "Destination may only contain digits, * and #, with an optional leading +".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// The channel that rings the extension's phone and the context the
+/// destination is dialled in, so the extension's own dialling rules apply.
+pub fn originate_target(extension: &PbxExtension) -> Result<(String, String), AppError> {
+    let config = parse_extension_config(extension.extension_type, &extension.config_data)?;
+    let number = &extension.extension_number;
+    Ok(match config {
+        ExtensionConfig::Sip(sip) => (
+            format!("PJSIP/{}", number),
+            sip.context.unwrap_or_else(|| DIAL_CONTEXT.to_string()),
+        ),
+        ExtensionConfig::Iax(_) => (format!("IAX2/{}", number), DIAL_CONTEXT.to_string()),
+        ExtensionConfig::Custom(custom) => (custom.dial_string, DIAL_CONTEXT.to_string()),
+    })
+}
+
+/// Final status of a call whose phone never answered.
+pub fn originate_failure_status(reason: u32) -> CallStatus {
+    match reason {
+        REASON_BUSY => CallStatus::Busy,
+        REASON_HANGUP | REASON_RING_TIMEOUT => CallStatus::NoAnswer,
+        _ => CallStatus::Failed,
+    }
+}
+
+fn ami_config() -> Result<AmiConfig, AppError> {
+    AmiConfig::from_env()
+        .ok_or_else(|| AppError::Internal("AMI is not configured; set AMI_HOST to place calls".into()))
+}
+
+pub struct OriginateService {
+    pool: PgPool,
+}
+
+impl OriginateService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Rings the extension and dials `destination` once it answers. Returns
+    /// the call record right away; the AMI call tracker moves it through
+    /// answer and hangup, and a phone that never answers marks it as
+    /// busy, unanswered or failed.
+    pub async fn originate(
+        &self,
+        extension: &PbxExtension,
+        destination: &str,
+        ring_timeout: Option<u32>,
+        user_id: Uuid,
+    ) -> Result<CallRecord, AppError> {
+        validate_destination(destination)?;
+        let (channel, context) = originate_target(extension)?;
+        let config = ami_config()?;
+        let ring_timeout = ring_timeout.unwrap_or(DEFAULT_RING_TIMEOUT);
+
+        let pbx = PbxService::new(self.pool.clone());
+        let channel_id = Uuid::new_v4().to_string();
+        let record = pbx
+            .create_originated_call(&extension.extension_number, destination, &channel_id, user_id)
+            .await?;
+
+        let sent = async {
+            let mut client = AmiClient::connect(&config).await?;
+            let caller_id = format!("\"{}\" <{}>", extension.name.replace('"', ""), extension.extension_number);
+            let timeout_ms = (ring_timeout * 1000).to_string();
+            let response = client
+                .send_action(
+                    "Originate",
+                    &[
+                        ("Channel", &channel),
+                        ("Context", &context),
+                        ("Exten", destination),
+                        ("Priority", "1"),
+                        ("CallerID", &caller_id),
+                        ("Timeout", &timeout_ms),
+                        ("ChannelId", &channel_id),
+                        ("Async", "true"),
+                    ],
+                )
+                .await?;
+            if !response.is_success() {
+                return Err(AppError::Internal(format!(
+                    "AMI Originate failed: {}",
+                    response.get("Message").unwrap_or("no message")
+                )));
+            }
+            let action_id = response.get("ActionID").unwrap_or_default().to_string();
+            Ok((client, action_id))
+        }
+        .await;
+
+        match sent {
+            Ok((client, action_id)) => {
+                let wait = Duration::from_secs(ring_timeout.into()) + RESPONSE_GRACE;
+                tokio::spawn(watch_originate(client, action_id, channel_id, pbx, wait));
+                Ok(record)
+            }
+            Err(e) => {
+                pbx.end_channel_call(&channel_id, Utc::now(), CallStatus::Failed).await?;
+                Err(e)
+            }
+        }
+    }
+
+    // Permissions
+    pub async fn is_permitted(&self, user_id: Uuid) -> Result<bool, AppError> {
+        let permitted = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM originate_permissions WHERE user_id = $1) as "exists!""#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(permitted)
+    }
+
+    pub async fn list_permissions(&self) -> Result<Vec<OriginatePermission>, AppError> {
+        let permissions = sqlx::query_as!(
+            OriginatePermission,
+            r#"
+            SELECT user_id, granted_by, created_at
+            FROM originate_permissions
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(permissions)
+    }
+
+    /// Granting twice keeps the original grant.
+    pub async fn grant_permission(&self, user_id: Uuid, granted_by: Uuid) -> Result<OriginatePermission, AppError> {
+        let user_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) as "exists!""#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !user_exists {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO originate_permissions (user_id, granted_by)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO NOTHING
+            "#,
+            user_id,
+            granted_by
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let permission = sqlx::query_as!(
+            OriginatePermission,
+            r#"
+            SELECT user_id, granted_by, created_at
+            FROM originate_permissions
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(permission)
+    }
+
+    pub async fn revoke_permission(&self, user_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM originate_permissions WHERE user_id = $1", user_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Permission not found".into()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Waits for the OriginateResponse of an asynchronous Originate. Asterisk
+/// reports a phone that could not be reached or did not answer only there,
+/// without a hangup the call tracker would see.
+async fn watch_originate(
+    mut client: AmiClient,
+    action_id: String,
+    channel_id: String,
+    pbx: PbxService,
+    wait: Duration,
+) {
+    let response = tokio::time::timeout(wait, async {
+        loop {
+            let event = client.next_event().await?;
+            if event.event() == Some("OriginateResponse") && event.get("ActionID") == Some(action_id.as_str()) {
+                return Ok::<_, AppError>(event);
+            }
+        }
+    })
+    .await;
+
+    match response {
+        Ok(Ok(event)) if event.get("Response") == Some("Failure") => {
+            let reason = event.get("Reason").and_then(|reason| reason.parse().ok()).unwrap_or(0);
+            // Calls the tracker already ended keep their status
+            if let Err(e) = pbx
+                .end_channel_call(&channel_id, Utc::now(), originate_failure_status(reason))
+                .await
+            {
+                tracing::error!("Failed to record failed originate {}: {}", channel_id, e);
+            }
+        }
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::warn!("Lost AMI session while placing call {}: {}", channel_id, e),
+        Err(_) => tracing::warn!("No originate response for call {}", channel_id),
+    }
+
+    if let Err(e) = client.logoff().await {
+        tracing::debug!("AMI logoff failed: {}", e);
+    }
+}
@@ -1,15 +1,414 @@
-use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sqlx::{types::JsonValue, PgPool};
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use validator::{Validate, ValidationErrors};
 
 use crate::{
     error::AppError,
     models::pbx::{
-        CallRecord, CallStatus, CreateCallRecordRequest, CreateExtensionRequest,
-        PbxExtension, UpdateCallRecordRequest, UpdateExtensionRequest,
+        CallEvent, CallEventSource, CallRecord, CallStatus, CreateCallRecordRequest, CreateExtensionRequest,
+        ExtensionConfig, ExtensionImportResult, ExtensionImportRow, ExtensionRegistrationStatus, ExtensionType,
+        PbxExtension, SipExtensionConfig, SipRegistration, SipTransport, UpdateCallRecordRequest,
+        UpdateExtensionRequest, UpsertRegistrationRequest,
+    },
+    services::{
+        cdr::{sanitize_csv_field, unsanitize_csv_field},
+        trunk::validate_codecs,
     },
 };
 
+/// Columns of the bulk import and export files. Import only requires the
+/// first three; the file may list them in any order.
+pub const EXTENSION_CSV_COLUMNS: [&str; 5] = [
+    "extension_number",
+    "name",
+    "extension_type",
+    "config_data",
+    "owner_id",
+];
+const REQUIRED_CSV_COLUMNS: usize = 3;
+
+/// One readable line per failed rule, in field order. `prefix` names the
+/// enclosing object, e.g. `config_data`.
+fn validation_messages(validation: &ValidationErrors, prefix: Option<&str>) -> Vec<String> {
+    let mut fields: Vec<_> = validation.field_errors().into_iter().collect();
+    fields.sort_by_key(|(field, _)| *field);
+    fields
+        .into_iter()
+        .flat_map(|(field, field_errors)| {
+            field_errors.iter().map(move |error| match prefix {
+                Some(prefix) => format!("{}.{} is invalid ({})", prefix, field, error.code),
+                None => format!("{} is invalid ({})", field, error.code),
+            })
+        })
+        .collect()
+}
+
+fn typed_config<T>(config_data: &JsonValue) -> Result<T, AppError>
+where
+    T: DeserializeOwned + Validate,
+{
+    if !config_data.is_object() {
+        return Err(AppError::Validation("config_data must be a JSON object".into()));
+    }
+    let config: T = serde_json::from_value(config_data.clone())
+        .map_err(|e| AppError::Validation(format!("config_data: {}", e)))?;
+    config
+        .validate()
+        .map_err(|validation| AppError::Validation(validation_messages(&validation, Some("config_data")).join("; ")))?;
+    Ok(config)
+}
+
+/// Reads `config_data` as the typed configuration of `extension_type`.
+pub fn parse_extension_config(extension_type: ExtensionType, config_data: &JsonValue) -> Result<ExtensionConfig, AppError> {
+    match extension_type {
+        ExtensionType::Sip => {
+            let config: SipExtensionConfig = typed_config(config_data)?;
+            if let Some(codecs) = &config.codecs {
+                validate_codecs(codecs)?;
+            }
+            Ok(ExtensionConfig::Sip(config))
+        }
+        ExtensionType::Iax => Ok(ExtensionConfig::Iax(typed_config(config_data)?)),
+        ExtensionType::Custom => Ok(ExtensionConfig::Custom(typed_config(config_data)?)),
+    }
+}
+
+/// Validates `config_data` and returns it in the form that is stored, with
+/// unset optional keys left out.
+pub fn normalize_extension_config(extension_type: ExtensionType, config_data: &JsonValue) -> Result<JsonValue, AppError> {
+    let value = match parse_extension_config(extension_type, config_data)? {
+        ExtensionConfig::Sip(config) => serde_json::to_value(config),
+        ExtensionConfig::Iax(config) => serde_json::to_value(config),
+        ExtensionConfig::Custom(config) => serde_json::to_value(config),
+    };
+    value.map_err(|e| AppError::Internal(format!("Failed to serialize config_data: {}", e)))
+}
+
+fn ring_timeout_schema() -> JsonValue {
+    json!({
+        "type": "integer",
+        "minimum": 5,
+        "maximum": 300,
+        "description": "Seconds to ring before the call counts as not answered. Defaults to 30."
+    })
+}
+
+/// JSON Schema (draft 2020-12) of the `config_data` of one extension type,
+/// matching the checks in `parse_extension_config`.
+pub fn extension_config_schema(extension_type: ExtensionType) -> JsonValue {
+    let (title, required, properties) = match extension_type {
+        ExtensionType::Sip => (
+            "SIP extension configuration",
+            vec!["secret"],
+            json!({
+                "secret": {
+                    "type": "string",
+                    "minLength": 6,
+                    "maxLength": 100,
+                    "description": "SIP password of the phone."
+                },
+                "auth_username": {
+                    "type": "string",
+                    "minLength": 1,
+                    "maxLength": 100,
+                    "description": "SIP username. Defaults to the extension number."
+                },
+                "context": {
+                    "type": "string",
+                    "minLength": 1,
+                    "maxLength": 80,
+                    "description": "Dialplan context for calls from the phone. Defaults to from-internal."
+                },
+                "codecs": {
+                    "type": "array",
+                    "items": { "type": "string", "pattern": "^[A-Za-z0-9_]+$" },
+                    "minItems": 1,
+                    "description": "Allowed codecs in order of preference. Defaults to ulaw, alaw."
+                },
+                "nat": {
+                    "type": "boolean",
+                    "description": "The phone is behind NAT; media is relayed through Asterisk."
+                },
+                "transport": {
+                    "type": "string",
+                    "enum": ["udp", "tcp", "tls", "ws", "wss"]
+                },
+                "dtmf_mode": {
+                    "type": "string",
+                    "enum": ["rfc4733", "inband", "info", "auto"]
+                },
+                "max_contacts": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 10,
+                    "description": "Devices that may register at once. Defaults to 1."
+                },
+                "ring_timeout": ring_timeout_schema()
+            }),
+        ),
+        ExtensionType::Iax => (
+            "IAX2 extension configuration",
+            vec![],
+            json!({
+                "trunk": {
+                    "type": "boolean",
+                    "description": "Use IAX2 trunking."
+                },
+                "encryption": {
+                    "type": "string",
+                    "enum": ["no", "aes128"]
+                },
+                "ring_timeout": ring_timeout_schema()
+            }),
+        ),
+        ExtensionType::Custom => (
+            "Custom extension configuration",
+            vec!["dial_string"],
+            json!({
+                "dial_string": {
+                    "type": "string",
+                    "minLength": 1,
+                    "maxLength": 255,
+                    "description": "Dial() target, e.g. Local/500@ivr."
+                },
+                "ring_timeout": ring_timeout_schema()
+            }),
+        ),
+    };
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "type": "object",
+        "additionalProperties": false,
+        "required": required,
+        "properties": properties
+    })
+}
+
+/// Schemas of all extension types, keyed like `extension_type`.
+pub fn extension_config_schemas() -> JsonValue {
+    json!({
+        "sip": extension_config_schema(ExtensionType::Sip),
+        "iax": extension_config_schema(ExtensionType::Iax),
+        "custom": extension_config_schema(ExtensionType::Custom),
+    })
+}
+
+/// One data row of an import file, either as a create request or with the
+/// reasons it could not be read.
+#[derive(Debug)]
+pub struct ParsedExtensionRow {
+    pub line: u64,
+    pub request: Result<CreateExtensionRequest, Vec<String>>,
+}
+
+fn extension_type_str(extension_type: &ExtensionType) -> &'static str {
+    match extension_type {
+        ExtensionType::Sip => "sip",
+        ExtensionType::Iax => "iax",
+        ExtensionType::Custom => "custom",
+    }
+}
+
+fn parse_extension_type(value: &str) -> Result<ExtensionType, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "sip" => Ok(ExtensionType::Sip),
+        "iax" => Ok(ExtensionType::Iax),
+        "custom" => Ok(ExtensionType::Custom),
+        _ => Err(format!("Unknown extension_type '{}'", value)),
+    }
+}
+
+/// Builds the same request a `POST /extensions` body would, and runs the
+/// same validation on it.
+fn parse_extension_record(record: &csv::StringRecord, columns: &[Option<usize>]) -> Result<CreateExtensionRequest, Vec<String>> {
+    let field = |column: usize| {
+        columns[column]
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .unwrap_or("")
+    };
+    let mut errors = Vec::new();
+
+    let extension_type = match parse_extension_type(field(2)) {
+        Ok(extension_type) => Some(extension_type),
+        Err(e) => {
+            errors.push(e);
+            None
+        }
+    };
+    let config_data = match field(3) {
+        "" => Some(serde_json::json!({})),
+        raw => match serde_json::from_str::<serde_json::Value>(raw) {
+            Ok(value) if value.is_object() => Some(value),
+            Ok(_) => {
+                errors.push("config_data must be a JSON object".into());
+                None
+            }
+            Err(e) => {
+                errors.push(format!("config_data is not valid JSON: {}", e));
+                None
+            }
+        },
+    };
+    let owner_id = match field(4) {
+        "" => Some(None),
+        raw => match Uuid::parse_str(raw) {
+            Ok(owner_id) => Some(Some(owner_id)),
+            Err(_) => {
+                errors.push(format!("Invalid owner_id '{}'", raw));
+                None
+            }
+        },
+    };
+
+    let (Some(extension_type), Some(config_data), Some(owner_id)) = (extension_type, config_data, owner_id) else {
+        return Err(errors);
+    };
+    let request = CreateExtensionRequest {
+        extension_number: field(0).to_string(),
+        name: unsanitize_csv_field(field(1)).to_string(),
+        extension_type,
+        config_data,
+        owner_id,
+    };
+    if let Err(validation) = request.validate() {
+        return Err(validation_messages(&validation, None));
+    }
+
+    match normalize_extension_config(request.extension_type, &request.config_data) {
+        Ok(config_data) => Ok(CreateExtensionRequest { config_data, ..request }),
+        Err(AppError::Validation(message)) => Err(vec![message]),
+        Err(e) => Err(vec![e.to_string()]),
+    }
+}
+
+/// Reads a bulk import file. A missing required column fails the whole file;
+/// problems in a row are reported with that row.
+pub fn parse_extension_csv(data: &[u8]) -> Result<Vec<ParsedExtensionRow>, AppError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::Validation(format!("Invalid CSV header: {}", e)))?
+        .clone();
+    let columns: Vec<Option<usize>> = EXTENSION_CSV_COLUMNS
+        .iter()
+        .map(|column| headers.iter().position(|header| header.trim().eq_ignore_ascii_case(column)))
+        .collect();
+    if let Some(missing) = (0..REQUIRED_CSV_COLUMNS).find(|&column| columns[column].is_none()) {
+        return Err(AppError::Validation(format!(
+            "CSV header is missing the '{}' column",
+            EXTENSION_CSV_COLUMNS[missing]
+        )));
+    }
+
+    let mut rows = Vec::new();
+    for (index, result) in reader.records().enumerate() {
+        let line = index as u64 + 2;
+        let row = match result {
+            Ok(record) => ParsedExtensionRow {
+                line: record.position().map_or(line, |position| position.line()),
+                request: parse_extension_record(&record, &columns),
+            },
+            Err(e) => ParsedExtensionRow {
+                line: e.position().map_or(line, |position| position.line()),
+                request: Err(vec![e.to_string()]),
+            },
+        };
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Adds the checks that need the rest of the file and the database: numbers
+/// repeated in the file, numbers already dialled elsewhere (`taken` maps a
+/// number to what uses it) and owners that do not exist.
+pub fn extension_import_report(
+    rows: &[ParsedExtensionRow],
+    taken: &HashMap<String, String>,
+    known_owners: &HashSet<Uuid>,
+) -> Vec<ExtensionImportRow> {
+    let mut first_line: HashMap<&str, u64> = HashMap::new();
+
+    rows.iter()
+        .map(|row| match &row.request {
+            Ok(request) => {
+                let number = request.extension_number.as_str();
+                let mut errors = Vec::new();
+                if let Some(used_by) = taken.get(number) {
+                    errors.push(format!("Number {} is already used by {}", number, used_by));
+                }
+                match first_line.get(number) {
+                    Some(line) => errors.push(format!("Number {} is repeated from line {}", number, line)),
+                    None => {
+                        first_line.insert(number, row.line);
+                    }
+                }
+                if let Some(owner_id) = request.owner_id {
+                    if !known_owners.contains(&owner_id) {
+                        errors.push(format!("Owner {} does not exist", owner_id));
+                    }
+                }
+                ExtensionImportRow {
+                    line: row.line,
+                    extension_number: Some(number.to_string()),
+                    errors,
+                }
+            }
+            Err(errors) => ExtensionImportRow {
+                line: row.line,
+                extension_number: None,
+                errors: errors.clone(),
+            },
+        })
+        .collect()
+}
+
+/// Writes extensions in the import format, so an export can be edited and
+/// imported on another system. Names that look like formulas get a leading
+/// quote, which the import strips again.
+pub fn render_extensions_csv(extensions: &[PbxExtension]) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let _ = writer.write_record(EXTENSION_CSV_COLUMNS);
+    for extension in extensions {
+        let _ = writer.write_record([
+            extension.extension_number.clone(),
+            sanitize_csv_field(&extension.name),
+            extension_type_str(&extension.extension_type).to_string(),
+            extension.config_data.to_string(),
+            extension.owner_id.map(|owner_id| owner_id.to_string()).unwrap_or_default(),
+        ]);
+    }
+    writer.into_inner().unwrap_or_default()
+}
+
+/// Rejects any status change other than ending an active call.
+pub fn check_call_transition(from: CallStatus, to: CallStatus) -> Result<(), AppError> {
+    if from.can_transition_to(to) {
+        return Ok(());
+    }
+    if from.is_final() {
+        return Err(AppError::Validation(format!("Call has already ended as {:?}", from)));
+    }
+    Err(AppError::Validation(format!("Cannot change call status from {:?} to {:?}", from, to)))
+}
+
+/// Call length in whole seconds; an end before the start is rejected.
+pub fn call_duration(start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<i32, AppError> {
+    if end_time < start_time {
+        return Err(AppError::Validation("End time is before the call started".into()));
+    }
+    i32::try_from((end_time - start_time).num_seconds())
+        .map_err(|_| AppError::Validation("Call duration is too long".into()))
+}
+
 pub struct PbxService {
     pool: PgPool,
 }
@@ -24,17 +423,40 @@ impl PbxService {
         &self,
         request: CreateExtensionRequest,
     ) -> Result<PbxExtension, AppError> {
+        let config_data = normalize_extension_config(request.extension_type, &request.config_data)?;
+
+        let group = sqlx::query!(
+            "SELECT id FROM ring_groups WHERE group_number = $1",
+            request.extension_number
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if group.is_some() {
+            return Err(AppError::Validation("Number is already used by a ring group".into()));
+        }
+
+        let room = sqlx::query!(
+            "SELECT id FROM conference_rooms WHERE room_number = $1",
+            request.extension_number
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if room.is_some() {
+            return Err(AppError::Validation("Number is already used by a conference room".into()));
+        }
+
         let extension = sqlx::query_as!(
             PbxExtension,
             r#"
-            INSERT INTO pbx_extensions (extension_number, name, type, config_data)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id, extension_number, name, type as "extension_type: _", config_data, created_at, updated_at
+            INSERT INTO pbx_extensions (extension_number, name, type, config_data, owner_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, extension_number, name, type as "extension_type: _", config_data, owner_id, created_at, updated_at
             "#,
             request.extension_number,
             request.name,
             request.extension_type as _,
-            request.config_data,
+            config_data,
+            request.owner_id,
         )
         .fetch_one(&self.pool)
         .await?;
@@ -46,7 +468,7 @@ impl PbxService {
         let extension = sqlx::query_as!(
             PbxExtension,
             r#"
-            SELECT id, extension_number, name, type as "extension_type: _", config_data, created_at, updated_at
+            SELECT id, extension_number, name, type as "extension_type: _", config_data, owner_id, created_at, updated_at
             FROM pbx_extensions
             WHERE id = $1
             "#,
@@ -63,7 +485,7 @@ impl PbxService {
         let extensions = sqlx::query_as!(
             PbxExtension,
             r#"
-            SELECT id, extension_number, name, type as "extension_type: _", config_data, created_at, updated_at
+            SELECT id, extension_number, name, type as "extension_type: _", config_data, owner_id, created_at, updated_at
             FROM pbx_extensions
             ORDER BY extension_number
             "#
@@ -74,11 +496,103 @@ impl PbxService {
         Ok(extensions)
     }
 
+    /// Validates every row of a bulk import file. Unless this is a dry run and
+    /// only if every row is valid, all extensions are created in one transaction.
+    pub async fn import_extensions_csv(&self, data: &[u8], dry_run: bool) -> Result<ExtensionImportResult, AppError> {
+        let rows = parse_extension_csv(data)?;
+        let numbers: Vec<String> = rows
+            .iter()
+            .filter_map(|row| row.request.as_ref().ok())
+            .map(|request| request.extension_number.clone())
+            .collect();
+        let owner_ids: Vec<Uuid> = rows
+            .iter()
+            .filter_map(|row| row.request.as_ref().ok())
+            .filter_map(|request| request.owner_id)
+            .collect();
+
+        let taken = sqlx::query!(
+            r#"
+            SELECT extension_number as "number!", 'extension' as "used_by!" FROM pbx_extensions WHERE extension_number = ANY($1)
+            UNION ALL
+            SELECT group_number, 'ring group' FROM ring_groups WHERE group_number = ANY($1)
+            UNION ALL
+            SELECT room_number, 'conference room' FROM conference_rooms WHERE room_number = ANY($1)
+            "#,
+            &numbers
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.number, format!("an existing {}", row.used_by)))
+        .collect::<HashMap<_, _>>();
+        let known_owners = sqlx::query!("SELECT id FROM users WHERE id = ANY($1)", &owner_ids)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.id)
+            .collect::<HashSet<_>>();
+
+        let report = extension_import_report(&rows, &taken, &known_owners);
+        let total = report.len() as u64;
+        let valid = report.iter().filter(|row| row.errors.is_empty()).count() as u64;
+        if dry_run || valid < total {
+            return Ok(ExtensionImportResult {
+                dry_run,
+                total,
+                valid,
+                created: 0,
+                rows: report,
+            });
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for request in rows.into_iter().filter_map(|row| row.request.ok()) {
+            sqlx::query!(
+                r#"
+                INSERT INTO pbx_extensions (extension_number, name, type, config_data, owner_id)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+                request.extension_number,
+                request.name,
+                request.extension_type as _,
+                request.config_data,
+                request.owner_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(ExtensionImportResult {
+            dry_run,
+            total,
+            valid,
+            created: total,
+            rows: report,
+        })
+    }
+
+    pub async fn export_extensions_csv(&self) -> Result<Vec<u8>, AppError> {
+        let extensions = self.list_extensions().await?;
+        Ok(render_extensions_csv(&extensions))
+    }
+
     pub async fn update_extension(
         &self,
         id: Uuid,
         request: UpdateExtensionRequest,
     ) -> Result<PbxExtension, AppError> {
+        // A new type is checked against the stored config and vice versa
+        let config_data = if request.extension_type.is_some() || request.config_data.is_some() {
+            let current = self.get_extension(id).await?;
+            let extension_type = request.extension_type.unwrap_or(current.extension_type);
+            let config_data = request.config_data.as_ref().unwrap_or(&current.config_data);
+            Some(normalize_extension_config(extension_type, config_data)?)
+        } else {
+            None
+        };
+
         let extension = sqlx::query_as!(
             PbxExtension,
             r#"
@@ -87,13 +601,16 @@ impl PbxService {
                 name = COALESCE($1, name),
                 type = COALESCE($2, type),
                 config_data = COALESCE($3, config_data),
-                updated_at = $4
-            WHERE id = $5
-            RETURNING id, extension_number, name, type as "extension_type: _", config_data, created_at, updated_at
+                owner_id = CASE WHEN $5 THEN NULL ELSE COALESCE($4, owner_id) END,
+                updated_at = $6
+            WHERE id = $7
+            RETURNING id, extension_number, name, type as "extension_type: _", config_data, owner_id, created_at, updated_at
             "#,
             request.name,
             request.extension_type as _,
-            request.config_data,
+            config_data,
+            request.owner_id,
+            request.clear_owner.unwrap_or(false),
             Utc::now(),
             id
         )
@@ -126,7 +643,14 @@ impl PbxService {
     pub async fn create_call_record(
         &self,
         request: CreateCallRecordRequest,
+        user_id: Uuid,
     ) -> Result<CallRecord, AppError> {
+        if request.status != CallStatus::Active {
+            return Err(AppError::Validation("New calls must start as active".into()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
         let record = sqlx::query_as!(
             CallRecord,
             r#"
@@ -139,17 +663,94 @@ impl PbxService {
             request.start_time,
             request.status as _,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        Self::record_call_event(&mut tx, record.id, None, record.status, CallEventSource::Api, Some(user_id))
+            .await?;
+        tx.commit().await?;
+
         Ok(record)
     }
 
+    /// Creates the record of a call the PBX is about to place. The channel
+    /// is originated with `uniqueid` as its id, so AMI events update this
+    /// record instead of creating another one.
+    pub async fn create_originated_call(
+        &self,
+        caller_id: &str,
+        recipient_id: &str,
+        uniqueid: &str,
+        user_id: Uuid,
+    ) -> Result<CallRecord, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let record = sqlx::query_as!(
+            CallRecord,
+            r#"
+            INSERT INTO call_records (caller_id, recipient_id, start_time, status, uniqueid)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, caller_id, recipient_id, start_time, end_time, duration, status as "status: _", recording_path, created_at
+            "#,
+            caller_id,
+            recipient_id,
+            Utc::now(),
+            CallStatus::Active as _,
+            uniqueid,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::record_call_event(&mut tx, record.id, None, record.status, CallEventSource::Api, Some(user_id))
+            .await?;
+        tx.commit().await?;
+
+        Ok(record)
+    }
+
+    /// Ends an active call. The row is locked while the transition is checked,
+    /// so two concurrent updates cannot both end the same call.
     pub async fn update_call_record(
         &self,
         id: Uuid,
         request: UpdateCallRecordRequest,
+        user_id: Uuid,
     ) -> Result<CallRecord, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = sqlx::query!(
+            r#"
+            SELECT start_time, answered_at, status as "status: CallStatus"
+            FROM call_records
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Call record not found".into()))?;
+
+        check_call_transition(current.status, request.status)?;
+        let duration = call_duration(current.start_time, request.end_time)?;
+        if let Some(answered_at) = request.answered_at {
+            if answered_at < current.start_time || answered_at > request.end_time {
+                return Err(AppError::Validation(
+                    "answered_at must lie between the start and the end of the call".into(),
+                ));
+            }
+        }
+        // Billing needs an answer time for every completed call
+        let answered_at = match request.status {
+            CallStatus::Completed => Some(
+                request
+                    .answered_at
+                    .or(current.answered_at)
+                    .unwrap_or(current.start_time),
+            ),
+            _ => request.answered_at.or(current.answered_at),
+        };
+
         let record = sqlx::query_as!(
             CallRecord,
             r#"
@@ -158,23 +759,82 @@ impl PbxService {
                 end_time = $1,
                 duration = $2,
                 status = $3,
-                recording_path = $4
-            WHERE id = $5
+                recording_path = COALESCE($4, recording_path),
+                answered_at = $5
+            WHERE id = $6
             RETURNING id, caller_id, recipient_id, start_time, end_time, duration, status as "status: _", recording_path, created_at
             "#,
             request.end_time,
-            request.duration,
+            duration,
             request.status as _,
             request.recording_path,
+            answered_at,
             id
         )
-        .fetch_optional(&self.pool)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Call record not found".into()))?;
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::record_call_event(
+            &mut tx,
+            id,
+            Some(current.status),
+            request.status,
+            CallEventSource::Api,
+            Some(user_id),
+        )
+        .await?;
+        tx.commit().await?;
 
         Ok(record)
     }
 
+    async fn record_call_event(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        call_id: Uuid,
+        from_status: Option<CallStatus>,
+        to_status: CallStatus,
+        source: CallEventSource,
+        user_id: Option<Uuid>,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO call_events (call_id, from_status, to_status, source, user_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            call_id,
+            from_status as Option<CallStatus>,
+            to_status as CallStatus,
+            source as CallEventSource,
+            user_id,
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_call_events(&self, call_id: Uuid) -> Result<Vec<CallEvent>, AppError> {
+        // Distinguish an unknown call from one without history
+        self.get_call_record(call_id).await?;
+
+        let events = sqlx::query_as!(
+            CallEvent,
+            r#"
+            SELECT id, call_id, from_status as "from_status: CallStatus",
+                   to_status as "to_status: CallStatus", source as "source: CallEventSource",
+                   user_id, occurred_at
+            FROM call_events
+            WHERE call_id = $1
+            ORDER BY occurred_at, id
+            "#,
+            call_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
     pub async fn get_call_record(&self, id: Uuid) -> Result<CallRecord, AppError> {
         let record = sqlx::query_as!(
             CallRecord,
@@ -229,4 +889,366 @@ impl PbxService {
 
         Ok(records)
     }
-} 
\ No newline at end of file
+
+    // Call tracking driven by AMI channel events
+    pub async fn start_channel_call(
+        &self,
+        uniqueid: &str,
+        caller_id: &str,
+        recipient_id: &str,
+        start_time: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            WITH started AS (
+                INSERT INTO call_records (caller_id, recipient_id, start_time, status, uniqueid)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (uniqueid) DO NOTHING
+                RETURNING id, status
+            )
+            INSERT INTO call_events (call_id, to_status, source, occurred_at)
+            SELECT id, status, $6, $3 FROM started
+            "#,
+            caller_id,
+            recipient_id,
+            start_time,
+            CallStatus::Active as _,
+            uniqueid,
+            CallEventSource::Ami as CallEventSource,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_channel_call_answered(
+        &self,
+        uniqueids: &[String],
+        answered_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE call_records
+            SET answered_at = $1
+            WHERE uniqueid = ANY($2) AND answered_at IS NULL AND status = 'active'
+            "#,
+            answered_at,
+            uniqueids,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn is_channel_call_answered(&self, uniqueid: &str) -> Result<bool, AppError> {
+        let answered = sqlx::query_scalar!(
+            r#"
+            SELECT answered_at IS NOT NULL as "answered!"
+            FROM call_records
+            WHERE uniqueid = $1
+            "#,
+            uniqueid
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(answered)
+    }
+
+    pub async fn end_channel_call(
+        &self,
+        uniqueid: &str,
+        end_time: DateTime<Utc>,
+        status: CallStatus,
+    ) -> Result<(), AppError> {
+        // Only active calls are ended, which keeps AMI in line with the
+        // transition rules enforced for API updates.
+        sqlx::query!(
+            r#"
+            WITH ended AS (
+                UPDATE call_records
+                SET end_time = GREATEST($1, start_time),
+                    duration = EXTRACT(EPOCH FROM (GREATEST($1, start_time) - start_time))::INTEGER,
+                    status = $2
+                WHERE uniqueid = $3 AND status = 'active'
+                RETURNING id, status
+            )
+            INSERT INTO call_events (call_id, from_status, to_status, source, occurred_at)
+            SELECT id, 'active', status, $4, $1 FROM ended
+            "#,
+            end_time,
+            status as _,
+            uniqueid,
+            CallEventSource::Ami as CallEventSource,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Ends AMI-tracked calls that are still active but whose channel is no
+    /// longer up, after Hangup events were missed. The hangup happened at
+    /// some point after `ended_at`, the last moment the tracker was listening.
+    pub async fn end_missing_channel_calls(
+        &self,
+        live_uniqueids: &[String],
+        ended_at: DateTime<Utc>,
+    ) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            WITH ended AS (
+                UPDATE call_records
+                SET end_time = GREATEST($1, start_time),
+                    duration = EXTRACT(EPOCH FROM (GREATEST($1, start_time) - start_time))::INTEGER,
+                    status = CASE WHEN answered_at IS NOT NULL THEN $2 ELSE $3 END
+                WHERE status = 'active'
+                  AND uniqueid IS NOT NULL
+                  AND NOT (uniqueid = ANY($4))
+                RETURNING id, status
+            )
+            INSERT INTO call_events (call_id, from_status, to_status, source, occurred_at)
+            SELECT id, 'active', status, $5, $1 FROM ended
+            "#,
+            ended_at,
+            CallStatus::Completed as _,
+            CallStatus::Failed as _,
+            live_uniqueids,
+            CallEventSource::Ami as CallEventSource,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Remembers a channel of an extension's phone; channels of anything
+    /// else, such as trunks, are ignored.
+    pub async fn open_extension_channel(&self, uniqueid: &str, extension_number: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO extension_channels (uniqueid, extension_number)
+            SELECT $1, extension_number FROM pbx_extensions WHERE extension_number = $2
+            ON CONFLICT (uniqueid) DO NOTHING
+            "#,
+            uniqueid,
+            extension_number,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_extension_channel_answered(&self, uniqueid: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE extension_channels SET answered = true WHERE uniqueid = $1",
+            uniqueid
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn close_extension_channel(&self, uniqueid: &str) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM extension_channels WHERE uniqueid = $1", uniqueid)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Forgets all channels, e.g. after hangups were missed while the AMI
+    /// session was down.
+    pub async fn clear_extension_channels(&self) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM extension_channels")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // SIP registration management
+    pub async fn get_extension_by_number(
+        &self,
+        extension_number: &str,
+    ) -> Result<PbxExtension, AppError> {
+        let extension = sqlx::query_as!(
+            PbxExtension,
+            r#"
+            SELECT id, extension_number, name, type as "extension_type: _", config_data, owner_id, created_at, updated_at
+            FROM pbx_extensions
+            WHERE extension_number = $1
+            "#,
+            extension_number
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Extension not found".into()))?;
+
+        Ok(extension)
+    }
+
+    pub async fn upsert_registration(
+        &self,
+        request: UpsertRegistrationRequest,
+    ) -> Result<SipRegistration, AppError> {
+        let registration = sqlx::query_as!(
+            SipRegistration,
+            r#"
+            INSERT INTO sip_registrations (
+                extension_id, contact_uri, user_agent, source_address,
+                transport, call_id, cseq, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (extension_id, contact_uri) DO UPDATE
+            SET user_agent = EXCLUDED.user_agent,
+                source_address = EXCLUDED.source_address,
+                transport = EXCLUDED.transport,
+                call_id = EXCLUDED.call_id,
+                cseq = EXCLUDED.cseq,
+                expires_at = EXCLUDED.expires_at,
+                registered_at = NOW()
+            RETURNING id, extension_id, contact_uri, user_agent, source_address,
+                      transport as "transport: SipTransport", call_id, cseq,
+                      expires_at, registered_at
+            "#,
+            request.extension_id,
+            request.contact_uri,
+            request.user_agent,
+            request.source_address,
+            request.transport as SipTransport,
+            request.call_id,
+            request.cseq,
+            request.expires_at,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(registration)
+    }
+
+    pub async fn remove_registration(
+        &self,
+        extension_id: Uuid,
+        contact_uri: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM sip_registrations
+            WHERE extension_id = $1 AND contact_uri = $2
+            "#,
+            extension_id,
+            contact_uri
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_all_registrations(&self, extension_id: Uuid) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM sip_registrations
+            WHERE extension_id = $1
+            "#,
+            extension_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_registrations(
+        &self,
+        extension_id: Uuid,
+    ) -> Result<Vec<SipRegistration>, AppError> {
+        let registrations = sqlx::query_as!(
+            SipRegistration,
+            r#"
+            SELECT id, extension_id, contact_uri, user_agent, source_address,
+                   transport as "transport: SipTransport", call_id, cseq,
+                   expires_at, registered_at
+            FROM sip_registrations
+            WHERE extension_id = $1 AND expires_at > NOW()
+            ORDER BY registered_at DESC
+            "#,
+            extension_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(registrations)
+    }
+
+    pub async fn purge_expired_registrations(&self) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM sip_registrations
+            WHERE expires_at <= NOW()
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_registration_status(
+        &self,
+        id: Uuid,
+    ) -> Result<ExtensionRegistrationStatus, AppError> {
+        let extension = self.get_extension(id).await?;
+        let contacts = self.list_registrations(extension.id).await?;
+
+        Ok(ExtensionRegistrationStatus {
+            extension_id: extension.id,
+            extension_number: extension.extension_number,
+            registered: !contacts.is_empty(),
+            contacts,
+        })
+    }
+
+    pub async fn list_registration_statuses(
+        &self,
+    ) -> Result<Vec<ExtensionRegistrationStatus>, AppError> {
+        let extensions = self.list_extensions().await?;
+        let registrations = sqlx::query_as!(
+            SipRegistration,
+            r#"
+            SELECT id, extension_id, contact_uri, user_agent, source_address,
+                   transport as "transport: SipTransport", call_id, cseq,
+                   expires_at, registered_at
+            FROM sip_registrations
+            WHERE expires_at > NOW()
+            ORDER BY registered_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(extensions
+            .into_iter()
+            .filter(|extension| extension.extension_type == ExtensionType::Sip)
+            .map(|extension| {
+                let contacts: Vec<SipRegistration> = registrations
+                    .iter()
+                    .filter(|registration| registration.extension_id == extension.id)
+                    .cloned()
+                    .collect();
+                ExtensionRegistrationStatus {
+                    extension_id: extension.id,
+                    extension_number: extension.extension_number,
+                    registered: !contacts.is_empty(),
+                    contacts,
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        chat::ChatServerEvent,
+        presence::{PresenceSignals, PresenceStatus, SetPresenceOverrideRequest, UserPresence},
+    },
+    services::chat::ChatHub,
+};
+
+/// How often presence is recomputed and changes are pushed.
+const PRESENCE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A manual override wins; otherwise a call beats do not disturb, which
+/// beats a meeting. Users with nothing going on are available when they have
+/// a chat connection or a registered phone.
+pub fn resolve_presence(signals: &PresenceSignals, manual: Option<PresenceStatus>) -> PresenceStatus {
+    if let Some(status) = manual {
+        return status;
+    }
+    if signals.on_call {
+        PresenceStatus::OnThePhone
+    } else if signals.dnd {
+        PresenceStatus::Dnd
+    } else if signals.in_meeting {
+        PresenceStatus::InAMeeting
+    } else if signals.online {
+        PresenceStatus::Available
+    } else {
+        PresenceStatus::Offline
+    }
+}
+
+/// Parses the comma-separated `user_ids` of a presence query.
+pub fn parse_user_ids(user_ids: &str) -> Result<Vec<Uuid>, AppError> {
+    user_ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .map_err(|_| AppError::Validation(format!("Invalid user id: {}", id)))
+        })
+        .collect()
+}
+
+pub struct PresenceService {
+    pool: PgPool,
+    hub: ChatHub,
+}
+
+impl PresenceService {
+    pub fn new(pool: PgPool, hub: ChatHub) -> Self {
+        Self { pool, hub }
+    }
+
+    /// Presence of the given users, or of all users, ordered by username.
+    pub async fn presence(&self, user_ids: Option<&[Uuid]>) -> Result<Vec<UserPresence>, AppError> {
+        let now = Utc::now();
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.id, u.username,
+                   EXISTS (
+                       SELECT 1 FROM pbx_extensions e
+                       JOIN extension_channels ch ON ch.extension_number = e.extension_number
+                       WHERE e.owner_id = u.id AND ch.answered
+                   ) as "on_call!",
+                   EXISTS (
+                       SELECT 1 FROM pbx_extensions e
+                       JOIN extension_call_handling h ON h.extension_id = e.id
+                       WHERE e.owner_id = u.id AND h.dnd
+                   ) as "dnd!",
+                   EXISTS (
+                       SELECT 1 FROM calendar_events ev
+                       WHERE (ev.creator_id = u.id OR u.id = ANY(ev.attendees))
+                         AND ev.status IN ('scheduled', 'rescheduled')
+                         AND ev.event_type IN ('meeting', 'appointment', 'other')
+                         AND ev.start_time <= $2 AND ev.end_time > $2
+                   ) as "in_meeting!",
+                   EXISTS (
+                       SELECT 1 FROM pbx_extensions e
+                       JOIN sip_registrations r ON r.extension_id = e.id
+                       WHERE e.owner_id = u.id AND r.expires_at > $2
+                   ) as "registered!",
+                   o.status as "manual_status?: PresenceStatus",
+                   o.note as "note?",
+                   o.expires_at as "until?"
+            FROM users u
+            LEFT JOIN presence_overrides o
+                ON o.user_id = u.id AND (o.expires_at IS NULL OR o.expires_at > $2)
+            WHERE ($1::uuid[] IS NULL OR u.id = ANY($1))
+            ORDER BY u.username
+            "#,
+            user_ids,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let signals = PresenceSignals {
+                    on_call: row.on_call,
+                    dnd: row.dnd,
+                    in_meeting: row.in_meeting,
+                    online: row.registered || self.hub.is_connected(row.id),
+                };
+                UserPresence {
+                    user_id: row.id,
+                    username: row.username,
+                    status: resolve_presence(&signals, row.manual_status),
+                    manual: row.manual_status.is_some(),
+                    note: row.note,
+                    until: row.until,
+                    signals,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn get_presence(&self, user_id: Uuid) -> Result<UserPresence, AppError> {
+        self.presence(Some(&[user_id]))
+            .await?
+            .pop()
+            .ok_or_else(|| AppError::NotFound("User not found".into()))
+    }
+
+    /// Sets the user's status by hand until `expires_at`, or until cleared.
+    pub async fn set_override(
+        &self,
+        user_id: Uuid,
+        request: SetPresenceOverrideRequest,
+    ) -> Result<UserPresence, AppError> {
+        if request.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(AppError::Validation("expires_at must be in the future".into()));
+        }
+        self.get_presence(user_id).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO presence_overrides (user_id, status, note, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) DO UPDATE
+            SET status = EXCLUDED.status,
+                note = EXCLUDED.note,
+                expires_at = EXCLUDED.expires_at,
+                updated_at = NOW()
+            "#,
+            user_id,
+            request.status as PresenceStatus,
+            request.note,
+            request.expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.refresh(Some(&[user_id])).await?;
+        self.get_presence(user_id).await
+    }
+
+    /// Returns the user to their derived status.
+    pub async fn clear_override(&self, user_id: Uuid) -> Result<UserPresence, AppError> {
+        sqlx::query!("DELETE FROM presence_overrides WHERE user_id = $1", user_id)
+            .execute(&self.pool)
+            .await?;
+
+        self.refresh(Some(&[user_id])).await?;
+        self.get_presence(user_id).await
+    }
+
+    /// Recomputes presence and pushes each change to the connected users,
+    /// whose connections keep the ones they subscribed to. Returns the
+    /// number of changes.
+    pub async fn refresh(&self, user_ids: Option<&[Uuid]>) -> Result<usize, AppError> {
+        let presence = self.presence(user_ids).await?;
+        let connected = self.hub.connected_users();
+
+        let mut changed = 0;
+        for presence in presence {
+            if !self.hub.record_presence(presence.user_id, presence.status) {
+                continue;
+            }
+            changed += 1;
+            self.hub.publish(connected.clone(), ChatServerEvent::Presence { presence });
+        }
+
+        Ok(changed)
+    }
+
+    async fn delete_expired_overrides(&self, now: DateTime<Utc>) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM presence_overrides WHERE expires_at <= $1",
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Periodically picks up calls, do not disturb, meetings and overrides
+    /// that started or ended, and pushes the resulting changes.
+    pub async fn run_presence_job(self) {
+        let mut interval = tokio::time::interval(PRESENCE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.delete_expired_overrides(Utc::now()).await {
+                tracing::error!("Failed to delete expired presence overrides: {}", e);
+            }
+            if let Err(e) = self.refresh(None).await {
+                tracing::error!("Presence refresh failed: {}", e);
+            }
+        }
+    }
+}
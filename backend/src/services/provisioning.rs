@@ -0,0 +1,446 @@
+use std::fmt::Write;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bcrypt::{hash, verify, DEFAULT_COST};
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    error::AppError,
+    models::{
+        pbx::{ExtensionType, PbxExtension},
+        provisioning::{
+            CreatePhoneDeviceRequest, PhoneDevice, PhoneVendor, ProvisioningFile, UpdatePhoneDeviceRequest,
+        },
+    },
+    services::pbx::PbxService,
+};
+
+const DEFAULT_SIP_PORT: u16 = 5060;
+
+/// Where provisioned phones register.
+#[derive(Debug, Clone)]
+pub struct SipServer {
+    pub host: String,
+    pub port: u16,
+}
+
+/// A rendered config file and the content type to serve it with.
+#[derive(Debug, Clone)]
+pub struct ProvisioningResponse {
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+/// Normalises a MAC address to twelve lowercase hex digits.
+pub fn normalize_mac(value: &str) -> Result<String, AppError> {
+    let mac: String = value
+        .chars()
+        .filter(|c| *c != ':' && *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if mac.len() != 12 || !mac.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::Validation(format!("Invalid MAC address '{}'", value)));
+    }
+    Ok(mac)
+}
+
+/// Recognises the per-device file names Yealink, Polycom and Grandstream
+/// phones request. Shared files such as `y000000000028.cfg` are not served.
+pub fn parse_provisioning_filename(filename: &str) -> Option<(String, ProvisioningFile)> {
+    let (mac, file) = if let Some(mac) = filename
+        .strip_prefix("cfg")
+        .and_then(|rest| rest.strip_suffix(".xml"))
+    {
+        (mac, ProvisioningFile::GrandstreamXml)
+    } else if let Some(mac) = filename.strip_suffix("-sip.cfg") {
+        (mac, ProvisioningFile::PolycomAccount)
+    } else if let Some(mac) = filename.strip_suffix(".cfg") {
+        (mac, ProvisioningFile::MacConfig)
+    } else {
+        return None;
+    };
+
+    if mac.len() != 12 {
+        return None;
+    }
+    normalize_mac(mac).ok().map(|mac| (mac, file))
+}
+
+/// Reads the username and password of an HTTP Basic `Authorization` header.
+pub fn parse_basic_auth(header: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = header.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("Basic") {
+        return None;
+    }
+    let decoded = STANDARD.decode(encoded.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+fn vendor_serves(vendor: PhoneVendor, file: ProvisioningFile) -> bool {
+    matches!(
+        (vendor, file),
+        (PhoneVendor::Yealink, ProvisioningFile::MacConfig)
+            | (PhoneVendor::Polycom, ProvisioningFile::MacConfig)
+            | (PhoneVendor::Polycom, ProvisioningFile::PolycomAccount)
+            | (PhoneVendor::Grandstream, ProvisioningFile::GrandstreamXml)
+    )
+}
+
+struct SipAccount<'a> {
+    display_name: &'a str,
+    user_name: &'a str,
+    auth_name: &'a str,
+    password: &'a str,
+}
+
+/// Uses the same credentials the generated pjsip.conf expects.
+fn sip_account(extension: &PbxExtension) -> SipAccount<'_> {
+    let config_str = |key: &str| extension.config_data.get(key).and_then(|value| value.as_str());
+    SipAccount {
+        display_name: &extension.name,
+        user_name: &extension.extension_number,
+        auth_name: config_str("auth_username").unwrap_or(&extension.extension_number),
+        password: config_str("secret").unwrap_or(""),
+    }
+}
+
+/// Values in the Yealink key = value format end at the line break.
+fn single_line(value: &str) -> String {
+    value.chars().filter(|c| *c != '\r' && *c != '\n').collect()
+}
+
+pub fn escape_xml(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn render_yealink_config(extension: &PbxExtension, server: &SipServer) -> String {
+    let account = sip_account(extension);
+    let mut out = String::from("#!version:1.0.0.1\n");
+    let _ = writeln!(out, "## Generated by OrionTel. Manual changes will be overwritten.");
+    let _ = writeln!(out, "account.1.enable = 1");
+    let _ = writeln!(out, "account.1.label = {}", single_line(account.display_name));
+    let _ = writeln!(out, "account.1.display_name = {}", single_line(account.display_name));
+    let _ = writeln!(out, "account.1.auth_name = {}", single_line(account.auth_name));
+    let _ = writeln!(out, "account.1.user_name = {}", single_line(account.user_name));
+    let _ = writeln!(out, "account.1.password = {}", single_line(account.password));
+    let _ = writeln!(out, "account.1.sip_server.1.address = {}", single_line(&server.host));
+    let _ = writeln!(out, "account.1.sip_server.1.port = {}", server.port);
+    out
+}
+
+/// The master config only points the phone at its account file.
+pub fn render_polycom_master_config(mac: &str) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" standalone=\"yes\"?>\n");
+    let _ = writeln!(
+        out,
+        "<APPLICATION APP_FILE_PATH=\"sip.ld\" CONFIG_FILES=\"{}-sip.cfg\" MISC_FILES=\"\" \
+         LOG_FILE_DIRECTORY=\"\" OVERRIDES_DIRECTORY=\"\" CONTACTS_DIRECTORY=\"\"/>",
+        escape_xml(mac)
+    );
+    out
+}
+
+pub fn render_polycom_account_config(extension: &PbxExtension, server: &SipServer) -> String {
+    let account = sip_account(extension);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    let _ = writeln!(out, "<!-- Generated by OrionTel. Manual changes will be overwritten. -->");
+    let _ = writeln!(out, "<polycomConfig>");
+    let _ = writeln!(out, "  <reg");
+    let _ = writeln!(out, "    reg.1.displayName=\"{}\"", escape_xml(account.display_name));
+    let _ = writeln!(out, "    reg.1.label=\"{}\"", escape_xml(account.display_name));
+    let _ = writeln!(out, "    reg.1.address=\"{}\"", escape_xml(account.user_name));
+    let _ = writeln!(out, "    reg.1.auth.userId=\"{}\"", escape_xml(account.auth_name));
+    let _ = writeln!(out, "    reg.1.auth.password=\"{}\"", escape_xml(account.password));
+    let _ = writeln!(out, "    reg.1.server.1.address=\"{}\"", escape_xml(&server.host));
+    let _ = writeln!(out, "    reg.1.server.1.port=\"{}\"", server.port);
+    let _ = writeln!(out, "  />");
+    let _ = writeln!(out, "</polycomConfig>");
+    out
+}
+
+/// Grandstream addresses settings by P-value number.
+pub fn render_grandstream_config(mac: &str, extension: &PbxExtension, server: &SipServer) -> String {
+    let account = sip_account(extension);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(out, "<!-- Generated by OrionTel. Manual changes will be overwritten. -->");
+    let _ = writeln!(out, "<gs_provision version=\"1\">");
+    let _ = writeln!(out, "  <mac>{}</mac>", escape_xml(mac));
+    let _ = writeln!(out, "  <config version=\"1\">");
+    // Account active, account name, SIP server, user ID, auth ID, password, display name
+    let _ = writeln!(out, "    <P271>1</P271>");
+    let _ = writeln!(out, "    <P270>{}</P270>", escape_xml(account.display_name));
+    let _ = writeln!(out, "    <P47>{}:{}</P47>", escape_xml(&server.host), server.port);
+    let _ = writeln!(out, "    <P35>{}</P35>", escape_xml(account.user_name));
+    let _ = writeln!(out, "    <P36>{}</P36>", escape_xml(account.auth_name));
+    let _ = writeln!(out, "    <P34>{}</P34>", escape_xml(account.password));
+    let _ = writeln!(out, "    <P3>{}</P3>", escape_xml(account.display_name));
+    let _ = writeln!(out, "  </config>");
+    let _ = writeln!(out, "</gs_provision>");
+    out
+}
+
+pub fn render_provisioning_file(
+    device: &PhoneDevice,
+    file: ProvisioningFile,
+    extension: &PbxExtension,
+    server: &SipServer,
+) -> Option<ProvisioningResponse> {
+    if !vendor_serves(device.vendor, file) {
+        return None;
+    }
+    let response = match (device.vendor, file) {
+        (PhoneVendor::Yealink, _) => ProvisioningResponse {
+            content_type: "text/plain; charset=utf-8",
+            body: render_yealink_config(extension, server),
+        },
+        (PhoneVendor::Polycom, ProvisioningFile::MacConfig) => ProvisioningResponse {
+            content_type: "application/xml",
+            body: render_polycom_master_config(&device.mac_address),
+        },
+        (PhoneVendor::Polycom, _) => ProvisioningResponse {
+            content_type: "application/xml",
+            body: render_polycom_account_config(extension, server),
+        },
+        (PhoneVendor::Grandstream, _) => ProvisioningResponse {
+            content_type: "application/xml",
+            body: render_grandstream_config(&device.mac_address, extension, server),
+        },
+    };
+    Some(response)
+}
+
+pub struct ProvisioningService {
+    pool: PgPool,
+    sip_server: Option<SipServer>,
+}
+
+impl ProvisioningService {
+    pub fn new(pool: PgPool) -> Self {
+        let sip_server = std::env::var("PROVISIONING_SIP_SERVER").ok().map(|host| SipServer {
+            host,
+            port: std::env::var("PROVISIONING_SIP_PORT")
+                .ok()
+                .map(|port| port.parse().expect("PROVISIONING_SIP_PORT must be a number"))
+                .unwrap_or(DEFAULT_SIP_PORT),
+        });
+        Self { pool, sip_server }
+    }
+
+    /// Only SIP extensions can be provisioned onto a phone.
+    async fn ensure_sip_extension(&self, extension_id: Uuid) -> Result<PbxExtension, AppError> {
+        let extension = PbxService::new(self.pool.clone()).get_extension(extension_id).await?;
+        if extension.extension_type != ExtensionType::Sip {
+            return Err(AppError::Validation("Only SIP extensions can be provisioned".into()));
+        }
+        Ok(extension)
+    }
+
+    fn hash_password(password: &str) -> Result<String, AppError> {
+        hash(password.as_bytes(), DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Password hashing error: {}", e)))
+    }
+
+    pub async fn create_device(&self, request: CreatePhoneDeviceRequest) -> Result<PhoneDevice, AppError> {
+        let mac_address = normalize_mac(&request.mac_address)?;
+        self.ensure_sip_extension(request.extension_id).await?;
+        let password_hash = Self::hash_password(&request.password)?;
+
+        let existing = sqlx::query!("SELECT id FROM phone_devices WHERE mac_address = $1", mac_address)
+            .fetch_optional(&self.pool)
+            .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation(format!("Device {} is already registered", mac_address)));
+        }
+
+        let device = sqlx::query_as!(
+            PhoneDevice,
+            r#"
+            INSERT INTO phone_devices (mac_address, extension_id, vendor, model, password_hash)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, mac_address, extension_id, vendor as "vendor: PhoneVendor", model, password_hash,
+                      last_fetched_at, last_fetch_address, last_fetch_user_agent, created_at, updated_at
+            "#,
+            mac_address,
+            request.extension_id,
+            request.vendor as PhoneVendor,
+            request.model,
+            password_hash,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(device)
+    }
+
+    pub async fn get_device(&self, id: Uuid) -> Result<PhoneDevice, AppError> {
+        let device = sqlx::query_as!(
+            PhoneDevice,
+            r#"
+            SELECT id, mac_address, extension_id, vendor as "vendor: PhoneVendor", model, password_hash,
+                   last_fetched_at, last_fetch_address, last_fetch_user_agent, created_at, updated_at
+            FROM phone_devices
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Device not found".into()))?;
+
+        Ok(device)
+    }
+
+    async fn find_device_by_mac(&self, mac_address: &str) -> Result<Option<PhoneDevice>, AppError> {
+        let device = sqlx::query_as!(
+            PhoneDevice,
+            r#"
+            SELECT id, mac_address, extension_id, vendor as "vendor: PhoneVendor", model, password_hash,
+                   last_fetched_at, last_fetch_address, last_fetch_user_agent, created_at, updated_at
+            FROM phone_devices
+            WHERE mac_address = $1
+            "#,
+            mac_address
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(device)
+    }
+
+    pub async fn list_devices(&self) -> Result<Vec<PhoneDevice>, AppError> {
+        let devices = sqlx::query_as!(
+            PhoneDevice,
+            r#"
+            SELECT id, mac_address, extension_id, vendor as "vendor: PhoneVendor", model, password_hash,
+                   last_fetched_at, last_fetch_address, last_fetch_user_agent, created_at, updated_at
+            FROM phone_devices
+            ORDER BY mac_address
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(devices)
+    }
+
+    pub async fn update_device(&self, id: Uuid, request: UpdatePhoneDeviceRequest) -> Result<PhoneDevice, AppError> {
+        if let Some(extension_id) = request.extension_id {
+            self.ensure_sip_extension(extension_id).await?;
+        }
+        let password_hash = request.password.as_deref().map(Self::hash_password).transpose()?;
+
+        let device = sqlx::query_as!(
+            PhoneDevice,
+            r#"
+            UPDATE phone_devices
+            SET
+                extension_id = COALESCE($1, extension_id),
+                vendor = COALESCE($2, vendor),
+                model = COALESCE($3, model),
+                password_hash = COALESCE($4, password_hash),
+                updated_at = $5
+            WHERE id = $6
+            RETURNING id, mac_address, extension_id, vendor as "vendor: PhoneVendor", model, password_hash,
+                      last_fetched_at, last_fetch_address, last_fetch_user_agent, created_at, updated_at
+            "#,
+            request.extension_id,
+            request.vendor as Option<PhoneVendor>,
+            request.model,
+            password_hash,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Device not found".into()))?;
+
+        Ok(device)
+    }
+
+    pub async fn delete_device(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM phone_devices WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Device not found".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Serves a config file to a phone. The phone must log in as the device
+    /// the file belongs to, so one phone cannot read another's credentials.
+    /// Every successful fetch is recorded on the device.
+    pub async fn fetch_config(
+        &self,
+        filename: &str,
+        credentials: Option<(String, String)>,
+        address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<ProvisioningResponse, AppError> {
+        let (mac_address, file) = parse_provisioning_filename(filename)
+            .ok_or_else(|| AppError::NotFound("Unknown provisioning file".into()))?;
+
+        let invalid = || AppError::Auth("Invalid device credentials".into());
+        let (username, password) = credentials.ok_or_else(invalid)?;
+        if normalize_mac(&username).ok().as_deref() != Some(mac_address.as_str()) {
+            return Err(invalid());
+        }
+        let device = self.find_device_by_mac(&mac_address).await?.ok_or_else(invalid)?;
+        let valid = verify(password.as_bytes(), &device.password_hash)
+            .map_err(|e| AppError::Internal(format!("Password verification error: {}", e)))?;
+        if !valid {
+            tracing::warn!("Rejected provisioning request for {} from {:?}", mac_address, address);
+            return Err(invalid());
+        }
+
+        let server = self
+            .sip_server
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("PROVISIONING_SIP_SERVER is not set".into()))?;
+        let extension = PbxService::new(self.pool.clone()).get_extension(device.extension_id).await?;
+        let response = render_provisioning_file(&device, file, &extension, server)
+            .ok_or_else(|| AppError::NotFound("Unknown provisioning file".into()))?;
+
+        // Both come from request headers; keep them within the column sizes
+        let address = address.map(|address| address.chars().take(100).collect::<String>());
+        let user_agent = user_agent.map(|user_agent| user_agent.chars().take(255).collect::<String>());
+        sqlx::query!(
+            r#"
+            UPDATE phone_devices
+            SET last_fetched_at = $1, last_fetch_address = $2, last_fetch_user_agent = $3
+            WHERE id = $4
+            "#,
+            Utc::now(),
+            address,
+            user_agent,
+            device.id
+        )
+        .execute(&self.pool)
+        .await?;
+        tracing::info!(
+            "Device {} fetched {} for extension {}",
+            device.mac_address,
+            filename,
+            extension.extension_number
+        );
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,418 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig},
+    error::AppError,
+    models::{
+        queue::{
+            CallQueue, CreateQueueRequest, QueueMember, QueueMemberRequest, QueueResponse,
+            QueueStrategy, UpdateQueueRequest,
+        },
+        routing::RouteDestinationType,
+    },
+    services::routing::RoutingService,
+};
+
+const DEFAULT_RING_TIMEOUT: i32 = 15;
+const DEFAULT_MAX_WAIT_TIME: i32 = 300;
+
+/// Queue members are dialed through the internal dialplan so every extension
+/// type can be a member.
+pub fn member_interface(extension_number: &str) -> String {
+    format!("Local/{}@from-internal/n", extension_number)
+}
+
+/// Queue names end up as Asterisk section names, so keep them to a safe charset.
+fn validate_queue_name(name: &str) -> Result<(), AppError> {
+    if name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(AppError::Validation(
+            "Queue name may only contain letters, digits, '-' and '_'".into(),
+        ))
+    }
+}
+
+pub struct QueueService {
+    pool: PgPool,
+}
+
+impl QueueService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// An overflow destination must exist, and needs a type to go with it. A
+    /// queue overflowing into itself would hold callers forever.
+    async fn validate_overflow(
+        &self,
+        queue_name: &str,
+        destination_type: Option<&RouteDestinationType>,
+        destination: Option<&str>,
+    ) -> Result<(), AppError> {
+        match destination_type {
+            Some(RouteDestinationType::Queue) if destination == Some(queue_name) => Err(AppError::Validation(
+                "A queue cannot overflow to itself".into(),
+            )),
+            Some(destination_type) => {
+                RoutingService::new(self.pool.clone())
+                    .validate_destination(destination_type, destination.unwrap_or(""))
+                    .await
+            }
+            None if destination.is_some() => Err(AppError::Validation(
+                "overflow_destination requires overflow_destination_type".into(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn create_queue(&self, request: CreateQueueRequest) -> Result<QueueResponse, AppError> {
+        validate_queue_name(&request.name)?;
+        self.validate_overflow(
+            &request.name,
+            request.overflow_destination_type.as_ref(),
+            request.overflow_destination.as_deref(),
+        )
+        .await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let existing = sqlx::query!("SELECT id FROM call_queues WHERE name = $1", request.name)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation("Queue already exists".into()));
+        }
+
+        let queue = sqlx::query_as!(
+            CallQueue,
+            r#"
+            INSERT INTO call_queues (
+                name, strategy, ring_timeout, max_wait_time,
+                overflow_destination_type, overflow_destination
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, strategy as "strategy: QueueStrategy", ring_timeout,
+                      max_wait_time,
+                      overflow_destination_type as "overflow_destination_type: RouteDestinationType",
+                      overflow_destination, created_at, updated_at
+            "#,
+            request.name,
+            request.strategy as QueueStrategy,
+            request.ring_timeout.unwrap_or(DEFAULT_RING_TIMEOUT),
+            request.max_wait_time.unwrap_or(DEFAULT_MAX_WAIT_TIME),
+            request.overflow_destination_type as Option<RouteDestinationType>,
+            request.overflow_destination,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::replace_members(&mut tx, queue.id, &request.members).await?;
+        tx.commit().await?;
+
+        self.get_queue(queue.id).await
+    }
+
+    async fn replace_members(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        queue_id: Uuid,
+        members: &[QueueMemberRequest],
+    ) -> Result<(), AppError> {
+        // Keep login/pause state for members that stay in the queue
+        let extension_ids: Vec<Uuid> = members.iter().map(|m| m.extension_id).collect();
+        sqlx::query!(
+            "DELETE FROM queue_members WHERE queue_id = $1 AND NOT (extension_id = ANY($2))",
+            queue_id,
+            &extension_ids
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        for member in members {
+            let exists = sqlx::query!("SELECT id FROM pbx_extensions WHERE id = $1", member.extension_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+            if exists.is_none() {
+                return Err(AppError::Validation(format!(
+                    "Extension {} does not exist",
+                    member.extension_id
+                )));
+            }
+
+            sqlx::query!(
+                r#"
+                INSERT INTO queue_members (queue_id, extension_id, penalty)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (queue_id, extension_id) DO UPDATE
+                SET penalty = EXCLUDED.penalty
+                "#,
+                queue_id,
+                member.extension_id,
+                member.penalty.unwrap_or(0),
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_queue(&self, id: Uuid) -> Result<QueueResponse, AppError> {
+        let queue = sqlx::query_as!(
+            CallQueue,
+            r#"
+            SELECT id, name, strategy as "strategy: QueueStrategy", ring_timeout,
+                   max_wait_time,
+                   overflow_destination_type as "overflow_destination_type: RouteDestinationType",
+                   overflow_destination, created_at, updated_at
+            FROM call_queues
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Queue not found".into()))?;
+
+        let members = self.list_members(queue.id).await?;
+        Ok(QueueResponse { queue, members })
+    }
+
+    pub async fn list_queues(&self) -> Result<Vec<QueueResponse>, AppError> {
+        let queues = sqlx::query_as!(
+            CallQueue,
+            r#"
+            SELECT id, name, strategy as "strategy: QueueStrategy", ring_timeout,
+                   max_wait_time,
+                   overflow_destination_type as "overflow_destination_type: RouteDestinationType",
+                   overflow_destination, created_at, updated_at
+            FROM call_queues
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut responses = Vec::new();
+        for queue in queues {
+            let members = self.list_members(queue.id).await?;
+            responses.push(QueueResponse { queue, members });
+        }
+
+        Ok(responses)
+    }
+
+    async fn list_members(&self, queue_id: Uuid) -> Result<Vec<QueueMember>, AppError> {
+        let members = sqlx::query_as!(
+            QueueMember,
+            r#"
+            SELECT m.id, m.queue_id, m.extension_id, e.extension_number,
+                   e.name as extension_name, m.penalty, m.logged_in, m.paused,
+                   m.pause_reason, m.updated_at
+            FROM queue_members m
+            JOIN pbx_extensions e ON e.id = m.extension_id
+            WHERE m.queue_id = $1
+            ORDER BY m.penalty, e.extension_number
+            "#,
+            queue_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+
+    pub async fn update_queue(
+        &self,
+        id: Uuid,
+        request: UpdateQueueRequest,
+    ) -> Result<QueueResponse, AppError> {
+        let clear_overflow = request.clear_overflow.unwrap_or(false);
+        let changes_overflow = request.overflow_destination_type.is_some() || request.overflow_destination.is_some();
+        if clear_overflow && changes_overflow {
+            return Err(AppError::Validation(
+                "clear_overflow cannot be combined with a new overflow destination".into(),
+            ));
+        }
+        // A new type is checked against the stored destination and vice versa
+        if changes_overflow {
+            let current = self.get_queue(id).await?.queue;
+            self.validate_overflow(
+                &current.name,
+                request
+                    .overflow_destination_type
+                    .as_ref()
+                    .or(current.overflow_destination_type.as_ref()),
+                request
+                    .overflow_destination
+                    .as_deref()
+                    .or(current.overflow_destination.as_deref()),
+            )
+            .await?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE call_queues
+            SET strategy = COALESCE($1, strategy),
+                ring_timeout = COALESCE($2, ring_timeout),
+                max_wait_time = COALESCE($3, max_wait_time),
+                overflow_destination_type = CASE WHEN $6 THEN NULL
+                                                 ELSE COALESCE($4, overflow_destination_type) END,
+                overflow_destination = CASE WHEN $6 THEN NULL ELSE COALESCE($5, overflow_destination) END,
+                updated_at = $7
+            WHERE id = $8
+            "#,
+            request.strategy as Option<QueueStrategy>,
+            request.ring_timeout,
+            request.max_wait_time,
+            request.overflow_destination_type as Option<RouteDestinationType>,
+            request.overflow_destination,
+            clear_overflow,
+            Utc::now(),
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Queue not found".into()));
+        }
+
+        if let Some(members) = &request.members {
+            Self::replace_members(&mut tx, id, members).await?;
+        }
+        tx.commit().await?;
+
+        self.get_queue(id).await
+    }
+
+    pub async fn delete_queue(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM call_queues WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Queue not found".into()));
+        }
+
+        Ok(())
+    }
+
+    // Agent state
+    async fn get_member(&self, queue_id: Uuid, extension_id: Uuid) -> Result<(String, QueueMember), AppError> {
+        let queue = self.get_queue(queue_id).await?;
+        let member = queue
+            .members
+            .into_iter()
+            .find(|member| member.extension_id == extension_id)
+            .ok_or_else(|| AppError::NotFound("Extension is not a member of this queue".into()))?;
+
+        Ok((queue.queue.name, member))
+    }
+
+    pub async fn set_agent_logged_in(
+        &self,
+        queue_id: Uuid,
+        extension_id: Uuid,
+        logged_in: bool,
+    ) -> Result<QueueMember, AppError> {
+        let (queue_name, member) = self.get_member(queue_id, extension_id).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE queue_members
+            SET logged_in = $1,
+                paused = CASE WHEN $1 THEN paused ELSE FALSE END,
+                pause_reason = CASE WHEN $1 THEN pause_reason ELSE NULL END,
+                updated_at = NOW()
+            WHERE id = $2
+            "#,
+            logged_in,
+            member.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let interface = member_interface(&member.extension_number);
+        let penalty = member.penalty.to_string();
+        if logged_in {
+            self.push_to_asterisk(
+                "QueueAdd",
+                &[
+                    ("Queue", queue_name.as_str()),
+                    ("Interface", interface.as_str()),
+                    ("Penalty", penalty.as_str()),
+                    ("MemberName", member.extension_name.as_str()),
+                ],
+            )
+            .await;
+        } else {
+            self.push_to_asterisk(
+                "QueueRemove",
+                &[("Queue", queue_name.as_str()), ("Interface", interface.as_str())],
+            )
+            .await;
+        }
+
+        self.get_member(queue_id, extension_id).await.map(|(_, member)| member)
+    }
+
+    pub async fn set_agent_paused(
+        &self,
+        queue_id: Uuid,
+        extension_id: Uuid,
+        paused: bool,
+        reason: Option<String>,
+    ) -> Result<QueueMember, AppError> {
+        let (queue_name, member) = self.get_member(queue_id, extension_id).await?;
+        if !member.logged_in {
+            return Err(AppError::Validation("Agent is not logged in to this queue".into()));
+        }
+
+        let reason = if paused { reason } else { None };
+        sqlx::query!(
+            r#"
+            UPDATE queue_members
+            SET paused = $1, pause_reason = $2, updated_at = NOW()
+            WHERE id = $3
+            "#,
+            paused,
+            reason,
+            member.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let interface = member_interface(&member.extension_number);
+        self.push_to_asterisk(
+            "QueuePause",
+            &[
+                ("Queue", queue_name.as_str()),
+                ("Interface", interface.as_str()),
+                ("Paused", if paused { "true" } else { "false" }),
+                ("Reason", reason.as_deref().unwrap_or("")),
+            ],
+        )
+        .await;
+
+        self.get_member(queue_id, extension_id).await.map(|(_, member)| member)
+    }
+
+    /// Applies an agent change to the running Asterisk. The database stays the
+    /// source of truth; the next config apply reconciles anything missed here.
+    async fn push_to_asterisk(&self, action: &str, fields: &[(&str, &str)]) {
+        if let Some(config) = AmiConfig::from_env() {
+            if let Err(e) = AmiClient::run_action(&config, action, fields).await {
+                tracing::warn!("Failed to push {} to Asterisk: {}", action, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    error::AppError,
+    models::recording::{
+        RecordingInfo, RecordingMode, RecordingPolicy, SetRecordingPolicyRequest, WavInfo,
+    },
+};
+
+/// Enough to cover the RIFF header plus any LIST/fact chunks before `data`.
+const HEADER_READ_SIZE: usize = 64 * 1024;
+const RETENTION_BATCH_SIZE: i64 = 1000;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+pub fn codec_name(format_tag: u16) -> &'static str {
+    match format_tag {
+        0x0001 => "pcm",
+        0x0003 => "ieee_float",
+        0x0006 => "alaw",
+        0x0007 => "ulaw",
+        0x0011 => "ima_adpcm",
+        0x0031 => "gsm",
+        0x0055 => "mp3",
+        0x0064 => "g726",
+        0x0065 => "g722",
+        _ => "unknown",
+    }
+}
+
+/// Parses the start of a WAV file. `file_size` is used when the recorder did
+/// not fill in the data chunk size, which happens for files still being written.
+pub fn parse_wav_header(data: &[u8], file_size: u64) -> Result<WavInfo, AppError> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(AppError::Validation("Not a WAV file".into()));
+    }
+
+    let mut format: Option<(u16, u16, u32, u32, u16)> = None;
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let chunk_id = &data[offset..offset + 4];
+        let chunk_size = read_u32(data, offset + 4);
+        let body = offset + 8;
+
+        if chunk_id == b"fmt " {
+            if chunk_size < 16 || body + 16 > data.len() {
+                return Err(AppError::Validation("Truncated WAV fmt chunk".into()));
+            }
+            let mut format_tag = read_u16(data, body);
+            // WAVE_FORMAT_EXTENSIBLE keeps the real format in the sub-format GUID
+            if format_tag == 0xFFFE && chunk_size >= 40 && body + 26 <= data.len() {
+                format_tag = read_u16(data, body + 24);
+            }
+            format = Some((
+                format_tag,
+                read_u16(data, body + 2),
+                read_u32(data, body + 4),
+                read_u32(data, body + 8),
+                read_u16(data, body + 14),
+            ));
+        } else if chunk_id == b"data" {
+            let (format_tag, channels, sample_rate, byte_rate, bits_per_sample) = format
+                .ok_or_else(|| AppError::Validation("WAV data chunk appears before fmt chunk".into()))?;
+
+            let available = file_size.saturating_sub(body as u64);
+            let data_size = if chunk_size == 0 || chunk_size == u32::MAX || u64::from(chunk_size) > available {
+                available
+            } else {
+                u64::from(chunk_size)
+            };
+            let duration_seconds = if byte_rate > 0 {
+                data_size as f64 / f64::from(byte_rate)
+            } else {
+                0.0
+            };
+
+            return Ok(WavInfo {
+                format_tag,
+                codec: codec_name(format_tag).to_string(),
+                channels,
+                sample_rate,
+                bits_per_sample,
+                byte_rate,
+                data_size,
+                duration_seconds,
+            });
+        }
+
+        // Chunks are padded to an even size
+        offset = body + chunk_size as usize + (chunk_size as usize & 1);
+    }
+
+    Err(AppError::Validation("WAV file has no data chunk".into()))
+}
+
+/// Inclusive byte range of a single-range `Range` request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RangeError {
+    /// Unparseable or multi-range header; the whole file is served instead.
+    Invalid,
+    /// Syntactically valid but outside the file; answered with 416.
+    Unsatisfiable,
+}
+
+pub fn parse_range(header: &str, file_size: u64) -> Result<ByteRange, RangeError> {
+    let spec = header
+        .trim()
+        .strip_prefix("bytes=")
+        .ok_or(RangeError::Invalid)?;
+    if spec.contains(',') {
+        return Err(RangeError::Invalid);
+    }
+    let (start, end) = spec.split_once('-').ok_or(RangeError::Invalid)?;
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // Suffix range: the last N bytes
+        let suffix: u64 = end.parse().map_err(|_| RangeError::Invalid)?;
+        if suffix == 0 || file_size == 0 {
+            return Err(RangeError::Unsatisfiable);
+        }
+        let suffix = suffix.min(file_size);
+        return Ok(ByteRange {
+            start: file_size - suffix,
+            end: file_size - 1,
+        });
+    }
+
+    let start: u64 = start.parse().map_err(|_| RangeError::Invalid)?;
+    let end: u64 = if end.is_empty() {
+        file_size.saturating_sub(1)
+    } else {
+        end.parse().map_err(|_| RangeError::Invalid)?
+    };
+    if end < start {
+        return Err(RangeError::Invalid);
+    }
+    if start >= file_size {
+        return Err(RangeError::Unsatisfiable);
+    }
+
+    Ok(ByteRange {
+        start,
+        end: end.min(file_size - 1),
+    })
+}
+
+pub fn content_type_for(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("wav") => "audio/wav",
+        Some("mp3") => "audio/mpeg",
+        Some("gsm") => "audio/gsm",
+        Some("ogg") => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct RecordingService {
+    pool: PgPool,
+    storage_dir: PathBuf,
+    default_retention_days: Option<i32>,
+}
+
+impl RecordingService {
+    pub fn new(pool: PgPool) -> Self {
+        let storage_dir = std::env::var("RECORDINGS_PATH")
+            .unwrap_or_else(|_| "/var/spool/asterisk/monitor".into());
+        let default_retention_days = std::env::var("RECORDING_RETENTION_DAYS")
+            .ok()
+            .map(|days| days.parse::<i32>().expect("RECORDING_RETENTION_DAYS must be a number"));
+
+        Self {
+            pool,
+            storage_dir: PathBuf::from(storage_dir),
+            default_retention_days,
+        }
+    }
+
+    /// Resolves a stored `recording_path` and refuses anything outside the
+    /// recordings directory, since call records can be edited through the API.
+    pub async fn resolve_path(&self, recording_path: &str) -> Result<PathBuf, AppError> {
+        let path = self.storage_dir.join(recording_path);
+        let path = tokio::fs::canonicalize(&path)
+            .await
+            .map_err(|_| AppError::NotFound("Recording file not found".into()))?;
+        let storage_dir = tokio::fs::canonicalize(&self.storage_dir)
+            .await
+            .map_err(|e| AppError::Internal(format!("Recordings directory unavailable: {}", e)))?;
+
+        if !path.starts_with(&storage_dir) {
+            return Err(AppError::Auth("Recording is outside the recordings directory".into()));
+        }
+
+        Ok(path)
+    }
+
+    /// Whether the user owns an extension on either end of the call.
+    pub async fn is_call_participant(&self, call_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let participant = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM call_records c
+                JOIN pbx_extensions e ON e.extension_number IN (c.caller_id, c.recipient_id)
+                WHERE c.id = $1 AND e.owner_id = $2
+            ) as "exists!"
+            "#,
+            call_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(participant)
+    }
+
+    pub async fn recording_path_for_call(&self, call_id: Uuid) -> Result<PathBuf, AppError> {
+        let call = sqlx::query!("SELECT recording_path FROM call_records WHERE id = $1", call_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Call record not found".into()))?;
+        let recording_path = call
+            .recording_path
+            .ok_or_else(|| AppError::NotFound("Call has no recording".into()))?;
+
+        self.resolve_path(&recording_path).await
+    }
+
+    pub async fn recording_info(&self, call_id: Uuid) -> Result<RecordingInfo, AppError> {
+        let path = self.recording_path_for_call(call_id).await?;
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|_| AppError::NotFound("Recording file not found".into()))?;
+        let size_bytes = file
+            .metadata()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?
+            .len();
+
+        let mut header = Vec::with_capacity(HEADER_READ_SIZE);
+        (&mut file)
+            .take(HEADER_READ_SIZE as u64)
+            .read_to_end(&mut header)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        Ok(RecordingInfo {
+            call_id,
+            content_type: content_type_for(&path).to_string(),
+            size_bytes,
+            wav: parse_wav_header(&header, size_bytes).ok(),
+        })
+    }
+
+    /// Links the MixMonitor file for a finished call, named after the call's
+    /// Asterisk linkedid, to its call record.
+    pub async fn attach_recording(&self, uniqueid: &str) -> Result<bool, AppError> {
+        if !uniqueid
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+        {
+            return Ok(false);
+        }
+
+        let file_name = format!("{}.wav", uniqueid);
+        if tokio::fs::metadata(self.storage_dir.join(&file_name)).await.is_err() {
+            return Ok(false);
+        }
+
+        let result = sqlx::query!(
+            "UPDATE call_records SET recording_path = $1 WHERE uniqueid = $2 AND recording_path IS NULL",
+            file_name,
+            uniqueid
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Policies
+    pub async fn list_policies(&self) -> Result<Vec<RecordingPolicy>, AppError> {
+        let policies = sqlx::query_as!(
+            RecordingPolicy,
+            r#"
+            SELECT p.id, p.extension_id, e.extension_number, p.mode as "mode: RecordingMode",
+                   p.retention_days, p.created_at, p.updated_at
+            FROM recording_policies p
+            JOIN pbx_extensions e ON e.id = p.extension_id
+            ORDER BY e.extension_number
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(policies)
+    }
+
+    pub async fn get_policy(&self, extension_id: Uuid) -> Result<RecordingPolicy, AppError> {
+        let policy = sqlx::query_as!(
+            RecordingPolicy,
+            r#"
+            SELECT p.id, p.extension_id, e.extension_number, p.mode as "mode: RecordingMode",
+                   p.retention_days, p.created_at, p.updated_at
+            FROM recording_policies p
+            JOIN pbx_extensions e ON e.id = p.extension_id
+            WHERE p.extension_id = $1
+            "#,
+            extension_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Recording policy not found".into()))?;
+
+        Ok(policy)
+    }
+
+    pub async fn set_policy(
+        &self,
+        extension_id: Uuid,
+        request: SetRecordingPolicyRequest,
+    ) -> Result<RecordingPolicy, AppError> {
+        let extension = sqlx::query!("SELECT id FROM pbx_extensions WHERE id = $1", extension_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        if extension.is_none() {
+            return Err(AppError::NotFound("Extension not found".into()));
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO recording_policies (extension_id, mode, retention_days)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (extension_id) DO UPDATE
+            SET mode = EXCLUDED.mode,
+                retention_days = EXCLUDED.retention_days,
+                updated_at = $4
+            "#,
+            extension_id,
+            request.mode as RecordingMode,
+            request.retention_days,
+            Utc::now(),
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_policy(extension_id).await
+    }
+
+    pub async fn delete_policy(&self, extension_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM recording_policies WHERE extension_id = $1",
+            extension_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Recording policy not found".into()));
+        }
+
+        Ok(())
+    }
+
+    // Retention
+    /// Deletes recordings past their retention age and clears `recording_path`.
+    /// A call involving several extensions keeps the longest retention.
+    pub async fn apply_retention(&self) -> Result<u64, AppError> {
+        let expired = sqlx::query!(
+            r#"
+            SELECT c.id, c.recording_path as "recording_path!"
+            FROM call_records c
+            LEFT JOIN LATERAL (
+                SELECT MAX(p.retention_days) AS days
+                FROM recording_policies p
+                JOIN pbx_extensions e ON e.id = p.extension_id
+                WHERE e.extension_number IN (c.caller_id, c.recipient_id)
+            ) policy ON TRUE
+            WHERE c.recording_path IS NOT NULL
+              AND c.start_time < NOW() - make_interval(days => COALESCE(policy.days, $1))
+            ORDER BY c.start_time
+            LIMIT $2
+            "#,
+            self.default_retention_days,
+            RETENTION_BATCH_SIZE
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut removed = 0;
+        for recording in expired {
+            match self.resolve_path(&recording.recording_path).await {
+                Ok(path) => {
+                    if let Err(e) = tokio::fs::remove_file(&path).await {
+                        tracing::warn!("Failed to delete recording {}: {}", path.display(), e);
+                        continue;
+                    }
+                }
+                // Already gone; just clear the reference
+                Err(AppError::NotFound(_)) => {}
+                // Not ours to delete, but the reference still expires
+                Err(AppError::Auth(message)) => {
+                    tracing::warn!(
+                        "Not deleting recording {} of call {}: {}",
+                        recording.recording_path,
+                        recording.id,
+                        message
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to resolve recording of call {}: {}", recording.id, e);
+                    continue;
+                }
+            }
+
+            sqlx::query!(
+                "UPDATE call_records SET recording_path = NULL WHERE id = $1",
+                recording.id
+            )
+            .execute(&self.pool)
+            .await?;
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    pub async fn run_retention_job(self) {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.apply_retention().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Removed {} expired recordings", count),
+                Err(e) => tracing::error!("Recording retention failed: {}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    error::AppError,
+    models::{
+        ring_group::{
+            CreateRingGroupRequest, RingGroup, RingGroupMember, RingGroupMemberRequest,
+            RingGroupResponse, RingGroupStrategy, UpdateRingGroupRequest,
+        },
+        routing::RouteDestinationType,
+    },
+    services::routing::RoutingService,
+};
+
+const DEFAULT_TIMEOUT: i32 = 30;
+const DEFAULT_MEMBER_TIMEOUT: i32 = 15;
+
+/// One member's turn in a sequential group: wait, then ring for `ring_time`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequentialStep {
+    pub extension_number: String,
+    pub wait: i32,
+    pub ring_time: i32,
+}
+
+/// Lays the members out one after another and cuts the schedule off at the
+/// group timeout, so failover happens on time.
+pub fn sequential_schedule(group: &RingGroup, members: &[RingGroupMember]) -> Vec<SequentialStep> {
+    let mut steps = Vec::new();
+    let mut elapsed = 0;
+
+    for member in members {
+        elapsed += member.delay;
+        if elapsed >= group.timeout {
+            break;
+        }
+        let ring_time = group.member_timeout.min(group.timeout - elapsed);
+        steps.push(SequentialStep {
+            extension_number: member.extension_number.clone(),
+            wait: member.delay,
+            ring_time,
+        });
+        elapsed += ring_time;
+    }
+
+    steps
+}
+
+/// Members of a simultaneous group whose delay is still inside the group timeout.
+pub fn simultaneous_members<'a>(group: &RingGroup, members: &'a [RingGroupMember]) -> Vec<&'a RingGroupMember> {
+    members
+        .iter()
+        .filter(|member| member.delay < group.timeout)
+        .collect()
+}
+
+pub struct RingGroupService {
+    pool: PgPool,
+}
+
+impl RingGroupService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Group numbers share the namespace of extension numbers. The database
+    /// enforces this too; checking here gives a readable error.
+    async fn ensure_number_available(&self, number: &str, group_id: Option<Uuid>) -> Result<(), AppError> {
+        if !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::Validation("Group number may only contain digits".into()));
+        }
+
+        let extension = sqlx::query!(
+            "SELECT id FROM pbx_extensions WHERE extension_number = $1",
+            number
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if extension.is_some() {
+            return Err(AppError::Validation("Number is already used by an extension".into()));
+        }
+
+        let group = sqlx::query!("SELECT id FROM ring_groups WHERE group_number = $1", number)
+            .fetch_optional(&self.pool)
+            .await?;
+        if matches!(group, Some(group) if Some(group.id) != group_id) {
+            return Err(AppError::Validation("Number is already used by a ring group".into()));
+        }
+
+        let room = sqlx::query!("SELECT id FROM conference_rooms WHERE room_number = $1", number)
+            .fetch_optional(&self.pool)
+            .await?;
+        if room.is_some() {
+            return Err(AppError::Validation("Number is already used by a conference room".into()));
+        }
+
+        Ok(())
+    }
+
+    async fn validate_failover(
+        &self,
+        destination_type: &Option<RouteDestinationType>,
+        destination: &Option<String>,
+    ) -> Result<(), AppError> {
+        if let Some(destination_type) = destination_type {
+            RoutingService::new(self.pool.clone())
+                .validate_destination(destination_type, destination.as_deref().unwrap_or(""))
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn create_group(&self, request: CreateRingGroupRequest) -> Result<RingGroupResponse, AppError> {
+        self.ensure_number_available(&request.group_number, None).await?;
+        self.validate_failover(&request.failover_destination_type, &request.failover_destination)
+            .await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let group = sqlx::query_as!(
+            RingGroup,
+            r#"
+            INSERT INTO ring_groups (
+                group_number, name, strategy, timeout, member_timeout,
+                failover_destination_type, failover_destination
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, group_number, name, strategy as "strategy: RingGroupStrategy",
+                      timeout, member_timeout,
+                      failover_destination_type as "failover_destination_type: RouteDestinationType",
+                      failover_destination, created_at, updated_at
+            "#,
+            request.group_number,
+            request.name,
+            request.strategy as RingGroupStrategy,
+            request.timeout.unwrap_or(DEFAULT_TIMEOUT),
+            request.member_timeout.unwrap_or(DEFAULT_MEMBER_TIMEOUT),
+            request.failover_destination_type as Option<RouteDestinationType>,
+            request.failover_destination,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::replace_members(&mut tx, group.id, &request.members).await?;
+        tx.commit().await?;
+
+        self.get_group(group.id).await
+    }
+
+    async fn replace_members(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        group_id: Uuid,
+        members: &[RingGroupMemberRequest],
+    ) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM ring_group_members WHERE ring_group_id = $1", group_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for (position, member) in members.iter().enumerate() {
+            let exists = sqlx::query!("SELECT id FROM pbx_extensions WHERE id = $1", member.extension_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+            if exists.is_none() {
+                return Err(AppError::Validation(format!(
+                    "Extension {} does not exist",
+                    member.extension_id
+                )));
+            }
+            if members[..position]
+                .iter()
+                .any(|other| other.extension_id == member.extension_id)
+            {
+                return Err(AppError::Validation(format!(
+                    "Extension {} is listed more than once",
+                    member.extension_id
+                )));
+            }
+
+            sqlx::query!(
+                r#"
+                INSERT INTO ring_group_members (ring_group_id, extension_id, position, delay)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                group_id,
+                member.extension_id,
+                position as i32,
+                member.delay.unwrap_or(0),
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_group(&self, id: Uuid) -> Result<RingGroupResponse, AppError> {
+        let group = sqlx::query_as!(
+            RingGroup,
+            r#"
+            SELECT id, group_number, name, strategy as "strategy: RingGroupStrategy",
+                   timeout, member_timeout,
+                   failover_destination_type as "failover_destination_type: RouteDestinationType",
+                   failover_destination, created_at, updated_at
+            FROM ring_groups
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Ring group not found".into()))?;
+
+        let members = self.list_members(group.id).await?;
+        Ok(RingGroupResponse { group, members })
+    }
+
+    pub async fn list_groups(&self) -> Result<Vec<RingGroupResponse>, AppError> {
+        let groups = sqlx::query_as!(
+            RingGroup,
+            r#"
+            SELECT id, group_number, name, strategy as "strategy: RingGroupStrategy",
+                   timeout, member_timeout,
+                   failover_destination_type as "failover_destination_type: RouteDestinationType",
+                   failover_destination, created_at, updated_at
+            FROM ring_groups
+            ORDER BY group_number
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut responses = Vec::new();
+        for group in groups {
+            let members = self.list_members(group.id).await?;
+            responses.push(RingGroupResponse { group, members });
+        }
+
+        Ok(responses)
+    }
+
+    async fn list_members(&self, group_id: Uuid) -> Result<Vec<RingGroupMember>, AppError> {
+        let members = sqlx::query_as!(
+            RingGroupMember,
+            r#"
+            SELECT m.id, m.ring_group_id, m.extension_id, e.extension_number,
+                   e.name as extension_name, m.position, m.delay
+            FROM ring_group_members m
+            JOIN pbx_extensions e ON e.id = m.extension_id
+            WHERE m.ring_group_id = $1
+            ORDER BY m.position
+            "#,
+            group_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+
+    pub async fn update_group(
+        &self,
+        id: Uuid,
+        request: UpdateRingGroupRequest,
+    ) -> Result<RingGroupResponse, AppError> {
+        if let Some(number) = &request.group_number {
+            self.ensure_number_available(number, Some(id)).await?;
+        }
+        self.validate_failover(&request.failover_destination_type, &request.failover_destination)
+            .await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE ring_groups
+            SET group_number = COALESCE($1, group_number),
+                name = COALESCE($2, name),
+                strategy = COALESCE($3, strategy),
+                timeout = COALESCE($4, timeout),
+                member_timeout = COALESCE($5, member_timeout),
+                failover_destination_type = COALESCE($6, failover_destination_type),
+                failover_destination = COALESCE($7, failover_destination),
+                updated_at = $8
+            WHERE id = $9
+            "#,
+            request.group_number,
+            request.name,
+            request.strategy as Option<RingGroupStrategy>,
+            request.timeout,
+            request.member_timeout,
+            request.failover_destination_type as Option<RouteDestinationType>,
+            request.failover_destination,
+            Utc::now(),
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Ring group not found".into()));
+        }
+
+        if let Some(members) = &request.members {
+            Self::replace_members(&mut tx, id, members).await?;
+        }
+        tx.commit().await?;
+
+        self.get_group(id).await
+    }
+
+    pub async fn delete_group(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM ring_groups WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Ring group not found".into()));
+        }
+
+        Ok(())
+    }
+}
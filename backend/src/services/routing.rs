@@ -0,0 +1,753 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    error::AppError,
+    models::{
+        routing::{
+            CreateInboundRouteRequest, CreateOutboundRouteRequest, InboundRoute, OutboundRoute,
+            RouteDestinationType, RouteDirection, RouteSimulationResult, RuleEvaluation,
+            SimulateRouteRequest, UpdateInboundRouteRequest, UpdateOutboundRouteRequest,
+        },
+        screening::ScreeningAction,
+        trunk::SipTrunk,
+    },
+    services::{pbx::PbxService, trunk::TrunkService},
+};
+
+const DEFAULT_PRIORITY: i32 = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+enum PatternToken {
+    Literal(char),
+    Class(Vec<char>),
+    OneOrMore,
+    ZeroOrMore,
+}
+
+/// An Asterisk-style dial pattern. Patterns starting with `_` use `X` (0-9),
+/// `Z` (1-9), `N` (2-9), `[...]` character classes, `.` (one or more) and `!`
+/// (zero or more); anything else must match the number exactly.
+#[derive(Debug, Clone)]
+pub struct DialPattern {
+    raw: String,
+    tokens: Option<Vec<PatternToken>>,
+}
+
+impl DialPattern {
+    pub fn parse(pattern: &str) -> Result<Self, AppError> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err(AppError::Validation("Dial pattern cannot be empty".into()));
+        }
+
+        let body = match pattern.strip_prefix('_') {
+            Some(body) => body,
+            None => {
+                return Ok(Self {
+                    raw: pattern.to_string(),
+                    tokens: None,
+                })
+            }
+        };
+
+        let mut tokens = Vec::new();
+        let mut chars = body.chars();
+        while let Some(ch) = chars.next() {
+            let token = match ch.to_ascii_uppercase() {
+                'X' => PatternToken::Class(('0'..='9').collect()),
+                'Z' => PatternToken::Class(('1'..='9').collect()),
+                'N' => PatternToken::Class(('2'..='9').collect()),
+                '.' => PatternToken::OneOrMore,
+                '!' => PatternToken::ZeroOrMore,
+                '[' => {
+                    let mut class = Vec::new();
+                    let mut previous: Option<char> = None;
+                    let mut closed = false;
+                    while let Some(c) = chars.next() {
+                        match c {
+                            ']' => {
+                                closed = true;
+                                break;
+                            }
+                            '-' if previous.is_some() => {
+                                let start = previous.take().unwrap();
+                                let end = chars.next().ok_or_else(|| {
+                                    AppError::Validation(format!("Unterminated range in pattern {}", pattern))
+                                })?;
+                                if end < start {
+                                    return Err(AppError::Validation(format!(
+                                        "Invalid range {}-{} in pattern {}",
+                                        start, end, pattern
+                                    )));
+                                }
+                                class.extend(start..=end);
+                            }
+                            c => {
+                                class.push(c);
+                                previous = Some(c);
+                            }
+                        }
+                    }
+                    if !closed || class.is_empty() {
+                        return Err(AppError::Validation(format!(
+                            "Invalid character class in pattern {}",
+                            pattern
+                        )));
+                    }
+                    PatternToken::Class(class)
+                }
+                _ => PatternToken::Literal(ch),
+            };
+
+            let is_wildcard = matches!(token, PatternToken::OneOrMore | PatternToken::ZeroOrMore);
+            tokens.push(token);
+            if is_wildcard && chars.clone().next().is_some() {
+                return Err(AppError::Validation(format!(
+                    "Wildcards '.' and '!' must end the pattern {}",
+                    pattern
+                )));
+            }
+        }
+
+        Ok(Self {
+            raw: pattern.to_string(),
+            tokens: Some(tokens),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn matches(&self, number: &str) -> bool {
+        let tokens = match &self.tokens {
+            Some(tokens) => tokens,
+            None => return self.raw == number,
+        };
+
+        let digits: Vec<char> = number.chars().collect();
+        let mut position = 0;
+        for token in tokens {
+            match token {
+                PatternToken::OneOrMore => return digits.len() > position,
+                PatternToken::ZeroOrMore => return true,
+                PatternToken::Literal(expected) => {
+                    if digits.get(position) != Some(expected) {
+                        return false;
+                    }
+                }
+                PatternToken::Class(class) => match digits.get(position) {
+                    Some(digit) if class.contains(digit) => {}
+                    _ => return false,
+                },
+            }
+            position += 1;
+        }
+
+        position == digits.len()
+    }
+}
+
+/// Walks inbound routes in priority order and stops at the first match.
+pub fn evaluate_inbound(
+    routes: &[InboundRoute],
+    did: &str,
+    caller_id: Option<&str>,
+) -> RouteSimulationResult {
+    let mut routes = routes.to_vec();
+    routes.sort_by(|a, b| (a.priority, &a.name).cmp(&(b.priority, &b.name)));
+
+    let mut evaluated = Vec::new();
+    for route in routes {
+        let (matched, reason) = inbound_match(&route, did, caller_id);
+        evaluated.push(RuleEvaluation {
+            route_id: route.id,
+            route_name: route.name.clone(),
+            matched,
+            reason: reason.clone(),
+        });
+
+        if matched {
+            return RouteSimulationResult {
+                direction: RouteDirection::Inbound,
+                matched: true,
+                route_id: Some(route.id),
+                route_name: Some(route.name),
+                reason,
+                destination_type: Some(route.destination_type),
+                destination: Some(route.destination),
+                trunk: None,
+                dialed_number: None,
+                evaluated,
+            };
+        }
+    }
+
+    RouteSimulationResult {
+        direction: RouteDirection::Inbound,
+        matched: false,
+        route_id: None,
+        route_name: None,
+        reason: format!("No enabled inbound route matches DID {}", did),
+        destination_type: None,
+        destination: None,
+        trunk: None,
+        dialed_number: None,
+        evaluated,
+    }
+}
+
+fn inbound_match(route: &InboundRoute, did: &str, caller_id: Option<&str>) -> (bool, String) {
+    if !route.enabled {
+        return (false, "Route is disabled".into());
+    }
+
+    let did_reason = match &route.did_pattern {
+        None => "any DID".to_string(),
+        Some(pattern) => match DialPattern::parse(pattern) {
+            Ok(pattern) if pattern.matches(did) => format!("DID {} matches {}", did, pattern.as_str()),
+            Ok(pattern) => return (false, format!("DID {} does not match {}", did, pattern.as_str())),
+            Err(e) => return (false, e.to_string()),
+        },
+    };
+
+    let caller_reason = match (&route.caller_id_pattern, caller_id) {
+        (None, _) => "any caller ID".to_string(),
+        (Some(pattern), None) => {
+            return (
+                false,
+                format!("Route requires caller ID matching {} but none was given", pattern),
+            )
+        }
+        (Some(pattern), Some(caller_id)) => match DialPattern::parse(pattern) {
+            Ok(pattern) if pattern.matches(caller_id) => {
+                format!("caller ID {} matches {}", caller_id, pattern.as_str())
+            }
+            Ok(pattern) => {
+                return (
+                    false,
+                    format!("Caller ID {} does not match {}", caller_id, pattern.as_str()),
+                )
+            }
+            Err(e) => return (false, e.to_string()),
+        },
+    };
+
+    (
+        true,
+        format!("{} and {} (priority {})", did_reason, caller_reason, route.priority),
+    )
+}
+
+/// A route over a disabled trunk is left out of the dialplan. A trunk that no
+/// longer exists does not remove the route; the dial simply fails.
+pub fn trunk_disabled(route: &OutboundRoute, trunks: &[SipTrunk]) -> bool {
+    trunks.iter().any(|trunk| trunk.name == route.trunk && !trunk.enabled)
+}
+
+/// Walks outbound routes in priority order and returns the first one whose
+/// dial pattern matches, along with the number that would be sent to the trunk.
+/// Routes are skipped exactly where the rendered dialplan leaves them out.
+pub fn evaluate_outbound(routes: &[OutboundRoute], trunks: &[SipTrunk], number: &str) -> RouteSimulationResult {
+    let mut routes = routes.to_vec();
+    routes.sort_by(|a, b| (a.priority, &a.name).cmp(&(b.priority, &b.name)));
+
+    let mut evaluated = Vec::new();
+    for route in routes {
+        let (matched, reason) = if !route.enabled {
+            (false, "Route is disabled".to_string())
+        } else if trunk_disabled(&route, trunks) {
+            (false, format!("Trunk {} is disabled", route.trunk))
+        } else {
+            match DialPattern::parse(&route.dial_pattern) {
+                Ok(pattern) if !pattern.matches(number) => {
+                    (false, format!("{} does not match {}", number, pattern.as_str()))
+                }
+                Ok(pattern) => (
+                    true,
+                    format!(
+                        "{} matches {} (priority {})",
+                        number,
+                        pattern.as_str(),
+                        route.priority
+                    ),
+                ),
+                Err(e) => (false, e.to_string()),
+            }
+        };
+
+        evaluated.push(RuleEvaluation {
+            route_id: route.id,
+            route_name: route.name.clone(),
+            matched,
+            reason: reason.clone(),
+        });
+
+        if matched {
+            return RouteSimulationResult {
+                direction: RouteDirection::Outbound,
+                matched: true,
+                route_id: Some(route.id),
+                route_name: Some(route.name.clone()),
+                reason,
+                destination_type: None,
+                destination: None,
+                dialed_number: Some(rewrite_number(&route, number)),
+                trunk: Some(route.trunk),
+                evaluated,
+            };
+        }
+    }
+
+    RouteSimulationResult {
+        direction: RouteDirection::Outbound,
+        matched: false,
+        route_id: None,
+        route_name: None,
+        reason: format!("No enabled outbound route matches {}", number),
+        destination_type: None,
+        destination: None,
+        trunk: None,
+        dialed_number: None,
+        evaluated,
+    }
+}
+
+/// Applies the route's prefix strip and prepend to a dialed number.
+pub fn rewrite_number(route: &OutboundRoute, number: &str) -> String {
+    let stripped: String = number.chars().skip(route.strip_digits.max(0) as usize).collect();
+    format!("{}{}", route.prepend.as_deref().unwrap_or(""), stripped)
+}
+
+pub struct RoutingService {
+    pool: PgPool,
+}
+
+impl RoutingService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn validate_destination(
+        &self,
+        destination_type: &RouteDestinationType,
+        destination: &str,
+    ) -> Result<(), AppError> {
+        match destination_type {
+            RouteDestinationType::Hangup => Ok(()),
+            RouteDestinationType::Voicemail => {
+                let mailbox = sqlx::query!(
+                    r#"
+                    SELECT b.id
+                    FROM voicemail_boxes b
+                    JOIN pbx_extensions e ON e.id = b.extension_id
+                    WHERE e.extension_number = $1
+                    "#,
+                    destination
+                )
+                .fetch_optional(&self.pool)
+                .await?;
+                if mailbox.is_none() {
+                    return Err(AppError::Validation(format!(
+                        "Extension {} has no voicemail box",
+                        destination
+                    )));
+                }
+                Ok(())
+            }
+            RouteDestinationType::Extension => {
+                // Ring group and conference room numbers are dialled like extensions
+                let group = sqlx::query!(
+                    r#"
+                    SELECT id FROM ring_groups WHERE group_number = $1
+                    UNION ALL
+                    SELECT id FROM conference_rooms WHERE room_number = $1
+                    "#,
+                    destination
+                )
+                .fetch_optional(&self.pool)
+                .await?;
+                if group.is_some() {
+                    return Ok(());
+                }
+
+                let pbx_service = PbxService::new(self.pool.clone());
+                match pbx_service.get_extension_by_number(destination).await {
+                    Ok(_) => Ok(()),
+                    Err(AppError::NotFound(_)) => Err(AppError::Validation(format!(
+                        "Destination extension {} does not exist",
+                        destination
+                    ))),
+                    Err(e) => Err(e),
+                }
+            }
+            RouteDestinationType::Ivr => {
+                let menu = sqlx::query!("SELECT id FROM ivr_menus WHERE name = $1", destination)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                if menu.is_none() {
+                    return Err(AppError::Validation(format!(
+                        "IVR menu {} does not exist",
+                        destination
+                    )));
+                }
+                Ok(())
+            }
+            RouteDestinationType::Queue => {
+                let queue = sqlx::query!("SELECT id FROM call_queues WHERE name = $1", destination)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                if queue.is_none() {
+                    return Err(AppError::Validation(format!(
+                        "Queue {} does not exist",
+                        destination
+                    )));
+                }
+                Ok(())
+            }
+            _ if destination.trim().is_empty() => {
+                Err(AppError::Validation("Destination is required".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Voicemail and message actions need somewhere to send the caller.
+    pub async fn validate_blocked_action(
+        &self,
+        action: ScreeningAction,
+        destination: Option<&str>,
+    ) -> Result<(), AppError> {
+        match (action, destination.map(str::trim)) {
+            (ScreeningAction::Reject | ScreeningAction::Busy, _) => Ok(()),
+            (ScreeningAction::Voicemail, Some(mailbox)) if !mailbox.is_empty() => {
+                self.validate_destination(&RouteDestinationType::Voicemail, mailbox)
+                    .await
+            }
+            (ScreeningAction::Message, Some(sound)) if !sound.is_empty() => Ok(()),
+            _ => Err(AppError::Validation(
+                "blocked_destination is required when blocked callers go to voicemail or hear a message".into(),
+            )),
+        }
+    }
+
+    // Inbound routes
+    pub async fn create_inbound_route(
+        &self,
+        request: CreateInboundRouteRequest,
+    ) -> Result<InboundRoute, AppError> {
+        for pattern in [&request.did_pattern, &request.caller_id_pattern].into_iter().flatten() {
+            DialPattern::parse(pattern)?;
+        }
+        self.validate_destination(&request.destination_type, &request.destination)
+            .await?;
+        let blocked_action = request.blocked_action.unwrap_or_default();
+        self.validate_blocked_action(blocked_action, request.blocked_destination.as_deref())
+            .await?;
+
+        let route = sqlx::query_as!(
+            InboundRoute,
+            r#"
+            INSERT INTO inbound_routes (
+                name, did_pattern, caller_id_pattern, destination_type,
+                destination, priority, enabled, blocked_action,
+                blocked_destination, reject_anonymous
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, name, did_pattern, caller_id_pattern,
+                      destination_type as "destination_type: RouteDestinationType",
+                      destination, priority, enabled,
+                      blocked_action as "blocked_action: ScreeningAction",
+                      blocked_destination, reject_anonymous, created_at, updated_at
+            "#,
+            request.name,
+            request.did_pattern,
+            request.caller_id_pattern,
+            request.destination_type as RouteDestinationType,
+            request.destination,
+            request.priority.unwrap_or(DEFAULT_PRIORITY),
+            request.enabled.unwrap_or(true),
+            blocked_action as ScreeningAction,
+            request.blocked_destination,
+            request.reject_anonymous.unwrap_or(false),
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(route)
+    }
+
+    pub async fn get_inbound_route(&self, id: Uuid) -> Result<InboundRoute, AppError> {
+        let route = sqlx::query_as!(
+            InboundRoute,
+            r#"
+            SELECT id, name, did_pattern, caller_id_pattern,
+                   destination_type as "destination_type: RouteDestinationType",
+                   destination, priority, enabled,
+                   blocked_action as "blocked_action: ScreeningAction",
+                   blocked_destination, reject_anonymous, created_at, updated_at
+            FROM inbound_routes
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Inbound route not found".into()))?;
+
+        Ok(route)
+    }
+
+    pub async fn list_inbound_routes(&self) -> Result<Vec<InboundRoute>, AppError> {
+        let routes = sqlx::query_as!(
+            InboundRoute,
+            r#"
+            SELECT id, name, did_pattern, caller_id_pattern,
+                   destination_type as "destination_type: RouteDestinationType",
+                   destination, priority, enabled,
+                   blocked_action as "blocked_action: ScreeningAction",
+                   blocked_destination, reject_anonymous, created_at, updated_at
+            FROM inbound_routes
+            ORDER BY priority, name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(routes)
+    }
+
+    pub async fn update_inbound_route(
+        &self,
+        id: Uuid,
+        request: UpdateInboundRouteRequest,
+    ) -> Result<InboundRoute, AppError> {
+        for pattern in [&request.did_pattern, &request.caller_id_pattern].into_iter().flatten() {
+            DialPattern::parse(pattern)?;
+        }
+        let existing = self.get_inbound_route(id).await?;
+        let destination_type = request
+            .destination_type
+            .clone()
+            .unwrap_or(existing.destination_type);
+        let destination = request
+            .destination
+            .clone()
+            .unwrap_or(existing.destination);
+        self.validate_destination(&destination_type, &destination).await?;
+        let blocked_action = request.blocked_action.unwrap_or(existing.blocked_action);
+        let blocked_destination = request
+            .blocked_destination
+            .clone()
+            .or(existing.blocked_destination);
+        self.validate_blocked_action(blocked_action, blocked_destination.as_deref())
+            .await?;
+
+        let route = sqlx::query_as!(
+            InboundRoute,
+            r#"
+            UPDATE inbound_routes
+            SET name = COALESCE($1, name),
+                did_pattern = COALESCE($2, did_pattern),
+                caller_id_pattern = COALESCE($3, caller_id_pattern),
+                destination_type = $4,
+                destination = $5,
+                priority = COALESCE($6, priority),
+                enabled = COALESCE($7, enabled),
+                blocked_action = $8,
+                blocked_destination = $9,
+                reject_anonymous = COALESCE($10, reject_anonymous),
+                updated_at = $11
+            WHERE id = $12
+            RETURNING id, name, did_pattern, caller_id_pattern,
+                      destination_type as "destination_type: RouteDestinationType",
+                      destination, priority, enabled,
+                      blocked_action as "blocked_action: ScreeningAction",
+                      blocked_destination, reject_anonymous, created_at, updated_at
+            "#,
+            request.name,
+            request.did_pattern,
+            request.caller_id_pattern,
+            destination_type as RouteDestinationType,
+            destination,
+            request.priority,
+            request.enabled,
+            blocked_action as ScreeningAction,
+            blocked_destination,
+            request.reject_anonymous,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Inbound route not found".into()))?;
+
+        Ok(route)
+    }
+
+    pub async fn delete_inbound_route(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM inbound_routes WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Inbound route not found".into()));
+        }
+
+        Ok(())
+    }
+
+    // Outbound routes
+    async fn ensure_trunk_exists(&self, name: &str) -> Result<(), AppError> {
+        let trunk = sqlx::query!("SELECT id FROM sip_trunks WHERE name = $1", name)
+            .fetch_optional(&self.pool)
+            .await?;
+        if trunk.is_none() {
+            return Err(AppError::Validation(format!("Trunk {} does not exist", name)));
+        }
+        Ok(())
+    }
+
+    pub async fn create_outbound_route(
+        &self,
+        request: CreateOutboundRouteRequest,
+    ) -> Result<OutboundRoute, AppError> {
+        DialPattern::parse(&request.dial_pattern)?;
+        self.ensure_trunk_exists(&request.trunk).await?;
+
+        let route = sqlx::query_as!(
+            OutboundRoute,
+            r#"
+            INSERT INTO outbound_routes (
+                name, dial_pattern, strip_digits, prepend, trunk, priority, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, name, dial_pattern, strip_digits, prepend, trunk,
+                      priority, enabled, created_at, updated_at
+            "#,
+            request.name,
+            request.dial_pattern,
+            request.strip_digits.unwrap_or(0),
+            request.prepend,
+            request.trunk,
+            request.priority.unwrap_or(DEFAULT_PRIORITY),
+            request.enabled.unwrap_or(true),
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(route)
+    }
+
+    pub async fn get_outbound_route(&self, id: Uuid) -> Result<OutboundRoute, AppError> {
+        let route = sqlx::query_as!(
+            OutboundRoute,
+            r#"
+            SELECT id, name, dial_pattern, strip_digits, prepend, trunk,
+                   priority, enabled, created_at, updated_at
+            FROM outbound_routes
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Outbound route not found".into()))?;
+
+        Ok(route)
+    }
+
+    pub async fn list_outbound_routes(&self) -> Result<Vec<OutboundRoute>, AppError> {
+        let routes = sqlx::query_as!(
+            OutboundRoute,
+            r#"
+            SELECT id, name, dial_pattern, strip_digits, prepend, trunk,
+                   priority, enabled, created_at, updated_at
+            FROM outbound_routes
+            ORDER BY priority, name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(routes)
+    }
+
+    pub async fn update_outbound_route(
+        &self,
+        id: Uuid,
+        request: UpdateOutboundRouteRequest,
+    ) -> Result<OutboundRoute, AppError> {
+        if let Some(pattern) = &request.dial_pattern {
+            DialPattern::parse(pattern)?;
+        }
+        if let Some(trunk) = &request.trunk {
+            self.ensure_trunk_exists(trunk).await?;
+        }
+
+        let route = sqlx::query_as!(
+            OutboundRoute,
+            r#"
+            UPDATE outbound_routes
+            SET name = COALESCE($1, name),
+                dial_pattern = COALESCE($2, dial_pattern),
+                strip_digits = COALESCE($3, strip_digits),
+                prepend = COALESCE($4, prepend),
+                trunk = COALESCE($5, trunk),
+                priority = COALESCE($6, priority),
+                enabled = COALESCE($7, enabled),
+                updated_at = $8
+            WHERE id = $9
+            RETURNING id, name, dial_pattern, strip_digits, prepend, trunk,
+                      priority, enabled, created_at, updated_at
+            "#,
+            request.name,
+            request.dial_pattern,
+            request.strip_digits,
+            request.prepend,
+            request.trunk,
+            request.priority,
+            request.enabled,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Outbound route not found".into()))?;
+
+        Ok(route)
+    }
+
+    pub async fn delete_outbound_route(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM outbound_routes WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Outbound route not found".into()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn simulate(
+        &self,
+        request: SimulateRouteRequest,
+    ) -> Result<RouteSimulationResult, AppError> {
+        let result = match request.direction {
+            RouteDirection::Inbound => {
+                let routes = self.list_inbound_routes().await?;
+                evaluate_inbound(&routes, &request.number, request.caller_id.as_deref())
+            }
+            RouteDirection::Outbound => {
+                let routes = self.list_outbound_routes().await?;
+                let trunks = TrunkService::new(self.pool.clone()).list_trunks().await?;
+                evaluate_outbound(&routes, &trunks, &request.number)
+            }
+        };
+
+        Ok(result)
+    }
+}
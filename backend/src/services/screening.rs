@@ -0,0 +1,507 @@
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig},
+    error::AppError,
+    models::screening::{
+        BlockCallerRequest, CreateScreeningEntryRequest, ScreeningAction, ScreeningCheckResult,
+        ScreeningDecision, ScreeningEntry, ScreeningList, ScreeningLogEntry, ScreeningMatch,
+        UpdateScreeningEntryRequest,
+    },
+    services::{
+        call_handling::AstDbWrite,
+        contact::{clean_number, normalize_e164, phone_country_code},
+        pbx::PbxService,
+    },
+};
+
+/// AstDB family read by the generated [caller-screening] dialplan.
+pub const SCREENING_FAMILY: &str = "SCREEN";
+
+/// Caller IDs that stand for a withheld number, matched case-insensitively.
+/// The dialplan uses the same expression.
+pub const ANONYMOUS_CALLER_REGEX: &str = "^(anonymous|unknown|<unknown>|restricted|private|unavailable)?$";
+
+/// Characters allowed in regex entries. Quotes and backslashes would break
+/// out of the dialplan expression the pattern is used in.
+fn is_regex_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "^$.[]()|?*+{},-#<>_:@".contains(c)
+}
+
+pub fn is_anonymous(caller_id: &str) -> bool {
+    Regex::new(ANONYMOUS_CALLER_REGEX)
+        .map(|anonymous| anonymous.is_match(&caller_id.trim().to_ascii_lowercase()))
+        .unwrap_or(false)
+}
+
+/// Checks a pattern and returns the form it is stored in: numbers keep only
+/// their digits and leading `+`, expressions are kept as written.
+pub fn validate_pattern(match_type: ScreeningMatch, pattern: &str) -> Result<String, AppError> {
+    let pattern = pattern.trim();
+    match match_type {
+        ScreeningMatch::Exact | ScreeningMatch::Prefix => {
+            if !pattern
+                .chars()
+                .all(|c| c.is_ascii_digit() || " +-()./".contains(c))
+            {
+                return Err(AppError::Validation(format!(
+                    "'{}' is not a phone number; use a regex entry to match other caller IDs",
+                    pattern
+                )));
+            }
+            let cleaned = clean_number(pattern);
+            if cleaned.trim_start_matches('+').is_empty() {
+                return Err(AppError::Validation(format!("'{}' is not a phone number", pattern)));
+            }
+            Ok(cleaned)
+        }
+        ScreeningMatch::Regex => {
+            if let Some(c) = pattern.chars().find(|c| !is_regex_char(*c)) {
+                return Err(AppError::Validation(format!(
+                    "Regex entries may not contain '{}'",
+                    c
+                )));
+            }
+            Regex::new(pattern)
+                .map_err(|e| AppError::Validation(format!("Invalid regex '{}': {}", pattern, e)))?;
+            Ok(pattern.to_string())
+        }
+    }
+}
+
+/// The national form of an international number without its `+`, when it
+/// belongs to `country_code`.
+fn national_form(digits: &str, country_code: Option<&str>) -> Option<String> {
+    let rest = digits.strip_prefix(country_code?)?;
+    match country_code {
+        // The North American plan has no trunk prefix
+        Some("1") => Some(rest.to_string()),
+        _ => Some(format!("0{}", rest)),
+    }
+}
+
+/// The POSIX extended regular expression an entry is matched with, both by
+/// Asterisk and by [`screen_caller`]. Exact and prefix entries match the
+/// number with `+`, with `00` and in national format, since trunks deliver
+/// caller IDs in any of them.
+pub fn screening_regex(match_type: ScreeningMatch, pattern: &str, country_code: Option<&str>) -> String {
+    // `+` is the only character of a stored number with a meaning in regexes
+    let literal = |number: &str| number.replace('+', "[+]");
+
+    match match_type {
+        ScreeningMatch::Regex => pattern.to_string(),
+        ScreeningMatch::Exact => match normalize_e164(pattern, country_code) {
+            Some(e164) => {
+                let digits = &e164[1..];
+                let mut forms = vec![format!("([+]|00)?{}", digits)];
+                forms.extend(national_form(digits, country_code));
+                format!("^({})$", forms.join("|"))
+            }
+            // Short numbers and extensions match as given
+            None => format!("^{}$", literal(pattern)),
+        },
+        ScreeningMatch::Prefix => {
+            let international = pattern
+                .strip_prefix('+')
+                .or_else(|| pattern.strip_prefix("00"));
+            match (international, country_code) {
+                (Some(digits), _) => {
+                    let mut forms = vec![format!("([+]|00){}", digits)];
+                    forms.extend(national_form(digits, country_code));
+                    format!("^({})", forms.join("|"))
+                }
+                (None, Some(code)) if code != "1" && pattern.len() > 1 && pattern.starts_with('0') => {
+                    format!("^(([+]|00){}{}|{})", code, &pattern[1..], pattern)
+                }
+                _ => format!("^{}", literal(pattern)),
+            }
+        }
+    }
+}
+
+fn is_active(entry: &ScreeningEntry, now: DateTime<Utc>) -> bool {
+    entry.expires_at.map_or(true, |expires_at| expires_at > now)
+}
+
+/// The entry that decides a call from `caller_id`. Allow entries are
+/// checked first; expired entries are ignored.
+pub fn screen_caller<'a>(
+    entries: &'a [ScreeningEntry],
+    caller_id: &str,
+    country_code: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<&'a ScreeningEntry> {
+    let caller_id = caller_id.trim();
+    let matching = |list: ScreeningList| {
+        entries
+            .iter()
+            .filter(move |entry| entry.list == list && is_active(entry, now))
+            .find(|entry| {
+                Regex::new(&screening_regex(entry.match_type, &entry.pattern, country_code))
+                    .is_ok_and(|regex| regex.is_match(caller_id))
+            })
+    };
+    matching(ScreeningList::Allow).or_else(|| matching(ScreeningList::Block))
+}
+
+/// The AstDB state that mirrors the active entries: `count` and, for each
+/// entry in the order the dialplan checks them, its expression, list, expiry
+/// as a Unix timestamp and id. The family is expected to be cleared first.
+pub fn astdb_writes(
+    entries: &[ScreeningEntry],
+    country_code: Option<&str>,
+    now: DateTime<Utc>,
+) -> Vec<AstDbWrite> {
+    let write = |key: String, value: String| AstDbWrite {
+        family: SCREENING_FAMILY,
+        key,
+        value: Some(value),
+    };
+
+    let ordered = [ScreeningList::Allow, ScreeningList::Block]
+        .into_iter()
+        .flat_map(|list| entries.iter().filter(move |entry| entry.list == list))
+        .filter(|entry| is_active(entry, now));
+
+    let mut writes = Vec::new();
+    let mut count = 0;
+    for entry in ordered {
+        let list = match entry.list {
+            ScreeningList::Allow => "allow",
+            ScreeningList::Block => "block",
+        };
+        writes.push(write(
+            format!("{}/match", count),
+            screening_regex(entry.match_type, &entry.pattern, country_code),
+        ));
+        writes.push(write(format!("{}/list", count), list.to_string()));
+        if let Some(expires_at) = entry.expires_at {
+            writes.push(write(format!("{}/expires", count), expires_at.timestamp().to_string()));
+        }
+        writes.push(write(format!("{}/id", count), entry.id.to_string()));
+        count += 1;
+    }
+    writes.push(write("count".to_string(), count.to_string()));
+
+    writes
+}
+
+pub struct ScreeningService {
+    pool: PgPool,
+    country_code: Option<String>,
+}
+
+impl ScreeningService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            country_code: phone_country_code(),
+        }
+    }
+
+    fn check_expiry(expires_at: Option<DateTime<Utc>>) -> Result<(), AppError> {
+        if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(AppError::Validation("expires_at must be in the future".into()));
+        }
+        Ok(())
+    }
+
+    pub async fn create_entry(
+        &self,
+        request: CreateScreeningEntryRequest,
+        created_by: Option<Uuid>,
+        call_id: Option<Uuid>,
+    ) -> Result<ScreeningEntry, AppError> {
+        let pattern = validate_pattern(request.match_type, &request.pattern)?;
+        Self::check_expiry(request.expires_at)?;
+
+        let entry = sqlx::query_as!(
+            ScreeningEntry,
+            r#"
+            INSERT INTO caller_screening_entries (
+                list, match_type, pattern, description, expires_at, created_by, call_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, list as "list: ScreeningList", match_type as "match_type: ScreeningMatch",
+                      pattern, description, expires_at, created_by, call_id, created_at, updated_at
+            "#,
+            request.list as ScreeningList,
+            request.match_type as ScreeningMatch,
+            pattern,
+            request.description,
+            request.expires_at,
+            created_by,
+            call_id,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.sync_to_asterisk().await?;
+        Ok(entry)
+    }
+
+    pub async fn get_entry(&self, id: Uuid) -> Result<ScreeningEntry, AppError> {
+        let entry = sqlx::query_as!(
+            ScreeningEntry,
+            r#"
+            SELECT id, list as "list: ScreeningList", match_type as "match_type: ScreeningMatch",
+                   pattern, description, expires_at, created_by, call_id, created_at, updated_at
+            FROM caller_screening_entries
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Screening entry not found".into()))?;
+
+        Ok(entry)
+    }
+
+    pub async fn list_entries(
+        &self,
+        list: Option<ScreeningList>,
+        include_expired: bool,
+    ) -> Result<Vec<ScreeningEntry>, AppError> {
+        let entries = sqlx::query_as!(
+            ScreeningEntry,
+            r#"
+            SELECT id, list as "list: ScreeningList", match_type as "match_type: ScreeningMatch",
+                   pattern, description, expires_at, created_by, call_id, created_at, updated_at
+            FROM caller_screening_entries
+            WHERE ($1::screening_list IS NULL OR list = $1)
+              AND ($2 OR expires_at IS NULL OR expires_at > NOW())
+            ORDER BY list, pattern
+            "#,
+            list as Option<ScreeningList>,
+            include_expired
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    pub async fn update_entry(
+        &self,
+        id: Uuid,
+        request: UpdateScreeningEntryRequest,
+    ) -> Result<ScreeningEntry, AppError> {
+        let existing = self.get_entry(id).await?;
+        let match_type = request.match_type.unwrap_or(existing.match_type);
+        let pattern = validate_pattern(match_type, request.pattern.as_deref().unwrap_or(&existing.pattern))?;
+        Self::check_expiry(request.expires_at)?;
+
+        let entry = sqlx::query_as!(
+            ScreeningEntry,
+            r#"
+            UPDATE caller_screening_entries
+            SET list = COALESCE($1, list),
+                match_type = $2,
+                pattern = $3,
+                description = COALESCE($4, description),
+                expires_at = CASE WHEN $5 THEN NULL ELSE COALESCE($6, expires_at) END,
+                updated_at = NOW()
+            WHERE id = $7
+            RETURNING id, list as "list: ScreeningList", match_type as "match_type: ScreeningMatch",
+                      pattern, description, expires_at, created_by, call_id, created_at, updated_at
+            "#,
+            request.list as Option<ScreeningList>,
+            match_type as ScreeningMatch,
+            pattern,
+            request.description,
+            request.permanent.unwrap_or(false),
+            request.expires_at,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Screening entry not found".into()))?;
+
+        self.sync_to_asterisk().await?;
+        Ok(entry)
+    }
+
+    pub async fn delete_entry(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM caller_screening_entries WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Screening entry not found".into()));
+        }
+
+        self.sync_to_asterisk().await?;
+        Ok(())
+    }
+
+    /// Blocks the caller of a call record. An active exact block of the same
+    /// number is returned as it is.
+    pub async fn block_caller(
+        &self,
+        call_id: Uuid,
+        user_id: Uuid,
+        request: BlockCallerRequest,
+    ) -> Result<ScreeningEntry, AppError> {
+        let call = PbxService::new(self.pool.clone()).get_call_record(call_id).await?;
+        if is_anonymous(&call.caller_id) {
+            return Err(AppError::Validation(
+                "The call has no caller ID; reject anonymous calls on the inbound route instead".into(),
+            ));
+        }
+        match PbxService::new(self.pool.clone())
+            .get_extension_by_number(&call.caller_id)
+            .await
+        {
+            Ok(_) => {
+                return Err(AppError::Validation(format!(
+                    "{} is an internal extension",
+                    call.caller_id
+                )))
+            }
+            Err(AppError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let pattern = validate_pattern(ScreeningMatch::Exact, &call.caller_id)?;
+        let existing = self
+            .list_entries(Some(ScreeningList::Block), false)
+            .await?
+            .into_iter()
+            .find(|entry| entry.match_type == ScreeningMatch::Exact && entry.pattern == pattern);
+        if let Some(entry) = existing {
+            return Ok(entry);
+        }
+
+        let description = request
+            .description
+            .or_else(|| Some(format!("Blocked from call at {}", call.start_time.format("%Y-%m-%d %H:%M"))));
+        self.create_entry(
+            CreateScreeningEntryRequest {
+                list: ScreeningList::Block,
+                match_type: ScreeningMatch::Exact,
+                pattern,
+                description,
+                expires_at: request.expires_at,
+            },
+            Some(user_id),
+            Some(call.id),
+        )
+        .await
+    }
+
+    /// What inbound routes would do with a call from `number`.
+    pub async fn check(&self, number: &str) -> Result<ScreeningCheckResult, AppError> {
+        let entries = self.list_entries(None, false).await?;
+        let entry = screen_caller(&entries, number, self.country_code.as_deref(), Utc::now()).cloned();
+
+        Ok(ScreeningCheckResult {
+            number: number.to_string(),
+            blocked: entry.as_ref().is_some_and(|entry| entry.list == ScreeningList::Block),
+            anonymous: is_anonymous(number),
+            entry,
+        })
+    }
+
+    /// Logs a blocked call against its call record. Entries and routes
+    /// deleted since the call are left out.
+    pub async fn record_decision(&self, decision: ScreeningDecision) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO caller_screening_log (call_id, entry_id, route_id, caller_id, pattern, anonymous, action)
+            SELECT (SELECT id FROM call_records WHERE uniqueid = $1),
+                   e.id,
+                   (SELECT id FROM inbound_routes WHERE id = $3),
+                   $4, e.pattern, $5, $6
+            FROM (SELECT 1) AS decision
+            LEFT JOIN caller_screening_entries e ON e.id = $2
+            "#,
+            decision.uniqueid,
+            decision.entry_id,
+            decision.route_id,
+            decision.caller_id,
+            decision.entry_id.is_none(),
+            decision.action as ScreeningAction,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_log(
+        &self,
+        call_id: Option<Uuid>,
+        entry_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ScreeningLogEntry>, AppError> {
+        let log = sqlx::query_as!(
+            ScreeningLogEntry,
+            r#"
+            SELECT id, call_id, entry_id, route_id, caller_id, pattern, anonymous,
+                   action as "action: ScreeningAction", occurred_at
+            FROM caller_screening_log
+            WHERE ($1::uuid IS NULL OR call_id = $1)
+              AND ($2::uuid IS NULL OR entry_id = $2)
+            ORDER BY occurred_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            call_id,
+            entry_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(log)
+    }
+
+    /// Rewrites the screening entries in AstDB from the database, e.g. after
+    /// Asterisk restarted or an entry changed.
+    pub async fn sync_to_asterisk(&self) -> Result<(), AppError> {
+        let Some(config) = AmiConfig::from_env() else {
+            return Ok(());
+        };
+        let entries = self.list_entries(None, false).await?;
+        let writes = astdb_writes(&entries, self.country_code.as_deref(), Utc::now());
+        if let Err(e) = Self::write_astdb(&config, &writes).await {
+            tracing::warn!("Failed to push caller screening entries to Asterisk: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn write_astdb(config: &AmiConfig, writes: &[AstDbWrite]) -> Result<(), AppError> {
+        let mut client = AmiClient::connect(config).await?;
+
+        // Deleting a missing family answers with an error, which is fine here
+        client
+            .send_action("DBDelTree", &[("Family", SCREENING_FAMILY)])
+            .await?;
+        for write in writes {
+            let Some(value) = &write.value else {
+                continue;
+            };
+            let response = client
+                .send_action("DBPut", &[("Family", write.family), ("Key", &write.key), ("Val", value)])
+                .await?;
+            if !response.is_success() {
+                return Err(AppError::Internal(format!(
+                    "AMI DBPut {}/{} failed: {}",
+                    write.family,
+                    write.key,
+                    response.get("Message").unwrap_or("no message")
+                )));
+            }
+        }
+
+        if let Err(e) = client.logoff().await {
+            tracing::debug!("AMI logoff failed: {}", e);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{
+    ami::client::{AmiClient, AmiConfig},
+    error::AppError,
+    models::trunk::{
+        CreateTrunkRequest, SipTrunk, TrunkRegistrationState, TrunkStatus, TrunkTransport,
+        UpdateTrunkRequest,
+    },
+};
+
+const DEFAULT_SIP_PORT: i32 = 5060;
+const DEFAULT_TLS_PORT: i32 = 5061;
+const DEFAULT_CODECS: [&str; 2] = ["ulaw", "alaw"];
+
+/// Suffixes of the extra sections rendered for each trunk.
+const DERIVED_SECTION_SUFFIXES: [&str; 3] = ["-auth", "-identify", "-reg"];
+
+/// Trunk names become PJSIP section names. Requiring a leading letter keeps
+/// them apart from extension numbers, which share pjsip.conf, and names that
+/// look like another trunk's derived sections or a transport are refused.
+pub fn validate_trunk_name(name: &str) -> Result<(), AppError> {
+    let lower = name.to_ascii_lowercase();
+    if lower.starts_with("transport-")
+        || DERIVED_SECTION_SUFFIXES.iter().any(|suffix| lower.ends_with(suffix))
+    {
+        return Err(AppError::Validation(format!(
+            "Trunk name '{}' clashes with generated PJSIP section names",
+            name
+        )));
+    }
+
+    let starts_with_letter = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic());
+    if starts_with_letter
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(AppError::Validation(
+            "Trunk name must start with a letter and may only contain letters, digits, '-' and '_'".into(),
+        ))
+    }
+}
+
+/// Accepts host names, IPv4 addresses and bracketed IPv6 addresses.
+pub fn validate_trunk_host(host: &str) -> Result<(), AppError> {
+    if host
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+    {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!("Invalid trunk host '{}'", host)))
+    }
+}
+
+pub fn validate_codecs(codecs: &[String]) -> Result<(), AppError> {
+    if codecs.is_empty() {
+        return Err(AppError::Validation("At least one codec is required".into()));
+    }
+    for codec in codecs {
+        if codec.is_empty() || !codec.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(AppError::Validation(format!("Invalid codec '{}'", codec)));
+        }
+    }
+    Ok(())
+}
+
+/// Name of the PJSIP registration section rendered for a trunk.
+pub fn registration_section(trunk_name: &str) -> String {
+    format!("{}-reg", trunk_name)
+}
+
+/// Maps the `Status` of an `OutboundRegistrationDetail` event.
+pub fn registration_state(status: &str) -> TrunkRegistrationState {
+    match status.trim().to_ascii_lowercase().as_str() {
+        "registered" => TrunkRegistrationState::Registered,
+        "unregistered" => TrunkRegistrationState::Unregistered,
+        "rejected" => TrunkRegistrationState::Rejected,
+        "stopped" => TrunkRegistrationState::Stopped,
+        _ => TrunkRegistrationState::Unknown,
+    }
+}
+
+/// `reported` maps registration section names to the status Asterisk gave.
+pub fn trunk_status(trunk: &SipTrunk, reported: &HashMap<String, String>) -> TrunkStatus {
+    let detail = reported.get(&registration_section(&trunk.name)).cloned();
+    let state = if !trunk.enabled || !trunk.register {
+        TrunkRegistrationState::Disabled
+    } else {
+        detail
+            .as_deref()
+            .map(registration_state)
+            .unwrap_or(TrunkRegistrationState::Unknown)
+    };
+
+    TrunkStatus {
+        trunk_id: trunk.id,
+        name: trunk.name.clone(),
+        enabled: trunk.enabled,
+        register: trunk.register,
+        state,
+        detail,
+    }
+}
+
+fn default_port(transport: TrunkTransport) -> i32 {
+    match transport {
+        TrunkTransport::Tls => DEFAULT_TLS_PORT,
+        TrunkTransport::Udp | TrunkTransport::Tcp => DEFAULT_SIP_PORT,
+    }
+}
+
+fn check_credentials(register: bool, username: &Option<String>, secret: &Option<String>) -> Result<(), AppError> {
+    if register && (username.is_none() || secret.is_none()) {
+        return Err(AppError::Validation(
+            "Registration requires a username and secret".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub struct TrunkService {
+    pool: PgPool,
+}
+
+impl TrunkService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_trunk(&self, request: CreateTrunkRequest) -> Result<SipTrunk, AppError> {
+        validate_trunk_name(&request.name)?;
+        validate_trunk_host(&request.host)?;
+        let codecs = request
+            .codecs
+            .unwrap_or_else(|| DEFAULT_CODECS.iter().map(|codec| codec.to_string()).collect());
+        validate_codecs(&codecs)?;
+        let register = request.register.unwrap_or(false);
+        check_credentials(register, &request.username, &request.secret)?;
+
+        let existing = sqlx::query!("SELECT id FROM sip_trunks WHERE name = $1", request.name)
+            .fetch_optional(&self.pool)
+            .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation("Trunk already exists".into()));
+        }
+
+        let transport = request.transport.unwrap_or(TrunkTransport::Udp);
+        let trunk = sqlx::query_as!(
+            SipTrunk,
+            r#"
+            INSERT INTO sip_trunks (
+                name, host, port, transport, username, secret, register,
+                outbound_caller_id, max_channels, codecs, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, name, host, port, transport as "transport: TrunkTransport",
+                      username, secret, register, outbound_caller_id, max_channels,
+                      codecs, enabled, created_at, updated_at
+            "#,
+            request.name,
+            request.host,
+            request.port.unwrap_or_else(|| default_port(transport)),
+            transport as TrunkTransport,
+            request.username,
+            request.secret,
+            register,
+            request.outbound_caller_id,
+            request.max_channels,
+            &codecs,
+            request.enabled.unwrap_or(true),
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(trunk)
+    }
+
+    pub async fn get_trunk(&self, id: Uuid) -> Result<SipTrunk, AppError> {
+        let trunk = sqlx::query_as!(
+            SipTrunk,
+            r#"
+            SELECT id, name, host, port, transport as "transport: TrunkTransport",
+                   username, secret, register, outbound_caller_id, max_channels,
+                   codecs, enabled, created_at, updated_at
+            FROM sip_trunks
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Trunk not found".into()))?;
+
+        Ok(trunk)
+    }
+
+    pub async fn list_trunks(&self) -> Result<Vec<SipTrunk>, AppError> {
+        let trunks = sqlx::query_as!(
+            SipTrunk,
+            r#"
+            SELECT id, name, host, port, transport as "transport: TrunkTransport",
+                   username, secret, register, outbound_caller_id, max_channels,
+                   codecs, enabled, created_at, updated_at
+            FROM sip_trunks
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(trunks)
+    }
+
+    /// Renaming a trunk also renames it in the outbound routes that use it.
+    pub async fn update_trunk(&self, id: Uuid, request: UpdateTrunkRequest) -> Result<SipTrunk, AppError> {
+        if let Some(name) = &request.name {
+            validate_trunk_name(name)?;
+        }
+        if let Some(host) = &request.host {
+            validate_trunk_host(host)?;
+        }
+        if let Some(codecs) = &request.codecs {
+            validate_codecs(codecs)?;
+        }
+
+        let current = self.get_trunk(id).await?;
+        check_credentials(
+            request.register.unwrap_or(current.register),
+            &request.username.clone().or(current.username.clone()),
+            &request.secret.clone().or(current.secret.clone()),
+        )?;
+
+        if let Some(name) = &request.name {
+            let existing = sqlx::query!("SELECT id FROM sip_trunks WHERE name = $1 AND id <> $2", name, id)
+                .fetch_optional(&self.pool)
+                .await?;
+            if existing.is_some() {
+                return Err(AppError::Validation("Trunk already exists".into()));
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let trunk = sqlx::query_as!(
+            SipTrunk,
+            r#"
+            UPDATE sip_trunks
+            SET name = COALESCE($1, name),
+                host = COALESCE($2, host),
+                port = COALESCE($3, port),
+                transport = COALESCE($4, transport),
+                username = COALESCE($5, username),
+                secret = COALESCE($6, secret),
+                register = COALESCE($7, register),
+                outbound_caller_id = COALESCE($8, outbound_caller_id),
+                max_channels = COALESCE($9, max_channels),
+                codecs = COALESCE($10, codecs),
+                enabled = COALESCE($11, enabled),
+                updated_at = $12
+            WHERE id = $13
+            RETURNING id, name, host, port, transport as "transport: TrunkTransport",
+                      username, secret, register, outbound_caller_id, max_channels,
+                      codecs, enabled, created_at, updated_at
+            "#,
+            request.name,
+            request.host,
+            request.port,
+            request.transport as Option<TrunkTransport>,
+            request.username,
+            request.secret,
+            request.register,
+            request.outbound_caller_id,
+            request.max_channels,
+            request.codecs.as_deref(),
+            request.enabled,
+            Utc::now(),
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Trunk not found".into()))?;
+
+        if trunk.name != current.name {
+            sqlx::query!(
+                "UPDATE outbound_routes SET trunk = $1, updated_at = $2 WHERE trunk = $3",
+                trunk.name,
+                Utc::now(),
+                current.name
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(trunk)
+    }
+
+    pub async fn delete_trunk(&self, id: Uuid) -> Result<(), AppError> {
+        let trunk = self.get_trunk(id).await?;
+
+        let route = sqlx::query!(
+            "SELECT name FROM outbound_routes WHERE trunk = $1 LIMIT 1",
+            trunk.name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(route) = route {
+            return Err(AppError::Validation(format!(
+                "Trunk is used by outbound route {}",
+                route.name
+            )));
+        }
+
+        let result = sqlx::query!("DELETE FROM sip_trunks WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Trunk not found".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Registration state of every trunk as Asterisk currently reports it.
+    /// Without AMI, or when Asterisk cannot be reached, registering trunks
+    /// show as `unknown`.
+    pub async fn list_statuses(&self) -> Result<Vec<TrunkStatus>, AppError> {
+        let trunks = self.list_trunks().await?;
+
+        let mut reported = HashMap::new();
+        if let Some(config) = AmiConfig::from_env() {
+            match AmiClient::run_list_action(&config, "PJSIPShowRegistrationsOutbound", &[]).await {
+                Ok(events) => {
+                    for event in events
+                        .iter()
+                        .filter(|event| event.event() == Some("OutboundRegistrationDetail"))
+                    {
+                        if let (Some(name), Some(status)) = (event.get("ObjectName"), event.get("Status")) {
+                            reported.insert(name.to_string(), status.to_string());
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to read trunk registrations from Asterisk: {}", e),
+            }
+        }
+
+        Ok(trunks
+            .iter()
+            .map(|trunk| trunk_status(trunk, &reported))
+            .collect())
+    }
+}
@@ -0,0 +1,675 @@
+use std::path::{Path, PathBuf};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::{DateTime, TimeZone, Utc};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    error::AppError,
+    models::{
+        email::OutgoingAttachment,
+        voicemail::{
+            CreateVoicemailBoxRequest, DepositVoicemailQuery, SpoolMessage, UpdateVoicemailBoxRequest,
+            VoicemailBox, VoicemailMessage,
+        },
+    },
+    services::email::EmailService,
+};
+
+const DEFAULT_MAX_MESSAGES: i32 = 100;
+const GREETING_FILE: &str = "greeting.wav";
+/// Voicemail context the generated voicemail.conf puts every mailbox in.
+const SPOOL_CONTEXT: &str = "default";
+/// Greeting Asterisk plays for `VoiceMail(...,u)`, the only flag the dialplan uses.
+const SPOOL_GREETING_FILE: &str = "unavail.wav";
+const MAX_CALLER_ID_LEN: usize = 50;
+
+/// Asterisk only accepts numeric voicemail passwords.
+pub fn validate_pin(pin: &str) -> Result<(), AppError> {
+    if (4..=10).contains(&pin.len()) && pin.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(AppError::Validation("PIN must be 4 to 10 digits".into()))
+    }
+}
+
+/// File extension for a supported audio content type.
+pub fn audio_extension(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    match mime.to_ascii_lowercase().as_str() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => Some("wav"),
+        "audio/mpeg" => Some("mp3"),
+        "audio/ogg" => Some("ogg"),
+        "audio/gsm" | "audio/x-gsm" => Some("gsm"),
+        _ => None,
+    }
+}
+
+/// Reads the `[message]` section Asterisk writes next to each recording.
+/// Older Asterisk versions have no `msg_id`, so the time and calling
+/// channel stand in for it.
+pub fn parse_spool_message(text: &str) -> Option<SpoolMessage> {
+    let value = |key: &str| {
+        text.lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(name, _)| name.trim() == key)
+            .map(|(_, value)| value.trim())
+    };
+
+    let origtime: i64 = value("origtime")?.parse().ok()?;
+    let received_at = Utc.timestamp_opt(origtime, 0).single()?;
+    let msg_id = match value("msg_id").filter(|id| !id.is_empty()) {
+        Some(id) => id.to_string(),
+        None => format!("{}-{}", origtime, value("callerchan").unwrap_or("")),
+    };
+    // callerid is a name-addr such as "Alice" <1002>, or just the number
+    let callerid = value("callerid").unwrap_or("");
+    let number = match callerid.rsplit_once('<') {
+        Some((_, rest)) => rest.trim_end_matches('>').trim(),
+        None => callerid.trim_matches('"').trim(),
+    };
+    let caller_id = if number.is_empty() || number.eq_ignore_ascii_case("unknown") {
+        "unknown".to_string()
+    } else {
+        number.chars().take(MAX_CALLER_ID_LEN).collect()
+    };
+    let duration = value("duration")
+        .and_then(|duration| duration.parse::<i32>().ok())
+        .unwrap_or(0)
+        .max(0);
+
+    Some(SpoolMessage {
+        msg_id,
+        caller_id,
+        duration,
+        received_at,
+    })
+}
+
+pub struct VoicemailService {
+    pool: PgPool,
+    storage_dir: PathBuf,
+    /// Asterisk's voicemail spool: `VoiceMail()` records into it and plays
+    /// greetings from it.
+    spool_dir: PathBuf,
+}
+
+impl VoicemailService {
+    pub fn new(pool: PgPool) -> Self {
+        let storage_dir = std::env::var("VOICEMAIL_PATH")
+            .unwrap_or_else(|_| "/var/lib/oriontel/voicemail".into());
+        let spool_dir = std::env::var("VOICEMAIL_SPOOL_PATH")
+            .unwrap_or_else(|_| "/var/spool/asterisk/voicemail".into());
+        Self {
+            pool,
+            storage_dir: PathBuf::from(storage_dir),
+            spool_dir: PathBuf::from(spool_dir),
+        }
+    }
+
+    fn mailbox_dir(&self, mailbox_id: Uuid) -> PathBuf {
+        self.storage_dir.join(mailbox_id.to_string())
+    }
+
+    /// The mailbox number becomes a directory name, so it must not be able
+    /// to leave the spool.
+    fn spool_mailbox_dir(&self, mailbox: &VoicemailBox) -> Result<PathBuf, AppError> {
+        let number = &mailbox.extension_number;
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(AppError::Validation(format!("Invalid mailbox number '{}'", number)));
+        }
+        Ok(self.spool_dir.join(SPOOL_CONTEXT).join(number))
+    }
+
+    pub fn greeting_path(&self, mailbox: &VoicemailBox) -> Option<PathBuf> {
+        mailbox
+            .has_greeting
+            .then(|| self.mailbox_dir(mailbox.id).join(GREETING_FILE))
+    }
+
+    // Mailboxes
+    pub async fn create_mailbox(
+        &self,
+        request: CreateVoicemailBoxRequest,
+    ) -> Result<VoicemailBox, AppError> {
+        validate_pin(&request.pin)?;
+
+        let extension = sqlx::query!("SELECT id FROM pbx_extensions WHERE id = $1", request.extension_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        if extension.is_none() {
+            return Err(AppError::Validation("Extension does not exist".into()));
+        }
+
+        let existing = sqlx::query!(
+            "SELECT id FROM voicemail_boxes WHERE extension_id = $1",
+            request.extension_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if existing.is_some() {
+            return Err(AppError::Validation("Extension already has a mailbox".into()));
+        }
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO voicemail_boxes (extension_id, pin, email, owner_id, max_messages)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#,
+            request.extension_id,
+            request.pin,
+            request.email,
+            request.owner_id,
+            request.max_messages.unwrap_or(DEFAULT_MAX_MESSAGES),
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_mailbox(row.id).await
+    }
+
+    pub async fn get_mailbox(&self, id: Uuid) -> Result<VoicemailBox, AppError> {
+        let mailbox = sqlx::query_as!(
+            VoicemailBox,
+            r#"
+            SELECT b.id, b.extension_id, e.extension_number, e.name as extension_name,
+                   b.pin, b.email, b.owner_id, b.has_greeting, b.max_messages,
+                   b.created_at, b.updated_at
+            FROM voicemail_boxes b
+            JOIN pbx_extensions e ON e.id = b.extension_id
+            WHERE b.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Mailbox not found".into()))?;
+
+        Ok(mailbox)
+    }
+
+    pub async fn list_mailboxes(&self) -> Result<Vec<VoicemailBox>, AppError> {
+        let mailboxes = sqlx::query_as!(
+            VoicemailBox,
+            r#"
+            SELECT b.id, b.extension_id, e.extension_number, e.name as extension_name,
+                   b.pin, b.email, b.owner_id, b.has_greeting, b.max_messages,
+                   b.created_at, b.updated_at
+            FROM voicemail_boxes b
+            JOIN pbx_extensions e ON e.id = b.extension_id
+            ORDER BY e.extension_number
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(mailboxes)
+    }
+
+    pub async fn list_mailboxes_for_owner(&self, owner_id: Uuid) -> Result<Vec<VoicemailBox>, AppError> {
+        let mailboxes = sqlx::query_as!(
+            VoicemailBox,
+            r#"
+            SELECT b.id, b.extension_id, e.extension_number, e.name as extension_name,
+                   b.pin, b.email, b.owner_id, b.has_greeting, b.max_messages,
+                   b.created_at, b.updated_at
+            FROM voicemail_boxes b
+            JOIN pbx_extensions e ON e.id = b.extension_id
+            WHERE b.owner_id = $1
+            ORDER BY e.extension_number
+            "#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(mailboxes)
+    }
+
+    pub async fn update_mailbox(
+        &self,
+        id: Uuid,
+        request: UpdateVoicemailBoxRequest,
+    ) -> Result<VoicemailBox, AppError> {
+        if let Some(pin) = &request.pin {
+            validate_pin(pin)?;
+        }
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE voicemail_boxes
+            SET pin = COALESCE($1, pin),
+                email = COALESCE($2, email),
+                owner_id = COALESCE($3, owner_id),
+                max_messages = COALESCE($4, max_messages),
+                updated_at = $5
+            WHERE id = $6
+            "#,
+            request.pin,
+            request.email,
+            request.owner_id,
+            request.max_messages,
+            Utc::now(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Mailbox not found".into()));
+        }
+
+        self.get_mailbox(id).await
+    }
+
+    pub async fn delete_mailbox(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM voicemail_boxes WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Mailbox not found".into()));
+        }
+
+        // Message rows are gone with the mailbox; the audio files follow
+        let dir = self.mailbox_dir(id);
+        if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove voicemail directory {}: {}", dir.display(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_greeting(
+        &self,
+        id: Uuid,
+        content_type: &str,
+        data: &[u8],
+    ) -> Result<VoicemailBox, AppError> {
+        if audio_extension(content_type) != Some("wav") {
+            return Err(AppError::Validation("Greeting must be a WAV file".into()));
+        }
+        if data.is_empty() {
+            return Err(AppError::Validation("Greeting is empty".into()));
+        }
+
+        let mailbox = self.get_mailbox(id).await?;
+        write_file(&self.mailbox_dir(mailbox.id).join(GREETING_FILE), data).await?;
+        // Asterisk plays the copy in its own spool
+        write_file(&self.spool_mailbox_dir(&mailbox)?.join(SPOOL_GREETING_FILE), data).await?;
+
+        sqlx::query!(
+            "UPDATE voicemail_boxes SET has_greeting = TRUE, updated_at = $1 WHERE id = $2",
+            Utc::now(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_mailbox(id).await
+    }
+
+    // Messages
+    pub async fn list_messages(&self, mailbox_id: Uuid) -> Result<Vec<VoicemailMessage>, AppError> {
+        let messages = sqlx::query_as!(
+            VoicemailMessage,
+            r#"
+            SELECT id, mailbox_id, caller_id, duration, file_path, content_type,
+                   size_bytes, read, received_at
+            FROM voicemail_messages
+            WHERE mailbox_id = $1
+            ORDER BY received_at DESC
+            "#,
+            mailbox_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    pub async fn get_message(
+        &self,
+        mailbox_id: Uuid,
+        id: Uuid,
+    ) -> Result<VoicemailMessage, AppError> {
+        let message = sqlx::query_as!(
+            VoicemailMessage,
+            r#"
+            SELECT id, mailbox_id, caller_id, duration, file_path, content_type,
+                   size_bytes, read, received_at
+            FROM voicemail_messages
+            WHERE id = $1 AND mailbox_id = $2
+            "#,
+            id,
+            mailbox_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Message not found".into()))?;
+
+        Ok(message)
+    }
+
+    pub async fn set_message_read(
+        &self,
+        mailbox_id: Uuid,
+        id: Uuid,
+        read: bool,
+    ) -> Result<VoicemailMessage, AppError> {
+        let result = sqlx::query!(
+            "UPDATE voicemail_messages SET read = $1 WHERE id = $2 AND mailbox_id = $3",
+            read,
+            id,
+            mailbox_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Message not found".into()));
+        }
+
+        self.get_message(mailbox_id, id).await
+    }
+
+    pub async fn delete_message(&self, mailbox_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let message = self.get_message(mailbox_id, id).await?;
+
+        sqlx::query!("DELETE FROM voicemail_messages WHERE id = $1", message.id)
+            .execute(&self.pool)
+            .await?;
+
+        if let Err(e) = tokio::fs::remove_file(&message.file_path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove voicemail file {}: {}", message.file_path, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stores an uploaded message. See `store_message`.
+    pub async fn deposit_message(
+        &self,
+        mailbox_id: Uuid,
+        query: DepositVoicemailQuery,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<VoicemailMessage, AppError> {
+        let extension = audio_extension(content_type)
+            .ok_or_else(|| AppError::Validation(format!("Unsupported audio type: {}", content_type)))?;
+        if data.is_empty() {
+            return Err(AppError::Validation("Message audio is empty".into()));
+        }
+
+        let mailbox = self.get_mailbox(mailbox_id).await?;
+        self.store_message(
+            &mailbox,
+            &query.caller_id,
+            query.duration,
+            content_type,
+            extension,
+            data,
+            Utc::now(),
+            None,
+        )
+        .await
+    }
+
+    /// Imports the new messages Asterisk recorded for a mailbox, as announced
+    /// by a `MessageWaiting` event. The spool copies stay where they are so
+    /// the phone can still play them; `spool_msg_id` keeps each message from
+    /// being imported twice. Returns how many messages were imported.
+    pub async fn import_spool(&self, extension_number: &str) -> Result<usize, AppError> {
+        let mailbox_id = sqlx::query_scalar!(
+            r#"
+            SELECT b.id
+            FROM voicemail_boxes b
+            JOIN pbx_extensions e ON e.id = b.extension_id
+            WHERE e.extension_number = $1
+            "#,
+            extension_number
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(mailbox_id) = mailbox_id else {
+            return Ok(0);
+        };
+        let mailbox = self.get_mailbox(mailbox_id).await?;
+
+        let inbox = self.spool_mailbox_dir(&mailbox)?.join("INBOX");
+        let mut entries = match tokio::fs::read_dir(&inbox).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(AppError::Internal(format!("Failed to read {}: {}", inbox.display(), e))),
+        };
+        let mut descriptions = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", inbox.display(), e)))?
+        {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("txt") {
+                descriptions.push(path);
+            }
+        }
+        descriptions.sort();
+
+        let mut imported = 0;
+        for description in descriptions {
+            let Ok(text) = tokio::fs::read_to_string(&description).await else {
+                continue;
+            };
+            let Some(spooled) = parse_spool_message(&text) else {
+                tracing::warn!("Skipping unreadable voicemail description {}", description.display());
+                continue;
+            };
+            let known = sqlx::query!(
+                "SELECT id FROM voicemail_messages WHERE mailbox_id = $1 AND spool_msg_id = $2",
+                mailbox.id,
+                spooled.msg_id
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+            if known.is_some() {
+                continue;
+            }
+
+            // voicemail.conf records WAV only
+            let audio = description.with_extension("wav");
+            let data = match tokio::fs::read(&audio).await {
+                Ok(data) if !data.is_empty() => data,
+                _ => continue,
+            };
+            let stored = self
+                .store_message(
+                    &mailbox,
+                    &spooled.caller_id,
+                    spooled.duration,
+                    "audio/wav",
+                    "wav",
+                    data,
+                    spooled.received_at,
+                    Some(&spooled.msg_id),
+                )
+                .await;
+            match stored {
+                Ok(_) => imported += 1,
+                // Later messages would not fit either; they are picked up
+                // once the user deletes some
+                Err(AppError::Validation(reason)) => {
+                    tracing::warn!(
+                        "Stopped importing voicemail for mailbox {}: {}",
+                        mailbox.extension_number,
+                        reason
+                    );
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(imported)
+    }
+
+    /// Imports every mailbox, for messages left while no AMI session was
+    /// listening for `MessageWaiting`.
+    pub async fn import_all_spools(&self) -> Result<usize, AppError> {
+        let mut imported = 0;
+        for mailbox in self.list_mailboxes().await? {
+            match self.import_spool(&mailbox.extension_number).await {
+                Ok(count) => imported += count,
+                Err(e) => tracing::warn!(
+                    "Failed to import voicemail for mailbox {}: {}",
+                    mailbox.extension_number,
+                    e
+                ),
+            }
+        }
+        Ok(imported)
+    }
+
+    /// Stores a new message on disk, records it and emails it to the mailbox
+    /// address when one is set. Email failures do not lose the message.
+    #[allow(clippy::too_many_arguments)]
+    async fn store_message(
+        &self,
+        mailbox: &VoicemailBox,
+        caller_id: &str,
+        duration: i32,
+        content_type: &str,
+        extension: &str,
+        data: Vec<u8>,
+        received_at: DateTime<Utc>,
+        spool_msg_id: Option<&str>,
+    ) -> Result<VoicemailMessage, AppError> {
+        let count = sqlx::query!(
+            r#"SELECT COUNT(*) as "count!" FROM voicemail_messages WHERE mailbox_id = $1"#,
+            mailbox.id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count;
+        if count >= i64::from(mailbox.max_messages) {
+            return Err(AppError::Validation("Mailbox is full".into()));
+        }
+
+        let id = Uuid::new_v4();
+        let path = self
+            .mailbox_dir(mailbox.id)
+            .join(format!("{}.{}", id, extension));
+        write_file(&path, &data).await?;
+
+        let file_path = path.to_string_lossy().to_string();
+        let inserted = sqlx::query_as!(
+            VoicemailMessage,
+            r#"
+            INSERT INTO voicemail_messages (
+                id, mailbox_id, caller_id, duration, file_path, content_type, size_bytes,
+                received_at, spool_msg_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, mailbox_id, caller_id, duration, file_path, content_type,
+                      size_bytes, read, received_at
+            "#,
+            id,
+            mailbox.id,
+            caller_id,
+            duration,
+            file_path,
+            content_type,
+            data.len() as i64,
+            received_at,
+            spool_msg_id,
+        )
+        .fetch_one(&self.pool)
+        .await;
+
+        let message = match inserted {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(e.into());
+            }
+        };
+
+        if let Some(address) = &mailbox.email {
+            if let Err(e) = self.email_message(mailbox, address, &message, data).await {
+                tracing::warn!(
+                    "Failed to email voicemail {} to {}: {}",
+                    message.id,
+                    address,
+                    e
+                );
+            }
+        }
+
+        Ok(message)
+    }
+
+    async fn email_message(
+        &self,
+        mailbox: &VoicemailBox,
+        address: &str,
+        message: &VoicemailMessage,
+        data: Vec<u8>,
+    ) -> Result<(), AppError> {
+        let email_service = EmailService::new(self.pool.clone())?;
+        let extension = Path::new(&message.file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("wav");
+
+        let subject = format!(
+            "New voicemail from {} for extension {}",
+            message.caller_id, mailbox.extension_number
+        );
+        let body = format!(
+            "You have a new voicemail in mailbox {} ({}).\n\nFrom: {}\nDuration: {} seconds\nReceived: {}\n",
+            mailbox.extension_number,
+            mailbox.extension_name,
+            message.caller_id,
+            message.duration,
+            message.received_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        );
+
+        email_service
+            .send_with_attachment(
+                address,
+                &subject,
+                &body,
+                OutgoingAttachment {
+                    filename: format!("voicemail-{}.{}", message.received_at.format("%Y%m%d-%H%M%S"), extension),
+                    content_type: message.content_type.clone(),
+                    data,
+                },
+            )
+            .await
+    }
+}
+
+async fn write_file(path: &Path, data: &[u8]) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to create {}: {}", path.display(), e)))?;
+    file.write_all(data)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to write {}: {}", path.display(), e)))?;
+    file.sync_all()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to sync {}: {}", path.display(), e)))?;
+
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use chrono::Utc;
+use md5::{Digest, Md5};
+
+/// How long a nonce issued in a challenge stays valid.
+const NONCE_LIFETIME_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Default)]
+pub struct DigestCredentials {
+    pub username: String,
+    pub realm: String,
+    pub nonce: String,
+    pub uri: String,
+    pub response: String,
+    pub algorithm: Option<String>,
+    pub qop: Option<String>,
+    pub nc: Option<String>,
+    pub cnonce: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DigestOutcome {
+    Valid,
+    StaleNonce,
+    Invalid,
+}
+
+pub fn md5_hex(input: &str) -> String {
+    format!("{:x}", Md5::digest(input.as_bytes()))
+}
+
+impl DigestCredentials {
+    /// Parses the value of an `Authorization: Digest ...` header.
+    pub fn parse(header: &str) -> Option<Self> {
+        let params = header.trim().strip_prefix("Digest")?.trim();
+        let mut credentials = DigestCredentials::default();
+
+        for (key, value) in split_params(params) {
+            match key.to_ascii_lowercase().as_str() {
+                "username" => credentials.username = value,
+                "realm" => credentials.realm = value,
+                "nonce" => credentials.nonce = value,
+                "uri" => credentials.uri = value,
+                "response" => credentials.response = value,
+                "algorithm" => credentials.algorithm = Some(value),
+                "qop" => credentials.qop = Some(value),
+                "nc" => credentials.nc = Some(value),
+                "cnonce" => credentials.cnonce = Some(value),
+                _ => {}
+            }
+        }
+
+        if credentials.username.is_empty() || credentials.nonce.is_empty() || credentials.response.is_empty() {
+            return None;
+        }
+
+        Some(credentials)
+    }
+
+    /// Computes the expected response for the given password (RFC 2617 section 3.2.2).
+    pub fn expected_response(&self, method: &str, password: &str) -> String {
+        let ha1 = md5_hex(&format!("{}:{}:{}", self.username, self.realm, password));
+        let ha2 = md5_hex(&format!("{}:{}", method, self.uri));
+
+        match (&self.qop, &self.nc, &self.cnonce) {
+            (Some(qop), Some(nc), Some(cnonce)) => md5_hex(&format!(
+                "{}:{}:{}:{}:{}:{}",
+                ha1, self.nonce, nc, cnonce, qop, ha2
+            )),
+            _ => md5_hex(&format!("{}:{}:{}", ha1, self.nonce, ha2)),
+        }
+    }
+}
+
+/// Issues and verifies stateless nonces. A nonce is the issue timestamp
+/// followed by an HMAC-like hash of that timestamp, so any registrar sharing
+/// the same key can check it without keeping a nonce table.
+pub struct NonceIssuer {
+    realm: String,
+    key: String,
+}
+
+impl NonceIssuer {
+    pub fn new(realm: String, key: String) -> Self {
+        Self { realm, key }
+    }
+
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    pub fn issue(&self) -> String {
+        self.nonce_at(Utc::now().timestamp())
+    }
+
+    fn nonce_at(&self, timestamp: i64) -> String {
+        let signature = md5_hex(&format!("{}:{}:{}", timestamp, self.realm, self.key));
+        format!("{:x}{}", timestamp, signature)
+    }
+
+    pub fn challenge(&self, stale: bool) -> String {
+        let mut challenge = format!(
+            "Digest realm=\"{}\", nonce=\"{}\", algorithm=MD5, qop=\"auth\"",
+            self.realm,
+            self.issue()
+        );
+        if stale {
+            challenge.push_str(", stale=true");
+        }
+        challenge
+    }
+
+    /// Checks the credentials against the request they were sent with. The
+    /// digest URI must name the Request-URI so a response captured for one
+    /// target can't be replayed against another.
+    pub fn verify(
+        &self,
+        credentials: &DigestCredentials,
+        method: &str,
+        request_uri: &str,
+        password: &str,
+    ) -> DigestOutcome {
+        if credentials.realm != self.realm || credentials.uri != request_uri {
+            return DigestOutcome::Invalid;
+        }
+        if let Some(algorithm) = &credentials.algorithm {
+            if !algorithm.eq_ignore_ascii_case("MD5") {
+                return DigestOutcome::Invalid;
+            }
+        }
+        if credentials.expected_response(method, password) != credentials.response.to_ascii_lowercase() {
+            return DigestOutcome::Invalid;
+        }
+
+        // The signature is always 32 hex characters; the timestamp is whatever precedes it
+        let nonce = &credentials.nonce;
+        if nonce.len() <= 32 {
+            return DigestOutcome::Invalid;
+        }
+        let (timestamp_hex, _) = nonce.split_at(nonce.len() - 32);
+        let timestamp = match i64::from_str_radix(timestamp_hex, 16) {
+            Ok(timestamp) => timestamp,
+            Err(_) => return DigestOutcome::Invalid,
+        };
+        if self.nonce_at(timestamp) != *nonce {
+            return DigestOutcome::Invalid;
+        }
+        if Utc::now().timestamp() - timestamp > NONCE_LIFETIME_SECS {
+            return DigestOutcome::StaleNonce;
+        }
+
+        DigestOutcome::Valid
+    }
+}
+
+fn split_params(params: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    let mut push = |segment: &str| {
+        if let Some((key, value)) = segment.split_once('=') {
+            result.push((key.trim().to_string(), value.trim().trim_matches('"').to_string()));
+        }
+    };
+    for (index, ch) in params.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                push(&params[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    push(&params[start..]);
+    result
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+
+use crate::error::AppError;
+
+/// Largest request accepted over either transport; also bounds Content-Length.
+pub const MAX_MESSAGE_SIZE: usize = 65_535;
+
+#[derive(Debug, Clone)]
+pub struct SipRequest {
+    pub method: String,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SipResponse {
+    pub status_code: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactHeader {
+    pub uri: String,
+    pub expires: Option<u32>,
+}
+
+// Compact header forms from RFC 3261 section 7.3.3
+fn expand_header_name(name: &str) -> &str {
+    match name {
+        "v" | "V" => "Via",
+        "f" | "F" => "From",
+        "t" | "T" => "To",
+        "i" | "I" => "Call-ID",
+        "m" | "M" => "Contact",
+        "l" | "L" => "Content-Length",
+        "c" | "C" => "Content-Type",
+        _ => name,
+    }
+}
+
+impl SipRequest {
+    pub fn parse(data: &[u8]) -> Result<Self, AppError> {
+        let header_end = find_header_end(data)
+            .ok_or_else(|| AppError::Validation("Incomplete SIP message".into()))?;
+        let head = std::str::from_utf8(&data[..header_end])
+            .map_err(|_| AppError::Validation("SIP headers are not valid UTF-8".into()))?;
+
+        let mut lines = head.split("\r\n");
+        let request_line = lines
+            .next()
+            .ok_or_else(|| AppError::Validation("Missing SIP request line".into()))?;
+        let mut parts = request_line.split_whitespace();
+        let (method, uri, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(uri), Some(version)) => (method, uri, version),
+            _ => return Err(AppError::Validation("Malformed SIP request line".into())),
+        };
+        if version != "SIP/2.0" {
+            return Err(AppError::Validation(format!("Unsupported SIP version: {}", version)));
+        }
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in lines {
+            if line.is_empty() {
+                continue;
+            }
+            // Header folding: continuation lines start with whitespace
+            if line.starts_with(' ') || line.starts_with('\t') {
+                if let Some(last) = headers.last_mut() {
+                    last.1.push(' ');
+                    last.1.push_str(line.trim());
+                }
+                continue;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| AppError::Validation(format!("Malformed SIP header: {}", line)))?;
+            headers.push((
+                expand_header_name(name.trim()).to_string(),
+                value.trim().to_string(),
+            ));
+        }
+
+        let body_start = header_end + 4;
+        let content_length = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        if content_length > MAX_MESSAGE_SIZE {
+            return Err(AppError::Validation("Content-Length exceeds the maximum message size".into()));
+        }
+        let body_end = (body_start + content_length).min(data.len());
+
+        Ok(Self {
+            method: method.to_uppercase(),
+            uri: uri.to_string(),
+            headers,
+            body: data[body_start.min(data.len())..body_end].to_vec(),
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn cseq(&self) -> Option<(u32, &str)> {
+        let value = self.header("CSeq")?;
+        let (number, method) = value.split_once(' ')?;
+        Some((number.trim().parse().ok()?, method.trim()))
+    }
+
+    /// Returns the user part of the To header URI, which for REGISTER is the
+    /// address-of-record being bound.
+    pub fn to_user(&self) -> Option<String> {
+        self.header("To").and_then(|value| uri_user(extract_uri(value)))
+    }
+
+    pub fn contacts(&self) -> Vec<ContactHeader> {
+        self.header_values("Contact")
+            .into_iter()
+            .flat_map(split_header_list)
+            .filter_map(|contact| {
+                let contact = contact.trim();
+                if contact == "*" {
+                    return Some(ContactHeader {
+                        uri: "*".into(),
+                        expires: None,
+                    });
+                }
+                let uri = extract_uri(contact);
+                if uri.is_empty() {
+                    return None;
+                }
+                // Parameters after the closing '>' belong to the header, not the URI
+                let params = contact.rsplit_once('>').map(|(_, p)| p).unwrap_or("");
+                let expires = header_param(params, "expires").and_then(|v| v.parse().ok());
+                Some(ContactHeader {
+                    uri: uri.to_string(),
+                    expires,
+                })
+            })
+            .collect()
+    }
+
+    /// Builds a response that echoes the transaction headers of this request.
+    pub fn response(&self, status_code: u16, reason: &str) -> SipResponse {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for via in self.header_values("Via") {
+            headers.push(("Via".into(), via.to_string()));
+        }
+        if let Some(from) = self.header("From") {
+            headers.push(("From".into(), from.to_string()));
+        }
+        if let Some(to) = self.header("To") {
+            let to = if status_code > 100 && !to.contains("tag=") {
+                format!("{};tag={}", to, &uuid::Uuid::new_v4().simple().to_string()[..10])
+            } else {
+                to.to_string()
+            };
+            headers.push(("To".into(), to));
+        }
+        if let Some(call_id) = self.header("Call-ID") {
+            headers.push(("Call-ID".into(), call_id.to_string()));
+        }
+        if let Some(cseq) = self.header("CSeq") {
+            headers.push(("CSeq".into(), cseq.to_string()));
+        }
+
+        SipResponse {
+            status_code,
+            reason: reason.to_string(),
+            headers,
+        }
+    }
+}
+
+impl SipResponse {
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl fmt::Display for SipResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SIP/2.0 {} {}\r\n", self.status_code, self.reason)?;
+        for (name, value) in &self.headers {
+            write!(f, "{}: {}\r\n", name, value)?;
+        }
+        write!(f, "Content-Length: 0\r\n\r\n")
+    }
+}
+
+/// Returns the offset of the blank line separating headers from the body.
+pub fn find_header_end(data: &[u8]) -> Option<usize> {
+    data.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+/// Returns the full length of the first message in a stream buffer, or `None`
+/// if more data is needed. Used to frame SIP over TCP.
+pub fn message_length(data: &[u8]) -> Option<usize> {
+    let header_end = find_header_end(data)?;
+    let head = String::from_utf8_lossy(&data[..header_end]);
+    let content_length = head
+        .split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| {
+            let name = name.trim();
+            name.eq_ignore_ascii_case("Content-Length") || name.eq_ignore_ascii_case("l")
+        })
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    // An absurd length never completes; the reader drops the connection once
+    // the buffer passes the size limit
+    let total = (header_end + 4).checked_add(content_length)?;
+    (data.len() >= total).then_some(total)
+}
+
+/// Extracts the URI from a name-addr (`"Name" <sip:1000@host>;tag=x`) or a
+/// bare addr-spec (`sip:1000@host;tag=x`).
+pub fn extract_uri(value: &str) -> &str {
+    if let Some(start) = value.find('<') {
+        let rest = &value[start + 1..];
+        return rest.split('>').next().unwrap_or(rest).trim();
+    }
+    value.split(';').next().unwrap_or(value).trim()
+}
+
+pub fn uri_user(uri: &str) -> Option<String> {
+    let without_scheme = uri
+        .strip_prefix("sips:")
+        .or_else(|| uri.strip_prefix("sip:"))
+        .unwrap_or(uri);
+    let (user, _) = without_scheme.split_once('@')?;
+    let user = user.split(';').next().unwrap_or(user);
+    let user = user.split(':').next().unwrap_or(user);
+    (!user.is_empty()).then(|| user.to_string())
+}
+
+/// Looks up a `;name=value` parameter in a header parameter list.
+pub fn header_param<'a>(params: &'a str, name: &str) -> Option<&'a str> {
+    params
+        .split(';')
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+}
+
+/// Splits a comma separated header list while respecting quoted strings and
+/// angle-bracketed URIs.
+fn split_header_list(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, ch) in value.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            '<' if !quoted => depth += 1,
+            '>' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                items.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
@@ -0,0 +1,331 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use sqlx::PgPool;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UdpSocket},
+};
+
+use crate::{
+    error::AppError,
+    models::pbx::{ExtensionType, SipTransport, UpsertRegistrationRequest},
+    services::pbx::PbxService,
+    sip::{
+        digest::{DigestCredentials, DigestOutcome, NonceIssuer},
+        message::{message_length, SipRequest, SipResponse, MAX_MESSAGE_SIZE},
+    },
+};
+
+const DEFAULT_EXPIRES: u32 = 3600;
+const MIN_EXPIRES: u32 = 60;
+const MAX_EXPIRES: u32 = 7200;
+const SERVER_HEADER: &str = "OrionTel Registrar";
+
+pub struct SipRegistrar {
+    service: PbxService,
+    nonces: NonceIssuer,
+}
+
+impl SipRegistrar {
+    pub fn new(pool: PgPool, realm: String, nonce_secret: String) -> Self {
+        Self {
+            service: PbxService::new(pool),
+            nonces: NonceIssuer::new(realm, nonce_secret),
+        }
+    }
+
+    pub fn from_env(pool: PgPool) -> Self {
+        let realm = std::env::var("SIP_REALM").unwrap_or_else(|_| "oriontel".into());
+        let nonce_secret = std::env::var("SIP_NONCE_SECRET")
+            .or_else(|_| std::env::var("JWT_SECRET"))
+            .expect("SIP_NONCE_SECRET or JWT_SECRET must be set");
+        Self::new(pool, realm, nonce_secret)
+    }
+
+    pub async fn run_udp(self: Arc<Self>, addr: SocketAddr) -> std::io::Result<()> {
+        let socket = Arc::new(UdpSocket::bind(addr).await?);
+        tracing::info!("SIP registrar listening on udp://{}", addr);
+
+        let mut buffer = vec![0u8; MAX_MESSAGE_SIZE];
+        loop {
+            let (len, source) = socket.recv_from(&mut buffer).await?;
+            let datagram = buffer[..len].to_vec();
+            let registrar = self.clone();
+            let socket = socket.clone();
+            tokio::spawn(async move {
+                if let Some(response) = registrar.handle_datagram(&datagram, source, SipTransport::Udp).await {
+                    if let Err(e) = socket.send_to(&response.to_bytes(), source).await {
+                        tracing::warn!("Failed to send SIP response to {}: {}", source, e);
+                    }
+                }
+            });
+        }
+    }
+
+    pub async fn run_tcp(self: Arc<Self>, addr: SocketAddr) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("SIP registrar listening on tcp://{}", addr);
+
+        loop {
+            let (stream, source) = listener.accept().await?;
+            let registrar = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = registrar.serve_tcp_connection(stream, source).await {
+                    tracing::debug!("SIP TCP connection from {} closed: {}", source, e);
+                }
+            });
+        }
+    }
+
+    async fn serve_tcp_connection(&self, mut stream: TcpStream, source: SocketAddr) -> std::io::Result<()> {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+
+            // Keep-alive CRLFs (RFC 5626) are answered with a single CRLF
+            while buffer.starts_with(b"\r\n\r\n") {
+                buffer.drain(..4);
+                stream.write_all(b"\r\n").await?;
+            }
+
+            while let Some(length) = message_length(&buffer) {
+                let message: Vec<u8> = buffer.drain(..length).collect();
+                if let Some(response) = self.handle_datagram(&message, source, SipTransport::Tcp).await {
+                    stream.write_all(&response.to_bytes()).await?;
+                }
+            }
+
+            if buffer.len() > MAX_MESSAGE_SIZE {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "SIP message exceeds maximum size",
+                ));
+            }
+        }
+    }
+
+    /// Periodically drops bindings whose expiry has passed.
+    pub async fn run_expiry_sweeper(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            match self.service.purge_expired_registrations().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Expired {} SIP registrations", count),
+                Err(e) => tracing::error!("Failed to purge SIP registrations: {}", e),
+            }
+        }
+    }
+
+    async fn handle_datagram(
+        &self,
+        data: &[u8],
+        source: SocketAddr,
+        transport: SipTransport,
+    ) -> Option<SipResponse> {
+        // Responses and keep-alives are not requests; ignore them
+        if data.starts_with(b"SIP/2.0") || data.iter().all(|b| b.is_ascii_whitespace()) {
+            return None;
+        }
+
+        let request = match SipRequest::parse(data) {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::debug!("Discarding malformed SIP message from {}: {}", source, e);
+                return None;
+            }
+        };
+
+        Some(self.handle(&request, source, transport).await)
+    }
+
+    pub async fn handle(
+        &self,
+        request: &SipRequest,
+        source: SocketAddr,
+        transport: SipTransport,
+    ) -> SipResponse {
+        let response = match request.method.as_str() {
+            "REGISTER" => match self.handle_register(request, source, transport).await {
+                Ok(response) => response,
+                Err(AppError::Validation(message)) => {
+                    tracing::debug!("Rejected REGISTER from {}: {}", source, message);
+                    request.response(400, "Bad Request")
+                }
+                Err(e) => {
+                    tracing::error!("REGISTER from {} failed: {}", source, e);
+                    request.response(500, "Server Internal Error")
+                }
+            },
+            "OPTIONS" => request
+                .response(200, "OK")
+                .with_header("Allow", "REGISTER, OPTIONS"),
+            _ => request
+                .response(405, "Method Not Allowed")
+                .with_header("Allow", "REGISTER, OPTIONS"),
+        };
+
+        response.with_header("Server", SERVER_HEADER)
+    }
+
+    async fn handle_register(
+        &self,
+        request: &SipRequest,
+        source: SocketAddr,
+        transport: SipTransport,
+    ) -> Result<SipResponse, AppError> {
+        let call_id = request
+            .header("Call-ID")
+            .ok_or_else(|| AppError::Validation("Missing Call-ID header".into()))?;
+        let (cseq, _) = request
+            .cseq()
+            .ok_or_else(|| AppError::Validation("Missing or malformed CSeq header".into()))?;
+        let aor_user = request
+            .to_user()
+            .ok_or_else(|| AppError::Validation("Missing user in To header".into()))?;
+
+        let extension = match self.service.get_extension_by_number(&aor_user).await {
+            Ok(extension) if extension.extension_type == ExtensionType::Sip => extension,
+            Ok(_) | Err(AppError::NotFound(_)) => return Ok(request.response(404, "Not Found")),
+            Err(e) => return Err(e),
+        };
+
+        let secret = match extension.config_data.get("secret").and_then(|v| v.as_str()) {
+            Some(secret) => secret,
+            None => {
+                tracing::warn!(
+                    "Extension {} has no SIP secret configured; refusing registration",
+                    extension.extension_number
+                );
+                return Ok(request.response(403, "Forbidden"));
+            }
+        };
+
+        // Authenticate with digest credentials
+        let credentials = request
+            .header("Authorization")
+            .and_then(DigestCredentials::parse);
+        let credentials = match credentials {
+            Some(credentials) => credentials,
+            None => {
+                return Ok(request
+                    .response(401, "Unauthorized")
+                    .with_header("WWW-Authenticate", self.nonces.challenge(false)));
+            }
+        };
+
+        let auth_username = extension
+            .config_data
+            .get("auth_username")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&extension.extension_number);
+        if credentials.username != auth_username {
+            return Ok(request.response(403, "Forbidden"));
+        }
+
+        match self.nonces.verify(&credentials, &request.method, &request.uri, secret) {
+            DigestOutcome::Valid => {}
+            DigestOutcome::StaleNonce => {
+                return Ok(request
+                    .response(401, "Unauthorized")
+                    .with_header("WWW-Authenticate", self.nonces.challenge(true)));
+            }
+            DigestOutcome::Invalid => {
+                tracing::warn!(
+                    "Digest authentication failed for extension {} from {}",
+                    extension.extension_number,
+                    source
+                );
+                return Ok(request.response(403, "Forbidden"));
+            }
+        }
+
+        // Apply contact bindings (RFC 3261 section 10.3)
+        let header_expires = request
+            .header("Expires")
+            .and_then(|v| v.trim().parse::<u32>().ok());
+        let contacts = request.contacts();
+
+        let wildcard = contacts.iter().any(|contact| contact.uri == "*");
+        if wildcard && (contacts.len() > 1 || header_expires != Some(0)) {
+            return Ok(request.response(400, "Bad Request"));
+        }
+
+        // Validate every contact before touching any binding so a rejected
+        // request leaves the registration state unchanged
+        let bindings: Vec<_> = contacts
+            .iter()
+            .filter(|contact| contact.uri != "*")
+            .map(|contact| {
+                let expires = contact.expires.or(header_expires).unwrap_or(DEFAULT_EXPIRES);
+                (contact, expires)
+            })
+            .collect();
+        if bindings
+            .iter()
+            .any(|(_, expires)| *expires != 0 && *expires < MIN_EXPIRES)
+        {
+            return Ok(request
+                .response(423, "Interval Too Brief")
+                .with_header("Min-Expires", MIN_EXPIRES.to_string()));
+        }
+
+        // A binding from the same Call-ID may only be changed by a later CSeq
+        let existing = self.service.list_registrations(extension.id).await?;
+        let out_of_order = existing.iter().any(|binding| {
+            let affected = wildcard
+                || bindings
+                    .iter()
+                    .any(|(contact, _)| contact.uri == binding.contact_uri);
+            affected && binding.call_id == call_id && binding.cseq as i64 >= cseq as i64
+        });
+        if out_of_order {
+            return Ok(request.response(500, "Server Internal Error"));
+        }
+
+        if wildcard {
+            self.service.remove_all_registrations(extension.id).await?;
+        }
+
+        for (contact, expires) in bindings {
+            if expires == 0 {
+                self.service.remove_registration(extension.id, &contact.uri).await?;
+                continue;
+            }
+
+            let expires = expires.min(MAX_EXPIRES);
+            self.service
+                .upsert_registration(UpsertRegistrationRequest {
+                    extension_id: extension.id,
+                    contact_uri: contact.uri.clone(),
+                    user_agent: request.header("User-Agent").map(str::to_string),
+                    source_address: source.to_string(),
+                    transport: transport.clone(),
+                    call_id: call_id.to_string(),
+                    cseq: cseq as i32,
+                    expires_at: Utc::now() + chrono::Duration::seconds(expires as i64),
+                })
+                .await?;
+        }
+
+        // The 200 OK lists every current binding for the address-of-record
+        let now = Utc::now();
+        let mut response = request.response(200, "OK").with_header("Date", now.to_rfc2822());
+        for binding in self.service.list_registrations(extension.id).await? {
+            let remaining = (binding.expires_at - now).num_seconds().max(0);
+            response = response.with_header(
+                "Contact",
+                format!("<{}>;expires={}", binding.contact_uri, remaining),
+            );
+        }
+
+        Ok(response)
+    }
+}
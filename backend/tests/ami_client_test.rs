@@ -0,0 +1,193 @@
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+use oriontel_backend::{
+    ami::{
+        call_tracker::{
+            channel_extension, interpret_channel_event, interpret_event, live_uniqueids, status_for_hangup,
+            CallEvent, ChannelEvent,
+        },
+        client::{AmiClient, AmiConfig, AmiMessage},
+    },
+    models::pbx::CallStatus,
+};
+
+/// Starts a fake AMI server that accepts one login and then replays `events`.
+async fn spawn_fake_ami(accept_login: bool, events: Vec<&'static str>) -> AmiConfig {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+
+        write_half.write_all(b"Asterisk Call Manager/5.0.1\r\n").await.unwrap();
+
+        // Read the Login action up to the blank line
+        let mut action_id = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            if let Some(id) = line.strip_prefix("ActionID: ") {
+                action_id = id.trim().to_string();
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+
+        let response = if accept_login {
+            format!("Response: Success\r\nActionID: {}\r\nMessage: Authentication accepted\r\n\r\n", action_id)
+        } else {
+            format!("Response: Error\r\nActionID: {}\r\nMessage: Authentication failed\r\n\r\n", action_id)
+        };
+        write_half.write_all(response.as_bytes()).await.unwrap();
+
+        for event in events {
+            write_half.write_all(event.as_bytes()).await.unwrap();
+        }
+    });
+
+    AmiConfig {
+        host: "127.0.0.1".into(),
+        port,
+        username: "oriontel".into(),
+        secret: "secret".into(),
+    }
+}
+
+#[tokio::test]
+async fn test_login_and_receive_call_events() {
+    let config = spawn_fake_ami(
+        true,
+        vec![
+            "Event: Newchannel\r\nChannel: PJSIP/1001-00000001\r\nCallerIDNum: 1001\r\nExten: 1002\r\nUniqueid: 1700000000.1\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: Newchannel\r\nChannel: PJSIP/1002-00000002\r\nCallerIDNum: 1002\r\nExten: s\r\nUniqueid: 1700000000.2\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: BridgeEnter\r\nChannel: PJSIP/1002-00000002\r\nUniqueid: 1700000000.2\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: Hangup\r\nChannel: PJSIP/1001-00000001\r\nUniqueid: 1700000000.1\r\nLinkedid: 1700000000.1\r\nCause: 16\r\n\r\n",
+        ],
+    )
+    .await;
+
+    let mut client = AmiClient::connect(&config).await.unwrap();
+
+    let mut call_events = Vec::new();
+    for _ in 0..4 {
+        let event = client.next_event().await.unwrap();
+        if let Some(call_event) = interpret_event(&event) {
+            call_events.push(call_event);
+        }
+    }
+
+    assert_eq!(
+        call_events,
+        vec![
+            CallEvent::Started {
+                uniqueid: "1700000000.1".into(),
+                caller_id: "1001".into(),
+                recipient_id: "1002".into(),
+            },
+            CallEvent::Answered {
+                uniqueids: vec!["1700000000.1".into()],
+            },
+            CallEvent::Ended {
+                uniqueid: "1700000000.1".into(),
+                cause: 16,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_channel_events_follow_the_answering_phone() {
+    // An external call: the call record follows the trunk channel, while
+    // presence needs the phone that answers
+    let config = spawn_fake_ami(
+        true,
+        vec![
+            "Event: Newchannel\r\nChannel: PJSIP/provider-00000001\r\nCallerIDNum: 0301234567\r\nExten: 4930123400\r\nUniqueid: 1700000000.1\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: Newchannel\r\nChannel: PJSIP/1002-00000002\r\nCallerIDNum: 1002\r\nExten: s\r\nUniqueid: 1700000000.2\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: Newstate\r\nChannel: PJSIP/1002-00000002\r\nChannelState: 5\r\nUniqueid: 1700000000.2\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: Newstate\r\nChannel: PJSIP/1002-00000002\r\nChannelState: 6\r\nUniqueid: 1700000000.2\r\nLinkedid: 1700000000.1\r\n\r\n",
+            "Event: Hangup\r\nChannel: PJSIP/1002-00000002\r\nUniqueid: 1700000000.2\r\nLinkedid: 1700000000.1\r\nCause: 16\r\n\r\n",
+        ],
+    )
+    .await;
+
+    let mut client = AmiClient::connect(&config).await.unwrap();
+
+    let mut channel_events = Vec::new();
+    for _ in 0..5 {
+        let event = client.next_event().await.unwrap();
+        if let Some(channel_event) = interpret_channel_event(&event) {
+            channel_events.push(channel_event);
+        }
+    }
+
+    assert_eq!(
+        channel_events,
+        vec![
+            ChannelEvent::Opened {
+                uniqueid: "1700000000.1".into(),
+                extension_number: "provider".into(),
+            },
+            ChannelEvent::Opened {
+                uniqueid: "1700000000.2".into(),
+                extension_number: "1002".into(),
+            },
+            ChannelEvent::Answered {
+                uniqueid: "1700000000.2".into(),
+            },
+            ChannelEvent::Closed {
+                uniqueid: "1700000000.2".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_live_uniqueids() {
+    let channel = |uniqueid: &str, linkedid: &str| AmiMessage {
+        fields: vec![
+            ("Event".into(), "CoreShowChannel".into()),
+            ("Channel".into(), "PJSIP/1002-00000002".into()),
+            ("Uniqueid".into(), uniqueid.into()),
+            ("Linkedid".into(), linkedid.into()),
+        ],
+    };
+    let channels = vec![
+        channel("1700000000.2", "1700000000.1"),
+        channel("1700000000.3", "1700000000.1"),
+        AmiMessage {
+            fields: vec![("Event".into(), "CoreShowChannelsComplete".into())],
+        },
+    ];
+
+    assert_eq!(live_uniqueids(&channels), vec!["1700000000.1", "1700000000.2", "1700000000.3"]);
+}
+
+#[test]
+fn test_channel_extension() {
+    assert_eq!(channel_extension("PJSIP/1001-0000002a"), Some("1001"));
+    assert_eq!(channel_extension("IAX2/1001-12345"), Some("1001"));
+    assert_eq!(channel_extension("PJSIP/my-trunk-0000002b"), Some("my-trunk"));
+    assert_eq!(channel_extension("Local/1001@from-internal-00000001;1"), None);
+    assert_eq!(channel_extension("PJSIP/1001"), None);
+}
+
+#[tokio::test]
+async fn test_login_rejected() {
+    let config = spawn_fake_ami(false, vec![]).await;
+    assert!(AmiClient::connect(&config).await.is_err());
+}
+
+#[test]
+fn test_status_for_hangup() {
+    assert_eq!(status_for_hangup(true, 16), CallStatus::Completed);
+    assert_eq!(status_for_hangup(false, 17), CallStatus::Busy);
+    assert_eq!(status_for_hangup(false, 19), CallStatus::NoAnswer);
+    assert_eq!(status_for_hangup(false, 34), CallStatus::Failed);
+}
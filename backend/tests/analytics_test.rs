@@ -0,0 +1,90 @@
+use serde_json::json;
+
+use oriontel_backend::{
+    models::analytics::{CallCounts, CallDirection, DirectionStats},
+    services::analytics::{call_kpis, percentage, sum_counts},
+};
+
+fn counts(answered: i64, busy: i64, no_answer: i64, failed: i64, active: i64, talk_seconds: i64) -> CallCounts {
+    CallCounts {
+        total: answered + busy + no_answer + failed + active,
+        answered,
+        busy,
+        no_answer,
+        failed,
+        active,
+        talk_seconds,
+    }
+}
+
+#[test]
+fn test_percentage_rounds_to_two_decimals() {
+    assert_eq!(percentage(1, 3), Some(33.33));
+    assert_eq!(percentage(2, 3), Some(66.67));
+    assert_eq!(percentage(0, 5), Some(0.0));
+    assert_eq!(percentage(0, 0), None);
+}
+
+#[test]
+fn test_kpis_ignore_active_calls() {
+    let kpis = call_kpis(counts(6, 2, 1, 1, 5, 900));
+
+    assert_eq!(kpis.counts.total, 15);
+    assert_eq!(kpis.asr, Some(60.0));
+    assert_eq!(kpis.busy_rate, Some(20.0));
+    assert_eq!(kpis.no_answer_rate, Some(10.0));
+    assert_eq!(kpis.acd_seconds, Some(150.0));
+}
+
+#[test]
+fn test_kpis_without_finished_calls() {
+    let kpis = call_kpis(counts(0, 0, 0, 0, 2, 0));
+    assert_eq!(kpis.asr, None);
+    assert_eq!(kpis.busy_rate, None);
+    assert_eq!(kpis.acd_seconds, None);
+
+    let kpis = call_kpis(counts(0, 3, 1, 0, 0, 0));
+    assert_eq!(kpis.asr, Some(0.0));
+    assert_eq!(kpis.acd_seconds, None);
+}
+
+#[test]
+fn test_acd_rounds_to_tenths() {
+    let kpis = call_kpis(counts(3, 0, 0, 0, 0, 100));
+    assert_eq!(kpis.acd_seconds, Some(33.3));
+}
+
+#[test]
+fn test_sum_counts() {
+    let parts = [counts(1, 2, 0, 0, 1, 60), counts(4, 0, 3, 1, 0, 240)];
+    let sum = sum_counts(&parts);
+
+    assert_eq!(sum, counts(5, 2, 3, 1, 1, 300));
+    assert_eq!(sum_counts(&[]), CallCounts::default());
+}
+
+#[test]
+fn test_stats_serialize_flat() {
+    let stats = DirectionStats {
+        direction: CallDirection::Outbound,
+        kpis: call_kpis(counts(1, 1, 0, 0, 0, 45)),
+    };
+
+    assert_eq!(
+        serde_json::to_value(&stats).unwrap(),
+        json!({
+            "direction": "outbound",
+            "total": 2,
+            "answered": 1,
+            "busy": 1,
+            "no_answer": 0,
+            "failed": 0,
+            "active": 0,
+            "talk_seconds": 45,
+            "asr": 50.0,
+            "acd_seconds": 45.0,
+            "busy_rate": 50.0,
+            "no_answer_rate": 0.0
+        })
+    );
+}
@@ -0,0 +1,174 @@
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        asterisk::{ConfigSnapshot, RenderedConfigFile},
+        pbx::{ExtensionType, PbxExtension},
+        queue::{CallQueue, QueueMember, QueueResponse, QueueStrategy},
+        routing::RouteDestinationType,
+    },
+    services::asterisk_config::{
+        render_extensions_conf, render_pjsip_conf, render_queues_conf, write_config_set,
+    },
+};
+
+fn extension(number: &str, name: &str, extension_type: ExtensionType, config_data: serde_json::Value) -> PbxExtension {
+    PbxExtension {
+        id: Uuid::new_v4(),
+        extension_number: number.to_string(),
+        name: name.to_string(),
+        extension_type,
+        config_data,
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn snapshot() -> ConfigSnapshot {
+    ConfigSnapshot {
+        extensions: vec![
+            extension(
+                "1001",
+                "Alice",
+                ExtensionType::Sip,
+                json!({ "secret": "s3cret", "codecs": ["g722", "ulaw"], "nat": true }),
+            ),
+            extension("1002", "Bob", ExtensionType::Iax, json!({})),
+            extension(
+                "1003",
+                "Lobby",
+                ExtensionType::Custom,
+                json!({ "dial_string": "Local/500@ivr" }),
+            ),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_render_pjsip_conf() {
+    let config = render_pjsip_conf(&snapshot());
+
+    assert!(config.contains("[1001]\ntype=endpoint\n"));
+    assert!(config.contains("allow=g722,ulaw\n"));
+    assert!(config.contains("auth=1001-auth\n"));
+    assert!(config.contains("rtp_symmetric=yes\n"));
+    assert!(config.contains("[1001-auth]\ntype=auth\nauth_type=userpass\nusername=1001\npassword=s3cret\n"));
+    assert!(config.contains("[1001]\ntype=aor\n"));
+
+    // Only SIP extensions become PJSIP endpoints
+    assert!(!config.contains("[1002]"));
+    assert!(!config.contains("[1003]"));
+}
+
+#[test]
+fn test_render_extensions_conf() {
+    let config = render_extensions_conf(&snapshot());
+
+    assert!(config.contains("[from-internal]\n"));
+    assert!(config.contains(
+        "exten => 1001,1,NoOp(Alice)\n same => n,Gosub(call-handling,s,1(1001))\n same => n,Dial(PJSIP/1001,30)\n same => n,Gosub(call-handling,dialed,1(1001))\n"
+    ));
+    assert!(config.contains(" same => n,Dial(IAX2/1002,30)\n"));
+    assert!(config.contains(" same => n,Dial(Local/500@ivr,30)\n"));
+}
+
+#[test]
+fn test_values_cannot_inject_config_lines() {
+    let snapshot = ConfigSnapshot {
+        extensions: vec![extension(
+            "1004",
+            "Mallory",
+            ExtensionType::Sip,
+            json!({ "secret": "pw\n[evil]\ntype=endpoint;x" }),
+        )],
+        ..Default::default()
+    };
+
+    let config = render_pjsip_conf(&snapshot);
+    assert!(!config.contains("\n[evil]"));
+    assert!(config.contains("password=pw[evil]type=endpoint\\;x\n"));
+}
+
+#[test]
+fn test_render_queues() {
+    let queue = CallQueue {
+        id: Uuid::new_v4(),
+        name: "support".into(),
+        strategy: QueueStrategy::RoundRobin,
+        ring_timeout: 20,
+        max_wait_time: 120,
+        overflow_destination_type: Some(RouteDestinationType::Voicemail),
+        overflow_destination: Some("1001".into()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    let member = |number: &str, name: &str, logged_in: bool| QueueMember {
+        id: Uuid::new_v4(),
+        queue_id: queue.id,
+        extension_id: Uuid::new_v4(),
+        extension_number: number.into(),
+        extension_name: name.into(),
+        penalty: 0,
+        logged_in,
+        paused: false,
+        pause_reason: None,
+        updated_at: Utc::now(),
+    };
+    let snapshot = ConfigSnapshot {
+        queues: vec![QueueResponse {
+            members: vec![member("1001", "Alice", true), member("1002", "Bob", false)],
+            queue,
+        }],
+        ..Default::default()
+    };
+
+    let queues_conf = render_queues_conf(&snapshot);
+    assert!(queues_conf.contains("[support]\nstrategy=rrmemory\ntimeout=20\n"));
+    assert!(queues_conf.contains("member => Local/1001@from-internal/n,0,Alice\n"));
+    assert!(!queues_conf.contains("1002"));
+
+    let extensions_conf = render_extensions_conf(&snapshot);
+    assert!(extensions_conf.contains("[queues]\nexten => support,1,Answer()\n same => n,Queue(support,t,,,120)\n same => n,VoiceMail(1001@default,u)\n"));
+}
+
+fn config_file(name: &str, content: &str) -> RenderedConfigFile {
+    RenderedConfigFile {
+        name: name.to_string(),
+        content: content.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_write_config_set_is_all_or_nothing() {
+    let dir = std::env::temp_dir().join(format!("oriontel-config-{}", Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    tokio::fs::write(dir.join("pjsip.conf"), "old pjsip").await.unwrap();
+    tokio::fs::write(dir.join("extensions.conf"), "old extensions").await.unwrap();
+
+    // The second file cannot be written, so the first must not be replaced
+    let failing = vec![
+        config_file("pjsip.conf", "new pjsip"),
+        config_file("missing/extensions.conf", "new extensions"),
+    ];
+    assert!(write_config_set(&dir, &failing).await.is_err());
+    assert_eq!(tokio::fs::read_to_string(dir.join("pjsip.conf")).await.unwrap(), "old pjsip");
+    let mut entries = tokio::fs::read_dir(&dir).await.unwrap();
+    while let Some(entry) = entries.next_entry().await.unwrap() {
+        assert!(!entry.file_name().to_string_lossy().ends_with(".tmp"));
+    }
+
+    let files = vec![
+        config_file("pjsip.conf", "new pjsip"),
+        config_file("extensions.conf", "new extensions"),
+    ];
+    let written = write_config_set(&dir, &files).await.unwrap();
+    assert_eq!(written.len(), 2);
+    assert_eq!(tokio::fs::read_to_string(dir.join("pjsip.conf")).await.unwrap(), "new pjsip");
+    assert_eq!(tokio::fs::read_to_string(dir.join("extensions.conf")).await.unwrap(), "new extensions");
+
+    tokio::fs::remove_dir_all(&dir).await.unwrap();
+}
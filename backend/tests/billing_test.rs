@@ -0,0 +1,135 @@
+use std::str::FromStr;
+
+use chrono::{Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::billing::{CallCharge, Rate, UnratedReason},
+    services::billing::{
+        build_invoices, find_rate, rate_call, select_rate, talk_seconds, validate_currency, validate_prefix,
+    },
+};
+
+fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+fn rate(prefix: &str, per_minute: &str, fee: &str, increment: i32) -> Rate {
+    Rate {
+        id: Uuid::new_v4(),
+        rate_table_id: Uuid::new_v4(),
+        prefix: prefix.to_string(),
+        description: Some(format!("Prefix {}", prefix)),
+        rate_per_minute: dec(per_minute),
+        connection_fee: dec(fee),
+        billing_increment: increment,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn charge(extension: &str, currency: &str, prefix: &str, seconds: i32, cost: &str) -> CallCharge {
+    CallCharge {
+        call_id: Uuid::new_v4(),
+        extension_id: Uuid::nil(),
+        extension_number: extension.to_string(),
+        extension_name: format!("Ext {}", extension),
+        currency: currency.to_string(),
+        prefix: prefix.to_string(),
+        description: None,
+        billed_seconds: seconds,
+        cost: dec(cost),
+    }
+}
+
+#[test]
+fn test_find_rate_longest_prefix() {
+    let rates = vec![rate("44", "0.02", "0", 60), rate("447", "0.10", "0", 60), rate("1", "0.01", "0", 60)];
+
+    assert_eq!(find_rate(&rates, "447700900123").unwrap().prefix, "447");
+    assert_eq!(find_rate(&rates, "+442071234567").unwrap().prefix, "44");
+    assert_eq!(find_rate(&rates, "15551234").unwrap().prefix, "1");
+    assert!(find_rate(&rates, "33123456").is_none());
+}
+
+#[test]
+fn test_select_rate_reports_why_a_call_is_unrated() {
+    let rates = vec![rate("44", "0.02", "0", 60)];
+
+    let (matched, talk) = select_rate(&rates, "447700900123", Some(30)).unwrap();
+    assert_eq!((matched.prefix.as_str(), talk), ("44", 30));
+    assert_eq!(select_rate(&rates, "33123456", Some(30)).unwrap_err(), UnratedReason::NoMatchingRate);
+    assert_eq!(select_rate(&rates, "447700900123", None).unwrap_err(), UnratedReason::NotAnswered);
+}
+
+#[test]
+fn test_rate_call() {
+    // 61 seconds in 60-second increments bills two minutes
+    let per_minute = rate("44", "0.02", "0.05", 60);
+    assert_eq!(rate_call(&per_minute, 61).billed_seconds, 120);
+    assert_eq!(rate_call(&per_minute, 61).cost, dec("0.09"));
+
+    // Per-second billing of 61 seconds at 0.06/min
+    let per_second = rate("1", "0.06", "0", 1);
+    assert_eq!(rate_call(&per_second, 61).billed_seconds, 61);
+    assert_eq!(rate_call(&per_second, 61).cost, dec("0.061"));
+
+    // An answered call with no talk time only pays the connection fee
+    assert_eq!(rate_call(&per_minute, 0).billed_seconds, 0);
+    assert_eq!(rate_call(&per_minute, 0).cost, dec("0.05"));
+
+    // Costs keep six decimal places
+    let odd = rate("49", "0.0123457", "0", 1);
+    assert_eq!(rate_call(&odd, 1).cost, dec("0.000206"));
+}
+
+#[test]
+fn test_talk_seconds() {
+    let start = Utc.with_ymd_and_hms(2024, 4, 1, 9, 0, 0).unwrap();
+    assert_eq!(talk_seconds(Some(start + Duration::seconds(10)), start + Duration::seconds(70)), Some(60));
+    // Without an answer time there is no talk time to bill
+    assert_eq!(talk_seconds(None, start + Duration::seconds(70)), None);
+}
+
+#[test]
+fn test_validate_rate_fields() {
+    assert!(validate_prefix("447").is_ok());
+    assert!(validate_prefix("+44").is_err());
+    assert!(validate_prefix("").is_err());
+    assert!(validate_currency("EUR").is_ok());
+    assert!(validate_currency("eur").is_err());
+    assert!(validate_currency("EURO").is_err());
+}
+
+#[test]
+fn test_build_invoices() {
+    let start = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+    let charges = vec![
+        charge("1001", "EUR", "44", 120, "0.041"),
+        charge("1001", "EUR", "44", 60, "0.02"),
+        charge("1001", "EUR", "1", 30, "0.005"),
+        charge("1001", "USD", "1", 60, "0.01"),
+        charge("1002", "EUR", "44", 60, "0.02"),
+    ];
+
+    let invoices = build_invoices(&charges, start, end);
+    assert_eq!(invoices.len(), 3);
+
+    let eur = &invoices[0];
+    assert_eq!((eur.extension_number.as_str(), eur.currency.as_str()), ("1001", "EUR"));
+    assert_eq!(eur.lines.len(), 2);
+    assert_eq!(eur.lines[0].prefix, "1");
+    assert_eq!(eur.lines[1].calls, 2);
+    assert_eq!(eur.lines[1].billed_seconds, 180);
+    assert_eq!(eur.lines[1].cost, dec("0.061"));
+    assert_eq!(eur.total_calls, 3);
+    assert_eq!(eur.total_seconds, 210);
+    // 0.066 rounded to cents
+    assert_eq!(eur.total, dec("0.07"));
+    assert_eq!(eur.period_start, start);
+
+    assert_eq!(invoices[1].currency, "USD");
+    assert_eq!(invoices[2].extension_number, "1002");
+}
@@ -0,0 +1,143 @@
+use uuid::Uuid;
+use validator::Validate;
+
+mod common_ami;
+use common_ami::message;
+
+use oriontel_backend::{
+    ami::call_tracker::{interpret_event, CallEvent},
+    models::{
+        asterisk::ConfigSnapshot,
+        call_handling::{CallHandlingSettings, FeatureCodeChange, FollowMeDestination, SetCallHandlingRequest},
+    },
+    services::{
+        asterisk_config::render_extensions_conf,
+        call_handling::{astdb_writes, parse_feature_code, validate_forward_number, AstDbWrite},
+    },
+};
+
+fn settings() -> CallHandlingSettings {
+    CallHandlingSettings {
+        extension_id: Uuid::new_v4(),
+        extension_number: "1001".into(),
+        dnd: false,
+        forward_always: None,
+        forward_busy: Some("2000".into()),
+        forward_no_answer: None,
+        follow_me_enabled: true,
+        follow_me: vec![
+            FollowMeDestination { number: "1002".into(), ring_time: 15 },
+            FollowMeDestination { number: "0015551234".into(), ring_time: 25 },
+        ],
+        updated_at: None,
+    }
+}
+
+#[test]
+fn test_validate_forward_number() {
+    assert!(validate_forward_number("1002", "1001").is_ok());
+    assert!(validate_forward_number("0015551234", "1001").is_ok());
+    assert!(validate_forward_number("1001", "1001").is_err());
+    assert!(validate_forward_number("+15551234", "1001").is_err());
+    assert!(validate_forward_number("", "1001").is_err());
+}
+
+#[test]
+fn test_set_call_handling_request_validation() {
+    let request: SetCallHandlingRequest = serde_json::from_value(serde_json::json!({
+        "forward_busy": "2000",
+        "follow_me": [{ "number": "1002", "ring_time": 2 }]
+    }))
+    .unwrap();
+    assert!(!request.dnd);
+    assert!(request.validate().is_err());
+
+    let request: SetCallHandlingRequest = serde_json::from_value(serde_json::json!({
+        "dnd": true,
+        "follow_me": [{ "number": "1002", "ring_time": 20 }]
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+}
+
+#[test]
+fn test_astdb_writes() {
+    let writes = astdb_writes(&settings());
+    let write = |family: &'static str, key: &str, value: Option<&str>| AstDbWrite {
+        family,
+        key: key.to_string(),
+        value: value.map(str::to_string),
+    };
+
+    assert_eq!(
+        writes,
+        vec![
+            write("DND", "1001", None),
+            write("CF", "1001", None),
+            write("CFB", "1001", Some("2000")),
+            write("CFNA", "1001", None),
+            write("FM", "1001/enabled", Some("1")),
+            write("FM", "1001/count", Some("2")),
+            write("FM", "1001/0/number", Some("1002")),
+            write("FM", "1001/0/time", Some("15")),
+            write("FM", "1001/1/number", Some("0015551234")),
+            write("FM", "1001/1/time", Some("25")),
+        ]
+    );
+
+    let mut disabled = settings();
+    disabled.dnd = true;
+    disabled.follow_me_enabled = false;
+    let writes = astdb_writes(&disabled);
+    assert_eq!(writes[0], write("DND", "1001", Some("1")));
+    assert!(!writes.iter().any(|write| write.key == "1001/enabled"));
+}
+
+#[test]
+fn test_interpret_feature_code_event() {
+    let event = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "CallHandling"),
+        ("Extension", "1001"),
+        ("Setting", "forward_always"),
+        ("Value", "2000"),
+    ]);
+    assert_eq!(
+        interpret_event(&event),
+        Some(CallEvent::CallHandlingChanged {
+            extension_number: "1001".into(),
+            change: FeatureCodeChange::ForwardAlways(Some("2000".into())),
+        })
+    );
+
+    assert_eq!(parse_feature_code("forward_busy", ""), Some(FeatureCodeChange::ForwardBusy(None)));
+    assert_eq!(parse_feature_code("dnd", "0"), Some(FeatureCodeChange::Dnd(false)));
+    assert_eq!(parse_feature_code("follow_me", "1"), Some(FeatureCodeChange::FollowMe(true)));
+    assert_eq!(parse_feature_code("voicemail", "1"), None);
+
+    let other = message(&[("Event", "UserEvent"), ("UserEvent", "Something"), ("Extension", "1001")]);
+    assert_eq!(interpret_event(&other), None);
+}
+
+#[test]
+fn test_render_call_handling_dialplan() {
+    let config = render_extensions_conf(&ConfigSnapshot::default());
+
+    assert!(config.contains("[call-handling]\n"));
+    assert!(config.contains("exten => s,1,GotoIf($[\"${DB(DND/${ARG1})}\" = \"1\"]?busy,1)\n"));
+    assert!(config.contains(" same => n,Dial(Local/${DB(CF/${ARG1})}@from-internal)\n"));
+    assert!(config.contains(" same => n,Dial(Local/${DB(FM/${ARG1}/${i}/number)}@from-internal,${DB(FM/${ARG1}/${i}/time)})\n"));
+    assert!(config.contains(" same => n(busy-tone),Busy(10)\n"));
+    assert!(config.contains(" same => n,GotoIf($[0${FWDHOPS} >= 5]?done)\n"));
+
+    assert!(config.contains(
+        "exten => *78,1,Answer()\n same => n,Set(DB(DND/${CALLERID(num)})=1)\n same => n,UserEvent(CallHandling,Extension: ${CALLERID(num)},Setting: dnd,Value: 1)\n"
+    ));
+    assert!(config.contains(
+        "exten => _*72X.,1,Answer()\n same => n,Set(DB(CF/${CALLERID(num)})=${EXTEN:3})\n"
+    ));
+    assert!(config.contains(
+        "exten => *53,1,Answer()\n same => n,NoOp(${DB_DELETE(CFNA/${CALLERID(num)})})\n same => n,UserEvent(CallHandling,Extension: ${CALLERID(num)},Setting: forward_no_answer,Value: )\n"
+    ));
+    assert!(config.contains(" same => n,NoOp(${DB_DELETE(FM/${CALLERID(num)}/enabled)})\n"));
+}
@@ -0,0 +1,48 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use oriontel_backend::{
+    error::AppError,
+    models::pbx::CallStatus,
+    services::pbx::{call_duration, check_call_transition},
+};
+
+const FINAL_STATUSES: [CallStatus; 4] = [
+    CallStatus::Completed,
+    CallStatus::Failed,
+    CallStatus::Busy,
+    CallStatus::NoAnswer,
+];
+
+#[test]
+fn test_active_call_can_end_with_any_final_status() {
+    for status in FINAL_STATUSES {
+        assert!(CallStatus::Active.can_transition_to(status));
+        assert!(check_call_transition(CallStatus::Active, status).is_ok());
+    }
+    assert!(!CallStatus::Active.can_transition_to(CallStatus::Active));
+}
+
+#[test]
+fn test_ended_call_cannot_change() {
+    for from in FINAL_STATUSES {
+        assert!(matches!(
+            check_call_transition(from, CallStatus::Active),
+            Err(AppError::Validation(_))
+        ));
+        for to in FINAL_STATUSES {
+            assert!(!from.can_transition_to(to));
+        }
+    }
+}
+
+#[test]
+fn test_call_duration() {
+    let start = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+
+    assert_eq!(call_duration(start, start).unwrap(), 0);
+    assert_eq!(call_duration(start, start + Duration::milliseconds(90_900)).unwrap(), 90);
+    assert!(matches!(
+        call_duration(start, start - Duration::seconds(1)),
+        Err(AppError::Validation(_))
+    ));
+}
@@ -0,0 +1,61 @@
+use chrono::{TimeZone, Utc};
+
+use oriontel_backend::{
+    models::pbx::CallStatus,
+    services::cdr::{disposition_status, parse_master_csv, sanitize_csv_field, unsanitize_csv_field},
+};
+
+const MASTER_CSV: &str = concat!(
+    "\"\",\"1001\",\"5551234\",\"from-internal\",\"\"\"Alice\"\" <1001>\",\"PJSIP/1001-00000001\",\"PJSIP/carrier-00000002\",\"Dial\",\"PJSIP/5551234@carrier\",\"2024-03-01 09:00:00\",\"2024-03-01 09:00:05\",\"2024-03-01 09:01:05\",65,60,\"ANSWERED\",\"DOCUMENTATION\",\"1709283600.1\",\"\"\n",
+    "\"\",\"\",\"1002\",\"from-trunk\",\"\"\"Carrier\"\" <0800123>\",\"PJSIP/carrier-00000003\",\"PJSIP/1002-00000004\",\"Dial\",\"PJSIP/1002\",\"2024-03-01 10:00:00\",\"\",\"2024-03-01 10:00:30\",30,0,\"NO ANSWER\",\"DOCUMENTATION\",\"1709287200.3\",\"\"\n",
+    "\"\",\"1003\",\"1001\",\"from-internal\",\"\",\"PJSIP/1003-00000005\",\"\",\"Dial\",\"\",\"2024-03-01 11:00:00\",\"\",\"2024-03-01 11:00:01\",1,0,\"BUSY\",\"DOCUMENTATION\"\n",
+    "\"\",\"1004\",\"1001\",\"from-internal\",\"\",\"PJSIP/1004-00000006\",\"\",\"Dial\",\"\",\"yesterday\",\"\",\"\",1,0,\"BUSY\",\"DOCUMENTATION\",\"1709290800.7\"\n",
+);
+
+#[test]
+fn test_parse_master_csv() {
+    let (records, errors) = parse_master_csv(MASTER_CSV.as_bytes());
+
+    assert_eq!(records.len(), 2);
+    let answered = &records[0];
+    assert_eq!(answered.uniqueid, "1709283600.1");
+    assert_eq!(answered.caller_id, "1001");
+    assert_eq!(answered.recipient_id, "5551234");
+    assert_eq!(answered.start_time, Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap());
+    assert_eq!(answered.answered_at, Some(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 5).unwrap()));
+    assert_eq!(answered.duration, 65);
+    assert_eq!(answered.status, CallStatus::Completed);
+
+    // Without src the caller ID falls back to clid
+    let missed = &records[1];
+    assert_eq!(missed.caller_id, "\"Carrier\" <0800123>");
+    assert_eq!(missed.answered_at, None);
+    assert_eq!(missed.status, CallStatus::NoAnswer);
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].line, 3);
+    assert!(errors[0].message.contains("uniqueid"));
+    assert_eq!(errors[1].line, 4);
+    assert!(errors[1].message.contains("Invalid timestamp"));
+}
+
+#[test]
+fn test_disposition_status() {
+    assert_eq!(disposition_status("ANSWERED"), CallStatus::Completed);
+    assert_eq!(disposition_status("BUSY"), CallStatus::Busy);
+    assert_eq!(disposition_status("no answer"), CallStatus::NoAnswer);
+    assert_eq!(disposition_status("CONGESTION"), CallStatus::Failed);
+}
+
+#[test]
+fn test_sanitize_csv_field() {
+    assert_eq!(sanitize_csv_field("+15551234"), "+15551234");
+    assert_eq!(sanitize_csv_field("1001"), "1001");
+    assert_eq!(sanitize_csv_field("=HYPERLINK(\"x\")"), "'=HYPERLINK(\"x\")");
+    assert_eq!(sanitize_csv_field("+cmd|' /C calc'!A0"), "'+cmd|' /C calc'!A0");
+    assert_eq!(sanitize_csv_field("@SUM(1)"), "'@SUM(1)");
+
+    assert_eq!(unsanitize_csv_field("'=HYPERLINK(\"x\")"), "=HYPERLINK(\"x\")");
+    assert_eq!(unsanitize_csv_field("'Quoted"), "'Quoted");
+    assert_eq!(unsanitize_csv_field("+15551234"), "+15551234");
+}
@@ -0,0 +1,128 @@
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::chat::{ChatClientEvent, ChatMessage, ChatServerEvent},
+    services::chat::{direct_key, page_size, render_snippet, split_page, validate_message_body, ChatHub},
+};
+
+fn chat_message(body: &str) -> ChatMessage {
+    ChatMessage {
+        id: Uuid::new_v4(),
+        conversation_id: Uuid::new_v4(),
+        sender_id: Uuid::new_v4(),
+        sender_username: "alice".to_string(),
+        body: body.to_string(),
+        created_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_direct_key_is_order_independent() {
+    let a = Uuid::new_v4();
+    let b = Uuid::new_v4();
+    assert_eq!(direct_key(a, b), direct_key(b, a));
+    assert_ne!(direct_key(a, b), direct_key(a, Uuid::new_v4()));
+    assert_eq!(direct_key(a, b).len(), 73);
+}
+
+#[test]
+fn test_validate_message_body() {
+    assert!(validate_message_body("hello").is_ok());
+    assert!(validate_message_body("  \n\t").is_err());
+    assert!(validate_message_body(&"ä".repeat(4000)).is_ok());
+    assert!(validate_message_body(&"a".repeat(4001)).is_err());
+}
+
+#[test]
+fn test_paging() {
+    assert_eq!(page_size(None), 50);
+    assert_eq!(page_size(Some(0)), 1);
+    assert_eq!(page_size(Some(1000)), 200);
+
+    let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+    let (page, cursor) = split_page(ids.clone(), 3, |id| *id);
+    assert_eq!(page, ids[..3]);
+    assert_eq!(cursor, Some(ids[2]));
+
+    let (page, cursor) = split_page(ids.clone(), 4, |id| *id);
+    assert_eq!(page, ids);
+    assert_eq!(cursor, None);
+}
+
+#[test]
+fn test_client_frames() {
+    let conversation_id = Uuid::new_v4();
+    let frame = json!({"type": "send", "conversation_id": conversation_id, "body": "hi"});
+    assert_eq!(
+        serde_json::from_value::<ChatClientEvent>(frame).unwrap(),
+        ChatClientEvent::Send {
+            conversation_id,
+            body: "hi".into(),
+        }
+    );
+
+    let frame = json!({"type": "typing", "conversation_id": conversation_id});
+    assert_eq!(
+        serde_json::from_value::<ChatClientEvent>(frame).unwrap(),
+        ChatClientEvent::Typing { conversation_id }
+    );
+
+    assert!(serde_json::from_value::<ChatClientEvent>(json!({"type": "delete"})).is_err());
+}
+
+#[test]
+fn test_server_frames() {
+    let message = chat_message("hi");
+    let value = serde_json::to_value(ChatServerEvent::Message {
+        message: message.clone(),
+    })
+    .unwrap();
+    assert_eq!(value["type"], "message");
+    assert_eq!(value["message"]["body"], "hi");
+
+    let value = serde_json::to_value(ChatServerEvent::ConversationLeft {
+        conversation_id: message.conversation_id,
+        user_id: message.sender_id,
+    })
+    .unwrap();
+    assert_eq!(value["type"], "conversation_left");
+}
+
+#[tokio::test]
+async fn test_hub_delivers_to_subscribers() {
+    let hub = ChatHub::new();
+    let mut receiver = hub.subscribe();
+    let recipient = Uuid::new_v4();
+
+    // Events without recipients are dropped
+    hub.publish(vec![], ChatServerEvent::Error { message: "none".into() });
+    hub.publish(
+        vec![recipient],
+        ChatServerEvent::Error {
+            message: "hello".into(),
+        },
+    );
+
+    let delivery = receiver.recv().await.unwrap();
+    assert_eq!(delivery.recipients, vec![recipient]);
+    assert_eq!(
+        delivery.event,
+        ChatServerEvent::Error {
+            message: "hello".into()
+        }
+    );
+}
+
+#[test]
+fn test_render_snippet_escapes_message_text() {
+    assert_eq!(
+        render_snippet("the \u{E000}invoice\u{E001} is attached"),
+        "the <b>invoice</b> is attached"
+    );
+    assert_eq!(
+        render_snippet("<img src=x onerror=\"alert('\u{E000}hi\u{E001}')\"> & more"),
+        "&lt;img src=x onerror=&quot;alert(&#39;<b>hi</b>&#39;)&quot;&gt; &amp; more"
+    );
+}
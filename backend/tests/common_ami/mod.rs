@@ -0,0 +1,11 @@
+use oriontel_backend::ami::client::AmiMessage;
+
+/// Builds an AMI event from header pairs, as the client would parse it.
+pub fn message(fields: &[(&str, &str)]) -> AmiMessage {
+    AmiMessage {
+        fields: fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+mod common_ami;
+use common_ami::message;
+
+use oriontel_backend::{
+    ami::call_tracker::{interpret_event, CallEvent},
+    models::{
+        asterisk::ConfigSnapshot,
+        conference::{ConferenceParticipant, ConferenceRoom},
+    },
+    services::{
+        asterisk_config::{render_confbridge_conf, render_extensions_conf},
+        conference::{parse_participants, validate_pins},
+    },
+};
+
+fn room(number: &str, user_pin: Option<&str>, admin_pin: Option<&str>) -> ConferenceRoom {
+    ConferenceRoom {
+        id: Uuid::new_v4(),
+        room_number: number.to_string(),
+        name: "Weekly sync".to_string(),
+        user_pin: user_pin.map(str::to_string),
+        admin_pin: admin_pin.map(str::to_string),
+        max_participants: Some(10),
+        record: true,
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_validate_pins() {
+    assert!(validate_pins(None, None).is_ok());
+    assert!(validate_pins(Some("1234"), Some("9876")).is_ok());
+    assert!(validate_pins(Some("1234"), Some("1234")).is_err());
+    assert!(validate_pins(Some("12a4"), None).is_err());
+    assert!(validate_pins(None, Some("12")).is_err());
+}
+
+#[test]
+fn test_parse_participants() {
+    let events = vec![
+        message(&[
+            ("Event", "ConfbridgeList"),
+            ("Conference", "800"),
+            ("Channel", "PJSIP/1001-00000001"),
+            ("CallerIDNum", "1001"),
+            ("CallerIDName", "Alice"),
+            ("Admin", "Yes"),
+            ("Muted", "No"),
+        ]),
+        message(&[
+            ("Event", "ConfbridgeList"),
+            ("Conference", "800"),
+            ("Channel", "PJSIP/carrier-00000002"),
+            ("CallerIDNum", "<unknown>"),
+            ("CallerIDName", ""),
+            ("Admin", "No"),
+            ("Muted", "Yes"),
+        ]),
+        message(&[("Event", "ConfbridgeListComplete"), ("EventList", "Complete")]),
+    ];
+
+    assert_eq!(
+        parse_participants(&events),
+        vec![
+            ConferenceParticipant {
+                channel: "PJSIP/1001-00000001".into(),
+                caller_id_num: Some("1001".into()),
+                caller_id_name: Some("Alice".into()),
+                admin: true,
+                muted: false,
+            },
+            ConferenceParticipant {
+                channel: "PJSIP/carrier-00000002".into(),
+                caller_id_num: None,
+                caller_id_name: None,
+                admin: false,
+                muted: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_interpret_conference_events() {
+    let join = message(&[
+        ("Event", "ConfbridgeJoin"),
+        ("Conference", "800"),
+        ("Channel", "PJSIP/1001-00000001"),
+        ("CallerIDNum", "1001"),
+        ("CallerIDName", "Alice"),
+        ("Admin", "No"),
+    ]);
+    assert_eq!(
+        interpret_event(&join),
+        Some(CallEvent::ConferenceJoined {
+            conference: "800".into(),
+            channel: "PJSIP/1001-00000001".into(),
+            caller_id_num: Some("1001".into()),
+            caller_id_name: Some("Alice".into()),
+            admin: false,
+        })
+    );
+
+    let leave = message(&[
+        ("Event", "ConfbridgeLeave"),
+        ("Conference", "800"),
+        ("Channel", "PJSIP/1001-00000001"),
+    ]);
+    assert_eq!(
+        interpret_event(&leave),
+        Some(CallEvent::ConferenceLeft {
+            conference: "800".into(),
+            channel: "PJSIP/1001-00000001".into(),
+        })
+    );
+
+    let end = message(&[("Event", "ConfbridgeEnd"), ("Conference", "800")]);
+    assert_eq!(
+        interpret_event(&end),
+        Some(CallEvent::ConferenceEnded { conference: "800".into() })
+    );
+}
+
+#[test]
+fn test_render_conference_dialplan() {
+    let snapshot = ConfigSnapshot {
+        conference_rooms: vec![
+            room("800", Some("1234"), Some("9876")),
+            room("801", None, None),
+            room("802", None, Some("5555")),
+        ],
+        ..Default::default()
+    };
+
+    let dialplan = render_extensions_conf(&snapshot);
+    assert!(dialplan.contains("exten => 800,1,Goto(conferences,800,1)\n"));
+    assert!(dialplan.contains("[conferences]\nexten => 800,1,NoOp(Conference Weekly sync)\n"));
+    assert!(dialplan.contains(" same => n,GotoIf($[\"${PIN}\" = \"9876\"]?admin)\n"));
+    assert!(dialplan.contains(" same => n,GotoIf($[\"${PIN}\" = \"1234\"]?user)\n"));
+    assert!(dialplan.contains(" same => n(admin),ConfBridge(800,conference-800,oriontel-admin)\n"));
+    assert!(dialplan.contains(" same => n(user),ConfBridge(800,conference-800,oriontel-user)\n"));
+
+    // No PIN at all joins straight away
+    assert!(dialplan.contains(
+        "exten => 801,1,NoOp(Conference Weekly sync)\n same => n,Answer()\n same => n,ConfBridge(801,conference-801,oriontel-user)\n"
+    ));
+
+    // Only an admin PIN: users press # to join
+    assert!(dialplan.contains(" same => n,GotoIf($[\"${PIN}\" = \"\"]?user)\n"));
+}
+
+#[test]
+fn test_render_confbridge_conf() {
+    let mut unrecorded = room("801", None, None);
+    unrecorded.record = false;
+    unrecorded.max_participants = None;
+
+    let snapshot = ConfigSnapshot {
+        conference_rooms: vec![room("800", Some("1234"), None), unrecorded],
+        ..Default::default()
+    };
+
+    let conf = render_confbridge_conf(&snapshot);
+    assert!(conf.contains("[oriontel-admin]\ntype=user\nadmin=yes\nmarked=yes\n"));
+    assert!(conf.contains("[conference-800]\ntype=bridge\nmax_members=10\nrecord_conference=yes\n"));
+    assert!(conf.contains("[conference-801]\ntype=bridge\nrecord_conference=no\n"));
+}
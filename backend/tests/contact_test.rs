@@ -0,0 +1,213 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::contact::{Contact, ContactEmail, ContactLabel, ContactNumber, VcardVersion},
+    services::contact::{
+        clean_number, default_display_name, lookup_candidates, normalize_e164, parse_vcards, render_vcards,
+    },
+};
+
+fn contact() -> Contact {
+    Contact {
+        id: Uuid::new_v4(),
+        owner_id: None,
+        display_name: "Doe, Jane; Jr.".to_string(),
+        first_name: Some("Jane".to_string()),
+        last_name: Some("Doe".to_string()),
+        organization: Some("Acme".to_string()),
+        title: None,
+        notes: Some(format!("Prefers calls after lunch.\n{}", "x".repeat(120))),
+        numbers: vec![
+            ContactNumber {
+                label: ContactLabel::Work,
+                number: "030 1234567".to_string(),
+                e164: Some("+49301234567".to_string()),
+            },
+            ContactNumber {
+                label: ContactLabel::Mobile,
+                number: "1001".to_string(),
+                e164: None,
+            },
+        ],
+        emails: vec![ContactEmail {
+            label: ContactLabel::Home,
+            email: "jane@example.com".to_string(),
+        }],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_clean_number() {
+    assert_eq!(clean_number(" +49 (30) 123-45 67 "), "+49301234567");
+    assert_eq!(clean_number("*97#"), "*97#");
+    assert_eq!(clean_number("0049 30"), "004930");
+}
+
+#[test]
+fn test_normalize_e164() {
+    assert_eq!(normalize_e164("+49 30 1234567", None).as_deref(), Some("+49301234567"));
+    assert_eq!(normalize_e164("0049 30 1234567", None).as_deref(), Some("+49301234567"));
+    assert_eq!(normalize_e164("030 1234567", Some("49")).as_deref(), Some("+49301234567"));
+    assert_eq!(normalize_e164("(555) 123-4567", Some("1")).as_deref(), Some("+15551234567"));
+    assert_eq!(normalize_e164("1 555 123 4567", Some("1")).as_deref(), Some("+15551234567"));
+
+    // National numbers need a country code
+    assert_eq!(normalize_e164("030 1234567", None), None);
+    // Extensions, feature codes and impossible lengths stay as they are
+    assert_eq!(normalize_e164("1001", Some("49")), None);
+    assert_eq!(normalize_e164("*97", Some("49")), None);
+    assert_eq!(normalize_e164("+1234567890123456", None), None);
+}
+
+#[test]
+fn test_lookup_candidates() {
+    // Caller IDs often arrive without the +
+    assert_eq!(lookup_candidates("49301234567", Some("49")), vec!["+49301234567"]);
+    assert_eq!(lookup_candidates("030 1234567", Some("49")), vec!["+49301234567"]);
+    assert_eq!(
+        lookup_candidates("5551234567", Some("1")),
+        vec!["+15551234567", "+5551234567"]
+    );
+    assert!(lookup_candidates("1001", None).is_empty());
+}
+
+#[test]
+fn test_default_display_name() {
+    assert_eq!(default_display_name(Some("Jane"), Some("Doe"), Some("Acme")).as_deref(), Some("Jane Doe"));
+    assert_eq!(default_display_name(None, Some(" Doe "), None).as_deref(), Some("Doe"));
+    assert_eq!(default_display_name(Some(""), None, Some("Acme")).as_deref(), Some("Acme"));
+    assert_eq!(default_display_name(None, None, Some(" ")), None);
+}
+
+#[test]
+fn test_parse_vcard_3() {
+    let data = "BEGIN:VCARD\r\n\
+                VERSION:3.0\r\n\
+                N:Doe;Jane;;;\r\n\
+                FN:Jane Doe\r\n\
+                ORG:Acme\\, Inc.;Sales\r\n\
+                TITLE:Head of Sales\r\n\
+                TEL;TYPE=CELL:+49 170 1234567\r\n\
+                item1.TEL;type=WORK,VOICE:030 1234567\r\n\
+                TEL;TYPE=HOME,FAX:030 7654321\r\n\
+                EMAIL;TYPE=INTERNET,HOME:jane@example.com\r\n\
+                NOTE:First line\\nsecond line with a long \r\n  continuation\r\n\
+                X-CUSTOM:ignored\r\n\
+                END:VCARD\r\n";
+
+    let cards = parse_vcards(data);
+    assert_eq!(cards.len(), 1);
+    let card = cards[0].as_ref().unwrap();
+    assert_eq!(card.display_name.as_deref(), Some("Jane Doe"));
+    assert_eq!(card.first_name.as_deref(), Some("Jane"));
+    assert_eq!(card.last_name.as_deref(), Some("Doe"));
+    assert_eq!(card.organization.as_deref(), Some("Acme, Inc."));
+    assert_eq!(card.title.as_deref(), Some("Head of Sales"));
+    assert_eq!(
+        card.notes.as_deref(),
+        Some("First line\nsecond line with a long  continuation")
+    );
+
+    let numbers: Vec<_> = card.numbers.iter().map(|n| (n.label, n.number.as_str())).collect();
+    assert_eq!(
+        numbers,
+        vec![
+            (ContactLabel::Mobile, "+49 170 1234567"),
+            (ContactLabel::Work, "030 1234567"),
+            (ContactLabel::Fax, "030 7654321"),
+        ]
+    );
+    assert_eq!(card.emails[0].label, ContactLabel::Home);
+    assert_eq!(card.emails[0].email, "jane@example.com");
+}
+
+#[test]
+fn test_parse_vcard_4_and_2_1() {
+    let data = "BEGIN:VCARD\n\
+                VERSION:4.0\n\
+                FN:Acme Reception\n\
+                TEL;VALUE=uri;TYPE=\"voice,work\":tel:+49-30-1234567;ext=12\n\
+                EMAIL;TYPE=work:front@acme.example\n\
+                END:VCARD\n\
+                BEGIN:VCARD\n\
+                VERSION:2.1\n\
+                N:Smith;John\n\
+                TEL;CELL:0170 1234567\n\
+                END:VCARD\n";
+
+    let cards = parse_vcards(data);
+    assert_eq!(cards.len(), 2);
+
+    let reception = cards[0].as_ref().unwrap();
+    assert_eq!(reception.display_name.as_deref(), Some("Acme Reception"));
+    assert_eq!(reception.numbers[0].label, ContactLabel::Work);
+    assert_eq!(reception.numbers[0].number, "+49-30-1234567");
+    assert_eq!(reception.emails[0].label, ContactLabel::Work);
+
+    // Without FN the name is built from N
+    let john = cards[1].as_ref().unwrap();
+    assert_eq!(john.display_name.as_deref(), Some("John Smith"));
+    assert_eq!(john.numbers[0].label, ContactLabel::Mobile);
+}
+
+#[test]
+fn test_parse_vcards_reports_bad_cards() {
+    let data = "BEGIN:VCARD\nVERSION:3.0\nTEL:123\nEND:VCARD\n\
+                BEGIN:VCARD\nFN:No Version\nEND:VCARD\n\
+                BEGIN:VCARD\nVERSION:3.0\nFN:Fine\nEND:VCARD\n";
+
+    let cards = parse_vcards(data);
+    assert_eq!(cards.len(), 3);
+    assert!(cards[0].is_err());
+    assert!(cards[1].is_err());
+    assert!(cards[2].is_ok());
+
+    assert!(parse_vcards("not a vcard").is_empty());
+}
+
+#[test]
+fn test_render_vcard_4() {
+    let contact = contact();
+    let rendered = render_vcards(&[contact.clone()], VcardVersion::V4);
+
+    assert!(rendered.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\n"));
+    assert!(rendered.ends_with("END:VCARD\r\n"));
+    assert!(rendered.contains(&format!("UID:urn:uuid:{}\r\n", contact.id)));
+    assert!(rendered.contains("FN:Doe\\, Jane\\; Jr.\r\n"));
+    assert!(rendered.contains("N:Doe;Jane;;;\r\n"));
+    assert!(rendered.contains("TEL;VALUE=uri;TYPE=\"work,voice\":tel:+49301234567\r\n"));
+    // Numbers without an E.164 form are written as dialled
+    assert!(rendered.contains("TEL;VALUE=uri;TYPE=\"cell,voice\":tel:1001\r\n"));
+    assert!(rendered.contains("EMAIL;TYPE=home:jane@example.com\r\n"));
+
+    // Long lines are folded
+    for line in rendered.split("\r\n") {
+        assert!(line.len() <= 75, "line too long: {}", line);
+    }
+}
+
+#[test]
+fn test_render_vcard_3_round_trip() {
+    let contact = contact();
+    let rendered = render_vcards(&[contact.clone()], VcardVersion::V3);
+    assert!(rendered.contains("VERSION:3.0\r\n"));
+    assert!(rendered.contains("TEL;TYPE=WORK,VOICE:030 1234567\r\n"));
+    assert!(rendered.contains("EMAIL;TYPE=INTERNET,HOME:jane@example.com\r\n"));
+
+    let cards = parse_vcards(&rendered);
+    let card = cards[0].as_ref().unwrap();
+    assert_eq!(card.display_name.as_deref(), Some(contact.display_name.as_str()));
+    assert_eq!(card.first_name, contact.first_name);
+    assert_eq!(card.last_name, contact.last_name);
+    assert_eq!(card.organization, contact.organization);
+    assert_eq!(card.notes, contact.notes);
+
+    let numbers: Vec<_> = card.numbers.iter().map(|n| (n.label, n.number.clone())).collect();
+    let expected: Vec<_> = contact.numbers.iter().map(|n| (n.label, n.number.clone())).collect();
+    assert_eq!(numbers, expected);
+    assert_eq!(card.emails[0].email, contact.emails[0].email);
+    assert_eq!(card.emails[0].label, contact.emails[0].label);
+}
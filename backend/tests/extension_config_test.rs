@@ -0,0 +1,86 @@
+use serde_json::json;
+
+use oriontel_backend::{
+    models::pbx::{DtmfMode, ExtensionConfig, ExtensionType},
+    services::pbx::{extension_config_schema, normalize_extension_config, parse_extension_config},
+};
+
+#[test]
+fn test_parse_sip_config() {
+    let config = parse_extension_config(
+        ExtensionType::Sip,
+        &json!({ "secret": "s3cret", "codecs": ["opus", "ulaw"], "dtmf_mode": "rfc4733", "nat": true }),
+    )
+    .unwrap();
+    match config {
+        ExtensionConfig::Sip(sip) => {
+            assert_eq!(sip.secret, "s3cret");
+            assert_eq!(sip.codecs, Some(vec!["opus".to_string(), "ulaw".to_string()]));
+            assert_eq!(sip.dtmf_mode, Some(DtmfMode::Rfc4733));
+            assert_eq!(sip.nat, Some(true));
+        }
+        other => panic!("unexpected config {:?}", other),
+    }
+
+    // Typos, missing secrets and bad values are rejected
+    let typo = parse_extension_config(ExtensionType::Sip, &json!({ "secert": "s3cret" })).unwrap_err();
+    assert!(typo.to_string().contains("unknown field `secert`"));
+    assert!(parse_extension_config(ExtensionType::Sip, &json!({})).is_err());
+    assert!(parse_extension_config(ExtensionType::Sip, &json!({ "secret": "short" })).is_err());
+    assert!(parse_extension_config(ExtensionType::Sip, &json!({ "secret": "s3cret", "transport": "sctp" })).is_err());
+    assert!(parse_extension_config(ExtensionType::Sip, &json!({ "secret": "s3cret", "codecs": ["ulaw;x"] })).is_err());
+    assert!(parse_extension_config(ExtensionType::Sip, &json!({ "secret": "s3cret", "max_contacts": 0 })).is_err());
+    assert!(parse_extension_config(ExtensionType::Sip, &json!(["secret"])).is_err());
+}
+
+#[test]
+fn test_parse_iax_and_custom_config() {
+    assert!(parse_extension_config(ExtensionType::Iax, &json!({})).is_ok());
+    assert!(parse_extension_config(ExtensionType::Iax, &json!({ "trunk": true, "encryption": "aes128" })).is_ok());
+    assert!(parse_extension_config(ExtensionType::Iax, &json!({ "encryption": "rsa" })).is_err());
+    assert!(parse_extension_config(ExtensionType::Iax, &json!({ "secret": "s3cret" })).is_err());
+
+    assert!(parse_extension_config(ExtensionType::Custom, &json!({ "dial_string": "Local/500@ivr" })).is_ok());
+    assert!(parse_extension_config(ExtensionType::Custom, &json!({})).is_err());
+    assert!(parse_extension_config(ExtensionType::Custom, &json!({ "dial_string": "Local/500@ivr", "ring_timeout": 1 })).is_err());
+}
+
+#[test]
+fn test_normalize_extension_config() {
+    let config = normalize_extension_config(
+        ExtensionType::Sip,
+        &json!({ "secret": "s3cret", "auth_username": null, "ring_timeout": 20 }),
+    )
+    .unwrap();
+    assert_eq!(config, json!({ "secret": "s3cret", "ring_timeout": 20 }));
+}
+
+#[test]
+fn test_extension_config_schema() {
+    let schema = extension_config_schema(ExtensionType::Sip);
+    assert_eq!(schema["additionalProperties"], json!(false));
+    assert_eq!(schema["required"], json!(["secret"]));
+    assert_eq!(schema["properties"]["dtmf_mode"]["enum"], json!(["rfc4733", "inband", "info", "auto"]));
+
+    // Every documented key is accepted by the parser
+    let sample = json!({
+        "secret": "s3cret",
+        "auth_username": "alice",
+        "context": "from-internal",
+        "codecs": ["ulaw"],
+        "nat": false,
+        "transport": "wss",
+        "dtmf_mode": "auto",
+        "max_contacts": 2,
+        "ring_timeout": 30
+    });
+    let keys: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+    assert_eq!(keys.len(), sample.as_object().unwrap().len());
+    assert!(keys.iter().all(|key| sample.get(key.as_str()).is_some()));
+    assert!(parse_extension_config(ExtensionType::Sip, &sample).is_ok());
+
+    let custom = extension_config_schema(ExtensionType::Custom);
+    assert_eq!(custom["required"], json!(["dial_string"]));
+    let iax = extension_config_schema(ExtensionType::Iax);
+    assert_eq!(iax["required"], json!([]));
+}
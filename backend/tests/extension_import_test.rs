@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::pbx::{ExtensionType, PbxExtension},
+    services::pbx::{extension_import_report, parse_extension_csv, render_extensions_csv},
+};
+
+#[test]
+fn test_parse_extension_csv() {
+    let owner = Uuid::new_v4();
+    let data = format!(
+        "name,extension_number,extension_type,config_data,owner_id\n\
+         Alice,1001,sip,\"{{\"\"secret\"\":\"\"s3cret\"\"}}\",{}\n\
+         Bob,1002,IAX,,\n\
+         Carol,12,fax,[1],not-a-uuid\n",
+        owner
+    );
+    let rows = parse_extension_csv(data.as_bytes()).unwrap();
+    assert_eq!(rows.len(), 3);
+
+    let alice = rows[0].request.as_ref().unwrap();
+    assert_eq!(rows[0].line, 2);
+    assert_eq!(alice.extension_number, "1001");
+    assert_eq!(alice.extension_type, ExtensionType::Sip);
+    assert_eq!(alice.config_data, json!({ "secret": "s3cret" }));
+    assert_eq!(alice.owner_id, Some(owner));
+
+    let bob = rows[1].request.as_ref().unwrap();
+    assert_eq!(bob.extension_type, ExtensionType::Iax);
+    assert_eq!(bob.config_data, json!({}));
+    assert_eq!(bob.owner_id, None);
+
+    let errors = rows[2].request.as_ref().unwrap_err();
+    assert_eq!(rows[2].line, 4);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("extension_type"));
+    assert!(errors[1].contains("JSON object"));
+    assert!(errors[2].contains("owner_id"));
+
+    // Rows that parse still go through the CreateExtensionRequest rules
+    let rows = parse_extension_csv(b"extension_number,name,extension_type\n12,Short,sip\n").unwrap();
+    assert_eq!(rows[0].request.as_ref().unwrap_err(), &vec!["extension_number is invalid (length)".to_string()]);
+
+    // config_data is checked against the extension type
+    let rows = parse_extension_csv(b"extension_number,name,extension_type,config_data\n1001,Alice,sip,\"{\"\"secert\"\":\"\"s3cret\"\"}\"\n").unwrap();
+    assert!(rows[0].request.as_ref().unwrap_err()[0].contains("unknown field `secert`"));
+
+    assert!(parse_extension_csv(b"extension_number,name\n1001,Alice\n").is_err());
+}
+
+#[test]
+fn test_extension_import_report() {
+    let owner = Uuid::new_v4();
+    let data = format!(
+        "extension_number,name,extension_type,owner_id\n\
+         1001,Alice,iax,{}\n\
+         1002,Bob,iax,\n\
+         1001,Again,iax,\n\
+         500,Sales,iax,\n\
+         1003,Dave,iax,{}\n",
+        owner,
+        Uuid::new_v4()
+    );
+    let rows = parse_extension_csv(data.as_bytes()).unwrap();
+    let taken = HashMap::from([("500".to_string(), "an existing ring group".to_string())]);
+    let owners = HashSet::from([owner]);
+
+    let report = extension_import_report(&rows, &taken, &owners);
+    assert_eq!(report.len(), 5);
+    assert!(report[0].errors.is_empty());
+    assert!(report[1].errors.is_empty());
+    assert_eq!(report[2].errors, vec!["Number 1001 is repeated from line 2".to_string()]);
+    assert_eq!(report[3].errors, vec!["Number 500 is already used by an existing ring group".to_string()]);
+    assert!(report[4].errors[0].starts_with("Owner "));
+    assert_eq!(report[4].extension_number.as_deref(), Some("1003"));
+}
+
+#[test]
+fn test_render_extensions_csv_round_trip() {
+    let owner = Uuid::new_v4();
+    let extensions = vec![PbxExtension {
+        id: Uuid::new_v4(),
+        extension_number: "1001".into(),
+        name: "Alice, front desk".into(),
+        extension_type: ExtensionType::Custom,
+        config_data: json!({ "dial_string": "Local/500@ivr" }),
+        owner_id: Some(owner),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }];
+
+    let csv = render_extensions_csv(&extensions);
+    let text = String::from_utf8(csv.clone()).unwrap();
+    assert!(text.starts_with("extension_number,name,extension_type,config_data,owner_id\n"));
+
+    let rows = parse_extension_csv(&csv).unwrap();
+    let request = rows[0].request.as_ref().unwrap();
+    assert_eq!(request.name, "Alice, front desk");
+    assert_eq!(request.extension_type, ExtensionType::Custom);
+    assert_eq!(request.config_data, json!({ "dial_string": "Local/500@ivr" }));
+    assert_eq!(request.owner_id, Some(owner));
+}
+
+#[test]
+fn test_render_extensions_csv_round_trips_guarded_names() {
+    let names = ["=Reception", "+Sales", "-Night", "@Support", "'Quoted", "Plain"];
+    let extensions: Vec<PbxExtension> = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| PbxExtension {
+            id: Uuid::new_v4(),
+            extension_number: format!("20{:02}", index),
+            name: name.to_string(),
+            extension_type: ExtensionType::Custom,
+            config_data: json!({ "dial_string": "Local/500@ivr" }),
+            owner_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .collect();
+
+    let csv = render_extensions_csv(&extensions);
+    let text = String::from_utf8(csv.clone()).unwrap();
+    assert!(text.contains("\n2000,'=Reception,"));
+
+    let rows = parse_extension_csv(&csv).unwrap();
+    let imported: Vec<&str> = rows
+        .iter()
+        .map(|row| row.request.as_ref().unwrap().name.as_str())
+        .collect();
+    assert_eq!(imported, names);
+}
@@ -0,0 +1,235 @@
+use std::path::Path;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+mod common_ami;
+use common_ami::message;
+
+use oriontel_backend::{
+    ami::call_tracker::{interpret_event, CallEvent},
+    models::{
+        asterisk::ConfigSnapshot,
+        fax::{FaxAttemptResult, FaxJobStatus, FaxLine, ReceivedFax},
+    },
+    services::{
+        asterisk_config::render_extensions_conf,
+        fax::{
+            is_pdf, is_tiff, next_job_status, parse_fax_email, pdf_to_tiff_args, retry_delay,
+            validate_fax_number,
+        },
+    },
+};
+
+fn line(did: &str, station_id: Option<&str>) -> FaxLine {
+    FaxLine {
+        id: Uuid::new_v4(),
+        did: did.to_string(),
+        name: "Front office".to_string(),
+        email: Some("office@example.com".to_string()),
+        station_id: station_id.map(str::to_string),
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+// "%PDF-1.4 test" in base64
+const PDF_BASE64: &str = "JVBERi0xLjQgdGVzdA==";
+
+fn email(to: &str, attachment_type: &str) -> Vec<u8> {
+    format!(
+        "From: Front Office <Office@Example.com>\r\n\
+         To: {}\r\n\
+         Subject: Order\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+         \r\n\
+         --b1\r\n\
+         Content-Type: text/plain\r\n\
+         \r\n\
+         Please find the order attached.\r\n\
+         --b1\r\n\
+         Content-Type: {}; name=\"order.pdf\"\r\n\
+         Content-Disposition: attachment; filename=\"order.pdf\"\r\n\
+         Content-Transfer-Encoding: base64\r\n\
+         \r\n\
+         {}\r\n\
+         --b1--\r\n",
+        to, attachment_type, PDF_BASE64
+    )
+    .into_bytes()
+}
+
+#[test]
+fn test_validate_fax_number() {
+    assert!(validate_fax_number("4930123456").is_ok());
+    assert!(validate_fax_number("+4930123456").is_ok());
+    assert!(validate_fax_number("12").is_err());
+    assert!(validate_fax_number("+49 30 123").is_err());
+    assert!(validate_fax_number("++4930").is_err());
+}
+
+#[test]
+fn test_document_signatures() {
+    assert!(is_pdf(b"%PDF-1.7\n..."));
+    assert!(!is_pdf(b"<html>"));
+    assert!(is_tiff(b"II*\0rest"));
+    assert!(is_tiff(b"MM\0*rest"));
+    assert!(!is_tiff(b"%PDF-1.7"));
+}
+
+#[test]
+fn test_retry_schedule() {
+    assert_eq!(retry_delay(1), chrono::Duration::minutes(2));
+    assert_eq!(retry_delay(2), chrono::Duration::minutes(5));
+    assert_eq!(retry_delay(9), chrono::Duration::minutes(30));
+    assert_eq!(retry_delay(0), chrono::Duration::minutes(2));
+
+    assert_eq!(next_job_status(true, 3, 3), FaxJobStatus::Sent);
+    assert_eq!(next_job_status(false, 1, 3), FaxJobStatus::Queued);
+    assert_eq!(next_job_status(false, 3, 3), FaxJobStatus::Failed);
+}
+
+#[test]
+fn test_ghostscript_renders_fine_g3() {
+    let args = pdf_to_tiff_args(Path::new("/fax/in.pdf"), Path::new("/spool/out.tif"));
+    assert!(args.contains(&"-sDEVICE=tiffg3".to_string()));
+    assert!(args.contains(&"-r204x196".to_string()));
+    assert!(args.contains(&"-sOutputFile=/spool/out.tif".to_string()));
+    assert_eq!(args.last().unwrap(), "/fax/in.pdf");
+}
+
+#[test]
+fn test_parse_fax_email() {
+    let parsed = parse_fax_email(&email("+49-30-123.456@fax.example.com", "application/pdf")).unwrap();
+    assert_eq!(parsed.from, "office@example.com");
+    assert_eq!(parsed.destination, "+4930123456");
+    assert_eq!(parsed.token, None);
+    assert_eq!(parsed.document, b"%PDF-1.4 test");
+
+    let parsed = parse_fax_email(&email("+4930123456+3F2A9C@fax.example.com", "application/pdf")).unwrap();
+    assert_eq!(parsed.destination, "+4930123456");
+    assert_eq!(parsed.token.as_deref(), Some("3f2a9c"));
+    let parsed = parse_fax_email(&email("4930123456+3f2a9c@fax.example.com", "application/pdf")).unwrap();
+    assert_eq!(parsed.destination, "4930123456");
+    assert_eq!(parsed.token.as_deref(), Some("3f2a9c"));
+
+    // Mail clients often send PDFs as octet streams
+    let parsed = parse_fax_email(&email("4930123456@fax.example.com", "application/octet-stream")).unwrap();
+    assert!(is_pdf(&parsed.document));
+}
+
+#[test]
+fn test_parse_fax_email_rejects_bad_messages() {
+    assert!(parse_fax_email(&email("sales@fax.example.com", "application/pdf")).is_err());
+
+    let no_pdf = b"From: office@example.com\r\nTo: 4930123456@fax.example.com\r\nSubject: Hi\r\n\r\nNo attachment\r\n";
+    assert!(parse_fax_email(no_pdf).is_err());
+}
+
+#[test]
+fn test_interpret_fax_received() {
+    let event = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "FaxReceived"),
+        ("Uniqueid", "1711000000.42"),
+        ("FaxId", "1711000000.42"),
+        ("DID", "4930123456"),
+        ("Sender", "4940999"),
+        ("Station", " +49 40 999 "),
+        ("Pages", "3"),
+    ]);
+    assert_eq!(
+        interpret_event(&event),
+        Some(CallEvent::FaxReceived(ReceivedFax {
+            uniqueid: "1711000000.42".into(),
+            did: "4930123456".into(),
+            sender: "4940999".into(),
+            remote_station_id: Some("+49 40 999".into()),
+            pages: 3,
+        }))
+    );
+
+    let empty = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "FaxReceived"),
+        ("FaxId", "1711000000.43"),
+        ("DID", "4930123456"),
+        ("Pages", ""),
+    ]);
+    assert_eq!(interpret_event(&empty), None);
+}
+
+#[test]
+fn test_interpret_fax_result() {
+    let job_id = Uuid::new_v4();
+    let job = job_id.to_string();
+
+    let sent = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "FaxResult"),
+        ("Job", &job),
+        ("Status", "SUCCESS"),
+        ("Error", "OK"),
+        ("Pages", "2"),
+    ]);
+    assert_eq!(
+        interpret_event(&sent),
+        Some(CallEvent::FaxAttempted {
+            job_id,
+            result: FaxAttemptResult {
+                success: true,
+                error: None,
+                pages: Some(2),
+            },
+        })
+    );
+
+    let failed = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "FaxResult"),
+        ("Job", &job),
+        ("Status", "FAILED"),
+        ("Error", "Call failed with reason 5"),
+        ("Pages", "0"),
+    ]);
+    assert_eq!(
+        interpret_event(&failed),
+        Some(CallEvent::FaxAttempted {
+            job_id,
+            result: FaxAttemptResult {
+                success: false,
+                error: Some("Call failed with reason 5".into()),
+                pages: Some(0),
+            },
+        })
+    );
+
+    // Hangup of the placeholder channel after a failed originate
+    let no_status = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "FaxResult"),
+        ("Job", &job),
+        ("Status", ""),
+    ]);
+    assert_eq!(interpret_event(&no_status), None);
+}
+
+#[test]
+fn test_render_fax_contexts() {
+    let snapshot = ConfigSnapshot {
+        fax_lines: vec![line("4930123456", Some("+49 30 123456")), line("4930123457", None)],
+        ..Default::default()
+    };
+
+    let conf = render_extensions_conf(&snapshot);
+    assert!(conf.contains("[from-trunk]\nexten => 4930123456,1,Goto(fax-in,4930123456,1)\n"));
+    assert!(conf.contains(
+        "[fax-in]\nexten => 4930123456,1,NoOp(Fax line Front office)\n same => n,Set(FAXDID=4930123456)\n same => n,Set(FAXOPT(localstationid)=+49 30 123456)\n same => n,Answer()\n same => n,ReceiveFAX(${ASTSPOOLDIR}/fax/${UNIQUEID}.tif)\n"
+    ));
+    assert!(conf.contains(" same => n,Set(FAXOPT(localstationid)=4930123457)\n"));
+    assert!(conf.contains("exten => h,1,UserEvent(FaxReceived,FaxId: ${UNIQUEID},DID: ${FAXDID},"));
+    assert!(conf.contains("[fax-out]\nexten => s,1,Set(FAXOPT(localstationid)=${FAX_STATION})\n same => n,SendFAX(${FAX_FILE},z)\n"));
+    assert!(conf.contains("exten => failed,1,UserEvent(FaxResult,Job: ${FAX_JOB},Status: FAILED,"));
+}
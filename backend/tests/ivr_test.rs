@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::ivr::{IvrDestination, IvrDestinationType, IvrMenu, IvrNode, IvrOption},
+    services::ivr::{render_ivr_menu, validate_graph},
+};
+
+fn destination(destination_type: IvrDestinationType, target: &str) -> IvrDestination {
+    IvrDestination {
+        destination_type,
+        target: Some(target.to_string()),
+    }
+}
+
+fn node(key: &str, options: Vec<(&str, IvrDestination)>, timeout: Option<IvrDestination>) -> IvrNode {
+    IvrNode {
+        key: key.to_string(),
+        prompt: Some(format!("custom/{}", key)),
+        timeout: 5,
+        options: options
+            .into_iter()
+            .map(|(digit, destination)| IvrOption {
+                digit: digit.to_string(),
+                destination,
+            })
+            .collect(),
+        timeout_destination: timeout,
+        invalid_destination: None,
+    }
+}
+
+fn known() -> (HashSet<String>, HashSet<String>) {
+    (
+        ["1001".to_string(), "1002".to_string()].into_iter().collect(),
+        ["support".to_string()].into_iter().collect(),
+    )
+}
+
+fn main_menu() -> Vec<IvrNode> {
+    vec![
+        node(
+            "main",
+            vec![
+                ("1", destination(IvrDestinationType::Extension, "1001")),
+                ("2", destination(IvrDestinationType::Queue, "support")),
+                ("9", destination(IvrDestinationType::Node, "hours")),
+            ],
+            Some(destination(IvrDestinationType::Extension, "1002")),
+        ),
+        node("hours", vec![], Some(destination(IvrDestinationType::Node, "main"))),
+    ]
+}
+
+#[test]
+fn test_valid_graph() {
+    let (extensions, queues) = known();
+    let errors = validate_graph("main", &main_menu(), &extensions, &queues);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_unreachable_and_missing_targets() {
+    let (extensions, queues) = known();
+    let mut nodes = main_menu();
+    nodes[0].options[0].destination = destination(IvrDestinationType::Extension, "2000");
+    nodes[0].options[1].destination = destination(IvrDestinationType::Queue, "sales");
+    nodes.push(node("orphan", vec![], None));
+
+    let errors = validate_graph("main", &nodes, &extensions, &queues);
+    assert!(errors.iter().any(|e| e.contains("missing extension 2000")));
+    assert!(errors.iter().any(|e| e.contains("missing queue sales")));
+    assert!(errors.iter().any(|e| e.contains("'orphan' is unreachable")));
+
+    let errors = validate_graph("start", &main_menu(), &extensions, &queues);
+    assert!(errors.iter().any(|e| e.contains("Entry node 'start' does not exist")));
+}
+
+#[test]
+fn test_cycle_without_input_is_rejected() {
+    let (extensions, queues) = known();
+    let nodes = vec![
+        node("main", vec![("1", destination(IvrDestinationType::Node, "a"))], None),
+        node("a", vec![], Some(destination(IvrDestinationType::Node, "b"))),
+        node("b", vec![], Some(destination(IvrDestinationType::Node, "a"))),
+    ];
+
+    let errors = validate_graph("main", &nodes, &extensions, &queues);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("loop without waiting for input"));
+}
+
+#[test]
+fn test_bad_digits_and_keys() {
+    let (extensions, queues) = known();
+    let nodes = vec![node(
+        "bad-key",
+        vec![
+            ("1", IvrDestination { destination_type: IvrDestinationType::Hangup, target: None }),
+            ("1", IvrDestination { destination_type: IvrDestinationType::Hangup, target: None }),
+            ("12", IvrDestination { destination_type: IvrDestinationType::Hangup, target: None }),
+        ],
+        None,
+    )];
+
+    let errors = validate_graph("bad-key", &nodes, &extensions, &queues);
+    assert!(errors.iter().any(|e| e.contains("may only contain")));
+    assert!(errors.iter().any(|e| e.contains("option 1 is defined more than once")));
+    assert!(errors.iter().any(|e| e.contains("'12' is not a DTMF key")));
+}
+
+#[test]
+fn test_render_dialplan() {
+    let menu = IvrMenu {
+        id: Uuid::new_v4(),
+        name: "reception".into(),
+        description: None,
+        entry_node: "main".into(),
+        nodes: Json(main_menu()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    let dialplan = render_ivr_menu(&menu);
+    assert!(dialplan.contains("[ivr-reception]\nexten => s,1,Answer()\n same => n,Goto(ivrnode-reception-main,s,1)\n"));
+    assert!(dialplan.contains("[ivrnode-reception-main]\nexten => s,1,Background(custom/main)\n same => n,WaitExten(5)\n"));
+    assert!(dialplan.contains("exten => 1,1,Goto(from-internal,1001,1)\n"));
+    assert!(dialplan.contains("exten => 2,1,Goto(queues,support,1)\n"));
+    assert!(dialplan.contains("exten => 9,1,Goto(ivrnode-reception-hours,s,1)\n"));
+    assert!(dialplan.contains("exten => t,1,Goto(from-internal,1002,1)\n"));
+    assert!(dialplan.contains("exten => i,1,Playback(invalid)\n same => n,Goto(s,1)\n"));
+    assert!(dialplan.contains("[ivrnode-reception-hours]\nexten => s,1,Playback(custom/hours)\n same => n,Goto(ivrnode-reception-main,s,1)\n"));
+}
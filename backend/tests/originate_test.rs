@@ -0,0 +1,60 @@
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::pbx::{CallStatus, ExtensionType, PbxExtension},
+    services::originate::{originate_failure_status, originate_target, validate_destination},
+};
+
+fn extension(number: &str, extension_type: ExtensionType, config_data: serde_json::Value) -> PbxExtension {
+    PbxExtension {
+        id: Uuid::new_v4(),
+        extension_number: number.to_string(),
+        name: "Front desk".to_string(),
+        extension_type,
+        config_data,
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_validate_destination() {
+    assert!(validate_destination("4940999888").is_ok());
+    assert!(validate_destination("+4940999888").is_ok());
+    assert!(validate_destination("*72").is_ok());
+    assert!(validate_destination("").is_err());
+    assert!(validate_destination("+").is_err());
+    assert!(validate_destination("040 999").is_err());
+    assert!(validate_destination("101&PJSIP/102").is_err());
+}
+
+#[test]
+fn test_originate_target() {
+    let sip = extension("101", ExtensionType::Sip, json!({"secret": "s3cret!!"}));
+    assert_eq!(
+        originate_target(&sip).unwrap(),
+        ("PJSIP/101".to_string(), "from-internal".to_string())
+    );
+
+    // The phone's own context decides what it may dial
+    let restricted = extension("102", ExtensionType::Sip, json!({"secret": "s3cret!!", "context": "local-only"}));
+    assert_eq!(originate_target(&restricted).unwrap().1, "local-only");
+
+    let iax = extension("103", ExtensionType::Iax, json!({}));
+    assert_eq!(originate_target(&iax).unwrap().0, "IAX2/103");
+
+    let custom = extension("104", ExtensionType::Custom, json!({"dial_string": "DAHDI/1"}));
+    assert_eq!(originate_target(&custom).unwrap().0, "DAHDI/1");
+}
+
+#[test]
+fn test_originate_failure_status() {
+    assert_eq!(originate_failure_status(5), CallStatus::Busy);
+    assert_eq!(originate_failure_status(3), CallStatus::NoAnswer);
+    assert_eq!(originate_failure_status(1), CallStatus::NoAnswer);
+    assert_eq!(originate_failure_status(0), CallStatus::Failed);
+    assert_eq!(originate_failure_status(8), CallStatus::Failed);
+}
@@ -0,0 +1,105 @@
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        chat::ChatClientEvent,
+        presence::{PresenceSignals, PresenceStatus},
+    },
+    services::{
+        chat::ChatHub,
+        presence::{parse_user_ids, resolve_presence},
+    },
+};
+
+#[test]
+fn test_resolve_presence_priority() {
+    let idle = PresenceSignals::default();
+    assert_eq!(resolve_presence(&idle, None), PresenceStatus::Offline);
+
+    let online = PresenceSignals { online: true, ..idle };
+    assert_eq!(resolve_presence(&online, None), PresenceStatus::Available);
+
+    let meeting = PresenceSignals { in_meeting: true, ..online };
+    assert_eq!(resolve_presence(&meeting, None), PresenceStatus::InAMeeting);
+
+    let dnd = PresenceSignals { dnd: true, ..meeting };
+    assert_eq!(resolve_presence(&dnd, None), PresenceStatus::Dnd);
+
+    // A phone call shows even without any connection
+    let on_call = PresenceSignals { on_call: true, ..dnd };
+    assert_eq!(resolve_presence(&on_call, None), PresenceStatus::OnThePhone);
+    assert_eq!(
+        resolve_presence(&PresenceSignals { on_call: true, ..idle }, None),
+        PresenceStatus::OnThePhone
+    );
+
+    assert_eq!(
+        resolve_presence(&on_call, Some(PresenceStatus::Available)),
+        PresenceStatus::Available
+    );
+    assert_eq!(resolve_presence(&idle, Some(PresenceStatus::Dnd)), PresenceStatus::Dnd);
+}
+
+#[test]
+fn test_presence_status_names() {
+    assert_eq!(json!(PresenceStatus::OnThePhone), json!("on_the_phone"));
+    assert_eq!(json!(PresenceStatus::InAMeeting), json!("in_a_meeting"));
+    assert_eq!(
+        serde_json::from_value::<PresenceStatus>(json!("dnd")).unwrap(),
+        PresenceStatus::Dnd
+    );
+}
+
+#[test]
+fn test_parse_user_ids() {
+    let a = Uuid::new_v4();
+    let b = Uuid::new_v4();
+    assert_eq!(parse_user_ids(&format!("{}, {},", a, b)).unwrap(), vec![a, b]);
+    assert_eq!(parse_user_ids("").unwrap(), Vec::<Uuid>::new());
+    assert!(parse_user_ids("not-a-uuid").is_err());
+}
+
+#[test]
+fn test_subscribe_frames() {
+    let user_id = Uuid::new_v4();
+    assert_eq!(
+        serde_json::from_value::<ChatClientEvent>(json!({"type": "subscribe_presence", "user_ids": [user_id]}))
+            .unwrap(),
+        ChatClientEvent::SubscribePresence {
+            user_ids: vec![user_id]
+        }
+    );
+    assert_eq!(
+        serde_json::from_value::<ChatClientEvent>(json!({"type": "subscribe_presence"})).unwrap(),
+        ChatClientEvent::SubscribePresence { user_ids: vec![] }
+    );
+}
+
+#[test]
+fn test_hub_tracks_connections() {
+    let hub = ChatHub::new();
+    let user_id = Uuid::new_v4();
+
+    hub.connect(user_id);
+    hub.connect(user_id);
+    hub.disconnect(user_id);
+    assert!(hub.is_connected(user_id));
+    assert_eq!(hub.connected_users(), vec![user_id]);
+
+    hub.disconnect(user_id);
+    assert!(!hub.is_connected(user_id));
+    // Unbalanced disconnects are ignored
+    hub.disconnect(user_id);
+    assert!(hub.connected_users().is_empty());
+}
+
+#[test]
+fn test_hub_reports_presence_changes() {
+    let hub = ChatHub::new();
+    let user_id = Uuid::new_v4();
+
+    assert!(hub.record_presence(user_id, PresenceStatus::Offline));
+    assert!(!hub.record_presence(user_id, PresenceStatus::Offline));
+    assert!(hub.record_presence(user_id, PresenceStatus::OnThePhone));
+}
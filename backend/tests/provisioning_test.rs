@@ -0,0 +1,128 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        pbx::{ExtensionType, PbxExtension},
+        provisioning::{PhoneDevice, PhoneVendor, ProvisioningFile},
+    },
+    services::provisioning::{
+        normalize_mac, parse_basic_auth, parse_provisioning_filename, render_provisioning_file, SipServer,
+    },
+};
+
+fn extension() -> PbxExtension {
+    PbxExtension {
+        id: Uuid::new_v4(),
+        extension_number: "1001".into(),
+        name: "Alice & Bob".into(),
+        extension_type: ExtensionType::Sip,
+        config_data: json!({ "secret": "s3cret<1>", "auth_username": "alice" }),
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn device(vendor: PhoneVendor) -> PhoneDevice {
+    PhoneDevice {
+        id: Uuid::new_v4(),
+        mac_address: "001565a1b2c3".into(),
+        extension_id: Uuid::new_v4(),
+        vendor,
+        model: None,
+        password_hash: String::new(),
+        last_fetched_at: None,
+        last_fetch_address: None,
+        last_fetch_user_agent: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn server() -> SipServer {
+    SipServer {
+        host: "pbx.example.com".into(),
+        port: 5060,
+    }
+}
+
+#[test]
+fn test_normalize_mac() {
+    assert_eq!(normalize_mac("00:15:65:A1:B2:C3").unwrap(), "001565a1b2c3");
+    assert_eq!(normalize_mac("00-15-65-a1-b2-c3").unwrap(), "001565a1b2c3");
+    assert!(normalize_mac("00:15:65:a1:b2").is_err());
+    assert!(normalize_mac("00:15:65:a1:b2:zz").is_err());
+}
+
+#[test]
+fn test_parse_provisioning_filename() {
+    assert_eq!(
+        parse_provisioning_filename("001565A1B2C3.cfg"),
+        Some(("001565a1b2c3".into(), ProvisioningFile::MacConfig))
+    );
+    assert_eq!(
+        parse_provisioning_filename("001565a1b2c3-sip.cfg"),
+        Some(("001565a1b2c3".into(), ProvisioningFile::PolycomAccount))
+    );
+    assert_eq!(
+        parse_provisioning_filename("cfg001565a1b2c3.xml"),
+        Some(("001565a1b2c3".into(), ProvisioningFile::GrandstreamXml))
+    );
+    assert_eq!(parse_provisioning_filename("y000000000028.cfg"), None);
+    assert_eq!(parse_provisioning_filename("001565a1b2c3.txt"), None);
+    assert_eq!(parse_provisioning_filename("00:15:65:a1:b2:c3.cfg"), None);
+}
+
+#[test]
+fn test_parse_basic_auth() {
+    let header = format!("Basic {}", STANDARD.encode("001565a1b2c3:pass:word"));
+    assert_eq!(
+        parse_basic_auth(&header),
+        Some(("001565a1b2c3".into(), "pass:word".into()))
+    );
+    assert_eq!(parse_basic_auth("Bearer abc"), None);
+    assert_eq!(parse_basic_auth("Basic !!!"), None);
+}
+
+#[test]
+fn test_render_yealink_config() {
+    let file = render_provisioning_file(&device(PhoneVendor::Yealink), ProvisioningFile::MacConfig, &extension(), &server())
+        .unwrap();
+    assert!(file.body.starts_with("#!version:1.0.0.1\n"));
+    assert!(file.body.contains("account.1.user_name = 1001\n"));
+    assert!(file.body.contains("account.1.auth_name = alice\n"));
+    assert!(file.body.contains("account.1.password = s3cret<1>\n"));
+    assert!(file.body.contains("account.1.sip_server.1.address = pbx.example.com\n"));
+
+    assert!(render_provisioning_file(&device(PhoneVendor::Yealink), ProvisioningFile::GrandstreamXml, &extension(), &server()).is_none());
+}
+
+#[test]
+fn test_render_polycom_config() {
+    let polycom = device(PhoneVendor::Polycom);
+    let master = render_provisioning_file(&polycom, ProvisioningFile::MacConfig, &extension(), &server()).unwrap();
+    assert!(master.body.contains("CONFIG_FILES=\"001565a1b2c3-sip.cfg\""));
+    assert!(!master.body.contains("s3cret"));
+
+    let account = render_provisioning_file(&polycom, ProvisioningFile::PolycomAccount, &extension(), &server()).unwrap();
+    assert_eq!(account.content_type, "application/xml");
+    assert!(account.body.contains("reg.1.displayName=\"Alice &amp; Bob\""));
+    assert!(account.body.contains("reg.1.auth.password=\"s3cret&lt;1&gt;\""));
+    assert!(account.body.contains("reg.1.server.1.port=\"5060\""));
+}
+
+#[test]
+fn test_render_grandstream_config() {
+    let file = render_provisioning_file(&device(PhoneVendor::Grandstream), ProvisioningFile::GrandstreamXml, &extension(), &server())
+        .unwrap();
+    assert!(file.body.contains("<mac>001565a1b2c3</mac>"));
+    assert!(file.body.contains("<P47>pbx.example.com:5060</P47>"));
+    assert!(file.body.contains("<P35>1001</P35>"));
+    assert!(file.body.contains("<P36>alice</P36>"));
+    assert!(file.body.contains("<P34>s3cret&lt;1&gt;</P34>"));
+
+    assert!(render_provisioning_file(&device(PhoneVendor::Grandstream), ProvisioningFile::MacConfig, &extension(), &server()).is_none());
+}
@@ -0,0 +1,124 @@
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        asterisk::ConfigSnapshot,
+        pbx::{ExtensionType, PbxExtension},
+        recording::{RecordingMode, RecordingPolicy},
+    },
+    services::{
+        asterisk_config::{render_extensions_conf, render_pjsip_conf},
+        recording::{parse_range, parse_wav_header, ByteRange, RangeError},
+    },
+};
+
+fn wav_header(format_tag: u16, channels: u16, sample_rate: u32, bits: u16, data_size: u32) -> Vec<u8> {
+    let block_align = channels * bits / 8;
+    let byte_rate = sample_rate * u32::from(block_align);
+    let mut data = Vec::new();
+    data.extend_from_slice(b"RIFF");
+    data.extend_from_slice(&(36 + data_size).to_le_bytes());
+    data.extend_from_slice(b"WAVE");
+    // A LIST chunk with an odd size before fmt exercises chunk padding
+    data.extend_from_slice(b"LIST");
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(b"abc\0");
+    data.extend_from_slice(b"fmt ");
+    data.extend_from_slice(&16u32.to_le_bytes());
+    data.extend_from_slice(&format_tag.to_le_bytes());
+    data.extend_from_slice(&channels.to_le_bytes());
+    data.extend_from_slice(&sample_rate.to_le_bytes());
+    data.extend_from_slice(&byte_rate.to_le_bytes());
+    data.extend_from_slice(&block_align.to_le_bytes());
+    data.extend_from_slice(&bits.to_le_bytes());
+    data.extend_from_slice(b"data");
+    data.extend_from_slice(&data_size.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_parse_wav_header() {
+    let header = wav_header(1, 1, 8000, 16, 160_000);
+    let info = parse_wav_header(&header, header.len() as u64 + 160_000).unwrap();
+    assert_eq!(info.codec, "pcm");
+    assert_eq!(info.channels, 1);
+    assert_eq!(info.sample_rate, 8000);
+    assert_eq!(info.bits_per_sample, 16);
+    assert_eq!(info.data_size, 160_000);
+    assert!((info.duration_seconds - 10.0).abs() < f64::EPSILON);
+
+    let ulaw = wav_header(7, 1, 8000, 8, 0);
+    let info = parse_wav_header(&ulaw, ulaw.len() as u64 + 8000).unwrap();
+    assert_eq!(info.codec, "ulaw");
+    // A zero data size falls back to the file size
+    assert_eq!(info.data_size, 8000);
+    assert!((info.duration_seconds - 1.0).abs() < f64::EPSILON);
+
+    assert!(parse_wav_header(b"ID3\x03not a wav file", 100).is_err());
+    assert!(parse_wav_header(&header[..30], 100).is_err());
+}
+
+#[test]
+fn test_parse_range() {
+    assert_eq!(parse_range("bytes=0-99", 1000), Ok(ByteRange { start: 0, end: 99 }));
+    assert_eq!(parse_range("bytes=500-", 1000), Ok(ByteRange { start: 500, end: 999 }));
+    assert_eq!(parse_range("bytes=-200", 1000), Ok(ByteRange { start: 800, end: 999 }));
+    assert_eq!(parse_range("bytes=900-5000", 1000), Ok(ByteRange { start: 900, end: 999 }));
+    assert_eq!(parse_range("bytes=900-5000", 1000).unwrap().length(), 100);
+
+    assert_eq!(parse_range("bytes=1000-", 1000), Err(RangeError::Unsatisfiable));
+    assert_eq!(parse_range("bytes=-0", 1000), Err(RangeError::Unsatisfiable));
+    assert_eq!(parse_range("bytes=0-1,5-6", 1000), Err(RangeError::Invalid));
+    assert_eq!(parse_range("items=0-1", 1000), Err(RangeError::Invalid));
+    assert_eq!(parse_range("bytes=9-1", 1000), Err(RangeError::Invalid));
+}
+
+#[test]
+fn test_render_recording_policy() {
+    let alice = PbxExtension {
+        id: Uuid::new_v4(),
+        extension_number: "1001".into(),
+        name: "Alice".into(),
+        extension_type: ExtensionType::Sip,
+        config_data: json!({ "secret": "s3cret" }),
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    let bob = PbxExtension {
+        id: Uuid::new_v4(),
+        extension_number: "1002".into(),
+        name: "Bob".into(),
+        extension_type: ExtensionType::Sip,
+        config_data: json!({ "secret": "s3cret" }),
+        owner_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    let snapshot = ConfigSnapshot {
+        recording_policies: vec![RecordingPolicy {
+            id: Uuid::new_v4(),
+            extension_id: alice.id,
+            extension_number: "1001".into(),
+            mode: RecordingMode::Always,
+            retention_days: Some(30),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }],
+        extensions: vec![alice, bob],
+        ..Default::default()
+    };
+
+    let pjsip = render_pjsip_conf(&snapshot);
+    assert_eq!(pjsip.matches("set_var=RECORD_CALLS=1").count(), 1);
+
+    let extensions = render_extensions_conf(&snapshot);
+    assert!(extensions.contains("exten => 1001,1,NoOp(Alice)\n same => n,Gosub(record-call,s,1)\n"));
+    assert!(extensions.contains(
+        "exten => 1002,1,NoOp(Bob)\n same => n,GosubIf($[\"${RECORD_CALLS}\" = \"1\"]?record-call,s,1)\n"
+    ));
+    assert!(extensions.contains("[record-call]\n"));
+    assert!(extensions.contains(" same => n,MixMonitor(${CHANNEL(linkedid)}.wav)\n"));
+}
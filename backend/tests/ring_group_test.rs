@@ -0,0 +1,95 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        asterisk::ConfigSnapshot,
+        ring_group::{RingGroup, RingGroupMember, RingGroupResponse, RingGroupStrategy},
+        routing::RouteDestinationType,
+    },
+    services::{
+        asterisk_config::render_extensions_conf,
+        ring_group::{sequential_schedule, SequentialStep},
+    },
+};
+
+fn group(strategy: RingGroupStrategy, timeout: i32, member_timeout: i32) -> RingGroup {
+    RingGroup {
+        id: Uuid::new_v4(),
+        group_number: "600".into(),
+        name: "Sales".into(),
+        strategy,
+        timeout,
+        member_timeout,
+        failover_destination_type: Some(RouteDestinationType::Voicemail),
+        failover_destination: Some("1001".into()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn member(number: &str, position: i32, delay: i32) -> RingGroupMember {
+    RingGroupMember {
+        id: Uuid::new_v4(),
+        ring_group_id: Uuid::new_v4(),
+        extension_id: Uuid::new_v4(),
+        extension_number: number.into(),
+        extension_name: format!("Ext {}", number),
+        position,
+        delay,
+    }
+}
+
+#[test]
+fn test_sequential_schedule_stops_at_group_timeout() {
+    let group = group(RingGroupStrategy::Sequential, 40, 15);
+    let members = vec![member("1001", 0, 0), member("1002", 1, 5), member("1003", 2, 0), member("1004", 3, 0)];
+
+    let steps = sequential_schedule(&group, &members);
+    assert_eq!(
+        steps,
+        vec![
+            SequentialStep { extension_number: "1001".into(), wait: 0, ring_time: 15 },
+            SequentialStep { extension_number: "1002".into(), wait: 5, ring_time: 15 },
+            SequentialStep { extension_number: "1003".into(), wait: 0, ring_time: 5 },
+        ]
+    );
+}
+
+#[test]
+fn test_render_simultaneous_group() {
+    let snapshot = ConfigSnapshot {
+        ring_groups: vec![RingGroupResponse {
+            group: group(RingGroupStrategy::Simultaneous, 30, 15),
+            members: vec![member("1001", 0, 0), member("1002", 1, 10), member("1003", 2, 45)],
+        }],
+        ..Default::default()
+    };
+
+    let config = render_extensions_conf(&snapshot);
+    assert!(config.contains("exten => 600,1,Goto(ringgroups,600,1)\n"));
+    assert!(config.contains(
+        "[ringgroups]\nexten => 600,1,NoOp(Ring group Sales)\n same => n,Dial(Local/1001@from-internal&Local/1002@ringgroup-delay-10,30)\n same => n,VoiceMail(1001@default,u)\n"
+    ));
+    assert!(config.contains("[ringgroup-delay-10]\nexten => _X.,1,Wait(10)\n same => n,Goto(from-internal,${EXTEN},1)\n"));
+    assert!(!config.contains("1003"));
+}
+
+#[test]
+fn test_render_sequential_group() {
+    let mut sequential = group(RingGroupStrategy::Sequential, 30, 10);
+    sequential.failover_destination_type = None;
+    let snapshot = ConfigSnapshot {
+        ring_groups: vec![RingGroupResponse {
+            group: sequential,
+            members: vec![member("1001", 0, 0), member("1002", 1, 2)],
+        }],
+        ..Default::default()
+    };
+
+    let config = render_extensions_conf(&snapshot);
+    assert!(config.contains(
+        " same => n,Dial(Local/1001@from-internal,10)\n same => n,Wait(2)\n same => n,Dial(Local/1002@from-internal,10)\n same => n,Hangup()\n"
+    ));
+    assert!(!config.contains("ringgroup-delay"));
+}
@@ -0,0 +1,212 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        asterisk::ConfigSnapshot,
+        routing::{InboundRoute, OutboundRoute, RouteDestinationType},
+        screening::ScreeningAction,
+        trunk::{SipTrunk, TrunkTransport},
+    },
+    services::{
+        asterisk_config::render_extensions_conf,
+        routing::{evaluate_inbound, evaluate_outbound, DialPattern},
+    },
+};
+
+fn inbound(name: &str, did: Option<&str>, caller_id: Option<&str>, destination: &str, priority: i32) -> InboundRoute {
+    InboundRoute {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        did_pattern: did.map(str::to_string),
+        caller_id_pattern: caller_id.map(str::to_string),
+        destination_type: RouteDestinationType::Extension,
+        destination: destination.to_string(),
+        priority,
+        enabled: true,
+        blocked_action: ScreeningAction::Reject,
+        blocked_destination: None,
+        reject_anonymous: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn outbound(name: &str, pattern: &str, strip_digits: i32, prepend: Option<&str>, priority: i32) -> OutboundRoute {
+    OutboundRoute {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        dial_pattern: pattern.to_string(),
+        strip_digits,
+        prepend: prepend.map(str::to_string),
+        trunk: "carrier".to_string(),
+        priority,
+        enabled: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn trunk(name: &str, enabled: bool) -> SipTrunk {
+    SipTrunk {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        host: "sip.provider.example".to_string(),
+        port: 5060,
+        transport: TrunkTransport::Udp,
+        username: None,
+        secret: None,
+        register: false,
+        outbound_caller_id: None,
+        max_channels: None,
+        codecs: vec!["ulaw".to_string()],
+        enabled,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_dial_patterns() {
+    let local = DialPattern::parse("_9NXXXXXX").unwrap();
+    assert!(local.matches("95551234"));
+    assert!(!local.matches("91551234"));
+    assert!(!local.matches("955512345"));
+
+    let international = DialPattern::parse("_900.").unwrap();
+    assert!(international.matches("90044207946"));
+    assert!(!international.matches("900"));
+
+    let range = DialPattern::parse("_[1-35]XX").unwrap();
+    assert!(range.matches("300"));
+    assert!(range.matches("599"));
+    assert!(!range.matches("400"));
+
+    let exact = DialPattern::parse("5551234").unwrap();
+    assert!(exact.matches("5551234"));
+    assert!(!exact.matches("5551235"));
+
+    assert!(DialPattern::parse("_9[2-").is_err());
+    assert!(DialPattern::parse("_9.X").is_err());
+}
+
+#[test]
+fn test_inbound_caller_id_route_takes_priority() {
+    let routes = vec![
+        inbound("Main DID", Some("5551000"), None, "1000", 100),
+        inbound("VIP", Some("5551000"), Some("_0800."), "1001", 10),
+    ];
+
+    let vip = evaluate_inbound(&routes, "5551000", Some("0800123456"));
+    assert!(vip.matched);
+    assert_eq!(vip.route_name.as_deref(), Some("VIP"));
+    assert_eq!(vip.destination.as_deref(), Some("1001"));
+
+    let regular = evaluate_inbound(&routes, "5551000", Some("0212345678"));
+    assert_eq!(regular.route_name.as_deref(), Some("Main DID"));
+    assert_eq!(regular.evaluated.len(), 2);
+    assert!(!regular.evaluated[0].matched);
+    assert!(regular.evaluated[0].reason.contains("does not match"));
+
+    let unknown = evaluate_inbound(&routes, "5559999", None);
+    assert!(!unknown.matched);
+}
+
+#[test]
+fn test_outbound_rewrites_number() {
+    let mut disabled = outbound("Disabled", "_X.", 0, None, 1);
+    disabled.enabled = false;
+    let routes = vec![
+        disabled,
+        outbound("International", "_900.", 1, Some("+"), 10),
+        outbound("Local", "_9NXXXXXX", 1, Some("021"), 20),
+    ];
+
+    let international = evaluate_outbound(&routes, &[], "90044207946");
+    assert_eq!(international.route_name.as_deref(), Some("International"));
+    assert_eq!(international.dialed_number.as_deref(), Some("+0044207946"));
+    assert_eq!(international.trunk.as_deref(), Some("carrier"));
+    assert_eq!(international.evaluated[0].reason, "Route is disabled");
+
+    let local = evaluate_outbound(&routes, &[], "95551234");
+    assert_eq!(local.route_name.as_deref(), Some("Local"));
+    assert_eq!(local.dialed_number.as_deref(), Some("0215551234"));
+}
+
+#[test]
+fn test_dialplan_follows_route_priority() {
+    // The catch-all wins in the simulator, although Asterisk would prefer the
+    // more specific pattern within one context
+    let outbound_routes = vec![
+        outbound("Premium", "_900.", 1, None, 20),
+        outbound("Everything", "_9X.", 1, None, 10),
+        outbound("Duplicate", "_9X.", 1, None, 30),
+    ];
+    assert_eq!(
+        evaluate_outbound(&outbound_routes, &[], "90044207946").route_name.as_deref(),
+        Some("Everything")
+    );
+    let inbound_routes = vec![
+        inbound("Sales", Some("5551000"), None, "1001", 20),
+        inbound("Night", None, None, "1002", 10),
+    ];
+    let snapshot = ConfigSnapshot {
+        outbound_routes,
+        inbound_routes,
+        ..Default::default()
+    };
+
+    let conf = render_extensions_conf(&snapshot);
+    assert!(conf.contains("include => outbound-route-1\ninclude => outbound-route-2\ninclude => outbound-route-3\n"));
+    assert!(conf.contains("[outbound-route-1]\nexten => _9X.,1,NoOp(Outbound route Everything)\n"));
+    assert!(conf.contains("[outbound-route-2]\nexten => _900.,1,NoOp(Outbound route Premium)\n"));
+    assert!(conf.contains("[outbound-route-3]\nexten => _9X.,1,NoOp(Outbound route Duplicate)\n"));
+    assert_eq!(conf.matches("exten => _9X.,1,").count(), 2);
+
+    assert!(conf.contains("[from-trunk]\ninclude => inbound-route-1\ninclude => inbound-route-2\n"));
+    assert!(conf.contains("[inbound-route-1]\nexten => _X!,1,NoOp(Inbound route Night)\n"));
+    assert!(conf.contains("[inbound-route-2]\nexten => 5551000,1,NoOp(Inbound route Sales)\n"));
+}
+
+#[test]
+fn test_simulation_agrees_with_dialplan() {
+    let mut backup = outbound("Backup", "_9X.", 1, None, 5);
+    backup.trunk = "backup".to_string();
+    let routes = vec![
+        backup,
+        outbound("Short", "_9X", 3, None, 10),
+        outbound("Everything", "_9X.", 1, None, 20),
+    ];
+    let trunks = vec![trunk("carrier", true), trunk("backup", false)];
+    let snapshot = ConfigSnapshot {
+        outbound_routes: routes.clone(),
+        trunks: trunks.clone(),
+        ..Default::default()
+    };
+    let conf = render_extensions_conf(&snapshot);
+
+    // Route contexts in the order from-internal includes them
+    let rendered: Vec<(String, String)> = conf
+        .split("\n[outbound-route-")
+        .skip(1)
+        .filter_map(|section| {
+            let line = section.lines().nth(1)?;
+            let (pattern, rest) = line.strip_prefix("exten => ")?.split_once(",1,NoOp(Outbound route ")?;
+            Some((pattern.to_string(), rest.trim_end_matches(')').to_string()))
+        })
+        .collect();
+    assert_eq!(rendered.len(), 2);
+
+    for number in ["91", "912", "90044207946"] {
+        let dialplan = rendered
+            .iter()
+            .find(|(pattern, _)| DialPattern::parse(pattern).unwrap().matches(number))
+            .map(|(_, name)| name.as_str());
+        let simulated = evaluate_outbound(&routes, &trunks, number);
+        assert_eq!(simulated.route_name.as_deref(), dialplan, "number {}", number);
+    }
+
+    let simulated = evaluate_outbound(&routes, &trunks, "91");
+    assert_eq!(simulated.route_name.as_deref(), Some("Short"));
+    assert_eq!(simulated.evaluated[0].reason, "Trunk backup is disabled");
+}
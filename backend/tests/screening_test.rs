@@ -0,0 +1,238 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+mod common_ami;
+use common_ami::message;
+
+use oriontel_backend::{
+    ami::call_tracker::{interpret_event, CallEvent},
+    models::{
+        asterisk::ConfigSnapshot,
+        routing::{InboundRoute, RouteDestinationType},
+        screening::{ScreeningAction, ScreeningDecision, ScreeningEntry, ScreeningList, ScreeningMatch},
+    },
+    services::{
+        asterisk_config::render_extensions_conf,
+        screening::{astdb_writes, is_anonymous, screen_caller, screening_regex, validate_pattern, SCREENING_FAMILY},
+    },
+};
+
+fn entry(list: ScreeningList, match_type: ScreeningMatch, pattern: &str) -> ScreeningEntry {
+    ScreeningEntry {
+        id: Uuid::new_v4(),
+        list,
+        match_type,
+        pattern: pattern.to_string(),
+        description: None,
+        expires_at: None,
+        created_by: None,
+        call_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_validate_pattern() {
+    assert_eq!(
+        validate_pattern(ScreeningMatch::Exact, " +49 (30) 123-4567 ").unwrap(),
+        "+49301234567"
+    );
+    assert_eq!(validate_pattern(ScreeningMatch::Prefix, "0900").unwrap(), "0900");
+    assert!(validate_pattern(ScreeningMatch::Exact, "spam").is_err());
+    assert!(validate_pattern(ScreeningMatch::Prefix, "+").is_err());
+
+    assert_eq!(
+        validate_pattern(ScreeningMatch::Regex, "^(0900|0137)").unwrap(),
+        "^(0900|0137)"
+    );
+    assert!(validate_pattern(ScreeningMatch::Regex, "[0-").is_err());
+    // Would break out of the dialplan expression
+    assert!(validate_pattern(ScreeningMatch::Regex, "a\"b").is_err());
+    assert!(validate_pattern(ScreeningMatch::Regex, "\\d+").is_err());
+}
+
+#[test]
+fn test_screening_regex() {
+    assert_eq!(
+        screening_regex(ScreeningMatch::Exact, "+49301234567", Some("49")),
+        "^(([+]|00)?49301234567|0301234567)$"
+    );
+    assert_eq!(
+        screening_regex(ScreeningMatch::Exact, "+49301234567", None),
+        "^(([+]|00)?49301234567)$"
+    );
+    assert_eq!(
+        screening_regex(ScreeningMatch::Exact, "5551234567", Some("1")),
+        "^(([+]|00)?15551234567|5551234567)$"
+    );
+    assert_eq!(screening_regex(ScreeningMatch::Exact, "1001", Some("49")), "^1001$");
+
+    assert_eq!(
+        screening_regex(ScreeningMatch::Prefix, "0900", Some("49")),
+        "^(([+]|00)49900|0900)"
+    );
+    assert_eq!(
+        screening_regex(ScreeningMatch::Prefix, "+1900", Some("1")),
+        "^(([+]|00)1900|900)"
+    );
+    assert_eq!(screening_regex(ScreeningMatch::Prefix, "+44", Some("49")), "^(([+]|00)44)");
+    assert_eq!(screening_regex(ScreeningMatch::Prefix, "0900", None), "^0900");
+
+    assert_eq!(screening_regex(ScreeningMatch::Regex, "^0800[0-9]+$", None), "^0800[0-9]+$");
+}
+
+#[test]
+fn test_screen_caller() {
+    let premium = entry(ScreeningList::Block, ScreeningMatch::Prefix, "0900");
+    let exception = entry(ScreeningList::Allow, ScreeningMatch::Exact, "+49900123456");
+    let expired = ScreeningEntry {
+        expires_at: Some(Utc::now() - Duration::hours(1)),
+        ..entry(ScreeningList::Block, ScreeningMatch::Exact, "+49301234567")
+    };
+    let entries = vec![premium.clone(), exception.clone(), expired];
+    let now = Utc::now();
+
+    let decide = |caller_id: &str| screen_caller(&entries, caller_id, Some("49"), now).map(|entry| entry.id);
+    assert_eq!(decide("+499001111"), Some(premium.id));
+    assert_eq!(decide("09001111"), Some(premium.id));
+    // Allow entries win, in whatever form the number arrives
+    assert_eq!(decide("0900123456"), Some(exception.id));
+    assert_eq!(decide("0049900123456"), Some(exception.id));
+    assert_eq!(decide("0301234567"), None);
+    assert_eq!(decide("0301111111"), None);
+}
+
+#[test]
+fn test_is_anonymous() {
+    for caller_id in ["", "Anonymous", " restricted ", "<unknown>", "unknown"] {
+        assert!(is_anonymous(caller_id), "{:?}", caller_id);
+    }
+    assert!(!is_anonymous("1001"));
+    assert!(!is_anonymous("+49301234567"));
+}
+
+#[test]
+fn test_astdb_writes() {
+    let blocked = entry(ScreeningList::Block, ScreeningMatch::Exact, "1001");
+    let expires_at = Utc::now() + Duration::days(1);
+    let allowed = ScreeningEntry {
+        expires_at: Some(expires_at),
+        ..entry(ScreeningList::Allow, ScreeningMatch::Regex, "^0800")
+    };
+    let expired = ScreeningEntry {
+        expires_at: Some(Utc::now() - Duration::days(1)),
+        ..entry(ScreeningList::Block, ScreeningMatch::Exact, "1002")
+    };
+
+    let writes = astdb_writes(&[blocked.clone(), allowed.clone(), expired], None, Utc::now());
+    assert!(writes.iter().all(|write| write.family == SCREENING_FAMILY));
+    let pairs: Vec<(&str, &str)> = writes
+        .iter()
+        .map(|write| (write.key.as_str(), write.value.as_deref().unwrap()))
+        .collect();
+    let expires = expires_at.timestamp().to_string();
+    let allowed_id = allowed.id.to_string();
+    let blocked_id = blocked.id.to_string();
+    assert_eq!(
+        pairs,
+        vec![
+            ("0/match", "^0800"),
+            ("0/list", "allow"),
+            ("0/expires", expires.as_str()),
+            ("0/id", allowed_id.as_str()),
+            ("1/match", "^1001$"),
+            ("1/list", "block"),
+            ("1/id", blocked_id.as_str()),
+            ("count", "2"),
+        ]
+    );
+}
+
+#[test]
+fn test_interpret_call_screening() {
+    let entry_id = Uuid::new_v4();
+    let route_id = Uuid::new_v4();
+    let event = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "CallScreening"),
+        ("Call", "1712345678.42"),
+        ("Entry", &entry_id.to_string()),
+        ("Route", &route_id.to_string()),
+        ("Caller", "+49301234567"),
+        ("Action", "busy"),
+    ]);
+    assert_eq!(
+        interpret_event(&event),
+        Some(CallEvent::CallScreened(ScreeningDecision {
+            uniqueid: "1712345678.42".into(),
+            entry_id: Some(entry_id),
+            route_id: Some(route_id),
+            caller_id: "+49301234567".into(),
+            action: ScreeningAction::Busy,
+        }))
+    );
+
+    let anonymous = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "CallScreening"),
+        ("Call", "1712345678.43"),
+        ("Entry", ""),
+        ("Route", &route_id.to_string()),
+        ("Caller", ""),
+        ("Action", "reject"),
+    ]);
+    assert_eq!(
+        interpret_event(&anonymous),
+        Some(CallEvent::CallScreened(ScreeningDecision {
+            uniqueid: "1712345678.43".into(),
+            entry_id: None,
+            route_id: Some(route_id),
+            caller_id: "anonymous".into(),
+            action: ScreeningAction::Reject,
+        }))
+    );
+
+    let unknown_action = message(&[
+        ("Event", "UserEvent"),
+        ("UserEvent", "CallScreening"),
+        ("Call", "1712345678.44"),
+        ("Action", "ignore"),
+    ]);
+    assert_eq!(interpret_event(&unknown_action), None);
+}
+
+#[test]
+fn test_render_caller_screening() {
+    let route = InboundRoute {
+        id: Uuid::new_v4(),
+        name: "Main".to_string(),
+        did_pattern: Some("4930123400".to_string()),
+        caller_id_pattern: None,
+        destination_type: RouteDestinationType::Extension,
+        destination: "1001".to_string(),
+        priority: 100,
+        enabled: true,
+        blocked_action: ScreeningAction::Voicemail,
+        blocked_destination: Some("1001".to_string()),
+        reject_anonymous: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    let snapshot = ConfigSnapshot {
+        inbound_routes: vec![route.clone()],
+        ..Default::default()
+    };
+
+    let conf = render_extensions_conf(&snapshot);
+    assert!(conf.contains(&format!(
+        "exten => 4930123400,1,NoOp(Inbound route Main)\n same => n,Gosub(caller-screening,s,1(voicemail,1001,1,{}))\n same => n,Goto(from-internal,1001,1)\n",
+        route.id
+    )));
+    assert!(conf.contains("[caller-screening]\nexten => s,1,Set(LOCAL(caller)=${CALLERID(num)})\n"));
+    assert!(conf.contains(" same => n,GotoIf(${REGEX(\"${DB(SCREEN/${i}/match)}\" ${caller})}?matched)\n"));
+    assert!(conf.contains(" same => n(blocked),UserEvent(CallScreening,Call: ${CHANNEL(linkedid)},Entry: ${entry},"));
+    assert!(conf.contains(",Caller: ${FILTER(0-9+*#a-zA-Z,${caller})},Action: ${ARG1})"));
+    assert!(conf.contains("exten => reject,1,Hangup(21)\n"));
+    assert!(conf.contains("exten => voicemail,1,VoiceMail(${ARG2}@default,u)\n"));
+}
@@ -0,0 +1,110 @@
+use oriontel_backend::sip::{
+    digest::{DigestCredentials, DigestOutcome, NonceIssuer},
+    message::{message_length, SipRequest},
+};
+
+const REGISTER: &str = "REGISTER sip:pbx.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP 192.0.2.10:5060;branch=z9hG4bK776asdhds\r\n\
+Max-Forwards: 70\r\n\
+To: \"Alice\" <sip:1001@pbx.example.com>\r\n\
+From: \"Alice\" <sip:1001@pbx.example.com>;tag=456248\r\n\
+i: 843817637684230@998sdasdh09\r\n\
+CSeq: 1826 REGISTER\r\n\
+m: <sip:1001@192.0.2.10:5060;transport=udp>;expires=600, <sip:1001@198.51.100.7>\r\n\
+User-Agent: Yealink SIP-T46S\r\n\
+Expires: 1800\r\n\
+l: 0\r\n\
+\r\n";
+
+#[test]
+fn test_parse_register() {
+    let request = SipRequest::parse(REGISTER.as_bytes()).unwrap();
+
+    assert_eq!(request.method, "REGISTER");
+    assert_eq!(request.uri, "sip:pbx.example.com");
+    assert_eq!(request.to_user().as_deref(), Some("1001"));
+    assert_eq!(request.header("Call-ID"), Some("843817637684230@998sdasdh09"));
+    assert_eq!(request.cseq(), Some((1826, "REGISTER")));
+    assert_eq!(request.header("user-agent"), Some("Yealink SIP-T46S"));
+
+    let contacts = request.contacts();
+    assert_eq!(contacts.len(), 2);
+    assert_eq!(contacts[0].uri, "sip:1001@192.0.2.10:5060;transport=udp");
+    assert_eq!(contacts[0].expires, Some(600));
+    assert_eq!(contacts[1].uri, "sip:1001@198.51.100.7");
+    assert_eq!(contacts[1].expires, None);
+}
+
+#[test]
+fn test_response_echoes_transaction_headers() {
+    let request = SipRequest::parse(REGISTER.as_bytes()).unwrap();
+    let response = request.response(200, "OK").to_string();
+
+    assert!(response.starts_with("SIP/2.0 200 OK\r\n"));
+    assert!(response.contains("Via: SIP/2.0/UDP 192.0.2.10:5060;branch=z9hG4bK776asdhds\r\n"));
+    assert!(response.contains("Call-ID: 843817637684230@998sdasdh09\r\n"));
+    assert!(response.contains("CSeq: 1826 REGISTER\r\n"));
+    assert!(response.contains(";tag="));
+    assert!(response.ends_with("Content-Length: 0\r\n\r\n"));
+}
+
+#[test]
+fn test_tcp_framing() {
+    let mut stream = REGISTER.as_bytes().to_vec();
+    assert_eq!(message_length(&stream[..stream.len() - 2]), None);
+
+    stream.extend_from_slice(b"OPTIONS sip:pbx SIP/2.0\r\n");
+    assert_eq!(message_length(&stream), Some(REGISTER.len()));
+}
+
+#[test]
+fn test_oversized_content_length() {
+    let request = REGISTER.replace("l: 0", "l: 18446744073709551615");
+    assert!(request.contains("18446744073709551615"));
+
+    assert!(SipRequest::parse(request.as_bytes()).is_err());
+    assert_eq!(message_length(request.as_bytes()), None);
+}
+
+#[test]
+fn test_digest_rfc2617_vector() {
+    let credentials = DigestCredentials::parse(
+        "Digest username=\"Mufasa\", realm=\"testrealm@host.com\", \
+         nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", uri=\"/dir/index.html\", \
+         qop=auth, nc=00000001, cnonce=\"0a4f113b\", \
+         response=\"6629fae49393a05397450978507c4ef1\"",
+    )
+    .unwrap();
+
+    assert_eq!(
+        credentials.expected_response("GET", "Circle Of Life"),
+        credentials.response
+    );
+}
+
+#[test]
+fn test_nonce_verification() {
+    let issuer = NonceIssuer::new("oriontel".into(), "test_secret".into());
+    let mut credentials = DigestCredentials {
+        username: "1001".into(),
+        realm: "oriontel".into(),
+        nonce: issuer.issue(),
+        uri: "sip:pbx.example.com".into(),
+        ..Default::default()
+    };
+    credentials.response = credentials.expected_response("REGISTER", "s3cret");
+
+    let uri = "sip:pbx.example.com";
+    assert_eq!(issuer.verify(&credentials, "REGISTER", uri, "s3cret"), DigestOutcome::Valid);
+    assert_eq!(issuer.verify(&credentials, "REGISTER", uri, "wrong"), DigestOutcome::Invalid);
+
+    // The digest URI must match the Request-URI it was sent with
+    assert_eq!(
+        issuer.verify(&credentials, "REGISTER", "sip:other.example.com", "s3cret"),
+        DigestOutcome::Invalid
+    );
+
+    // A nonce signed with a different key must be rejected
+    let other = NonceIssuer::new("oriontel".into(), "other_secret".into());
+    assert_eq!(other.verify(&credentials, "REGISTER", uri, "s3cret"), DigestOutcome::Invalid);
+}
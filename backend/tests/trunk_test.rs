@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use oriontel_backend::{
+    models::{
+        asterisk::ConfigSnapshot,
+        routing::OutboundRoute,
+        trunk::{SipTrunk, TrunkRegistrationState, TrunkTransport},
+    },
+    services::{
+        asterisk_config::{render_extensions_conf, render_pjsip_conf},
+        trunk::{registration_state, trunk_status, validate_codecs, validate_trunk_host, validate_trunk_name},
+    },
+};
+
+fn trunk(name: &str) -> SipTrunk {
+    SipTrunk {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        host: "sip.provider.example".to_string(),
+        port: 5060,
+        transport: TrunkTransport::Udp,
+        username: Some("acct123".to_string()),
+        secret: Some("s3cret".to_string()),
+        register: true,
+        outbound_caller_id: None,
+        max_channels: None,
+        codecs: vec!["ulaw".to_string(), "g722".to_string()],
+        enabled: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn route(name: &str, pattern: &str, trunk: &str) -> OutboundRoute {
+    OutboundRoute {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        dial_pattern: pattern.to_string(),
+        strip_digits: 1,
+        prepend: None,
+        trunk: trunk.to_string(),
+        priority: 100,
+        enabled: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_trunk_validation() {
+    assert!(validate_trunk_name("provider-1").is_ok());
+    assert!(validate_trunk_name("1001").is_err());
+    assert!(validate_trunk_name("bad name").is_err());
+    assert!(validate_trunk_name("provider-auth").is_err());
+    assert!(validate_trunk_name("provider-Identify").is_err());
+    assert!(validate_trunk_name("provider-reg").is_err());
+    assert!(validate_trunk_name("transport-udp").is_err());
+    assert!(validate_trunk_name("provider-regional").is_ok());
+    assert!(validate_trunk_host("203.0.113.10").is_ok());
+    assert!(validate_trunk_host("[2001:db8::1]").is_ok());
+    assert!(validate_trunk_host("host;evil").is_err());
+    assert!(validate_codecs(&["ulaw".to_string(), "opus".to_string()]).is_ok());
+    assert!(validate_codecs(&[]).is_err());
+    assert!(validate_codecs(&["ulaw,alaw".to_string()]).is_err());
+}
+
+#[test]
+fn test_render_registering_trunk() {
+    let snapshot = ConfigSnapshot {
+        trunks: vec![trunk("provider")],
+        ..Default::default()
+    };
+
+    let conf = render_pjsip_conf(&snapshot);
+    assert!(conf.contains("[provider]\ntype=endpoint\ntransport=transport-udp\ncontext=from-trunk\n"));
+    assert!(conf.contains("allow=ulaw,g722\n"));
+    assert!(conf.contains("outbound_auth=provider-auth\n"));
+    assert!(conf.contains("[provider-auth]\ntype=auth\nauth_type=userpass\nusername=acct123\npassword=s3cret\n"));
+    assert!(conf.contains("[provider]\ntype=aor\ncontact=sip:sip.provider.example:5060\n"));
+    assert!(conf.contains("[provider-identify]\ntype=identify\nendpoint=provider\nmatch=sip.provider.example\n"));
+    assert!(conf.contains("[provider-reg]\ntype=registration\n"));
+    assert!(conf.contains("client_uri=sip:acct123@sip.provider.example:5060\n"));
+}
+
+#[test]
+fn test_disabled_and_non_registering_trunks() {
+    let mut ip_auth = trunk("ipauth");
+    ip_auth.register = false;
+    ip_auth.username = None;
+    ip_auth.secret = None;
+    let mut disabled = trunk("old");
+    disabled.enabled = false;
+
+    let snapshot = ConfigSnapshot {
+        trunks: vec![ip_auth, disabled],
+        outbound_routes: vec![route("Old", "_9X.", "old")],
+        ..Default::default()
+    };
+
+    let conf = render_pjsip_conf(&snapshot);
+    assert!(conf.contains("[ipauth]\ntype=endpoint\n"));
+    assert!(!conf.contains("ipauth-auth"));
+    assert!(!conf.contains("ipauth-reg"));
+    assert!(!conf.contains("[old]"));
+
+    let dialplan = render_extensions_conf(&snapshot);
+    assert!(!dialplan.contains("Outbound route Old"));
+}
+
+#[test]
+fn test_outbound_route_uses_trunk_settings() {
+    let mut provider = trunk("provider");
+    provider.outbound_caller_id = Some("+15550100".to_string());
+    provider.max_channels = Some(4);
+
+    let snapshot = ConfigSnapshot {
+        trunks: vec![provider],
+        outbound_routes: vec![route("National", "_9NXXNXXXXXX", "provider")],
+        ..Default::default()
+    };
+
+    let dialplan = render_extensions_conf(&snapshot);
+    assert!(dialplan.contains(" same => n,Set(CALLERID(num)=+15550100)\n"));
+    assert!(dialplan.contains(" same => n,Set(GROUP(trunks)=provider)\n"));
+    assert!(dialplan.contains(" same => n,GotoIf($[${GROUP_COUNT(provider@trunks)} > 4]?trunk-full)\n"));
+    assert!(dialplan.contains(" same => n,Dial(PJSIP/${EXTEN:1}@provider)\n"));
+    assert!(dialplan.contains(" same => n(trunk-full),Congestion()\n"));
+}
+
+#[test]
+fn test_trunk_status() {
+    let registered = trunk("provider");
+    let mut static_trunk = trunk("static");
+    static_trunk.register = false;
+    let missing = trunk("missing");
+
+    let mut reported = HashMap::new();
+    reported.insert("provider-reg".to_string(), "Registered".to_string());
+
+    let status = trunk_status(&registered, &reported);
+    assert_eq!(status.state, TrunkRegistrationState::Registered);
+    assert_eq!(status.detail.as_deref(), Some("Registered"));
+    assert_eq!(trunk_status(&static_trunk, &reported).state, TrunkRegistrationState::Disabled);
+    assert_eq!(trunk_status(&missing, &reported).state, TrunkRegistrationState::Unknown);
+
+    assert_eq!(registration_state("Rejected"), TrunkRegistrationState::Rejected);
+    assert_eq!(registration_state("Unregistered"), TrunkRegistrationState::Unregistered);
+    assert_eq!(registration_state("Stopped"), TrunkRegistrationState::Stopped);
+}
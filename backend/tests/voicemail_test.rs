@@ -0,0 +1,133 @@
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+mod common_ami;
+use common_ami::message;
+
+use oriontel_backend::{
+    ami::call_tracker::{interpret_event, CallEvent},
+    models::{
+        asterisk::ConfigSnapshot,
+        voicemail::{SpoolMessage, VoicemailBox},
+    },
+    services::{
+        asterisk_config::render_voicemail_conf,
+        voicemail::{audio_extension, parse_spool_message, validate_pin},
+    },
+};
+
+fn mailbox(number: &str, name: &str, pin: &str) -> VoicemailBox {
+    VoicemailBox {
+        id: Uuid::new_v4(),
+        extension_id: Uuid::new_v4(),
+        extension_number: number.to_string(),
+        extension_name: name.to_string(),
+        pin: pin.to_string(),
+        email: Some("alice@example.com".into()),
+        owner_id: None,
+        has_greeting: false,
+        max_messages: 100,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_validate_pin() {
+    assert!(validate_pin("1234").is_ok());
+    assert!(validate_pin("0123456789").is_ok());
+    assert!(validate_pin("123").is_err());
+    assert!(validate_pin("12345678901").is_err());
+    assert!(validate_pin("12a4").is_err());
+}
+
+#[test]
+fn test_audio_extension() {
+    assert_eq!(audio_extension("audio/wav"), Some("wav"));
+    assert_eq!(audio_extension("audio/x-wav; codecs=1"), Some("wav"));
+    assert_eq!(audio_extension("audio/MPEG"), Some("mp3"));
+    assert_eq!(audio_extension("application/octet-stream"), None);
+}
+
+#[test]
+fn test_pin_is_not_serialized() {
+    let value = serde_json::to_value(mailbox("1001", "Alice", "4321")).unwrap();
+    assert!(value.get("pin").is_none());
+    assert_eq!(value["extension_number"], "1001");
+}
+
+#[test]
+fn test_render_voicemail_conf() {
+    let snapshot = ConfigSnapshot {
+        voicemail_boxes: vec![
+            mailbox("1001", "Alice", "4321"),
+            mailbox("1002", "Bob, Jr.", "1111"),
+        ],
+        ..Default::default()
+    };
+
+    let config = render_voicemail_conf(&snapshot);
+    assert!(config.contains("[general]\nformat=wav\nattach=no\n"));
+    assert!(config.contains("[default]\n1001 => 4321,Alice\n1002 => 1111,Bob\\, Jr.\n"));
+    assert!(!config.contains("alice@example.com"));
+}
+
+#[test]
+fn test_parse_spool_message() {
+    let text = "\
+;
+; Message Information file
+;
+[message]
+origtime=1712345678
+category=
+msg_id=1712345678-00000001
+callerchan=PJSIP/1002-00000003
+callerid=\"Bob\" <1002>
+duration=14
+";
+    assert_eq!(
+        parse_spool_message(text),
+        Some(SpoolMessage {
+            msg_id: "1712345678-00000001".into(),
+            caller_id: "1002".into(),
+            duration: 14,
+            received_at: Utc.timestamp_opt(1712345678, 0).unwrap(),
+        })
+    );
+
+    // Older spools have no msg_id, and anonymous callers no number
+    let legacy = parse_spool_message(
+        "[message]\norigtime=1712345679\ncallerchan=PJSIP/trunk-00000004\ncallerid=Unknown\n",
+    )
+    .unwrap();
+    assert_eq!(legacy.msg_id, "1712345679-PJSIP/trunk-00000004");
+    assert_eq!(legacy.caller_id, "unknown");
+    assert_eq!(legacy.duration, 0);
+
+    assert_eq!(parse_spool_message("[message]\ncallerid=1002\n"), None);
+}
+
+#[test]
+fn test_interpret_message_waiting() {
+    let event = message(&[
+        ("Event", "MessageWaiting"),
+        ("Mailbox", "1001@default"),
+        ("Waiting", "1"),
+        ("New", "1"),
+        ("Old", "0"),
+    ]);
+    assert_eq!(
+        interpret_event(&event),
+        Some(CallEvent::VoicemailWaiting {
+            mailbox: "1001".into()
+        })
+    );
+
+    // Nothing to import once the phone has played the last new message
+    let cleared = message(&[("Event", "MessageWaiting"), ("Mailbox", "1001@default"), ("Waiting", "0")]);
+    assert_eq!(interpret_event(&cleared), None);
+
+    let other_context = message(&[("Event", "MessageWaiting"), ("Mailbox", "1001@other"), ("Waiting", "1")]);
+    assert_eq!(interpret_event(&other_context), None);
+}